@@ -1,11 +1,17 @@
 use std::{any::Any, vec};
 
+use chrono::DateTime;
 use dora_node_api::{
-    self, Event, EventStream,
-    arrow::array::{AsArray, UInt8Array},
+    self, Event, EventStream, MetadataParameters, Parameter,
+    arrow::{
+        array::{ArrayData, AsArray, UInt8Array, make_array},
+        buffer::Buffer,
+        datatypes::DataType,
+    },
     merged::{MergeExternal, MergedEvent},
+    tensor::{self, ImageInfo, TensorInfo},
 };
-use eyre::bail;
+use eyre::{ContextCompat, bail};
 
 #[cfg(feature = "ros2-bridge")]
 use dora_ros2_bridge::{_core, ros2_client};
@@ -37,6 +43,34 @@ mod ffi {
         error: String,
     }
 
+    /// Layout of an n-dimensional tensor, see the `dora_message::metadata::tensor` module.
+    struct DoraTensorInfo {
+        shape: Vec<usize>,
+        /// Byte strides of each dimension, empty for contiguous row-major tensors.
+        strides: Vec<usize>,
+        dtype: String,
+    }
+
+    struct DoraTensor {
+        id: String,
+        /// Raw bytes of the tensor elements.
+        data: Vec<u8>,
+        info: DoraTensorInfo,
+    }
+
+    struct DoraImageInfo {
+        width: usize,
+        height: usize,
+        encoding: String,
+    }
+
+    struct DoraImage {
+        id: String,
+        /// Raw pixel data, or the encoded file for compressed encodings.
+        data: Vec<u8>,
+        info: DoraImageInfo,
+    }
+
     pub struct CombinedEvents {
         events: Box<MergedEvents>,
     }
@@ -51,6 +85,7 @@ mod ffi {
         type DoraEvent;
         type MergedEvents;
         type MergedDoraEvent;
+        type DoraMetadata;
 
         fn init_dora_node() -> Result<DoraNode>;
 
@@ -60,11 +95,32 @@ mod ffi {
         fn next_event(events: &mut Box<Events>) -> Box<DoraEvent>;
         fn event_type(event: &Box<DoraEvent>) -> DoraEventType;
         fn event_as_input(event: Box<DoraEvent>) -> Result<DoraInput>;
+        fn event_as_tensor(event: Box<DoraEvent>) -> Result<DoraTensor>;
+        fn event_as_image(event: Box<DoraEvent>) -> Result<DoraImage>;
+        fn event_metadata(event: &Box<DoraEvent>) -> Result<Box<DoraMetadata>>;
         fn send_output(
             output_sender: &mut Box<OutputSender>,
             id: String,
             data: &[u8],
         ) -> DoraResult;
+        fn send_output_with_metadata(
+            output_sender: &mut Box<OutputSender>,
+            id: String,
+            data: &[u8],
+            metadata: &DoraMetadata,
+        ) -> DoraResult;
+        fn send_tensor(
+            output_sender: &mut Box<OutputSender>,
+            id: String,
+            info: &DoraTensorInfo,
+            data: &[u8],
+        ) -> DoraResult;
+        fn send_image(
+            output_sender: &mut Box<OutputSender>,
+            id: String,
+            info: &DoraImageInfo,
+            data: &[u8],
+        ) -> DoraResult;
 
         fn next(self: &mut CombinedEvents) -> CombinedEvent;
 
@@ -78,11 +134,49 @@ mod ffi {
             schema_ptr: *mut u8,
         ) -> DoraResult;
 
+        unsafe fn send_arrow_output_with_metadata(
+            output_sender: &mut Box<OutputSender>,
+            id: String,
+            array_ptr: *mut u8,
+            schema_ptr: *mut u8,
+            metadata: &DoraMetadata,
+        ) -> DoraResult;
+
         unsafe fn event_as_arrow_input(
             event: Box<DoraEvent>,
             out_array: *mut u8,
             out_schema: *mut u8,
         ) -> DoraResult;
+
+        fn new_metadata() -> Box<DoraMetadata>;
+        fn keys(self: &DoraMetadata) -> Vec<String>;
+        fn contains(self: &DoraMetadata, key: &str) -> bool;
+        fn type_name(self: &DoraMetadata, key: &str) -> Result<String>;
+        fn remove(self: &mut DoraMetadata, key: &str) -> bool;
+
+        fn get_bool(self: &DoraMetadata, key: &str) -> Result<bool>;
+        fn get_int(self: &DoraMetadata, key: &str) -> Result<i64>;
+        fn get_float(self: &DoraMetadata, key: &str) -> Result<f64>;
+        fn get_str(self: &DoraMetadata, key: &str) -> Result<String>;
+        fn get_binary(self: &DoraMetadata, key: &str) -> Result<Vec<u8>>;
+        fn get_timestamp(self: &DoraMetadata, key: &str) -> Result<i64>;
+        fn get_map(self: &DoraMetadata, key: &str) -> Result<Box<DoraMetadata>>;
+        fn get_list_int(self: &DoraMetadata, key: &str) -> Result<Vec<i64>>;
+        fn get_list_float(self: &DoraMetadata, key: &str) -> Result<Vec<f64>>;
+        fn get_list_string(self: &DoraMetadata, key: &str) -> Result<Vec<String>>;
+        fn get_list_bool(self: &DoraMetadata, key: &str) -> Result<Vec<bool>>;
+
+        fn set_bool(self: &mut DoraMetadata, key: &str, value: bool);
+        fn set_int(self: &mut DoraMetadata, key: &str, value: i64);
+        fn set_float(self: &mut DoraMetadata, key: &str, value: f64);
+        fn set_str(self: &mut DoraMetadata, key: &str, value: &str);
+        fn set_binary(self: &mut DoraMetadata, key: &str, value: &[u8]);
+        fn set_timestamp(self: &mut DoraMetadata, key: &str, nanos: i64);
+        fn set_map(self: &mut DoraMetadata, key: &str, value: &DoraMetadata);
+        fn set_list_int(self: &mut DoraMetadata, key: &str, value: &[i64]);
+        fn set_list_float(self: &mut DoraMetadata, key: &str, value: &[f64]);
+        fn set_list_string(self: &mut DoraMetadata, key: &str, value: Vec<String>);
+        fn set_list_bool(self: &mut DoraMetadata, key: &str, value: Vec<bool>);
     }
 }
 
@@ -218,9 +312,18 @@ unsafe fn event_as_arrow_input(
 pub struct OutputSender(dora_node_api::DoraNode);
 
 fn send_output(sender: &mut Box<OutputSender>, id: String, data: &[u8]) -> ffi::DoraResult {
+    send_output_with_metadata(sender, id, data, &DoraMetadata::default())
+}
+
+fn send_output_with_metadata(
+    sender: &mut Box<OutputSender>,
+    id: String,
+    data: &[u8],
+    metadata: &DoraMetadata,
+) -> ffi::DoraResult {
     let result = sender
         .0
-        .send_output_raw(id.into(), Default::default(), data.len(), |out| {
+        .send_output_raw(id.into(), metadata.0.clone(), data.len(), |out| {
             out.copy_from_slice(data)
         });
     let error = match result {
@@ -230,6 +333,349 @@ fn send_output(sender: &mut Box<OutputSender>, id: String, data: &[u8]) -> ffi::
     ffi::DoraResult { error }
 }
 
+fn event_as_tensor(event: Box<DoraEvent>) -> eyre::Result<ffi::DoraTensor> {
+    let Some(Event::Input { id, metadata, data }) = event.0 else {
+        bail!("not an input event");
+    };
+    let info = TensorInfo::from_parameters(&metadata.parameters, Some(data.data_type()))?;
+    let dtype = tensor::dtype_name(&info.dtype)
+        .with_context(|| format!("unsupported tensor dtype {}", info.dtype))?
+        .to_owned();
+    Ok(ffi::DoraTensor {
+        id: id.into(),
+        data: primitive_bytes(&data)?,
+        info: ffi::DoraTensorInfo {
+            shape: info.shape,
+            strides: info.strides.unwrap_or_default(),
+            dtype,
+        },
+    })
+}
+
+fn event_as_image(event: Box<DoraEvent>) -> eyre::Result<ffi::DoraImage> {
+    let Some(Event::Input { id, metadata, data }) = event.0 else {
+        bail!("not an input event");
+    };
+    let info = ImageInfo::from_parameters(&metadata.parameters)?;
+    let expected_type = info.encoding.dtype().unwrap_or(DataType::UInt8);
+    if data.data_type() != &expected_type {
+        bail!(
+            "expected {expected_type} data for {} image, got {}",
+            info.encoding,
+            data.data_type()
+        );
+    }
+    Ok(ffi::DoraImage {
+        id: id.into(),
+        data: primitive_bytes(&data)?,
+        info: ffi::DoraImageInfo {
+            width: info.width,
+            height: info.height,
+            encoding: info.encoding.to_string(),
+        },
+    })
+}
+
+/// Copies the value buffer of the given primitive array.
+fn primitive_bytes(data: &dora_node_api::ArrowData) -> eyre::Result<Vec<u8>> {
+    let width = data
+        .data_type()
+        .primitive_width()
+        .with_context(|| format!("expected primitive array, got {}", data.data_type()))?;
+    if data.null_count() != 0 {
+        bail!("tensor data must not contain null values");
+    }
+    let data = data.to_data();
+    let start = data.offset() * width;
+    Ok(data.buffers()[0].as_slice()[start..start + data.len() * width].to_vec())
+}
+
+fn send_tensor(
+    sender: &mut Box<OutputSender>,
+    id: String,
+    info: &ffi::DoraTensorInfo,
+    data: &[u8],
+) -> ffi::DoraResult {
+    let result = (|| {
+        let dtype = tensor::dtype_from_name(&info.dtype)
+            .with_context(|| format!("unsupported tensor dtype `{}`", info.dtype))?;
+        let info = TensorInfo {
+            shape: info.shape.clone(),
+            strides: (!info.strides.is_empty()).then(|| info.strides.clone()),
+            dtype,
+        };
+        let width = info.dtype.primitive_width().unwrap_or(1);
+        match &info.strides {
+            None => {
+                let expected = info.num_elements() * width;
+                if data.len() != expected {
+                    bail!(
+                        "expected {expected} bytes for {} tensor of shape {:?}, got {}",
+                        info.dtype,
+                        info.shape,
+                        data.len()
+                    );
+                }
+            }
+            Some(strides) if strides.len() != info.shape.len() => {
+                bail!(
+                    "tensor strides {strides:?} don't match the number of dimensions of shape {:?}",
+                    info.shape
+                );
+            }
+            Some(strides) if info.num_elements() > 0 => {
+                let end: usize = info
+                    .shape
+                    .iter()
+                    .zip(strides)
+                    .map(|(dim, stride)| (dim - 1) * stride)
+                    .sum();
+                if end + width > data.len() {
+                    bail!(
+                        "tensor of shape {:?} and strides {strides:?} exceeds the {} data bytes",
+                        info.shape,
+                        data.len()
+                    );
+                }
+            }
+            Some(_) => {}
+        }
+        let mut parameters = MetadataParameters::default();
+        info.write_parameters(&mut parameters)?;
+        sender.send_primitive(id, parameters, info.dtype, data)
+    })();
+    let error = match result {
+        Ok(()) => String::new(),
+        Err(err) => format!("{err:?}"),
+    };
+    ffi::DoraResult { error }
+}
+
+fn send_image(
+    sender: &mut Box<OutputSender>,
+    id: String,
+    info: &ffi::DoraImageInfo,
+    data: &[u8],
+) -> ffi::DoraResult {
+    let result = (|| {
+        let info = ImageInfo {
+            width: info.width,
+            height: info.height,
+            encoding: info.encoding.parse()?,
+        };
+        if let Some(tensor) = info.tensor_info() {
+            let expected = tensor.num_elements() * tensor.dtype.primitive_width().unwrap_or(1);
+            if data.len() != expected {
+                bail!(
+                    "expected {expected} bytes for {}x{} {} image, got {}",
+                    info.width,
+                    info.height,
+                    info.encoding,
+                    data.len()
+                );
+            }
+        }
+        let mut parameters = MetadataParameters::default();
+        info.write_parameters(&mut parameters)?;
+        let dtype = info.encoding.dtype().unwrap_or(DataType::UInt8);
+        sender.send_primitive(id, parameters, dtype, data)
+    })();
+    let error = match result {
+        Ok(()) => String::new(),
+        Err(err) => format!("{err:?}"),
+    };
+    ffi::DoraResult { error }
+}
+
+impl OutputSender {
+    /// Sends the given raw bytes as Arrow primitive array of the given type.
+    fn send_primitive(
+        &mut self,
+        id: String,
+        parameters: MetadataParameters,
+        data_type: DataType,
+        data: &[u8],
+    ) -> eyre::Result<()> {
+        let width = data_type
+            .primitive_width()
+            .with_context(|| format!("{data_type} is not a primitive type"))?;
+        if data.len() % width != 0 {
+            bail!(
+                "data length {} is not a multiple of the {data_type} element size {width}",
+                data.len()
+            );
+        }
+        let array = ArrayData::try_new(
+            data_type,
+            data.len() / width,
+            None,
+            0,
+            vec![Buffer::from_slice_ref(data)],
+            vec![],
+        )?;
+        self.0.send_output(id.into(), parameters, make_array(array))
+    }
+}
+
+/// Metadata parameters of inputs and outputs.
+#[derive(Default)]
+pub struct DoraMetadata(MetadataParameters);
+
+fn new_metadata() -> Box<DoraMetadata> {
+    Box::default()
+}
+
+fn event_metadata(event: &DoraEvent) -> eyre::Result<Box<DoraMetadata>> {
+    let Some(Event::Input { metadata, .. }) = &event.0 else {
+        bail!("not an input event");
+    };
+    Ok(Box::new(DoraMetadata(metadata.parameters.clone())))
+}
+
+macro_rules! metadata_getters {
+    ($($name:ident($variant:ident) -> $t:ty),* $(,)?) => {
+        $(
+            fn $name(&self, key: &str) -> eyre::Result<$t> {
+                match self.get(key)? {
+                    Parameter::$variant(value) => Ok(value.clone()),
+                    other => bail!(
+                        "metadata parameter `{key}` is of type {}, not {}",
+                        parameter_type_name(other),
+                        stringify!($variant),
+                    ),
+                }
+            }
+        )*
+    };
+}
+
+impl DoraMetadata {
+    fn keys(&self) -> Vec<String> {
+        self.0.keys().cloned().collect()
+    }
+
+    fn contains(&self, key: &str) -> bool {
+        self.0.contains_key(key)
+    }
+
+    fn type_name(&self, key: &str) -> eyre::Result<String> {
+        self.get(key).map(|p| parameter_type_name(p).to_owned())
+    }
+
+    fn remove(&mut self, key: &str) -> bool {
+        self.0.remove(key).is_some()
+    }
+
+    fn get(&self, key: &str) -> eyre::Result<&Parameter> {
+        self.0
+            .get(key)
+            .with_context(|| format!("no metadata parameter `{key}`"))
+    }
+
+    metadata_getters!(
+        get_bool(Bool) -> bool,
+        get_int(Integer) -> i64,
+        get_float(Float) -> f64,
+        get_str(String) -> String,
+        get_binary(Binary) -> Vec<u8>,
+        get_list_int(ListInt) -> Vec<i64>,
+        get_list_float(ListFloat) -> Vec<f64>,
+        get_list_string(ListString) -> Vec<String>,
+        get_list_bool(ListBool) -> Vec<bool>,
+    );
+
+    /// Returns the timestamp as nanoseconds since the UNIX epoch.
+    fn get_timestamp(&self, key: &str) -> eyre::Result<i64> {
+        match self.get(key)? {
+            Parameter::Timestamp(timestamp) => timestamp
+                .timestamp_nanos_opt()
+                .with_context(|| format!("timestamp `{key}` is out of range")),
+            other => bail!(
+                "metadata parameter `{key}` is of type {}, not Timestamp",
+                parameter_type_name(other)
+            ),
+        }
+    }
+
+    fn get_map(&self, key: &str) -> eyre::Result<Box<DoraMetadata>> {
+        match self.get(key)? {
+            Parameter::Map(map) => Ok(Box::new(DoraMetadata(map.clone()))),
+            other => bail!(
+                "metadata parameter `{key}` is of type {}, not Map",
+                parameter_type_name(other)
+            ),
+        }
+    }
+
+    fn set(&mut self, key: &str, value: Parameter) {
+        self.0.insert(key.to_owned(), value);
+    }
+
+    fn set_bool(&mut self, key: &str, value: bool) {
+        self.set(key, Parameter::Bool(value));
+    }
+
+    fn set_int(&mut self, key: &str, value: i64) {
+        self.set(key, Parameter::Integer(value));
+    }
+
+    fn set_float(&mut self, key: &str, value: f64) {
+        self.set(key, Parameter::Float(value));
+    }
+
+    fn set_str(&mut self, key: &str, value: &str) {
+        self.set(key, Parameter::String(value.to_owned()));
+    }
+
+    fn set_binary(&mut self, key: &str, value: &[u8]) {
+        self.set(key, Parameter::Binary(value.to_vec()));
+    }
+
+    /// Sets a timestamp, given as nanoseconds since the UNIX epoch.
+    fn set_timestamp(&mut self, key: &str, nanos: i64) {
+        self.set(
+            key,
+            Parameter::Timestamp(DateTime::from_timestamp_nanos(nanos)),
+        );
+    }
+
+    fn set_map(&mut self, key: &str, value: &DoraMetadata) {
+        self.set(key, Parameter::Map(value.0.clone()));
+    }
+
+    fn set_list_int(&mut self, key: &str, value: &[i64]) {
+        self.set(key, Parameter::ListInt(value.to_vec()));
+    }
+
+    fn set_list_float(&mut self, key: &str, value: &[f64]) {
+        self.set(key, Parameter::ListFloat(value.to_vec()));
+    }
+
+    fn set_list_string(&mut self, key: &str, value: Vec<String>) {
+        self.set(key, Parameter::ListString(value));
+    }
+
+    fn set_list_bool(&mut self, key: &str, value: Vec<bool>) {
+        self.set(key, Parameter::ListBool(value));
+    }
+}
+
+fn parameter_type_name(parameter: &Parameter) -> &'static str {
+    match parameter {
+        Parameter::Bool(_) => "Bool",
+        Parameter::Integer(_) => "Integer",
+        Parameter::String(_) => "String",
+        Parameter::ListInt(_) => "ListInt",
+        Parameter::Float(_) => "Float",
+        Parameter::ListFloat(_) => "ListFloat",
+        Parameter::ListString(_) => "ListString",
+        Parameter::Binary(_) => "Binary",
+        Parameter::Timestamp(_) => "Timestamp",
+        Parameter::Map(_) => "Map",
+        Parameter::ListBool(_) => "ListBool",
+    }
+}
+
 pub struct MergedEvents {
     events: Option<Box<dyn Stream<Item = MergedEvent<ExternalEvent>> + Unpin>>,
     next_id: u32,
@@ -239,6 +685,18 @@ unsafe fn send_arrow_output(
     id: String,
     array_ptr: *mut u8,
     schema_ptr: *mut u8,
+) -> ffi::DoraResult {
+    unsafe {
+        send_arrow_output_with_metadata(sender, id, array_ptr, schema_ptr, &DoraMetadata::default())
+    }
+}
+
+unsafe fn send_arrow_output_with_metadata(
+    sender: &mut Box<OutputSender>,
+    id: String,
+    array_ptr: *mut u8,
+    schema_ptr: *mut u8,
+    metadata: &DoraMetadata,
 ) -> ffi::DoraResult {
     let array_ptr = array_ptr as *mut arrow::ffi::FFI_ArrowArray;
     let schema_ptr = schema_ptr as *mut arrow::ffi::FFI_ArrowSchema;
@@ -260,7 +718,7 @@ unsafe fn send_arrow_output(
             let arrow_array = arrow::array::make_array(array_data);
             let result = sender
                 .0
-                .send_output(id.into(), Default::default(), arrow_array);
+                .send_output(id.into(), metadata.0.clone(), arrow_array);
             match result {
                 Ok(()) => ffi::DoraResult {
                     error: String::new(),
@@ -154,6 +154,20 @@ ex:
 
 ```python
 node.send_output("string", b"string", {"open_telemetry_context": "7632e76"})
+```
+
+Metadata values can be `bool`, `int`, `float`, `str`, `bytes`, `datetime.datetime`,
+lists of `bool`, `int`, `float`, or `str`, and nested `dict`s of these values.
+Naive datetimes are interpreted as UTC."""
+
+    def stop_dataflow(self, reason: str=None) -> None:
+        """Requests the daemon to stop the whole dataflow.
+
+All nodes of the dataflow, including this one, receive a `STOP` event. The
+optional `reason` is reported in the result of the dataflow.
+
+```python
+node.stop_dataflow("finished processing all frames")
 ```"""
 
     def __iter__(self) -> typing.Any:
@@ -0,0 +1,161 @@
+"""Standard metadata conventions for n-dimensional tensors and images.
+
+Tensors and images are sent as flat pyarrow arrays, with their layout described by the
+following metadata keys, which are shared with the Rust and C++ APIs:
+
+- `shape`: size of each dimension, outermost first
+- `strides`: optional, byte offset between elements of each dimension
+- `dtype`: element type, e.g. `uint8` or `float32`
+- `width`, `height`: image size in pixels
+- `encoding`: image encoding, e.g. `rgb8`, `mono16`, or `jpeg`
+
+Example Use:
+```python
+from dora.tensor import arrow_to_image, image_to_arrow
+
+value, metadata = image_to_arrow(frame, "bgr8")
+node.send_output("image", value, metadata)
+
+event = node.next()
+frame, encoding = arrow_to_image(event["value"], event["metadata"])
+```
+"""
+
+import numpy as np
+import pyarrow as pa
+
+SHAPE = "shape"
+STRIDES = "strides"
+DTYPE = "dtype"
+WIDTH = "width"
+HEIGHT = "height"
+ENCODING = "encoding"
+
+DTYPES = (
+    "int8",
+    "int16",
+    "int32",
+    "int64",
+    "uint8",
+    "uint16",
+    "uint32",
+    "uint64",
+    "float16",
+    "float32",
+    "float64",
+)
+
+# number of channels and dtype of raw image encodings
+RAW_ENCODINGS = {
+    "rgb8": (3, "uint8"),
+    "bgr8": (3, "uint8"),
+    "rgba8": (4, "uint8"),
+    "bgra8": (4, "uint8"),
+    "mono8": (1, "uint8"),
+    "mono16": (1, "uint16"),
+}
+
+COMPRESSED_ENCODINGS = ("jpeg", "png")
+
+
+def numpy_to_arrow(array: np.ndarray) -> tuple[pa.Array, dict]:
+    """Convert a numpy array into a flat pyarrow array and its tensor metadata.
+
+    The array is flattened in row-major (C) order, which does not copy contiguous arrays.
+    """
+    if array.dtype.name not in DTYPES:
+        raise ValueError(f"unsupported tensor dtype `{array.dtype}`")
+    metadata = {
+        SHAPE: [int(dim) for dim in array.shape],
+        DTYPE: array.dtype.name,
+    }
+    return pa.array(np.ravel(array, order="C")), metadata
+
+
+def arrow_to_numpy(value: pa.Array, metadata: dict) -> np.ndarray:
+    """Convert a pyarrow array with tensor metadata into a numpy array.
+
+    The returned array shares the memory of `value` if possible.
+    """
+    array = value.to_numpy(zero_copy_only=False)
+    if DTYPE in metadata and array.dtype.name != metadata[DTYPE]:
+        raise ValueError(
+            f"tensor dtype `{metadata[DTYPE]}` does not match data type `{array.dtype}`"
+        )
+    shape = tuple(metadata[SHAPE])
+    strides = metadata.get(STRIDES)
+    if strides is None:
+        return array.reshape(shape)
+    if len(strides) != len(shape):
+        raise ValueError(
+            f"strides {strides} don't match the number of dimensions of shape {shape}"
+        )
+    if 0 not in shape:
+        end = sum((dim - 1) * stride for dim, stride in zip(shape, strides))
+        if end + array.itemsize > array.nbytes:
+            raise ValueError("tensor strides exceed the array size")
+    return np.lib.stride_tricks.as_strided(
+        array, shape=shape, strides=tuple(strides), writeable=False
+    )
+
+
+def image_to_arrow(
+    image, encoding: str, width=None, height=None
+) -> tuple[pa.Array, dict]:
+    """Convert an image into a pyarrow array and its image metadata.
+
+    Raw images are numpy arrays of shape `(height, width, channels)`, or `(height, width)`
+    for single-channel encodings. Compressed images (`jpeg`, `png`) are the encoded bytes,
+    which requires passing `width` and `height` explicitly.
+    """
+    encoding = encoding.lower()
+    if encoding in COMPRESSED_ENCODINGS:
+        if width is None or height is None:
+            raise ValueError("width and height are required for compressed images")
+        data = np.frombuffer(bytes(image), dtype=np.uint8)
+        metadata = {WIDTH: int(width), HEIGHT: int(height), ENCODING: encoding}
+        return pa.array(data), metadata
+    if encoding not in RAW_ENCODINGS:
+        raise ValueError(f"unknown image encoding `{encoding}`")
+
+    channels, dtype = RAW_ENCODINGS[encoding]
+    image = np.asarray(image)
+    expected_dims = 2 if channels == 1 else 3
+    if image.ndim == 3 and channels == 1 and image.shape[2] == 1:
+        image = image[:, :, 0]
+    if image.ndim != expected_dims or (channels > 1 and image.shape[2] != channels):
+        raise ValueError(
+            f"image of shape {image.shape} does not match encoding `{encoding}`"
+        )
+    if image.dtype.name != dtype:
+        raise ValueError(
+            f"image dtype `{image.dtype}` does not match encoding `{encoding}`"
+        )
+
+    value, metadata = numpy_to_arrow(image)
+    metadata.update(
+        {WIDTH: int(image.shape[1]), HEIGHT: int(image.shape[0]), ENCODING: encoding}
+    )
+    return value, metadata
+
+
+def arrow_to_image(value: pa.Array, metadata: dict) -> tuple[np.ndarray, str]:
+    """Convert a pyarrow array with image metadata into a numpy array and its encoding.
+
+    Raw images are returned with shape `(height, width, channels)`, or `(height, width)` for
+    single-channel encodings. Compressed images are returned as flat `uint8` array of the
+    encoded bytes, e.g. for decoding through `cv2.imdecode`.
+    """
+    encoding = metadata[ENCODING].lower()
+    if encoding in COMPRESSED_ENCODINGS:
+        return value.to_numpy(zero_copy_only=False), encoding
+    if encoding not in RAW_ENCODINGS:
+        raise ValueError(f"unknown image encoding `{encoding}`")
+
+    channels, dtype = RAW_ENCODINGS[encoding]
+    width, height = metadata[WIDTH], metadata[HEIGHT]
+    shape = [height, width] if channels == 1 else [height, width, channels]
+    tensor = {SHAPE: shape, DTYPE: dtype}
+    if STRIDES in metadata:
+        tensor[STRIDES] = metadata[STRIDES]
+    return arrow_to_numpy(value, tensor), encoding
@@ -190,6 +190,10 @@ impl Node {
     /// node.send_output("string", b"string", {"open_telemetry_context": "7632e76"})
     /// ```
     ///
+    /// Metadata values can be `bool`, `int`, `float`, `str`, `bytes`, `datetime.datetime`,
+    /// lists of `bool`, `int`, `float`, or `str`, and nested `dict`s of these values.
+    /// Naive datetimes are interpreted as UTC.
+    ///
     /// :type output_id: str
     /// :type data: pyarrow.Array
     /// :type metadata: dict, optional
@@ -223,6 +227,22 @@ impl Node {
         Ok(())
     }
 
+    /// Requests the daemon to stop the whole dataflow.
+    ///
+    /// All nodes of the dataflow, including this one, receive a `STOP` event. The
+    /// optional `reason` is reported in the result of the dataflow.
+    ///
+    /// ```python
+    /// node.stop_dataflow("finished processing all frames")
+    /// ```
+    ///
+    /// :type reason: str, optional
+    /// :rtype: None
+    #[pyo3(signature = (reason=None))]
+    pub fn stop_dataflow(&mut self, reason: Option<String>) -> eyre::Result<()> {
+        self.node.get_mut().stop_dataflow(reason)
+    }
+
     /// Returns the full dataflow descriptor that this node is part of.
     ///
     /// This method returns the parsed dataflow YAML file.
@@ -4,6 +4,7 @@ use std::{
 };
 
 use arrow::pyarrow::ToPyArrow;
+use chrono::{DateTime, FixedOffset, NaiveDateTime};
 use dora_node_api::{
     DoraNode, Event, EventStream, Metadata, MetadataParameters, Parameter, StopCause,
     merged::{MergeExternalSend, MergedEvent},
@@ -13,7 +14,9 @@ use futures::{Stream, StreamExt};
 use futures_concurrency::stream::Merge as _;
 use pyo3::{
     prelude::*,
-    types::{IntoPyDict, PyBool, PyDict, PyFloat, PyInt, PyList, PyString, PyTuple},
+    types::{
+        IntoPyDict, PyBool, PyByteArray, PyBytes, PyDict, PyFloat, PyInt, PyList, PyString, PyTuple,
+    },
 };
 
 /// Dora Event
@@ -202,79 +205,102 @@ impl PyEvent {
 }
 
 pub fn pydict_to_metadata(dict: Option<Bound<'_, PyDict>>) -> Result<MetadataParameters> {
+    match dict {
+        Some(dict) => pydict_to_parameters(&dict),
+        None => Ok(BTreeMap::default()),
+    }
+}
+
+fn pydict_to_parameters(dict: &Bound<'_, PyDict>) -> Result<MetadataParameters> {
     let mut parameters = BTreeMap::default();
-    if let Some(pymetadata) = dict {
-        for (key, value) in pymetadata.iter() {
-            let key = key.extract::<String>().context("Parsing metadata keys")?;
-            if value.is_exact_instance_of::<PyBool>() {
-                parameters.insert(key, Parameter::Bool(value.extract()?))
-            } else if value.is_instance_of::<PyInt>() {
-                parameters.insert(key, Parameter::Integer(value.extract::<i64>()?))
-            } else if value.is_instance_of::<PyFloat>() {
-                parameters.insert(key, Parameter::Float(value.extract::<f64>()?))
-            } else if value.is_instance_of::<PyString>() {
-                parameters.insert(key, Parameter::String(value.extract()?))
-            } else if (value.is_instance_of::<PyTuple>() || value.is_instance_of::<PyList>())
-                && value.len()? > 0
-                && value.get_item(0)?.is_exact_instance_of::<PyInt>()
-            {
-                let list: Vec<i64> = value.extract()?;
-                parameters.insert(key, Parameter::ListInt(list))
-            } else if (value.is_instance_of::<PyTuple>() || value.is_instance_of::<PyList>())
-                && value.len()? > 0
-                && value.get_item(0)?.is_exact_instance_of::<PyFloat>()
-            {
-                let list: Vec<f64> = value.extract()?;
-                parameters.insert(key, Parameter::ListFloat(list))
-            } else if value.is_instance_of::<PyList>()
-                && value.len()? > 0
-                && value.get_item(0)?.is_exact_instance_of::<PyString>()
-            {
-                let list: Vec<String> = value.extract()?;
-                parameters.insert(key, Parameter::ListString(list))
-            } else {
-                println!("could not convert type {value}");
-                parameters.insert(key, Parameter::String(value.str()?.to_string()))
-            };
-        }
+    for (key, value) in dict.iter() {
+        let key = key.extract::<String>().context("Parsing metadata keys")?;
+        let parameter = pyobject_to_parameter(&value)
+            .with_context(|| format!("failed to convert metadata value of `{key}`"))?;
+        parameters.insert(key, parameter);
     }
     Ok(parameters)
 }
 
+fn pyobject_to_parameter(value: &Bound<'_, PyAny>) -> Result<Parameter> {
+    let is_list = value.is_instance_of::<PyTuple>() || value.is_instance_of::<PyList>();
+    let parameter = if value.is_exact_instance_of::<PyBool>() {
+        Parameter::Bool(value.extract()?)
+    } else if value.is_instance_of::<PyInt>() {
+        Parameter::Integer(value.extract::<i64>()?)
+    } else if value.is_instance_of::<PyFloat>() {
+        Parameter::Float(value.extract::<f64>()?)
+    } else if value.is_instance_of::<PyString>() {
+        Parameter::String(value.extract()?)
+    } else if let Ok(bytes) = value.downcast::<PyBytes>() {
+        Parameter::Binary(bytes.as_bytes().to_vec())
+    } else if let Ok(bytes) = value.downcast::<PyByteArray>() {
+        Parameter::Binary(bytes.to_vec())
+    } else if let Ok(dict) = value.downcast::<PyDict>() {
+        Parameter::Map(pydict_to_parameters(dict)?)
+    } else if is_list && value.len()? > 0 && value.get_item(0)?.is_exact_instance_of::<PyBool>() {
+        Parameter::ListBool(value.extract()?)
+    } else if is_list && value.len()? > 0 && value.get_item(0)?.is_exact_instance_of::<PyInt>() {
+        Parameter::ListInt(value.extract()?)
+    } else if is_list && value.len()? > 0 && value.get_item(0)?.is_exact_instance_of::<PyFloat>() {
+        Parameter::ListFloat(value.extract()?)
+    } else if value.is_instance_of::<PyList>()
+        && value.len()? > 0
+        && value.get_item(0)?.is_exact_instance_of::<PyString>()
+    {
+        Parameter::ListString(value.extract()?)
+    } else if let Ok(timestamp) = value.extract::<DateTime<FixedOffset>>() {
+        Parameter::Timestamp(timestamp.to_utc())
+    } else if let Ok(timestamp) = value.extract::<NaiveDateTime>() {
+        // naive datetimes are interpreted as UTC
+        Parameter::Timestamp(timestamp.and_utc())
+    } else {
+        println!("could not convert type {value}");
+        Parameter::String(value.str()?.to_string())
+    };
+    Ok(parameter)
+}
+
 pub fn metadata_to_pydict<'a>(
     metadata: &'a Metadata,
     py: Python<'a>,
+) -> Result<pyo3::Bound<'a, PyDict>> {
+    parameters_to_pydict(&metadata.parameters, py)
+}
+
+fn parameters_to_pydict<'a>(
+    parameters: &MetadataParameters,
+    py: Python<'a>,
 ) -> Result<pyo3::Bound<'a, PyDict>> {
     let dict = PyDict::new(py);
-    for (k, v) in metadata.parameters.iter() {
-        match v {
-            Parameter::Bool(bool) => dict
-                .set_item(k, bool)
-                .context("Could not insert metadata into python dictionary")?,
-            Parameter::Integer(int) => dict
-                .set_item(k, int)
-                .context("Could not insert metadata into python dictionary")?,
-            Parameter::Float(float) => dict
-                .set_item(k, float)
-                .context("Could not insert metadata into python dictionary")?,
-            Parameter::String(s) => dict
-                .set_item(k, s)
-                .context("Could not insert metadata into python dictionary")?,
-            Parameter::ListInt(l) => dict
-                .set_item(k, l)
-                .context("Could not insert metadata into python dictionary")?,
-            Parameter::ListFloat(l) => dict
-                .set_item(k, l)
-                .context("Could not insert metadata into python dictionary")?,
-            Parameter::ListString(l) => dict
-                .set_item(k, l)
-                .context("Could not insert metadata into python dictionary")?,
-        }
+    for (k, v) in parameters.iter() {
+        dict.set_item(k, parameter_to_pyobject(v, py)?)
+            .context("Could not insert metadata into python dictionary")?;
     }
 
     Ok(dict)
 }
 
+fn parameter_to_pyobject<'a>(parameter: &Parameter, py: Python<'a>) -> Result<Bound<'a, PyAny>> {
+    let object = match parameter {
+        Parameter::Bool(bool) => bool.into_pyobject(py)?.to_owned().into_any(),
+        Parameter::Integer(int) => int.into_pyobject(py)?.into_any(),
+        Parameter::Float(float) => float.into_pyobject(py)?.into_any(),
+        Parameter::String(s) => s.into_pyobject(py)?.into_any(),
+        Parameter::ListInt(l) => l.into_pyobject(py)?,
+        Parameter::ListFloat(l) => l.into_pyobject(py)?,
+        Parameter::ListString(l) => l.into_pyobject(py)?,
+        Parameter::ListBool(l) => l.into_pyobject(py)?,
+        Parameter::Binary(bytes) => PyBytes::new(py, bytes).into_any(),
+        Parameter::Timestamp(timestamp) => timestamp
+            .into_pyobject(py)
+            .context("Could not convert timestamp into python datetime")?
+            .into_any(),
+        Parameter::Map(map) => parameters_to_pydict(map, py)?.into_any(),
+    };
+    Ok(object)
+}
+
 #[cfg(test)]
 mod tests {
     use std::{ptr::NonNull, sync::Arc};
@@ -289,10 +315,16 @@ mod tests {
     };
 
     use arrow_schema::{DataType, Field};
+    use chrono::DateTime;
+    use dora_node_api::Parameter;
     use dora_node_api::arrow_utils::{
         buffer_into_arrow_array, copy_array_into_sample, required_data_size,
     };
     use eyre::{Context, Result};
+    use pyo3::Python;
+    use std::collections::BTreeMap;
+
+    use super::{parameters_to_pydict, pydict_to_parameters};
 
     fn assert_roundtrip(arrow_array: &ArrayData) -> Result<()> {
         let size = required_data_size(arrow_array);
@@ -370,4 +402,42 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn metadata_roundtrip() -> Result<()> {
+        let timestamp = DateTime::from_timestamp_micros(1_700_000_000_123_456).unwrap();
+        let nested = BTreeMap::from([
+            ("frame_id".to_owned(), Parameter::from("camera_left")),
+            (
+                "distortion".to_owned(),
+                Parameter::from(vec![0.1, -0.2, 0.0]),
+            ),
+        ]);
+        let parameters = BTreeMap::from([
+            ("bool".to_owned(), Parameter::Bool(true)),
+            ("int".to_owned(), Parameter::Integer(-3)),
+            ("float".to_owned(), Parameter::Float(1.5)),
+            ("string".to_owned(), Parameter::from("text")),
+            ("list_int".to_owned(), Parameter::ListInt(vec![1, 2])),
+            ("list_float".to_owned(), Parameter::ListFloat(vec![0.5])),
+            (
+                "list_string".to_owned(),
+                Parameter::from(vec!["a".to_owned()]),
+            ),
+            (
+                "list_bool".to_owned(),
+                Parameter::ListBool(vec![true, false]),
+            ),
+            ("binary".to_owned(), Parameter::Binary(vec![0, 255, 7])),
+            ("timestamp".to_owned(), Parameter::Timestamp(timestamp)),
+            ("map".to_owned(), Parameter::Map(nested)),
+        ]);
+
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let dict = parameters_to_pydict(&parameters, py)?;
+            assert_eq!(pydict_to_parameters(&dict)?, parameters);
+            Ok(())
+        })
+    }
 }
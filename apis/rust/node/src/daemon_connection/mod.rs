@@ -10,6 +10,7 @@ use shared_memory_server::{ShmemClient, ShmemConf};
 use std::os::unix::net::UnixStream;
 use std::{
     net::{SocketAddr, TcpStream},
+    sync::Arc,
     time::Duration,
 };
 
@@ -22,6 +23,8 @@ pub enum DaemonChannel {
     Tcp(TcpStream),
     #[cfg(unix)]
     UnixDomain(UnixStream),
+    /// Connection to an in-process mock daemon, see [`crate::testing`].
+    InMemory(Arc<crate::testing::MockDaemonState>),
 }
 
 impl DaemonChannel {
@@ -81,6 +84,7 @@ impl DaemonChannel {
             DaemonChannel::Tcp(stream) => tcp::request(stream, request),
             #[cfg(unix)]
             DaemonChannel::UnixDomain(stream) => unix_domain::request(stream, request),
+            DaemonChannel::InMemory(daemon) => daemon.handle_request(request),
         }
     }
 }
@@ -1,15 +1,16 @@
 use std::{
-    collections::{BTreeMap, HashMap, VecDeque},
+    collections::{BTreeMap, BTreeSet, HashMap, VecDeque},
     pin::pin,
-    sync::Arc,
-    time::Duration,
+    sync::{Arc, Mutex},
+    thread::JoinHandle,
+    time::{Duration, Instant},
 };
 
 use dora_message::{
     DataflowId,
     daemon_to_node::{DaemonCommunication, DaemonReply, DataMessage, NodeEvent},
     id::DataId,
-    node_to_daemon::{DaemonRequest, Timestamped},
+    node_to_daemon::{DaemonRequest, InputStats, Timestamped},
 };
 pub use event::{Event, StopCause};
 use futures::{
@@ -25,17 +26,26 @@ use crate::{
     event_stream::data_conversion::{MappedInputData, RawData, SharedMemoryData},
 };
 use dora_core::{
-    config::{Input, NodeId},
+    config::{Input, NodeId, QueuePolicy},
     uhlc,
 };
 use eyre::{Context, eyre};
 
 pub use scheduler::Scheduler as EventScheduler;
 
-mod data_conversion;
+/// Interval in which statistics about the inputs are reported to the daemon.
+const INPUT_STATS_REPORT_INTERVAL: Duration = Duration::from_secs(1);
+/// Maximum interval in which consumed inputs are reported to the daemon.
+///
+/// Consumed inputs are reported in batches to avoid a round-trip to the daemon per input.
+/// Pending reports are always sent before waiting for new events.
+const CONSUMED_INPUTS_REPORT_INTERVAL: Duration = Duration::from_millis(10);
+
+pub(crate) mod data_conversion;
 mod event;
 pub mod merged;
 mod scheduler;
+pub mod synchronized;
 mod thread;
 
 /// Asynchronous iterator over the incoming [`Event`]s destined for this node.
@@ -61,9 +71,64 @@ pub struct EventStream {
     node_id: NodeId,
     receiver: flume::r#async::RecvStream<'static, EventItem>,
     _thread_handle: EventStreamThreadHandle,
-    close_channel: DaemonChannel,
+    close_channel: Arc<Mutex<DaemonChannel>>,
     clock: Arc<uhlc::HLC>,
     scheduler: Scheduler,
+    /// Inputs that need to be reported to the daemon once they are consumed.
+    ///
+    /// Contains all inputs with a [`QueuePolicy::Block`] queue policy. For replicas that are
+    /// load-balanced by the daemon, all inputs are reported.
+    reported_inputs: BTreeSet<DataId>,
+    /// Consumed inputs that were not reported to the daemon yet.
+    consumed_inputs: Vec<DataId>,
+    last_consumed_inputs_report: Instant,
+    /// Reports input statistics to the daemon, if enabled through the node config.
+    input_stats_reporter: Option<InputStatsReporter>,
+    /// Input statistics collected since the last report to the daemon.
+    input_stats: BTreeMap<DataId, InputStats>,
+    last_input_stats_report: Instant,
+    /// Whether the last input statistics report contained queued inputs.
+    ///
+    /// In this case, the statistics are reported again before waiting for new events.
+    /// Otherwise, the reported queue depth of idle nodes would go stale.
+    queued_inputs_reported: bool,
+}
+
+/// Sends input statistics to the daemon on a background thread.
+///
+/// This way, the node does not wait for the daemon when reporting the statistics.
+struct InputStatsReporter {
+    sender: flume::Sender<BTreeMap<DataId, InputStats>>,
+    thread: JoinHandle<()>,
+}
+
+impl InputStatsReporter {
+    fn spawn(channel: Arc<Mutex<DaemonChannel>>, clock: Arc<uhlc::HLC>) -> Self {
+        let (sender, receiver) = flume::unbounded();
+        let thread = std::thread::spawn(move || {
+            for inputs in receiver {
+                let request = Timestamped {
+                    inner: DaemonRequest::ReportInputStats { inputs },
+                    timestamp: clock.new_timestamp(),
+                };
+                let result = match channel
+                    .lock()
+                    .unwrap()
+                    .request(&request)
+                    .map_err(|e| eyre!(e))
+                    .wrap_err("failed to report input statistics to dora-daemon")
+                {
+                    Ok(DaemonReply::Empty) => Ok(()),
+                    Ok(other) => Err(eyre!("unexpected ReportInputStats reply: {other:?}")),
+                    Err(err) => Err(err),
+                };
+                if let Err(err) = result {
+                    tracing::warn!("{err:?}");
+                }
+            }
+        });
+        Self { sender, thread }
+    }
 }
 
 impl EventStream {
@@ -73,6 +138,8 @@ impl EventStream {
         node_id: &NodeId,
         daemon_communication: &DaemonCommunication,
         input_config: BTreeMap<DataId, Input>,
+        report_consumed_inputs: bool,
+        report_input_stats: bool,
         clock: Arc<uhlc::HLC>,
     ) -> eyre::Result<Self> {
         let channel = match daemon_communication {
@@ -111,6 +178,31 @@ impl EventStream {
             }
         };
 
+        let mut event_stream = Self::init_on_channels(
+            dataflow_id,
+            node_id,
+            channel,
+            close_channel,
+            input_config,
+            report_consumed_inputs,
+            clock,
+        )?;
+        if report_input_stats {
+            event_stream.report_input_stats_to_daemon();
+        }
+        Ok(event_stream)
+    }
+
+    /// Initializes the event stream on already connected daemon channels.
+    pub(crate) fn init_on_channels(
+        dataflow_id: DataflowId,
+        node_id: &NodeId,
+        channel: DaemonChannel,
+        close_channel: DaemonChannel,
+        input_config: BTreeMap<DataId, Input>,
+        report_consumed_inputs: bool,
+        clock: Arc<uhlc::HLC>,
+    ) -> eyre::Result<Self> {
         let mut queue_size_limit: HashMap<DataId, (usize, VecDeque<EventItem>)> = input_config
             .iter()
             .map(|(input, config)| {
@@ -126,16 +218,40 @@ impl EventStream {
             (1_000, VecDeque::new()),
         );
 
-        let scheduler = Scheduler::new(queue_size_limit);
+        let queue_policies: HashMap<DataId, QueuePolicy> = input_config
+            .iter()
+            .filter_map(|(input, config)| Some((input.clone(), config.queue_policy?)))
+            .collect();
+        let reported_inputs = if report_consumed_inputs {
+            input_config.keys().cloned().collect()
+        } else {
+            queue_policies
+                .iter()
+                .filter(|(_, policy)| **policy == QueuePolicy::Block)
+                .map(|(input, _)| input.clone())
+                .collect()
+        };
 
-        Self::init_on_channel(
+        let scheduler = Scheduler::new(queue_size_limit, queue_policies);
+
+        let mut event_stream = Self::init_on_channel(
             dataflow_id,
             node_id,
             channel,
             close_channel,
             clock,
             scheduler,
-        )
+        )?;
+        event_stream.reported_inputs = reported_inputs;
+        Ok(event_stream)
+    }
+
+    /// Starts reporting input statistics to the daemon, which uses them for metrics.
+    pub(crate) fn report_input_stats_to_daemon(&mut self) {
+        self.input_stats_reporter = Some(InputStatsReporter::spawn(
+            self.close_channel.clone(),
+            self.clock.clone(),
+        ));
     }
 
     pub(crate) fn init_on_channel(
@@ -173,9 +289,16 @@ impl EventStream {
             node_id: node_id.clone(),
             receiver: rx.into_stream(),
             _thread_handle: thread_handle,
-            close_channel,
+            close_channel: Arc::new(Mutex::new(close_channel)),
             clock,
             scheduler,
+            reported_inputs: BTreeSet::new(),
+            consumed_inputs: Vec::new(),
+            last_consumed_inputs_report: Instant::now(),
+            input_stats_reporter: None,
+            input_stats: BTreeMap::new(),
+            last_input_stats_report: Instant::now(),
+            queued_inputs_reported: false,
         })
     }
 
@@ -237,6 +360,8 @@ impl EventStream {
     pub async fn recv_async(&mut self) -> Option<Event> {
         loop {
             if self.scheduler.is_empty() {
+                self.report_consumed_inputs();
+                self.report_input_stats(true);
                 if let Some(event) = self.receiver.next().await {
                     self.scheduler.add_event(event);
                 } else {
@@ -251,7 +376,7 @@ impl EventStream {
             }
         }
         let event = self.scheduler.next();
-        event.map(Self::convert_event_item)
+        event.map(|item| self.convert_consumed_event_item(item))
     }
 
     /// Receives the next incoming [`Event`] asynchronously with a timeout.
@@ -278,6 +403,103 @@ impl EventStream {
         }
     }
 
+    /// Converts an item that is handed out to the user.
+    ///
+    /// Inputs with a blocking queue policy are reported to the daemon, which unblocks their
+    /// senders. Load-balanced replicas report all inputs. The reports are batched, see
+    /// [`CONSUMED_INPUTS_REPORT_INTERVAL`].
+    fn convert_consumed_event_item(&mut self, item: EventItem) -> Event {
+        if let EventItem::NodeEvent {
+            event: NodeEvent::Input { id, metadata, .. },
+            ..
+        } = &item
+        {
+            if self.reported_inputs.contains(id) {
+                self.consumed_inputs.push(id.clone());
+                if self.last_consumed_inputs_report.elapsed() >= CONSUMED_INPUTS_REPORT_INTERVAL {
+                    self.report_consumed_inputs();
+                }
+            }
+            if self.input_stats_reporter.is_some() {
+                let latency = self
+                    .clock
+                    .new_timestamp()
+                    .get_time()
+                    .to_duration()
+                    .saturating_sub(metadata.timestamp().get_time().to_duration());
+                self.input_stats
+                    .entry(id.clone())
+                    .or_default()
+                    .latency
+                    .record(latency);
+            }
+        }
+        self.report_input_stats(false);
+        Self::convert_event_item(item)
+    }
+
+    /// Reports the input statistics to the daemon once per [`INPUT_STATS_REPORT_INTERVAL`].
+    ///
+    /// Before `waiting` for new events, the statistics are also reported if the last report
+    /// contained queued inputs, so that the queue depth of idle nodes is updated.
+    fn report_input_stats(&mut self, waiting: bool) {
+        if self.input_stats_reporter.is_none() {
+            return;
+        }
+        if (waiting && self.queued_inputs_reported)
+            || self.last_input_stats_report.elapsed() >= INPUT_STATS_REPORT_INTERVAL
+        {
+            self.send_input_stats();
+        }
+    }
+
+    fn send_input_stats(&mut self) {
+        let Some(reporter) = &self.input_stats_reporter else {
+            return;
+        };
+        self.last_input_stats_report = Instant::now();
+        for (id, queue_depth) in self.scheduler.queue_depths() {
+            self.input_stats.entry(id.clone()).or_default().queue_depth = queue_depth;
+        }
+        for (id, dropped) in self.scheduler.take_dropped() {
+            self.input_stats.entry(id).or_default().dropped = dropped;
+        }
+        let inputs = std::mem::take(&mut self.input_stats);
+        self.queued_inputs_reported = inputs.values().any(|stats| stats.queue_depth > 0);
+        if reporter.sender.send(inputs).is_err() {
+            tracing::warn!("failed to report input statistics: reporter thread exited");
+        }
+    }
+
+    /// Reports all pending consumed inputs to the daemon.
+    fn report_consumed_inputs(&mut self) {
+        self.last_consumed_inputs_report = Instant::now();
+        if self.consumed_inputs.is_empty() {
+            return;
+        }
+        let request = Timestamped {
+            inner: DaemonRequest::ReportInputsConsumed {
+                inputs: std::mem::take(&mut self.consumed_inputs),
+            },
+            timestamp: self.clock.new_timestamp(),
+        };
+        let result = match self
+            .close_channel
+            .lock()
+            .unwrap()
+            .request(&request)
+            .map_err(|e| eyre!(e))
+            .wrap_err("failed to report consumed inputs to dora-daemon")
+        {
+            Ok(DaemonReply::Empty) => Ok(()),
+            Ok(other) => Err(eyre!("unexpected ReportInputsConsumed reply: {other:?}")),
+            Err(err) => Err(err),
+        };
+        if let Err(err) = result {
+            tracing::warn!("{err:?}");
+        }
+    }
+
     fn convert_event_item(item: EventItem) -> Event {
         match item {
             EventItem::NodeEvent { event, ack_channel } => match event {
@@ -336,21 +558,40 @@ impl Stream for EventStream {
         mut self: std::pin::Pin<&mut Self>,
         cx: &mut std::task::Context<'_>,
     ) -> std::task::Poll<Option<Self::Item>> {
-        self.receiver
-            .poll_next_unpin(cx)
-            .map(|item| item.map(Self::convert_event_item))
+        match self.receiver.poll_next_unpin(cx) {
+            std::task::Poll::Ready(item) => {
+                std::task::Poll::Ready(item.map(|item| self.convert_consumed_event_item(item)))
+            }
+            std::task::Poll::Pending => {
+                // report consumed inputs before waiting for new events
+                self.report_consumed_inputs();
+                self.report_input_stats(true);
+                std::task::Poll::Pending
+            }
+        }
     }
 }
 
 impl Drop for EventStream {
     #[tracing::instrument(skip(self), fields(%self.node_id))]
     fn drop(&mut self) {
+        // send the pending input statistics before closing the event stream
+        self.send_input_stats();
+        if let Some(reporter) = self.input_stats_reporter.take() {
+            drop(reporter.sender);
+            if reporter.thread.join().is_err() {
+                tracing::warn!("input statistics reporter thread panicked");
+            }
+        }
+
         let request = Timestamped {
             inner: DaemonRequest::EventStreamDropped,
             timestamp: self.clock.new_timestamp(),
         };
         let result = self
             .close_channel
+            .lock()
+            .unwrap()
             .request(&request)
             .map_err(|e| eyre!(e))
             .wrap_err("failed to signal event stream closure to dora-daemon")
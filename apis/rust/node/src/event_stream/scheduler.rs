@@ -1,6 +1,6 @@
 use std::collections::{HashMap, VecDeque};
 
-use dora_message::{daemon_to_node::NodeEvent, id::DataId};
+use dora_message::{config::QueuePolicy, daemon_to_node::NodeEvent, id::DataId};
 
 use super::thread::EventItem;
 pub(crate) const NON_INPUT_EVENT: &str = "dora/non_input_event";
@@ -75,17 +75,26 @@ pub(crate) const NON_INPUT_EVENT: &str = "dora/non_input_event";
 ///
 /// (If a perfect matching bounding box is required, we recommend to forward the input image as
 /// part of the bounding box output. This way, the receiving node only needs to subscribe to one
-/// input so no mismatches can happen.)
+/// input so no mismatches can happen.
+/// Alternatively, use [`EventStream::synchronize`][super::EventStream::synchronize] to group the
+/// inputs by their timestamps.)
 #[derive(Debug)]
 pub struct Scheduler {
     /// Tracks the last-used event ID
     last_used: VecDeque<DataId>,
     /// Tracks events per ID
     event_queues: HashMap<DataId, (usize, VecDeque<EventItem>)>,
+    /// What to do when a queue is full (defaults to [`QueuePolicy::DropOldest`])
+    queue_policies: HashMap<DataId, QueuePolicy>,
+    /// Number of dropped events per ID since the last call to `take_dropped`
+    dropped: HashMap<DataId, u64>,
 }
 
 impl Scheduler {
-    pub(crate) fn new(event_queues: HashMap<DataId, (usize, VecDeque<EventItem>)>) -> Self {
+    pub(crate) fn new(
+        event_queues: HashMap<DataId, (usize, VecDeque<EventItem>)>,
+        queue_policies: HashMap<DataId, QueuePolicy>,
+    ) -> Self {
         let topic = VecDeque::from_iter(
             event_queues
                 .keys()
@@ -95,6 +104,8 @@ impl Scheduler {
         Self {
             last_used: topic,
             event_queues,
+            queue_policies,
+            dropped: HashMap::new(),
         }
     }
 
@@ -114,10 +125,30 @@ impl Scheduler {
 
         // Enforce queue size limit
         if let Some((size, queue)) = self.event_queues.get_mut(event_id) {
-            // Remove the oldest event if at limit
             if &queue.len() >= size {
-                tracing::debug!("Discarding event for input `{event_id}` due to queue size limit");
-                queue.pop_front();
+                match self
+                    .queue_policies
+                    .get(event_id)
+                    .copied()
+                    .unwrap_or_default()
+                {
+                    QueuePolicy::DropOldest => {
+                        tracing::debug!(
+                            "Discarding oldest event for input `{event_id}` due to queue size limit"
+                        );
+                        queue.pop_front();
+                        *self.dropped.entry(event_id.clone()).or_default() += 1;
+                    }
+                    QueuePolicy::DropNewest => {
+                        tracing::debug!(
+                            "Discarding new event for input `{event_id}` due to queue size limit"
+                        );
+                        *self.dropped.entry(event_id.clone()).or_default() += 1;
+                        return;
+                    }
+                    // the daemon blocks the sender instead
+                    QueuePolicy::Block => {}
+                }
             }
             queue.push_back(event);
         } else {
@@ -151,9 +182,96 @@ impl Scheduler {
         None
     }
 
+    /// Returns the number of queued events per input ID.
+    pub(crate) fn queue_depths(&self) -> impl Iterator<Item = (&DataId, usize)> {
+        self.event_queues
+            .iter()
+            .filter(|(id, _)| id.as_str() != NON_INPUT_EVENT)
+            .map(|(id, (_size, queue))| (id, queue.len()))
+    }
+
+    /// Returns and resets the number of dropped events per input ID.
+    pub(crate) fn take_dropped(&mut self) -> HashMap<DataId, u64> {
+        std::mem::take(&mut self.dropped)
+    }
+
     pub(crate) fn is_empty(&self) -> bool {
         self.event_queues
             .iter()
             .all(|(_id, (_size, queue))| queue.is_empty())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dora_core::{metadata::ArrowTypeInfoExt, uhlc};
+    use dora_message::metadata::{ArrowTypeInfo, Metadata, Parameter};
+
+    fn input(id: &str, seq: i64) -> EventItem {
+        let parameters = [("seq".to_owned(), Parameter::Integer(seq))].into();
+        EventItem::NodeEvent {
+            event: NodeEvent::Input {
+                id: DataId::from(id.to_owned()),
+                metadata: Metadata::from_parameters(
+                    uhlc::HLC::default().new_timestamp(),
+                    ArrowTypeInfo::empty(),
+                    parameters,
+                ),
+                data: None,
+            },
+            ack_channel: flume::bounded(1).0,
+        }
+    }
+
+    fn scheduler(policy: QueuePolicy) -> Scheduler {
+        let id = DataId::from("input".to_owned());
+        Scheduler::new(
+            [
+                (id.clone(), (2, VecDeque::new())),
+                (NON_INPUT_EVENT.to_owned().into(), (1_000, VecDeque::new())),
+            ]
+            .into(),
+            [(id, policy)].into(),
+        )
+    }
+
+    fn received(scheduler: &mut Scheduler) -> Vec<i64> {
+        std::iter::from_fn(|| scheduler.next())
+            .map(|event| match event {
+                EventItem::NodeEvent {
+                    event: NodeEvent::Input { metadata, .. },
+                    ..
+                } => match metadata.parameters["seq"] {
+                    Parameter::Integer(seq) => seq,
+                    _ => unreachable!(),
+                },
+                _ => unreachable!(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn apply_queue_policies() {
+        for (policy, expected, dropped) in [
+            (QueuePolicy::DropOldest, vec![2, 3], 2),
+            (QueuePolicy::DropNewest, vec![0, 1], 2),
+            // the daemon limits the number of queued inputs instead
+            (QueuePolicy::Block, vec![0, 1, 2, 3], 0),
+        ] {
+            let mut scheduler = scheduler(policy);
+            for seq in 0..4 {
+                scheduler.add_event(input("input", seq));
+            }
+            let depths: Vec<_> = scheduler.queue_depths().map(|(_, depth)| depth).collect();
+            assert_eq!(depths, [expected.len()], "{policy:?}");
+            assert_eq!(
+                scheduler.take_dropped().values().sum::<u64>(),
+                dropped,
+                "{policy:?}"
+            );
+            assert_eq!(received(&mut scheduler), expected, "{policy:?}");
+            assert!(scheduler.is_empty());
+        }
+    }
+}
@@ -0,0 +1,360 @@
+//! Group inputs of several IDs into time-synchronized sets.
+//!
+//! Nodes that fuse multiple inputs (e.g. a camera image and the bounding boxes detected on it)
+//! often need inputs that belong together. The [`EventScheduler`][super::EventScheduler] and
+//! the chronological [`EventStream`] don't guarantee this, as inputs of different IDs arrive
+//! independently. This module provides the [`SynchronizedEventStream`], which buffers the
+//! inputs of the given IDs and matches them based on their [metadata timestamps][Metadata::timestamp].
+//!
+//! ## Example
+//!
+//! ```no_run
+//! use dora_node_api::{
+//!     DoraNode,
+//!     synchronized::{SyncPolicy, SynchronizedEvent},
+//! };
+//! use std::time::Duration;
+//!
+//! let (_node, events) = DoraNode::init_from_env()?;
+//! let mut events = events.synchronize(
+//!     ["image", "bbox"],
+//!     SyncPolicy::Approximate {
+//!         slop: Duration::from_millis(10),
+//!     },
+//! );
+//! while let Some(event) = events.recv() {
+//!     match event {
+//!         SynchronizedEvent::Inputs(inputs) => {
+//!             let image = &inputs["image"];
+//!             let bbox = &inputs["bbox"];
+//!             // ...
+//!         }
+//!         SynchronizedEvent::Event(other) => {
+//!             // other inputs and events
+//!         }
+//!     }
+//! }
+//! # Ok::<(), eyre::Report>(())
+//! ```
+
+use std::{
+    collections::{BTreeMap, VecDeque},
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use dora_arrow_convert::ArrowData;
+use dora_core::config::DataId;
+use dora_message::metadata::Metadata;
+use futures::{Stream, StreamExt};
+
+use super::{Event, EventStream};
+
+/// Default number of inputs that are buffered per ID while waiting for a match.
+pub const DEFAULT_SYNC_QUEUE_SIZE: usize = 10;
+
+/// Determines which inputs are considered to belong together.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncPolicy {
+    /// Only group inputs with identical timestamps.
+    ///
+    /// This is useful for inputs that are derived from the same source message, e.g. when
+    /// nodes forward the metadata of the input they are processing.
+    Exact,
+    /// Group inputs whose timestamps differ by at most `slop`.
+    ///
+    /// The oldest buffered inputs that fulfill this condition are grouped together. Inputs that
+    /// can't be matched anymore because all other IDs already received newer inputs outside of
+    /// the slop window are discarded.
+    Approximate {
+        /// Maximum time difference between the oldest and the newest input of a group.
+        slop: Duration,
+    },
+}
+
+impl SyncPolicy {
+    fn slop(&self) -> Duration {
+        match self {
+            SyncPolicy::Exact => Duration::ZERO,
+            SyncPolicy::Approximate { slop } => *slop,
+        }
+    }
+}
+
+/// An input that is part of a synchronized group.
+#[derive(Debug)]
+pub struct SynchronizedInput {
+    /// Meta information about this input, e.g. the timestamp.
+    pub metadata: Metadata,
+    /// The actual data in the Apache Arrow data format.
+    pub data: ArrowData,
+}
+
+/// An event yielded by the [`SynchronizedEventStream`].
+#[derive(Debug)]
+#[allow(clippy::large_enum_variant)]
+pub enum SynchronizedEvent {
+    /// A group of matching inputs, containing exactly one input per synchronized ID.
+    Inputs(BTreeMap<DataId, SynchronizedInput>),
+    /// Any other event, including inputs with IDs that are not synchronized.
+    Event(Event),
+}
+
+/// Adapter for an [`EventStream`] that groups the inputs of several IDs into time-synchronized
+/// sets.
+///
+/// Created through [`EventStream::synchronize`]. See the [module-level docs](self) for details.
+pub struct SynchronizedEventStream {
+    events: EventStream,
+    policy: SyncPolicy,
+    queue_size: usize,
+    queues: BTreeMap<DataId, VecDeque<SynchronizedInput>>,
+}
+
+impl EventStream {
+    /// Groups the inputs of the given IDs into time-synchronized sets.
+    ///
+    /// Inputs are matched based on their metadata timestamps, according to the given
+    /// [`SyncPolicy`]. Other inputs and events are passed through unchanged.
+    ///
+    /// The returned stream yields events in their chronological order, i.e. the
+    /// [`EventScheduler`][super::EventScheduler] is not used.
+    pub fn synchronize<I>(
+        self,
+        inputs: impl IntoIterator<Item = I>,
+        policy: SyncPolicy,
+    ) -> SynchronizedEventStream
+    where
+        I: AsRef<str>,
+    {
+        SynchronizedEventStream {
+            events: self,
+            policy,
+            queue_size: DEFAULT_SYNC_QUEUE_SIZE,
+            queues: inputs
+                .into_iter()
+                .map(|id| (DataId::from(id.as_ref().to_owned()), VecDeque::new()))
+                .collect(),
+        }
+    }
+}
+
+impl SynchronizedEventStream {
+    /// Sets the maximum number of inputs that are buffered per ID while waiting for a match.
+    ///
+    /// If a queue is full, its oldest input is discarded. Defaults to
+    /// [`DEFAULT_SYNC_QUEUE_SIZE`].
+    pub fn with_queue_size(mut self, queue_size: usize) -> Self {
+        self.queue_size = queue_size.max(1);
+        self
+    }
+
+    /// Synchronously waits for the next event.
+    ///
+    /// Blocks the thread until the next event or synchronized input group arrives.
+    /// Returns [`None`] once the event stream is closed.
+    pub fn recv(&mut self) -> Option<SynchronizedEvent> {
+        futures::executor::block_on(self.recv_async())
+    }
+
+    /// Asynchronously waits for the next event.
+    ///
+    /// Returns [`None`] once the event stream is closed.
+    pub async fn recv_async(&mut self) -> Option<SynchronizedEvent> {
+        self.next().await
+    }
+
+    /// Returns the underlying event stream.
+    ///
+    /// Inputs that are still buffered are discarded.
+    pub fn into_inner(self) -> EventStream {
+        self.events
+    }
+
+    /// Handles the given event and returns it if it should be passed through.
+    fn handle_event(&mut self, event: Event) -> Option<SynchronizedEvent> {
+        match event {
+            Event::Input { id, metadata, data } => match self.queues.get_mut(&id) {
+                Some(queue) => {
+                    if queue.len() >= self.queue_size {
+                        tracing::debug!(
+                            "Discarding unmatched input `{id}` due to sync queue size limit"
+                        );
+                        queue.pop_front();
+                    }
+                    queue.push_back(SynchronizedInput { metadata, data });
+                    self.next_group().map(SynchronizedEvent::Inputs)
+                }
+                None => Some(SynchronizedEvent::Event(Event::Input {
+                    id,
+                    metadata,
+                    data,
+                })),
+            },
+            other => Some(SynchronizedEvent::Event(other)),
+        }
+    }
+
+    /// Removes and returns the oldest group of matching inputs, if any.
+    fn next_group(&mut self) -> Option<BTreeMap<DataId, SynchronizedInput>> {
+        let slop = self.policy.slop();
+        loop {
+            let mut oldest: Option<(&DataId, Duration)> = None;
+            let mut newest = Duration::ZERO;
+            for (id, queue) in &self.queues {
+                let time = queue.front()?.metadata.timestamp().get_time().to_duration();
+                if oldest.is_none_or(|(_, t)| time < t) {
+                    oldest = Some((id, time));
+                }
+                newest = newest.max(time);
+            }
+            let (oldest_id, oldest_time) = oldest?;
+
+            if newest - oldest_time <= slop {
+                return self
+                    .queues
+                    .iter_mut()
+                    .map(|(id, queue)| Some((id.clone(), queue.pop_front()?)))
+                    .collect();
+            }
+
+            // the oldest input can't be matched anymore because all other queues only
+            // contain newer inputs
+            let oldest_id = oldest_id.clone();
+            if let Some(queue) = self.queues.get_mut(&oldest_id) {
+                tracing::trace!("Discarding input `{oldest_id}` without sync match");
+                queue.pop_front();
+            }
+        }
+    }
+}
+
+impl Stream for SynchronizedEventStream {
+    type Item = SynchronizedEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            match self.events.poll_next_unpin(cx) {
+                Poll::Ready(Some(event)) => {
+                    if let Some(event) = self.handle_event(event) {
+                        return Poll::Ready(Some(event));
+                    }
+                }
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{IntoArrow, testing::TestNodeBuilder};
+    use arrow::array::Array;
+    use dora_core::{
+        config::NodeId,
+        uhlc::{self, NTP64},
+    };
+    use std::sync::Arc;
+
+    fn stream(policy: SyncPolicy) -> SynchronizedEventStream {
+        let (_node, events, _daemon) = TestNodeBuilder::new(NodeId::from("fusion".to_owned()))
+            .init()
+            .unwrap();
+        events.synchronize(["image", "bbox"], policy)
+    }
+
+    fn input(id: &str, millis: u64) -> Event {
+        let data = 0u8.into_arrow();
+        let timestamp = uhlc::Timestamp::new(
+            NTP64::from(Duration::from_millis(millis)),
+            *uhlc::HLC::default().get_id(),
+        );
+        let type_info = crate::arrow_utils::copy_array_into_sample(
+            &mut vec![0; crate::arrow_utils::required_data_size(&data.to_data())],
+            &data.to_data(),
+        );
+        Event::Input {
+            id: DataId::from(id.to_owned()),
+            metadata: Metadata::new(timestamp, type_info),
+            data: ArrowData(Arc::new(data)),
+        }
+    }
+
+    /// Handles the given inputs and returns the timestamps of the resulting groups.
+    fn groups(stream: &mut SynchronizedEventStream, inputs: &[(&str, u64)]) -> Vec<Vec<u64>> {
+        inputs
+            .iter()
+            .filter_map(|(id, millis)| stream.handle_event(input(id, *millis)))
+            .map(|event| match event {
+                SynchronizedEvent::Inputs(inputs) => inputs
+                    .values()
+                    .map(|input| {
+                        let time = input.metadata.timestamp().get_time().to_duration();
+                        time.as_millis() as u64
+                    })
+                    .collect(),
+                SynchronizedEvent::Event(event) => panic!("unexpected event {event:?}"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn group_exact_timestamps() {
+        let mut stream = stream(SyncPolicy::Exact);
+        // group values are ordered by ID: `bbox`, then `image`
+        assert_eq!(
+            groups(
+                &mut stream,
+                &[("image", 10), ("bbox", 20), ("image", 20), ("image", 30)]
+            ),
+            [[20, 20]]
+        );
+        // the `image` input with timestamp 10 was discarded
+        assert_eq!(groups(&mut stream, &[("bbox", 30)]), [[30, 30]]);
+        assert!(stream.queues.values().all(|queue| queue.is_empty()));
+    }
+
+    #[test]
+    fn group_approximate_timestamps() {
+        let mut stream = stream(SyncPolicy::Approximate {
+            slop: Duration::from_millis(5),
+        });
+        assert_eq!(
+            groups(&mut stream, &[("image", 10), ("bbox", 14)]),
+            [[14, 10]]
+        );
+        // the image with timestamp 20 can't be matched with bbox 30 anymore
+        assert_eq!(
+            groups(&mut stream, &[("image", 20), ("bbox", 30), ("image", 33)]),
+            [[30, 33]]
+        );
+        assert!(stream.queues.values().all(|queue| queue.is_empty()));
+    }
+
+    #[test]
+    fn discard_inputs_of_full_queues() {
+        let mut stream = stream(SyncPolicy::Exact).with_queue_size(2);
+        assert!(groups(&mut stream, &[("image", 1), ("image", 2), ("image", 3)]).is_empty());
+        assert_eq!(stream.queues[&DataId::from("image".to_owned())].len(), 2);
+        assert_eq!(
+            groups(&mut stream, &[("bbox", 1), ("bbox", 2), ("bbox", 3)]),
+            [[2, 2], [3, 3]]
+        );
+    }
+
+    #[test]
+    fn pass_through_other_events() {
+        let mut stream = stream(SyncPolicy::Exact);
+        assert!(matches!(
+            stream.handle_event(input("other", 10)),
+            Some(SynchronizedEvent::Event(Event::Input { id, .. })) if id.as_str() == "other"
+        ));
+        assert!(matches!(
+            stream.handle_event(Event::Stop(crate::StopCause::Manual)),
+            Some(SynchronizedEvent::Event(Event::Stop(_)))
+        ));
+    }
+}
@@ -36,6 +36,34 @@
 //! For best performance, use the [Arrow](https://arrow.apache.org/docs/index.html) data format
 //! and one of the output functions that utilizes shared memory.
 //!
+//! #### Tensors and Images
+//!
+//! Tensors and images should be sent as flat Arrow primitive arrays, with their layout described
+//! by the standard metadata parameters of the [`tensor`] module:
+//!
+//! ```no_run
+//! use dora_node_api::{
+//!     DoraNode, IntoArrow, MetadataParameters,
+//!     tensor::{ImageEncoding, ImageInfo},
+//! };
+//!
+//! let (mut node, mut events) = DoraNode::init_from_env()?;
+//!
+//! let pixels: Vec<u8> = vec![0; 640 * 480 * 3];
+//! let image = ImageInfo {
+//!     width: 640,
+//!     height: 480,
+//!     encoding: ImageEncoding::Rgb8,
+//! };
+//! let mut parameters = MetadataParameters::default();
+//! image.write_parameters(&mut parameters)?;
+//! node.send_output("image".to_owned().into(), parameters, pixels.into_arrow())?;
+//! # Ok::<(), eyre::Report>(())
+//! ```
+//!
+//! With the `ndarray` feature enabled, `ndarray::Array`s can be sent directly through
+//! [`IntoArrow`] and received through `ndarray_view` and `into_ndarray`.
+//!
 //! ### Receiving Events
 //!
 //! The [`EventStream`] is an [`AsyncIterator`][std::async_iter::AsyncIterator] that yields the incoming [`Event`]s.
@@ -77,6 +105,11 @@
 //! - As dynamic nodes are identified by their node ID, this **ID must be unique**
 //!   across all running dataflows.
 //! - For distributed dataflows, nodes need to be manually spawned on the correct machine.
+//!
+//! ## Testing
+//!
+//! The [`testing`] module provides an in-process mock daemon, which allows testing the logic
+//! of a node in a unit test, without starting a dataflow.
 
 #![warn(missing_docs)]
 
@@ -85,9 +118,9 @@ pub use dora_arrow_convert::*;
 pub use dora_core::{self, uhlc};
 pub use dora_message::{
     DataflowId,
-    metadata::{Metadata, MetadataParameters, Parameter},
+    metadata::{Metadata, MetadataParameters, Parameter, tensor},
 };
-pub use event_stream::{Event, EventScheduler, EventStream, StopCause, merged};
+pub use event_stream::{Event, EventScheduler, EventStream, StopCause, merged, synchronized};
 pub use flume::Receiver;
 pub use futures;
 pub use node::{DataSample, DoraNode, ZERO_COPY_THRESHOLD, arrow_utils};
@@ -95,3 +128,4 @@ pub use node::{DataSample, DoraNode, ZERO_COPY_THRESHOLD, arrow_utils};
 mod daemon_connection;
 mod event_stream;
 mod node;
+pub mod testing;
@@ -90,16 +90,39 @@ impl ControlChannel {
         Ok(())
     }
 
+    pub fn request_dataflow_stop(&mut self, reason: Option<String>) -> eyre::Result<()> {
+        let reply = self
+            .channel
+            .request(&Timestamped {
+                inner: DaemonRequest::StopDataflow { reason },
+                timestamp: self.clock.new_timestamp(),
+            })
+            .wrap_err("failed to request dataflow stop from dora-daemon")?;
+        match reply {
+            DaemonReply::Result(result) => result
+                .map_err(|e| eyre!(e))
+                .wrap_err("dora-daemon failed to stop the dataflow")?,
+            other => bail!("unexpected stop dataflow reply: {other:?}"),
+        }
+        Ok(())
+    }
+
+    /// Sends the given message to the daemon.
+    ///
+    /// If `blocking` is set, this function waits until the receivers of the output have room
+    /// in their input queues.
     pub fn send_message(
         &mut self,
         output_id: DataId,
         metadata: Metadata,
         data: Option<DataMessage>,
+        blocking: bool,
     ) -> eyre::Result<()> {
         let request = DaemonRequest::SendMessage {
             output_id,
             metadata,
             data,
+            blocking,
         };
         let reply = self
             .channel
@@ -109,7 +132,10 @@ impl ControlChannel {
             })
             .wrap_err("failed to send SendMessage request to dora-daemon")?;
         match reply {
-            DaemonReply::Empty => Ok(()),
+            DaemonReply::Empty if !blocking => Ok(()),
+            DaemonReply::Result(result) if blocking => result
+                .map_err(|e| eyre!(e))
+                .wrap_err("failed to send message to dora-daemon"),
             other => bail!("unexpected SendMessage reply: {other:?}"),
         }
     }
@@ -64,6 +64,22 @@ enum TokioRuntime {
     Handle(Handle),
 }
 
+impl TokioRuntime {
+    /// Uses the current tokio runtime, if any, or starts a new one.
+    fn current_or_new() -> eyre::Result<Self> {
+        match Handle::try_current() {
+            Ok(handle) => Ok(TokioRuntime::Handle(handle)),
+            Err(_) => Ok(TokioRuntime::Runtime(
+                tokio::runtime::Builder::new_multi_thread()
+                    .worker_threads(2)
+                    .enable_all()
+                    .build()
+                    .context("tokio runtime failed")?,
+            )),
+        }
+    }
+}
+
 /// Allows sending outputs and retrieving node information.
 ///
 /// The main purpose of this struct is to send outputs via Dora. There are also functions available
@@ -72,6 +88,8 @@ pub struct DoraNode {
     id: NodeId,
     dataflow_id: DataflowId,
     node_config: NodeRunConfig,
+    /// Outputs that are mapped to inputs with a blocking queue policy.
+    blocking_outputs: BTreeSet<DataId>,
     control_channel: ControlChannel,
     clock: Arc<uhlc::HLC>,
 
@@ -177,20 +195,14 @@ impl DoraNode {
             daemon_communication,
             dataflow_descriptor,
             dynamic: _,
+            report_consumed_inputs,
+            blocking_outputs,
+            report_input_stats,
         } = node_config;
         let clock = Arc::new(uhlc::HLC::default());
         let input_config = run_config.inputs.clone();
 
-        let rt = match Handle::try_current() {
-            Ok(handle) => TokioRuntime::Handle(handle),
-            Err(_) => TokioRuntime::Runtime(
-                tokio::runtime::Builder::new_multi_thread()
-                    .worker_threads(2)
-                    .enable_all()
-                    .build()
-                    .context("tokio runtime failed")?,
-            ),
-        };
+        let rt = TokioRuntime::current_or_new()?;
 
         #[cfg(feature = "metrics")]
         {
@@ -214,6 +226,8 @@ impl DoraNode {
             &node_id,
             &daemon_communication,
             input_config,
+            report_consumed_inputs,
+            report_input_stats,
             clock.clone(),
         )
         .wrap_err("failed to init event stream")?;
@@ -228,6 +242,60 @@ impl DoraNode {
             id: node_id,
             dataflow_id,
             node_config: run_config.clone(),
+            blocking_outputs,
+            control_channel,
+            clock,
+            sent_out_shared_memory: HashMap::new(),
+            drop_stream,
+            cache: VecDeque::new(),
+            dataflow_descriptor: serde_yaml::from_value(dataflow_descriptor),
+            warned_unknown_output: BTreeSet::new(),
+            _rt: rt,
+        };
+        Ok((node, event_stream))
+    }
+
+    /// Initializes a node on daemon channels that are created by the given function.
+    ///
+    /// Used for nodes that are connected to an in-process mock daemon, see
+    /// [`testing`][crate::testing].
+    pub(crate) fn init_on_channels(
+        dataflow_id: DataflowId,
+        node_id: NodeId,
+        run_config: NodeRunConfig,
+        dataflow_descriptor: serde_yaml::Value,
+        report_input_stats: bool,
+        mut connect: impl FnMut() -> DaemonChannel,
+    ) -> eyre::Result<(Self, EventStream)> {
+        let clock = Arc::new(uhlc::HLC::default());
+        let rt = TokioRuntime::current_or_new()?;
+
+        let mut event_stream = EventStream::init_on_channels(
+            dataflow_id,
+            &node_id,
+            connect(),
+            connect(),
+            run_config.inputs.clone(),
+            // the mock daemon waits for consumed inputs to keep the scripted event order
+            true,
+            clock.clone(),
+        )
+        .wrap_err("failed to init event stream")?;
+        if report_input_stats {
+            event_stream.report_input_stats_to_daemon();
+        }
+        let drop_stream =
+            DropStream::init_on_channel(dataflow_id, &node_id, connect(), clock.clone())
+                .wrap_err("failed to init drop stream")?;
+        let control_channel =
+            ControlChannel::init_on_channel(dataflow_id, &node_id, connect(), clock.clone())
+                .wrap_err("failed to init control channel")?;
+
+        let node = Self {
+            id: node_id,
+            dataflow_id,
+            node_config: run_config,
+            blocking_outputs: BTreeSet::new(),
             control_channel,
             clock,
             sent_out_shared_memory: HashMap::new(),
@@ -384,6 +452,9 @@ impl DoraNode {
     ///
     /// Ignores the output if the given `output_id` is not specified as node output in the dataflow
     /// configuration file.
+    ///
+    /// Returns an error if the output declares a data type in the dataflow configuration file
+    /// and the given `type_info` doesn't match it.
     pub fn send_output_sample(
         &mut self,
         output_id: DataId,
@@ -391,6 +462,20 @@ impl DoraNode {
         parameters: MetadataParameters,
         sample: Option<DataSample>,
     ) -> eyre::Result<()> {
+        if let Some(expected) = self
+            .node_config
+            .outputs
+            .get(&output_id)
+            .and_then(|output| output.data_type.as_ref())
+        {
+            if !expected.matches(&type_info.data_type) {
+                bail!(
+                    "output `{output_id}` has type `{}`, but the dataflow declares type `{expected}`",
+                    type_info.data_type
+                );
+            }
+        }
+
         self.handle_finished_drop_tokens()?;
 
         let metadata = Metadata::from_parameters(self.clock.new_timestamp(), type_info, parameters);
@@ -400,8 +485,9 @@ impl DoraNode {
             None => (None, None),
         };
 
+        let blocking = self.blocking_outputs.contains(&output_id);
         self.control_channel
-            .send_message(output_id.clone(), metadata, data)
+            .send_message(output_id.clone(), metadata, data, blocking)
             .wrap_err_with(|| format!("failed to send output {output_id}"))?;
 
         if let Some((shared_memory, drop_token)) = shmem {
@@ -431,6 +517,18 @@ impl DoraNode {
         Ok(())
     }
 
+    /// Requests to stop the whole dataflow, including all nodes on other machines.
+    ///
+    /// All nodes of the dataflow receive a [`Stop`](crate::Event::Stop) event, including this
+    /// node. The given `reason` is reported as part of the dataflow result.
+    ///
+    /// This is useful for nodes that know when a run is complete, e.g. test harnesses.
+    pub fn stop_dataflow(&mut self, reason: Option<String>) -> eyre::Result<()> {
+        self.control_channel
+            .request_dataflow_stop(reason)
+            .wrap_err("failed to request dataflow stop")
+    }
+
     /// Returns the ID of the node as specified in the dataflow configuration file.
     pub fn id(&self) -> &NodeId {
         &self.id
@@ -546,6 +644,7 @@ impl Drop for DoraNode {
             .report_closed_outputs(
                 std::mem::take(&mut self.node_config.outputs)
                     .into_iter()
+                    .map(|output| output.id)
                     .collect(),
             )
             .context("failed to close outputs on drop")
@@ -0,0 +1,655 @@
+//! Utilities for testing node logic without a running Dora daemon.
+//!
+//! The [`TestNodeBuilder`] creates a [`DoraNode`] and [`EventStream`] pair that is connected to an
+//! in-process [`MockDaemon`] instead of a real daemon. Tests use the mock daemon to send scripted
+//! events to the node and to inspect the outputs that the node sent.
+//!
+//! ```
+//! use dora_node_api::{
+//!     Event, IntoArrow, MetadataParameters,
+//!     dora_core::config::{DataId, NodeId},
+//!     testing::TestNodeBuilder,
+//! };
+//! use std::time::Duration;
+//!
+//! let (mut node, mut events, daemon) = TestNodeBuilder::new(NodeId::from("doubler".to_owned()))
+//!     .input(DataId::from("number".to_owned()))
+//!     .output(DataId::from("doubled".to_owned()))
+//!     .init()?;
+//!
+//! // script the events that the node receives
+//! daemon.send_input(
+//!     DataId::from("number".to_owned()),
+//!     MetadataParameters::default(),
+//!     21u64.into_arrow(),
+//! )?;
+//! daemon.stop()?;
+//!
+//! // run the node logic
+//! while let Some(event) = events.recv() {
+//!     match event {
+//!         Event::Input { id: _, metadata, data } => {
+//!             let value: u64 = (&data).try_into()?;
+//!             node.send_output(
+//!                 DataId::from("doubled".to_owned()),
+//!                 metadata.parameters,
+//!                 (value * 2).into_arrow(),
+//!             )?;
+//!         }
+//!         Event::Stop(_) => break,
+//!         _ => {}
+//!     }
+//! }
+//!
+//! let output = daemon.recv_output_timeout(Duration::from_secs(1))?;
+//! assert_eq!(output.id.as_str(), "doubled");
+//! assert_eq!(u64::try_from(&output.data)?, 42);
+//! # Ok::<(), eyre::Report>(())
+//! ```
+//!
+//! The mock daemon passes the scripted events to the node one by one, waiting until the node
+//! received the previous input. This way, the events are received in the order in which they
+//! were sent, even though the [`EventStream`] would otherwise reorder them (see
+//! [`EventScheduler`][crate::EventScheduler]).
+//!
+//! Timer inputs are not generated automatically. Send them through
+//! [`send_input`][MockDaemon::send_input] like any other input.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::{Arc, Condvar, Mutex},
+    time::Duration,
+};
+
+use aligned_vec::{AVec, ConstAlign};
+use arrow::array::Array;
+use dora_core::{
+    config::{DataId, Input, InputMapping, NodeId, NodeRunConfig, Output, UserInputMapping},
+    uhlc,
+};
+use dora_message::{
+    DataflowId,
+    daemon_to_node::{DaemonReply, NodeDropEvent, NodeEvent},
+    metadata::{Metadata, MetadataParameters},
+    node_to_daemon::{DaemonRequest, DataMessage, DropToken, InputStats, Timestamped},
+};
+use eyre::{Context, bail, eyre};
+
+use crate::{
+    ArrowData, DoraNode, EventStream,
+    arrow_utils::{copy_array_into_sample, required_data_size},
+    daemon_connection::DaemonChannel,
+    event_stream::data_conversion::{MappedInputData, RawData},
+};
+
+/// Creates a [`DoraNode`] and [`EventStream`] pair that is connected to a [`MockDaemon`].
+pub struct TestNodeBuilder {
+    node_id: NodeId,
+    run_config: NodeRunConfig,
+    dataflow_descriptor: serde_yaml::Value,
+    report_input_stats: bool,
+}
+
+impl TestNodeBuilder {
+    /// Creates a builder for a node with the given ID and no inputs or outputs.
+    pub fn new(node_id: NodeId) -> Self {
+        Self {
+            node_id,
+            run_config: NodeRunConfig {
+                inputs: Default::default(),
+                outputs: Default::default(),
+            },
+            dataflow_descriptor: serde_yaml::Value::Null,
+            report_input_stats: false,
+        }
+    }
+
+    /// Adds an input with the given ID to the node.
+    pub fn input(mut self, id: DataId) -> Self {
+        let input = Input {
+            mapping: InputMapping::User(UserInputMapping {
+                source: NodeId::from("test".to_owned()),
+                output: id.clone(),
+            }),
+            queue_size: None,
+            queue_policy: None,
+            data_type: None,
+        };
+        self.run_config.inputs.insert(id, input);
+        self
+    }
+
+    /// Adds an output with the given ID to the node.
+    pub fn output(mut self, id: DataId) -> Self {
+        self.run_config.outputs.insert(Output::from(id));
+        self
+    }
+
+    /// Replaces the input and output configuration of the node.
+    ///
+    /// This is useful to test nodes with the same configuration as in a dataflow YAML file,
+    /// e.g. with declared output types.
+    pub fn run_config(mut self, run_config: NodeRunConfig) -> Self {
+        self.run_config = run_config;
+        self
+    }
+
+    /// Sets the dataflow descriptor that is returned by
+    /// [`DoraNode::dataflow_descriptor`].
+    pub fn dataflow_descriptor(mut self, descriptor: serde_yaml::Value) -> Self {
+        self.dataflow_descriptor = descriptor;
+        self
+    }
+
+    /// Lets the node report input statistics, like a daemon that serves metrics.
+    ///
+    /// The reports are available through [`MockDaemon::input_stats`].
+    pub fn report_input_stats(mut self, report: bool) -> Self {
+        self.report_input_stats = report;
+        self
+    }
+
+    /// Initializes the node, connected to a new mock daemon.
+    pub fn init(self) -> eyre::Result<(DoraNode, EventStream, MockDaemon)> {
+        let (events_tx, events_rx) = flume::unbounded();
+        let (outputs_tx, outputs_rx) = flume::unbounded();
+        let (drop_tx, drop_rx) = flume::unbounded();
+        let state = Arc::new(MockDaemonState {
+            clock: uhlc::HLC::default(),
+            events_tx: Mutex::new(Some(events_tx)),
+            events_rx,
+            pending_inputs: Mutex::new(PendingInputs::default()),
+            inputs_consumed: Condvar::new(),
+            outputs_tx,
+            drop_tx: Mutex::new(Some(drop_tx)),
+            drop_rx,
+            stop_requests: Mutex::new(Vec::new()),
+            input_stats: Mutex::new(Vec::new()),
+            closed_outputs: Mutex::new(Vec::new()),
+        });
+
+        let inputs = self.run_config.inputs.keys().cloned().collect();
+        let (node, events) = DoraNode::init_on_channels(
+            DataflowId::new_v7(uuid::Timestamp::now(uuid::NoContext)),
+            self.node_id,
+            self.run_config,
+            self.dataflow_descriptor,
+            self.report_input_stats,
+            || DaemonChannel::InMemory(state.clone()),
+        )?;
+        let daemon = MockDaemon {
+            state,
+            outputs: outputs_rx,
+            open_inputs: Mutex::new(inputs),
+        };
+        Ok((node, events, daemon))
+    }
+}
+
+/// In-process replacement for the Dora daemon, used for testing nodes.
+///
+/// Dropping the mock daemon closes the event stream of the node.
+pub struct MockDaemon {
+    state: Arc<MockDaemonState>,
+    outputs: flume::Receiver<TestOutput>,
+    open_inputs: Mutex<BTreeSet<DataId>>,
+}
+
+impl MockDaemon {
+    /// Sends the given Arrow array as input to the node.
+    ///
+    /// Returns an error if the node has no open input with the given ID.
+    pub fn send_input(
+        &self,
+        input_id: DataId,
+        parameters: MetadataParameters,
+        data: impl Array,
+    ) -> eyre::Result<()> {
+        if !self.open_inputs.lock().unwrap().contains(&input_id) {
+            bail!("node has no open input `{input_id}`");
+        }
+
+        let arrow_array = data.to_data();
+        let mut sample: AVec<u8, ConstAlign<128>> =
+            AVec::__from_elem(128, 0, required_data_size(&arrow_array));
+        let type_info = copy_array_into_sample(&mut sample, &arrow_array);
+        let metadata =
+            Metadata::from_parameters(self.state.clock.new_timestamp(), type_info, parameters);
+
+        self.state.send_event(NodeEvent::Input {
+            id: input_id,
+            metadata,
+            data: Some(DataMessage::Vec(sample)),
+        })
+    }
+
+    /// Notifies the node that the input with the given ID was closed.
+    pub fn close_input(&self, input_id: DataId) -> eyre::Result<()> {
+        if !self.open_inputs.lock().unwrap().remove(&input_id) {
+            bail!("node has no open input `{input_id}`");
+        }
+        self.state
+            .send_event(NodeEvent::InputClosed { id: input_id })
+    }
+
+    /// Sends a [`Stop`](crate::Event::Stop) event to the node and closes its event stream.
+    ///
+    /// Events that were sent before are still passed to the node.
+    pub fn stop(&self) -> eyre::Result<()> {
+        self.state.send_event(NodeEvent::Stop)?;
+        self.state.close_events();
+        Ok(())
+    }
+
+    /// Closes all inputs of the node, which stops it with
+    /// [`StopCause::AllInputsClosed`](crate::StopCause::AllInputsClosed).
+    ///
+    /// Events that were sent before are still passed to the node.
+    pub fn close_all_inputs(&self) -> eyre::Result<()> {
+        let open_inputs = std::mem::take(&mut *self.open_inputs.lock().unwrap());
+        for input_id in open_inputs {
+            self.state
+                .send_event(NodeEvent::InputClosed { id: input_id })?;
+        }
+        self.state.send_event(NodeEvent::AllInputsClosed)?;
+        self.state.close_events();
+        Ok(())
+    }
+
+    /// Returns the next output that the node sent, if any.
+    pub fn try_recv_output(&self) -> Option<TestOutput> {
+        self.outputs.try_recv().ok()
+    }
+
+    /// Waits for the next output that the node sends.
+    ///
+    /// Returns an error if the node sent no output within the given duration.
+    pub fn recv_output_timeout(&self, timeout: Duration) -> eyre::Result<TestOutput> {
+        self.outputs
+            .recv_timeout(timeout)
+            .map_err(|_| eyre!("node sent no output within {timeout:?}"))
+    }
+
+    /// Returns all outputs that the node sent since the last call.
+    pub fn outputs(&self) -> Vec<TestOutput> {
+        self.outputs.try_iter().collect()
+    }
+
+    /// Returns the reasons of all [`DoraNode::stop_dataflow`] calls of the node.
+    pub fn dataflow_stop_requests(&self) -> Vec<Option<String>> {
+        self.state.stop_requests.lock().unwrap().clone()
+    }
+
+    /// Returns the outputs that the node closed, in the order in which they were closed.
+    ///
+    /// The node closes its remaining outputs when it is dropped.
+    pub fn closed_outputs(&self) -> Vec<DataId> {
+        self.state.closed_outputs.lock().unwrap().clone()
+    }
+
+    /// Returns all input statistics that the node reported, see
+    /// [`TestNodeBuilder::report_input_stats`].
+    pub fn input_stats(&self) -> Vec<BTreeMap<DataId, InputStats>> {
+        self.state.input_stats.lock().unwrap().clone()
+    }
+}
+
+impl Drop for MockDaemon {
+    fn drop(&mut self) {
+        self.state.close_events();
+    }
+}
+
+/// An output message that a node sent to the [`MockDaemon`].
+#[derive(Debug)]
+pub struct TestOutput {
+    /// The ID of the output.
+    pub id: DataId,
+    /// The metadata of the message, including the parameters set by the node.
+    pub metadata: Metadata,
+    /// The data of the message.
+    pub data: ArrowData,
+}
+
+#[derive(Debug, Default)]
+struct PendingInputs {
+    /// Number of inputs that were passed to the node, but not received by it yet.
+    count: usize,
+    event_stream_dropped: bool,
+}
+
+#[doc(hidden)]
+pub struct MockDaemonState {
+    clock: uhlc::HLC,
+    events_tx: Mutex<Option<flume::Sender<Timestamped<NodeEvent>>>>,
+    events_rx: flume::Receiver<Timestamped<NodeEvent>>,
+    pending_inputs: Mutex<PendingInputs>,
+    inputs_consumed: Condvar,
+    outputs_tx: flume::Sender<TestOutput>,
+    drop_tx: Mutex<Option<flume::Sender<DropToken>>>,
+    drop_rx: flume::Receiver<DropToken>,
+    stop_requests: Mutex<Vec<Option<String>>>,
+    input_stats: Mutex<Vec<BTreeMap<DataId, InputStats>>>,
+    closed_outputs: Mutex<Vec<DataId>>,
+}
+
+impl MockDaemonState {
+    fn send_event(&self, event: NodeEvent) -> eyre::Result<()> {
+        let events_tx = self.events_tx.lock().unwrap();
+        let events_tx = events_tx
+            .as_ref()
+            .ok_or_else(|| eyre!("event stream of node was already closed"))?;
+        events_tx
+            .send(Timestamped {
+                inner: event,
+                timestamp: self.clock.new_timestamp(),
+            })
+            .map_err(|_| eyre!("event stream of node was already closed"))
+    }
+
+    fn close_events(&self) {
+        self.events_tx.lock().unwrap().take();
+    }
+
+    pub(crate) fn handle_request(
+        &self,
+        request: &Timestamped<DaemonRequest>,
+    ) -> eyre::Result<DaemonReply> {
+        if let Err(err) = self.clock.update_with_timestamp(&request.timestamp) {
+            tracing::warn!("failed to update HLC: {err}");
+        }
+        let reply = match &request.inner {
+            DaemonRequest::Register(_)
+            | DaemonRequest::Subscribe
+            | DaemonRequest::SubscribeDrop => DaemonReply::Result(Ok(())),
+            DaemonRequest::CloseOutputs(outputs) => {
+                self.closed_outputs
+                    .lock()
+                    .unwrap()
+                    .extend(outputs.iter().cloned());
+                DaemonReply::Result(Ok(()))
+            }
+            DaemonRequest::SendMessage {
+                output_id,
+                metadata,
+                data,
+                blocking,
+            } => {
+                self.handle_output(output_id.clone(), metadata.clone(), data.as_ref())?;
+                if *blocking {
+                    DaemonReply::Result(Ok(()))
+                } else {
+                    DaemonReply::Empty
+                }
+            }
+            DaemonRequest::NextEvent { drop_tokens: _ } => {
+                // inputs are sent as `DataMessage::Vec`, so there are no drop tokens
+                DaemonReply::NextEvents(self.next_event().into_iter().collect())
+            }
+            DaemonRequest::ReportInputsConsumed { inputs } => {
+                let mut pending = self.pending_inputs.lock().unwrap();
+                pending.count = pending.count.saturating_sub(inputs.len());
+                self.inputs_consumed.notify_all();
+                DaemonReply::Empty
+            }
+            DaemonRequest::ReportDropTokens { .. } => DaemonReply::Empty,
+            DaemonRequest::ReportInputStats { inputs } => {
+                self.input_stats.lock().unwrap().push(inputs.clone());
+                DaemonReply::Empty
+            }
+            DaemonRequest::NextFinishedDropTokens => {
+                // blocks until a token is available or the node reported `OutputsDone`
+                let events = self.drop_rx.recv().ok().map(|drop_token| Timestamped {
+                    inner: NodeDropEvent::OutputDropped { drop_token },
+                    timestamp: self.clock.new_timestamp(),
+                });
+                DaemonReply::NextDropEvents(events.into_iter().collect())
+            }
+            DaemonRequest::OutputsDone => {
+                self.drop_tx.lock().unwrap().take();
+                DaemonReply::Result(Ok(()))
+            }
+            DaemonRequest::EventStreamDropped => {
+                self.close_events();
+                self.pending_inputs.lock().unwrap().event_stream_dropped = true;
+                self.inputs_consumed.notify_all();
+                DaemonReply::Result(Ok(()))
+            }
+            DaemonRequest::NodeConfig { .. } => DaemonReply::NodeConfig {
+                result: Err("dynamic nodes are not supported by the mock daemon".into()),
+            },
+            DaemonRequest::StopDataflow { reason } => {
+                self.stop_requests.lock().unwrap().push(reason.clone());
+                // like the real daemon, stop all nodes of the dataflow, including this one
+                if self.send_event(NodeEvent::Stop).is_ok() {
+                    self.close_events();
+                }
+                DaemonReply::Result(Ok(()))
+            }
+        };
+        Ok(reply)
+    }
+
+    /// Waits until the node received all previous inputs, then returns the next scripted event.
+    ///
+    /// Returns `None` once the event stream is closed.
+    fn next_event(&self) -> Option<Timestamped<NodeEvent>> {
+        {
+            let pending = self.pending_inputs.lock().unwrap();
+            let pending = self
+                .inputs_consumed
+                .wait_while(pending, |p| p.count > 0 && !p.event_stream_dropped)
+                .unwrap();
+            if pending.event_stream_dropped {
+                return None;
+            }
+        }
+
+        let event = self.events_rx.recv().ok()?;
+        if let NodeEvent::Input { .. } = event.inner {
+            self.pending_inputs.lock().unwrap().count += 1;
+        }
+        Some(event)
+    }
+
+    fn handle_output(
+        &self,
+        id: DataId,
+        metadata: Metadata,
+        data: Option<&DataMessage>,
+    ) -> eyre::Result<()> {
+        let raw_data = match data {
+            None => RawData::Empty,
+            Some(DataMessage::Vec(data)) => RawData::Vec(data.clone()),
+            Some(DataMessage::SharedMemory {
+                shared_memory_id,
+                len,
+                drop_token,
+            }) => {
+                // copy the data because the node reuses the shared memory region once it
+                // receives the drop token
+                let mapped = unsafe { MappedInputData::map(shared_memory_id, *len) }?;
+                let data = AVec::from_slice(128, &mapped);
+                drop(mapped);
+                if let Some(drop_tx) = self.drop_tx.lock().unwrap().as_ref() {
+                    let _ = drop_tx.send(*drop_token);
+                }
+                RawData::Vec(data)
+            }
+        };
+        let data = raw_data
+            .into_arrow_array(&metadata.type_info)
+            .wrap_err_with(|| format!("failed to read output `{id}`"))?;
+
+        // the receiver is only closed if the `MockDaemon` was dropped
+        let _ = self.outputs_tx.send(TestOutput {
+            id,
+            metadata,
+            data: arrow::array::make_array(data).into(),
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Event, IntoArrow, StopCause};
+    use dora_message::metadata::Parameter;
+
+    fn parameters() -> MetadataParameters {
+        [("frame".to_owned(), Parameter::Integer(7))].into()
+    }
+
+    #[test]
+    fn deliver_scripted_input() -> eyre::Result<()> {
+        let input = DataId::from("number".to_owned());
+        let (_node, mut events, daemon) = TestNodeBuilder::new(NodeId::from("node".to_owned()))
+            .input(input.clone())
+            .init()?;
+        daemon.send_input(input.clone(), parameters(), 21u64.into_arrow())?;
+
+        let Some(Event::Input { id, metadata, data }) = events.recv() else {
+            panic!("expected input event");
+        };
+        assert_eq!(id, input);
+        assert_eq!(metadata.parameters, parameters());
+        assert_eq!(u64::try_from(&data)?, 21);
+
+        assert!(
+            daemon
+                .send_input(
+                    DataId::from("unknown".to_owned()),
+                    parameters(),
+                    1u64.into_arrow()
+                )
+                .is_err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn stop_ends_event_stream() -> eyre::Result<()> {
+        let input = DataId::from("number".to_owned());
+        let (_node, mut events, daemon) = TestNodeBuilder::new(NodeId::from("node".to_owned()))
+            .input(input.clone())
+            .init()?;
+        daemon.stop()?;
+
+        assert!(matches!(
+            events.recv(),
+            Some(Event::Stop(StopCause::Manual))
+        ));
+        assert!(events.recv().is_none());
+        assert!(
+            daemon
+                .send_input(input, parameters(), 1u64.into_arrow())
+                .is_err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn capture_outputs() -> eyre::Result<()> {
+        let output = DataId::from("doubled".to_owned());
+        let (mut node, _events, daemon) = TestNodeBuilder::new(NodeId::from("node".to_owned()))
+            .output(output.clone())
+            .init()?;
+        assert!(daemon.try_recv_output().is_none());
+
+        node.send_output(output.clone(), parameters(), 42u64.into_arrow())?;
+
+        let sent = daemon.recv_output_timeout(Duration::from_secs(1))?;
+        assert_eq!(sent.id, output);
+        assert_eq!(sent.metadata.parameters, parameters());
+        assert_eq!(u64::try_from(&sent.data)?, 42);
+        assert!(daemon.try_recv_output().is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn dropping_node_closes_outputs() -> eyre::Result<()> {
+        let outputs = ["boxes", "image"].map(|id| DataId::from(id.to_owned()));
+        let mut builder = TestNodeBuilder::new(NodeId::from("node".to_owned()));
+        for output in &outputs {
+            builder = builder.output(output.clone());
+        }
+        let (node, _events, daemon) = builder.init()?;
+        assert!(daemon.closed_outputs().is_empty());
+
+        drop(node);
+        let mut closed = daemon.closed_outputs();
+        closed.sort();
+        assert_eq!(closed, outputs);
+        Ok(())
+    }
+
+    #[test]
+    fn reject_outputs_with_undeclared_type() -> eyre::Result<()> {
+        let run_config: NodeRunConfig =
+            serde_yaml::from_str("outputs: [{id: boxes, type: {list: float32}}, plain]")?;
+        let (mut node, _events, daemon) = TestNodeBuilder::new(NodeId::from("detector".to_owned()))
+            .run_config(run_config)
+            .init()?;
+
+        let boxes = DataId::from("boxes".to_owned());
+        let result = node.send_output(
+            boxes.clone(),
+            MetadataParameters::default(),
+            vec![1u8, 2].into_arrow(),
+        );
+        assert!(result.is_err());
+        assert!(daemon.try_recv_output().is_none());
+
+        node.send_output(
+            boxes,
+            MetadataParameters::default(),
+            arrow::array::ListArray::from_iter_primitive::<arrow::datatypes::Float32Type, _, _>([
+                Some([Some(1.0), Some(2.0)]),
+            ]),
+        )?;
+        // outputs without declared type accept any data
+        node.send_output(
+            DataId::from("plain".to_owned()),
+            MetadataParameters::default(),
+            vec![1u8, 2].into_arrow(),
+        )?;
+
+        let outputs: Vec<_> = daemon.outputs().into_iter().map(|o| o.id).collect();
+        assert_eq!(
+            outputs,
+            ["boxes", "plain"].map(|id| DataId::from(id.to_owned()))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn report_input_stats_only_if_enabled() -> eyre::Result<()> {
+        let input = DataId::from("number".to_owned());
+        for report in [false, true] {
+            let (_node, mut events, daemon) = TestNodeBuilder::new(NodeId::from("node".to_owned()))
+                .input(input.clone())
+                .report_input_stats(report)
+                .init()?;
+            daemon.send_input(
+                input.clone(),
+                MetadataParameters::default(),
+                1u64.into_arrow(),
+            )?;
+            daemon.stop()?;
+            while events.recv().is_some() {}
+            // pending statistics are reported when the event stream is dropped
+            drop(events);
+
+            let stats = daemon.input_stats();
+            if report {
+                let received: u64 = stats.iter().map(|s| s[&input].latency.count()).sum();
+                assert_eq!(received, 1);
+                assert_eq!(stats.last().unwrap()[&input].queue_depth, 0);
+            } else {
+                assert!(stats.is_empty());
+            }
+        }
+        Ok(())
+    }
+}
@@ -1,4 +1,4 @@
-use communication_layer_request_reply::{TcpConnection, TcpRequestReplyConnection};
+use communication_layer_request_reply::TcpRequestReplyConnection;
 use dora_core::descriptor::Descriptor;
 use dora_message::{
     BuildId,
@@ -8,12 +8,13 @@ use dora_message::{
     id::NodeId,
 };
 use eyre::{Context, bail};
-use std::{
-    collections::BTreeMap,
-    net::{SocketAddr, TcpStream},
-};
+use std::{collections::BTreeMap, net::SocketAddr};
 
-use crate::{output::print_log_message, session::DataflowSession};
+use crate::{
+    common::{SecurityArgs, open_coordinator_connection},
+    output::print_log_message,
+    session::DataflowSession,
+};
 
 pub fn build_distributed_dataflow(
     session: &mut TcpRequestReplyConnection,
@@ -56,13 +57,12 @@ pub fn wait_until_dataflow_built(
     build_id: BuildId,
     session: &mut TcpRequestReplyConnection,
     coordinator_socket: SocketAddr,
+    security: &SecurityArgs,
     log_level: log::LevelFilter,
 ) -> eyre::Result<BuildId> {
     // subscribe to log messages
-    let mut log_session = TcpConnection {
-        stream: TcpStream::connect(coordinator_socket)
-            .wrap_err("failed to connect to dora coordinator")?,
-    };
+    let mut log_session = open_coordinator_connection(coordinator_socket, security)
+        .wrap_err("failed to connect to dora coordinator")?;
     log_session
         .send(
             &serde_json::to_vec(&ControlRequest::BuildLogSubscribe {
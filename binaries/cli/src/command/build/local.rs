@@ -3,9 +3,9 @@ use std::{collections::BTreeMap, path::PathBuf};
 use colored::Colorize;
 use dora_core::{
     build::{BuildInfo, BuildLogger, Builder, GitManager, LogLevelOrStdout, PrevGitSource},
-    descriptor::{Descriptor, DescriptorExt},
+    descriptor::{Descriptor, DescriptorExt, ResolvedNode},
 };
-use dora_message::{common::GitSource, id::NodeId};
+use dora_message::{SessionId, common::GitSource, id::NodeId};
 use eyre::Context;
 
 use crate::session::DataflowSession;
@@ -28,6 +28,34 @@ pub fn build_dataflow_locally(
     ))
 }
 
+/// Runs the build commands of a single node again, e.g. after its sources changed.
+///
+/// Git sources are not supported.
+pub fn build_node_locally(
+    node: ResolvedNode,
+    session_id: SessionId,
+    working_dir: PathBuf,
+    uv: bool,
+) -> eyre::Result<()> {
+    let runtime = tokio::runtime::Runtime::new()?;
+
+    runtime.block_on(async move {
+        let builder = Builder {
+            session_id,
+            base_working_dir: working_dir,
+            uv,
+        };
+        let logger = LocalBuildLogger {
+            node_id: node.id.clone(),
+        };
+        let task = builder
+            .build_node(node, None, None, logger, &mut GitManager::default())
+            .await?;
+        task.await?;
+        Ok(())
+    })
+}
+
 async fn build_dataflow(
     dataflow: Descriptor,
     git_sources: &BTreeMap<NodeId, GitSource>,
@@ -58,12 +58,13 @@ use std::{collections::BTreeMap, net::IpAddr};
 
 use super::{Executable, default_tracing};
 use crate::{
-    common::{connect_to_coordinator, local_working_dir, resolve_dataflow},
+    common::{SecurityArgs, connect_to_coordinator, local_working_dir, resolve_dataflow},
     session::DataflowSession,
 };
 
 use distributed::{build_distributed_dataflow, wait_until_dataflow_built};
 use local::build_dataflow_locally;
+pub(crate) use local::build_node_locally;
 
 mod distributed;
 mod git;
@@ -87,15 +88,18 @@ pub struct Build {
     // Run build on local machine
     #[clap(long, action)]
     local: bool,
+    #[clap(flatten)]
+    security: SecurityArgs,
 }
 
 impl Executable for Build {
     fn execute(self) -> eyre::Result<()> {
         default_tracing()?;
-        build(
+        build_with_security(
             self.dataflow,
             self.coordinator_addr,
             self.coordinator_port,
+            &self.security,
             self.uv,
             self.local,
         )
@@ -108,6 +112,24 @@ pub fn build(
     coordinator_port: Option<u16>,
     uv: bool,
     force_local: bool,
+) -> eyre::Result<()> {
+    build_with_security(
+        dataflow,
+        coordinator_addr,
+        coordinator_port,
+        &SecurityArgs::default(),
+        uv,
+        force_local,
+    )
+}
+
+fn build_with_security(
+    dataflow: String,
+    coordinator_addr: Option<IpAddr>,
+    coordinator_port: Option<u16>,
+    security: &SecurityArgs,
+    uv: bool,
+    force_local: bool,
 ) -> eyre::Result<()> {
     let dataflow_path = resolve_dataflow(dataflow).context("could not resolve dataflow")?;
     let dataflow_descriptor =
@@ -131,7 +153,8 @@ pub fn build(
         }
     }
 
-    let session = || connect_to_coordinator_with_defaults(coordinator_addr, coordinator_port);
+    let session =
+        || connect_to_coordinator_with_defaults(coordinator_addr, coordinator_port, security);
 
     let build_kind = if force_local {
         log::info!("Building locally, as requested through `--force-local`");
@@ -215,6 +238,7 @@ pub fn build(
                 build_id,
                 &mut *coordinator_session,
                 coordinator_socket(coordinator_addr, coordinator_port),
+                security,
                 log::LevelFilter::Info,
             )?;
 
@@ -239,9 +263,10 @@ enum BuildKind {
 fn connect_to_coordinator_with_defaults(
     coordinator_addr: Option<std::net::IpAddr>,
     coordinator_port: Option<u16>,
-) -> std::io::Result<Box<TcpRequestReplyConnection>> {
+    security: &SecurityArgs,
+) -> eyre::Result<Box<TcpRequestReplyConnection>> {
     let coordinator_socket = coordinator_socket(coordinator_addr, coordinator_port);
-    connect_to_coordinator(coordinator_socket)
+    connect_to_coordinator(coordinator_socket, security)
 }
 
 fn coordinator_socket(
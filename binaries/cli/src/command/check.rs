@@ -1,5 +1,8 @@
 use super::{Executable, default_tracing};
-use crate::{LOCALHOST, common::connect_to_coordinator};
+use crate::{
+    LOCALHOST,
+    common::{SecurityArgs, connect_to_coordinator},
+};
 use communication_layer_request_reply::TcpRequestReplyConnection;
 use dora_core::descriptor::DescriptorExt;
 use dora_core::{descriptor::Descriptor, topics::DORA_COORDINATOR_PORT_CONTROL_DEFAULT};
@@ -12,7 +15,10 @@ use std::{
 use std::{net::IpAddr, path::PathBuf};
 use termcolor::{Color, ColorChoice, ColorSpec, WriteColor};
 
-pub fn check_environment(coordinator_addr: SocketAddr) -> eyre::Result<()> {
+pub fn check_environment(
+    coordinator_addr: SocketAddr,
+    security: &SecurityArgs,
+) -> eyre::Result<()> {
     let mut error_occurred = false;
 
     let color_choice = if std::io::stdout().is_terminal() {
@@ -24,7 +30,7 @@ pub fn check_environment(coordinator_addr: SocketAddr) -> eyre::Result<()> {
 
     // check whether coordinator is running
     write!(stdout, "Dora Coordinator: ")?;
-    let mut session = match connect_to_coordinator(coordinator_addr) {
+    let mut session = match connect_to_coordinator(coordinator_addr, security) {
         Ok(session) => {
             let _ = stdout.set_color(ColorSpec::new().set_fg(Some(Color::Green)));
             writeln!(stdout, "ok")?;
@@ -92,6 +98,8 @@ pub struct Check {
     /// Port number of the coordinator control server
     #[clap(long, value_name = "PORT", default_value_t = DORA_COORDINATOR_PORT_CONTROL_DEFAULT)]
     coordinator_port: u16,
+    #[clap(flatten)]
+    security: SecurityArgs,
 }
 
 impl Executable for Check {
@@ -107,9 +115,15 @@ impl Executable for Check {
                     .ok_or_else(|| eyre::eyre!("dataflow path has no parent dir"))?
                     .to_owned();
                 Descriptor::blocking_read(&dataflow)?.check(&working_dir)?;
-                check_environment((self.coordinator_addr, self.coordinator_port).into())?
+                check_environment(
+                    (self.coordinator_addr, self.coordinator_port).into(),
+                    &self.security,
+                )?
             }
-            None => check_environment((self.coordinator_addr, self.coordinator_port).into())?,
+            None => check_environment(
+                (self.coordinator_addr, self.coordinator_port).into(),
+                &self.security,
+            )?,
         }
 
         Ok(())
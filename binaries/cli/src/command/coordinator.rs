@@ -1,13 +1,19 @@
 use super::Executable;
 use crate::LISTEN_WILDCARD;
 use dora_coordinator::Event;
-use dora_core::topics::{DORA_COORDINATOR_PORT_CONTROL_DEFAULT, DORA_COORDINATOR_PORT_DEFAULT};
+use dora_core::{
+    security::{DORA_TOKEN_ENV, ServerSecurity},
+    topics::{DORA_COORDINATOR_PORT_CONTROL_DEFAULT, DORA_COORDINATOR_PORT_DEFAULT},
+};
 
 #[cfg(feature = "tracing")]
 use dora_tracing::TracingBuilder;
 
-use eyre::Context;
-use std::net::{IpAddr, SocketAddr};
+use eyre::{Context, bail};
+use std::{
+    net::{IpAddr, SocketAddr},
+    path::PathBuf,
+};
 use tokio::runtime::Builder;
 use tracing::level_filters::LevelFilter;
 
@@ -26,6 +32,27 @@ pub struct Coordinator {
     /// Port number to bind to for control communication
     #[clap(long, default_value_t = DORA_COORDINATOR_PORT_CONTROL_DEFAULT)]
     control_port: u16,
+    /// Persist the coordinator state to the given file and restore it on start.
+    ///
+    /// This allows restarting the coordinator without stopping the running dataflows. The
+    /// daemons reconnect automatically after the restart.
+    #[clap(long, value_name = "PATH")]
+    state_file: Option<PathBuf>,
+    /// Encrypt all connections using TLS with the given certificate chain (PEM)
+    #[clap(long, value_name = "PATH", env = "DORA_TLS_CERT", requires = "tls_key")]
+    tls_cert: Option<PathBuf>,
+    /// Private key (PEM) for the certificate given through `--tls-cert`
+    #[clap(long, value_name = "PATH", env = "DORA_TLS_KEY", requires = "tls_cert")]
+    tls_key: Option<PathBuf>,
+    /// Only accept clients that authenticate with one of the tokens in the given file.
+    ///
+    /// The file contains one token per line. This allows giving each client its own token.
+    /// Can be combined with the `--token` argument.
+    #[clap(long, value_name = "PATH", env = "DORA_TOKEN_FILE")]
+    token_file: Option<PathBuf>,
+    /// Only accept clients that authenticate with the given token
+    #[clap(long, env = DORA_TOKEN_ENV, hide_env_values = true)]
+    token: Option<String>,
     /// Suppresses all log output to stdout.
     #[clap(long)]
     quiet: bool,
@@ -46,6 +73,8 @@ impl Executable for Coordinator {
                 .wrap_err("failed to set up tracing subscriber")?;
         }
 
+        let security = self.server_security()?;
+
         let rt = Builder::new_multi_thread()
             .enable_all()
             .build()
@@ -53,9 +82,14 @@ impl Executable for Coordinator {
         rt.block_on(async {
             let bind = SocketAddr::new(self.interface, self.port);
             let bind_control = SocketAddr::new(self.control_interface, self.control_port);
-            let (port, task) =
-                dora_coordinator::start(bind, bind_control, futures::stream::empty::<Event>())
-                    .await?;
+            let (port, task) = dora_coordinator::start(
+                bind,
+                bind_control,
+                futures::stream::empty::<Event>(),
+                self.state_file,
+                security,
+            )
+            .await?;
             if !self.quiet {
                 println!("Listening for incoming daemon connection on {port}");
             }
@@ -64,3 +98,29 @@ impl Executable for Coordinator {
         .context("failed to run dora-coordinator")
     }
 }
+
+impl Coordinator {
+    fn server_security(&self) -> eyre::Result<ServerSecurity> {
+        let mut tokens: Vec<String> = self.token.iter().cloned().collect();
+        if let Some(path) = &self.token_file {
+            let content = std::fs::read_to_string(path)
+                .wrap_err_with(|| format!("failed to read token file `{}`", path.display()))?;
+            tokens.extend(
+                content
+                    .lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty())
+                    .map(ToOwned::to_owned),
+            );
+            if tokens.is_empty() {
+                bail!("token file `{}` contains no tokens", path.display());
+            }
+        }
+
+        let mut security = ServerSecurity::default().with_tokens(tokens);
+        if let (Some(cert), Some(key)) = (&self.tls_cert, &self.tls_key) {
+            security = security.with_tls(cert, key)?;
+        }
+        Ok(security)
+    }
+}
@@ -1,5 +1,8 @@
 use super::Executable;
-use crate::{common::handle_dataflow_result, session::DataflowSession};
+use crate::{
+    common::{SecurityArgs, handle_dataflow_result},
+    session::DataflowSession,
+};
 use dora_core::topics::{
     DORA_COORDINATOR_PORT_DEFAULT, DORA_DAEMON_LOCAL_LISTEN_PORT_DEFAULT, LOCALHOST,
 };
@@ -22,6 +25,13 @@ pub struct Daemon {
     /// Unique identifier for the machine (required for distributed dataflows)
     #[clap(long)]
     machine_id: Option<String>,
+    /// Label describing a capability of this machine, in the form `key=value`. Can be given
+    /// multiple times.
+    ///
+    /// Nodes select daemons by label through the `labels` field of their deploy section. The
+    /// `arch`, `os`, `cpus`, and `memory_mb` labels are detected automatically.
+    #[clap(long = "label", value_name = "KEY=VALUE", value_parser = parse_label)]
+    labels: Vec<(String, String)>,
     /// Local listen port for event such as dynamic node.
     #[clap(long, default_value_t = DORA_DAEMON_LOCAL_LISTEN_PORT_DEFAULT)]
     local_listen_port: u16,
@@ -36,6 +46,14 @@ pub struct Daemon {
     /// Suppresses all log output to stdout.
     #[clap(long)]
     quiet: bool,
+    /// Serve Prometheus metrics about the dataflow messages on `/metrics` at the given port.
+    #[clap(long)]
+    metrics_port: Option<u16>,
+    /// Address that the metrics server binds to.
+    #[clap(long, default_value_t = LOCALHOST)]
+    metrics_addr: IpAddr,
+    #[clap(flatten)]
+    security: SecurityArgs,
 }
 
 impl Executable for Daemon {
@@ -82,10 +100,22 @@ impl Executable for Daemon {
                         handle_dataflow_result(result, None)
                     }
                     None => {
-                        dora_daemon::Daemon::run(SocketAddr::new(self.coordinator_addr, self.coordinator_port), self.machine_id, self.local_listen_port).await
+                        let metrics_addr = self.metrics_port.map(|port| SocketAddr::new(self.metrics_addr, port));
+                        let security = self.security.client_security()?;
+                        dora_daemon::Daemon::run(SocketAddr::new(self.coordinator_addr, self.coordinator_port), self.machine_id, self.labels.into_iter().collect(), self.local_listen_port, metrics_addr, security).await
                     }
                 }
             })
             .context("failed to run dora-daemon")
     }
 }
+
+fn parse_label(label: &str) -> eyre::Result<(String, String)> {
+    let (key, value) = label
+        .split_once('=')
+        .ok_or_else(|| eyre::eyre!("invalid label `{label}`, expected `key=value`"))?;
+    if key.is_empty() {
+        eyre::bail!("invalid label `{label}`, key must not be empty");
+    }
+    Ok((key.to_owned(), value.to_owned()))
+}
@@ -1,4 +1,5 @@
 use super::{Executable, default_tracing, up};
+use crate::common::SecurityArgs;
 use dora_core::topics::{DORA_COORDINATOR_PORT_CONTROL_DEFAULT, LOCALHOST};
 use std::net::IpAddr;
 use std::path::PathBuf;
@@ -15,6 +16,8 @@ pub struct Destroy {
     /// Port number of the coordinator control server
     #[clap(long, value_name = "PORT", default_value_t = DORA_COORDINATOR_PORT_CONTROL_DEFAULT)]
     coordinator_port: u16,
+    #[clap(flatten)]
+    security: SecurityArgs,
 }
 
 impl Executable for Destroy {
@@ -23,6 +26,7 @@ impl Executable for Destroy {
         up::destroy(
             self.config.as_deref(),
             (self.coordinator_addr, self.coordinator_port).into(),
+            &self.security,
         )
     }
 }
@@ -3,13 +3,13 @@ use std::io::Write;
 use super::{Executable, default_tracing};
 use crate::{
     LOCALHOST,
-    common::{connect_to_coordinator, query_running_dataflows},
+    common::{SecurityArgs, connect_to_coordinator, query_running_dataflows},
 };
 use clap::Args;
 use communication_layer_request_reply::TcpRequestReplyConnection;
 use dora_core::topics::DORA_COORDINATOR_PORT_CONTROL_DEFAULT;
 use dora_message::coordinator_to_cli::DataflowStatus;
-use eyre::eyre;
+use eyre::Context;
 use tabwriter::TabWriter;
 
 #[derive(Debug, Args)]
@@ -21,15 +21,19 @@ pub struct ListArgs {
     /// Port number of the coordinator control server
     #[clap(long, value_name = "PORT", default_value_t = DORA_COORDINATOR_PORT_CONTROL_DEFAULT)]
     pub coordinator_port: u16,
+    #[clap(flatten)]
+    pub security: SecurityArgs,
 }
 
 impl Executable for ListArgs {
     fn execute(self) -> eyre::Result<()> {
         default_tracing()?;
 
-        let mut session =
-            connect_to_coordinator((self.coordinator_addr, self.coordinator_port).into())
-                .map_err(|_| eyre!("Failed to connect to coordinator"))?;
+        let mut session = connect_to_coordinator(
+            (self.coordinator_addr, self.coordinator_port).into(),
+            &self.security,
+        )
+        .wrap_err("Failed to connect to coordinator")?;
 
         list(&mut *session)
     }
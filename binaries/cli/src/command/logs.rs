@@ -1,5 +1,5 @@
 use super::{Executable, default_tracing};
-use crate::common::{connect_to_coordinator, query_running_dataflows};
+use crate::common::{SecurityArgs, connect_to_coordinator, query_running_dataflows};
 use bat::{Input, PrettyPrinter};
 use clap::Args;
 use communication_layer_request_reply::TcpRequestReplyConnection;
@@ -23,15 +23,19 @@ pub struct LogsArgs {
     /// Port number of the coordinator control server
     #[clap(long, value_name = "PORT", default_value_t = DORA_COORDINATOR_PORT_CONTROL_DEFAULT)]
     pub coordinator_port: u16,
+    #[clap(flatten)]
+    pub security: SecurityArgs,
 }
 
 impl Executable for LogsArgs {
     fn execute(self) -> eyre::Result<()> {
         default_tracing()?;
 
-        let mut session =
-            connect_to_coordinator((self.coordinator_addr, self.coordinator_port).into())
-                .wrap_err("failed to connect to dora coordinator")?;
+        let mut session = connect_to_coordinator(
+            (self.coordinator_addr, self.coordinator_port).into(),
+            &self.security,
+        )
+        .wrap_err("failed to connect to dora coordinator")?;
         let list =
             query_running_dataflows(&mut *session).wrap_err("failed to query running dataflows")?;
         if let Some(dataflow) = self.dataflow {
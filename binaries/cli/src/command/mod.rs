@@ -7,32 +7,49 @@ mod graph;
 mod list;
 mod logs;
 mod new;
+mod node;
+mod record;
+mod replay;
+mod restart;
 mod run;
 mod runtime;
 mod self_;
 mod start;
 mod stop;
+mod test;
+mod topic;
 mod up;
 
 pub use build::build;
 pub use run::{run, run_func};
 
+use crate::common::{SecurityArgs, connect_to_coordinator, query_running_dataflows};
 use build::Build;
 use check::Check;
+use communication_layer_request_reply::TcpRequestReplyConnection;
 use coordinator::Coordinator;
 use daemon::Daemon;
 use destroy::Destroy;
-use eyre::Context;
+use dora_core::topics::{DORA_COORDINATOR_PORT_CONTROL_DEFAULT, LOCALHOST};
+use eyre::{Context, bail};
 use graph::Graph;
 use list::ListArgs;
 use logs::LogsArgs;
 use new::NewArgs;
+use node::NodeCommand;
+use record::Record;
+use replay::{Replay, ReplayNode};
+use restart::Restart;
 use run::Run;
 use runtime::Runtime;
 use self_::SelfSubCommand;
 use start::Start;
+use std::net::IpAddr;
 use stop::Stop;
+use test::{Test, TestNode};
+use topic::TopicCommand;
 use up::Up;
+use uuid::Uuid;
 
 /// dora-rs cli client
 #[derive(Debug, clap::Subcommand)]
@@ -42,21 +59,38 @@ pub enum Command {
     Build(Build),
     New(NewArgs),
     Run(Run),
+    Record(Record),
+    Replay(Replay),
+    Test(Test),
     Up(Up),
     Destroy(Destroy),
     Start(Start),
     Stop(Stop),
+    #[command(allow_missing_positional = true)]
+    Restart(Restart),
     List(ListArgs),
     // Planned for future releases:
     // Dashboard,
     #[command(allow_missing_positional = true)]
     Logs(LogsArgs),
+    Topic {
+        #[clap(subcommand)]
+        command: TopicCommand,
+    },
+    Node {
+        #[clap(subcommand)]
+        command: NodeCommand,
+    },
     // Metrics,
     // Stats,
     // Get,
     // Upgrade,
     Daemon(Daemon),
     Runtime(Runtime),
+    #[command(hide = true)]
+    ReplayNode(ReplayNode),
+    #[command(hide = true)]
+    TestNode(TestNode),
     Coordinator(Coordinator),
 
     Self_ {
@@ -78,6 +112,56 @@ fn default_tracing() -> eyre::Result<()> {
     Ok(())
 }
 
+/// Selects a running dataflow by UUID or name.
+#[derive(Debug, clap::Args)]
+struct DataflowSelector {
+    /// Identifier of the dataflow
+    #[clap(long, short, value_name = "UUID_OR_NAME")]
+    dataflow: Option<String>,
+    /// Address of the dora coordinator
+    #[clap(long, value_name = "IP", default_value_t = LOCALHOST)]
+    coordinator_addr: IpAddr,
+    /// Port number of the coordinator control server
+    #[clap(long, value_name = "PORT", default_value_t = DORA_COORDINATOR_PORT_CONTROL_DEFAULT)]
+    coordinator_port: u16,
+    #[clap(flatten)]
+    security: SecurityArgs,
+}
+
+impl DataflowSelector {
+    /// Connects to the coordinator and returns the UUID or name of the selected dataflow.
+    ///
+    /// Asks the user to choose a dataflow if none was given and multiple dataflows are running.
+    fn connect(
+        &self,
+    ) -> eyre::Result<(Box<TcpRequestReplyConnection>, Option<Uuid>, Option<String>)> {
+        let mut session = connect_to_coordinator(
+            (self.coordinator_addr, self.coordinator_port).into(),
+            &self.security,
+        )
+        .wrap_err("failed to connect to dora coordinator")?;
+
+        let (uuid, name) = match &self.dataflow {
+            Some(dataflow) => match Uuid::parse_str(dataflow) {
+                Ok(uuid) => (Some(uuid), None),
+                Err(_) => (None, Some(dataflow.clone())),
+            },
+            None => {
+                let list = query_running_dataflows(&mut *session)
+                    .wrap_err("failed to query running dataflows")?;
+                let active = list.get_active();
+                let selected = match &active[..] {
+                    [] => bail!("No dataflows are running"),
+                    [dataflow] => dataflow.clone(),
+                    _ => inquire::Select::new("Choose dataflow:", active).prompt()?,
+                };
+                (Some(selected.uuid), None)
+            }
+        };
+        Ok((session, uuid, name))
+    }
+}
+
 pub trait Executable {
     fn execute(self) -> eyre::Result<()>;
 }
@@ -91,15 +175,23 @@ impl Executable for Command {
             Command::Build(args) => args.execute(),
             Command::New(args) => args.execute(),
             Command::Run(args) => args.execute(),
+            Command::Record(args) => args.execute(),
+            Command::Replay(args) => args.execute(),
+            Command::Test(args) => args.execute(),
             Command::Up(args) => args.execute(),
             Command::Destroy(args) => args.execute(),
             Command::Start(args) => args.execute(),
             Command::Stop(args) => args.execute(),
+            Command::Restart(args) => args.execute(),
             Command::List(args) => args.execute(),
             Command::Logs(args) => args.execute(),
+            Command::Topic { command } => command.execute(),
+            Command::Node { command } => command.execute(),
             Command::Daemon(args) => args.execute(),
             Command::Self_ { command } => command.execute(),
             Command::Runtime(args) => args.execute(),
+            Command::ReplayNode(args) => args.execute(),
+            Command::TestNode(args) => args.execute(),
         }
     }
 }
@@ -0,0 +1,51 @@
+use std::path::PathBuf;
+
+use crate::command::{DataflowSelector, Executable};
+use dora_message::{
+    cli_to_coordinator::ControlRequest, coordinator_to_cli::ControlRequestReply, descriptor::Node,
+};
+use eyre::{Context, bail};
+
+#[derive(Debug, clap::Args)]
+/// Add a node to a running dataflow.
+///
+/// The node is defined in a YAML file, using the same format as the entries of the `nodes`
+/// list of a dataflow file. Relative paths are resolved against the working directory of
+/// the dataflow. The node is spawned on a machine that already runs the dataflow.
+pub struct Add {
+    /// Path to the YAML file that defines the node
+    #[clap(value_name = "PATH", value_hint = clap::ValueHint::FilePath)]
+    node: PathBuf,
+    #[clap(flatten)]
+    dataflow: DataflowSelector,
+}
+
+impl Executable for Add {
+    fn execute(self) -> eyre::Result<()> {
+        let raw = std::fs::read(&self.node)
+            .wrap_err_with(|| format!("failed to read `{}`", self.node.display()))?;
+        let node: Node = serde_yaml::from_slice(&raw).wrap_err_with(|| {
+            format!("failed to parse node definition `{}`", self.node.display())
+        })?;
+
+        let (mut session, uuid, name) = self.dataflow.connect()?;
+        let request = ControlRequest::AddNode {
+            uuid,
+            name,
+            node: Box::new(node),
+        };
+        let reply_raw = session
+            .request(&serde_json::to_vec(&request).unwrap())
+            .wrap_err("failed to send add node request")?;
+        let reply: ControlRequestReply =
+            serde_json::from_slice(&reply_raw).wrap_err("failed to parse reply")?;
+        match reply {
+            ControlRequestReply::NodeAdded { uuid, node_id } => {
+                println!("added node `{node_id}` to dataflow `{uuid}`");
+                Ok(())
+            }
+            ControlRequestReply::Error(err) => bail!("{err}"),
+            other => bail!("unexpected add node reply: {other:?}"),
+        }
+    }
+}
@@ -0,0 +1,28 @@
+//! The `dora node` commands modify the nodes of a running dataflow.
+//!
+//! Nodes are added or removed without restarting the other nodes of the dataflow, e.g. to
+//! attach debugging or visualization nodes to a running robot.
+
+use super::{Executable, default_tracing};
+use clap::Subcommand;
+
+mod add;
+mod remove;
+
+#[derive(Debug, Subcommand)]
+/// Add nodes to a running dataflow or remove them from it.
+pub enum NodeCommand {
+    Add(add::Add),
+    Remove(remove::Remove),
+}
+
+impl Executable for NodeCommand {
+    fn execute(self) -> eyre::Result<()> {
+        default_tracing()?;
+
+        match self {
+            NodeCommand::Add(args) => args.execute(),
+            NodeCommand::Remove(args) => args.execute(),
+        }
+    }
+}
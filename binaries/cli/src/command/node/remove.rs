@@ -0,0 +1,48 @@
+use std::time::Duration;
+
+use crate::command::{DataflowSelector, Executable};
+use dora_message::{cli_to_coordinator::ControlRequest, coordinator_to_cli::ControlRequestReply};
+use duration_str::parse;
+use eyre::{Context, bail};
+
+#[derive(Debug, clap::Args)]
+/// Stop a node of a running dataflow and remove it from the dataflow.
+///
+/// The inputs that are connected to the outputs of the node are closed. Replicated nodes
+/// are removed together with all their replicas.
+pub struct Remove {
+    /// ID of the node that should be removed
+    #[clap(value_name = "NODE_ID")]
+    node: String,
+    /// Kill the node if it doesn't stop after the given duration
+    #[clap(long, value_name = "DURATION")]
+    #[arg(value_parser = parse)]
+    grace_duration: Option<Duration>,
+    #[clap(flatten)]
+    dataflow: DataflowSelector,
+}
+
+impl Executable for Remove {
+    fn execute(self) -> eyre::Result<()> {
+        let (mut session, uuid, name) = self.dataflow.connect()?;
+        let request = ControlRequest::RemoveNode {
+            uuid,
+            name,
+            node_id: self.node.into(),
+            grace_duration: self.grace_duration,
+        };
+        let reply_raw = session
+            .request(&serde_json::to_vec(&request).unwrap())
+            .wrap_err("failed to send remove node request")?;
+        let reply: ControlRequestReply =
+            serde_json::from_slice(&reply_raw).wrap_err("failed to parse reply")?;
+        match reply {
+            ControlRequestReply::NodeRemoved { uuid, node_id } => {
+                println!("removed node `{node_id}` from dataflow `{uuid}`");
+                Ok(())
+            }
+            ControlRequestReply::Error(err) => bail!("{err}"),
+            other => bail!("unexpected remove node reply: {other:?}"),
+        }
+    }
+}
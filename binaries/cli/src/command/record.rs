@@ -0,0 +1,37 @@
+//! The `dora record` command runs a dataflow locally and records all node outputs to disk.
+//!
+//! The recorded outputs can be replayed using `dora replay`.
+
+use super::{Executable, run::run_modified};
+use eyre::Context;
+use std::path::PathBuf;
+
+#[derive(Debug, clap::Args)]
+/// Run a dataflow locally and record all outputs.
+///
+/// Each output is written to an Arrow IPC file at
+/// `<OUTPUT>/<dataflow-id>/<node-id>/<output-id>.arrow`. Use `dora replay`
+/// to feed the recorded outputs back into a dataflow.
+pub struct Record {
+    /// Path to the dataflow descriptor file
+    #[clap(value_name = "PATH")]
+    dataflow: String,
+    /// Directory to write the recordings to
+    #[clap(long, short, value_name = "DIR", default_value = "recordings")]
+    output: PathBuf,
+    // Use UV to run nodes.
+    #[clap(long, action)]
+    uv: bool,
+}
+
+impl Executable for Record {
+    fn execute(self) -> eyre::Result<()> {
+        let record_dir = std::env::current_dir()
+            .context("failed to get current working dir")?
+            .join(self.output);
+        run_modified(self.dataflow, self.uv, |descriptor| {
+            descriptor.debug.record_dir = Some(record_dir);
+            Ok(())
+        })
+    }
+}
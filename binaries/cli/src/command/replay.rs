@@ -0,0 +1,147 @@
+//! The `dora replay` command runs a dataflow locally, replacing some of its nodes with
+//! outputs recorded through `dora record`.
+//!
+//! The replaced nodes are substituted by replay nodes that send out the recorded messages
+//! with their original timing. All other nodes are started normally.
+
+use super::{Executable, run::run_modified};
+use dora_core::config::NodeId;
+use dora_daemon::record::{read_recorded_node, recorded_outputs};
+use dora_message::descriptor::EnvValue;
+use dora_node_api::{DoraNode, Event, EventStream, StopCause, uhlc::NTP64};
+use eyre::{Context, ContextCompat};
+use std::{
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+const REPLAY_SOURCE_ENV: &str = "DORA_REPLAY_SOURCE";
+
+#[derive(Debug, clap::Args)]
+/// Replay recorded outputs into a dataflow.
+///
+/// Runs the given dataflow locally, but replaces the given nodes by replay nodes that
+/// send out the outputs recorded by `dora record`, using the original timing.
+pub struct Replay {
+    /// Path to the dataflow descriptor file
+    #[clap(value_name = "PATH")]
+    dataflow: String,
+    /// Recording of a single dataflow run, i.e. `<record-dir>/<dataflow-id>`
+    #[clap(long, value_name = "DIR")]
+    recording: PathBuf,
+    /// ID of a node that should be replaced by its recorded outputs (can be given multiple times)
+    #[clap(long = "node", value_name = "NODE_ID", required = true)]
+    nodes: Vec<NodeId>,
+    // Use UV to run nodes.
+    #[clap(long, action)]
+    uv: bool,
+}
+
+impl Executable for Replay {
+    fn execute(self) -> eyre::Result<()> {
+        let recording = self
+            .recording
+            .canonicalize()
+            .wrap_err_with(|| format!("recording `{}` not found", self.recording.display()))?;
+        let current_exe = std::env::current_exe().context("failed to get current executable")?;
+
+        run_modified(self.dataflow, self.uv, |descriptor| {
+            for node_id in self.nodes {
+                let node = descriptor
+                    .nodes
+                    .iter_mut()
+                    .find(|n| n.id == node_id)
+                    .wrap_err_with(|| format!("no node with ID `{node_id}` in dataflow"))?;
+                let source = recording.join(node_id.to_string());
+                let outputs = recorded_outputs(&source)
+                    .wrap_err_with(|| format!("no recording found for node `{node_id}`"))?;
+
+                node.path = Some(current_exe.to_string_lossy().into_owned());
+                node.args = Some("replay-node".into());
+                node.env.get_or_insert_default().insert(
+                    REPLAY_SOURCE_ENV.into(),
+                    EnvValue::String(source.to_string_lossy().into_owned()),
+                );
+                // keep declared output types to check the replayed messages
+                node.outputs = outputs
+                    .into_keys()
+                    .map(|id| node.outputs.get(&id).cloned().unwrap_or_else(|| id.into()))
+                    .collect();
+                node.inputs = Default::default();
+                node.operators = None;
+                node.operator = None;
+                node.custom = None;
+                node.send_stdout_as = None;
+                node.build = None;
+                node.replicas = None;
+                node.git = None;
+                node.branch = None;
+                node.tag = None;
+                node.rev = None;
+            }
+            Ok(())
+        })
+    }
+}
+
+#[derive(Debug, clap::Args)]
+/// Run a replay node (used by `dora replay`)
+pub struct ReplayNode;
+
+impl Executable for ReplayNode {
+    fn execute(self) -> eyre::Result<()> {
+        let source = std::env::var(REPLAY_SOURCE_ENV)
+            .wrap_err_with(|| format!("env variable {REPLAY_SOURCE_ENV} must be set"))?;
+        let mut messages = read_recorded_node(source.as_ref())?;
+
+        let (mut node, mut events) = DoraNode::init_from_env()?;
+
+        let Some(first) = messages.next().transpose()? else {
+            return Ok(());
+        };
+        let first_timestamp = first.timestamp;
+        let start = Instant::now();
+        let mut events_closed = false;
+        for message in std::iter::once(Ok(first)).chain(messages) {
+            let message = message?;
+            let offset = NTP64(message.timestamp.saturating_sub(first_timestamp)).to_duration();
+            if !wait_until(&mut events, start + offset, &mut events_closed) {
+                break;
+            }
+            let type_info = message.metadata.type_info;
+            let parameters = message.metadata.parameters;
+            match message.data {
+                Some(data) => node.send_typed_output(
+                    message.output_id,
+                    type_info,
+                    parameters,
+                    data.len(),
+                    |out| out.copy_from_slice(&data),
+                )?,
+                None => node.send_output_sample(message.output_id, type_info, parameters, None)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Waits until the given deadline while handling incoming events.
+///
+/// Returns `false` if the node was stopped in the meantime.
+fn wait_until(events: &mut EventStream, deadline: Instant, events_closed: &mut bool) -> bool {
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining == Duration::ZERO {
+            return true;
+        }
+        if *events_closed {
+            std::thread::sleep(remaining);
+            return true;
+        }
+        match events.recv_timeout(remaining) {
+            Some(Event::Stop(StopCause::Manual)) => return false,
+            Some(_) => {}
+            None => *events_closed = true,
+        }
+    }
+}
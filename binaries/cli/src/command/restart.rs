@@ -0,0 +1,83 @@
+use super::{Executable, default_tracing};
+use crate::common::{SecurityArgs, connect_to_coordinator, query_running_dataflows};
+use dora_core::topics::{DORA_COORDINATOR_PORT_CONTROL_DEFAULT, LOCALHOST};
+use dora_message::{cli_to_coordinator::ControlRequest, coordinator_to_cli::ControlRequestReply};
+use duration_str::parse;
+use eyre::{Context, bail};
+use std::{net::IpAddr, time::Duration};
+use uuid::Uuid;
+
+#[derive(Debug, clap::Args)]
+/// Restart a single node of a running dataflow.
+///
+/// The node is stopped and spawned again with the same configuration. Its inputs and
+/// outputs stay connected, so the other nodes of the dataflow don't notice the restart.
+pub struct Restart {
+    /// Identifier of the dataflow
+    #[clap(value_name = "UUID_OR_NAME")]
+    dataflow: Option<String>,
+    /// ID of the node that should be restarted
+    #[clap(value_name = "NODE_ID")]
+    node: String,
+    /// Kill the node if it doesn't stop after the given duration
+    #[clap(long, value_name = "DURATION")]
+    #[arg(value_parser = parse)]
+    grace_duration: Option<Duration>,
+    /// Address of the dora coordinator
+    #[clap(long, value_name = "IP", default_value_t = LOCALHOST)]
+    coordinator_addr: IpAddr,
+    /// Port number of the coordinator control server
+    #[clap(long, value_name = "PORT", default_value_t = DORA_COORDINATOR_PORT_CONTROL_DEFAULT)]
+    coordinator_port: u16,
+    #[clap(flatten)]
+    security: SecurityArgs,
+}
+
+impl Executable for Restart {
+    fn execute(self) -> eyre::Result<()> {
+        default_tracing()?;
+
+        let mut session = connect_to_coordinator(
+            (self.coordinator_addr, self.coordinator_port).into(),
+            &self.security,
+        )
+        .wrap_err("failed to connect to dora coordinator")?;
+        let (uuid, name) = match self.dataflow {
+            Some(dataflow) => match Uuid::parse_str(&dataflow) {
+                Ok(uuid) => (Some(uuid), None),
+                Err(_) => (None, Some(dataflow)),
+            },
+            None => {
+                let list = query_running_dataflows(&mut *session)
+                    .wrap_err("failed to query running dataflows")?;
+                let active = list.get_active();
+                let selected = match &active[..] {
+                    [] => bail!("No dataflows are running"),
+                    [dataflow] => dataflow.clone(),
+                    _ => inquire::Select::new("Choose dataflow:", active).prompt()?,
+                };
+                (Some(selected.uuid), None)
+            }
+        };
+
+        let request = ControlRequest::RestartNode {
+            uuid,
+            name,
+            node_id: self.node.into(),
+            grace_duration: self.grace_duration,
+        };
+        let reply_raw = session
+            .request(&serde_json::to_vec(&request).unwrap())
+            .wrap_err("failed to send restart request")?;
+        let reply: ControlRequestReply =
+            serde_json::from_slice(&reply_raw).wrap_err("failed to parse reply")?;
+        match reply {
+            ControlRequestReply::NodeRestarted { uuid, node_id } => {
+                println!("restarting node `{node_id}` of dataflow `{uuid}`");
+                Ok(())
+            }
+            ControlRequestReply::Error(err) => bail!("{err}"),
+            other => bail!("unexpected restart reply: {other:?}"),
+        }
+    }
+}
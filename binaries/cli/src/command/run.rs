@@ -11,7 +11,9 @@ use crate::{
     output::print_log_message,
     session::DataflowSession,
 };
+use dora_core::descriptor::{Descriptor, DescriptorExt};
 use dora_daemon::{Daemon, LogDestination, flume};
+use dora_message::coordinator_to_cli::DataflowResult;
 use dora_tracing::TracingBuilder;
 use eyre::Context;
 use tokio::runtime::Builder;
@@ -36,6 +38,25 @@ pub fn run_func(dataflow: String, uv: bool) -> eyre::Result<()> {
 }
 
 pub fn run(dataflow: String, uv: bool) -> eyre::Result<()> {
+    run_modified(dataflow, uv, |_| Ok(()))
+}
+
+/// Runs the given dataflow locally after applying the given modification to its descriptor.
+pub(crate) fn run_modified(
+    dataflow: String,
+    uv: bool,
+    modify: impl FnOnce(&mut Descriptor) -> eyre::Result<()>,
+) -> eyre::Result<()> {
+    let result = run_dataflow_modified(dataflow, uv, modify)?;
+    handle_dataflow_result(result, None)
+}
+
+/// Like [`run_modified`], but returns the dataflow result instead of reporting it.
+pub(crate) fn run_dataflow_modified(
+    dataflow: String,
+    uv: bool,
+    modify: impl FnOnce(&mut Descriptor) -> eyre::Result<()>,
+) -> eyre::Result<DataflowResult> {
     #[cfg(feature = "tracing")]
     {
         let log_level = std::env::var("RUST_LOG").ok().unwrap_or("info".to_string());
@@ -48,6 +69,9 @@ pub fn run(dataflow: String, uv: bool) -> eyre::Result<()> {
     let dataflow_path = resolve_dataflow(dataflow).context("could not resolve dataflow")?;
     let dataflow_session =
         DataflowSession::read_session(&dataflow_path).context("failed to read DataflowSession")?;
+    let mut descriptor =
+        Descriptor::blocking_read(&dataflow_path).wrap_err("Failed to read yaml dataflow")?;
+    modify(&mut descriptor)?;
     let rt = Builder::new_multi_thread()
         .enable_all()
         .build()
@@ -60,15 +84,15 @@ pub fn run(dataflow: String, uv: bool) -> eyre::Result<()> {
         }
     });
 
-    let result = rt.block_on(Daemon::run_dataflow(
+    rt.block_on(Daemon::run_dataflow_descriptor(
         &dataflow_path,
+        descriptor,
         dataflow_session.build_id,
         dataflow_session.local_build,
         dataflow_session.session_id,
         uv,
         LogDestination::Channel { sender: log_tx },
-    ))?;
-    handle_dataflow_result(result, None)
+    ))
 }
 
 impl Executable for Run {
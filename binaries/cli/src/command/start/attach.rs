@@ -1,35 +1,58 @@
-use communication_layer_request_reply::{TcpConnection, TcpRequestReplyConnection};
-use dora_core::descriptor::{CoreNodeKind, Descriptor, DescriptorExt, resolve_path};
+use communication_layer_request_reply::TcpRequestReplyConnection;
+use dora_core::{
+    adjust_shared_library_path,
+    descriptor::{
+        CoreNodeKind, DYNAMIC_SOURCE, Descriptor, DescriptorExt, OperatorSource, ResolvedNode,
+        SHELL_SOURCE, resolve_path, source_is_url,
+    },
+};
 use dora_message::cli_to_coordinator::ControlRequest;
 use dora_message::common::LogMessage;
 use dora_message::coordinator_to_cli::ControlRequestReply;
+use dora_message::id::{NodeId, OperatorId};
 use eyre::Context;
 use notify::event::ModifyKind;
 use notify::{Config, Event as NotifyEvent, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::env::consts::EXE_EXTENSION;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
 use std::{
-    collections::HashMap,
-    net::{SocketAddr, TcpStream},
+    sync::mpsc,
+    time::{Duration, SystemTime},
 };
-use std::{path::PathBuf, sync::mpsc, time::Duration};
-use tracing::{error, info};
+use tracing::{error, info, warn};
 use uuid::Uuid;
 
-use crate::common::handle_dataflow_result;
+use crate::command::build::build_node_locally;
+use crate::common::{SecurityArgs, handle_dataflow_result, open_coordinator_connection};
 use crate::output::print_log_message;
+use crate::session::DataflowSession;
+
+/// Time to wait for further file changes before restarting a node.
+///
+/// Build tools often write their outputs in multiple steps, so we wait until the
+/// files are no longer modified.
+const RESTART_DEBOUNCE: Duration = Duration::from_millis(500);
 
+#[allow(clippy::too_many_arguments)]
 pub fn attach_dataflow(
     dataflow: Descriptor,
     dataflow_path: PathBuf,
     dataflow_id: Uuid,
     session: &mut TcpRequestReplyConnection,
     hot_reload: bool,
+    uv: bool,
     coordinator_socket: SocketAddr,
+    security: &SecurityArgs,
     log_level: log::LevelFilter,
 ) -> Result<(), eyre::ErrReport> {
     let (tx, rx) = mpsc::sync_channel(2);
 
     // Generate path hashmap
-    let mut node_path_lookup = HashMap::new();
+    let mut node_path_lookup: HashMap<PathBuf, Vec<ReloadTarget>> = HashMap::new();
+    // Nodes with a build command are rebuilt when their sources change instead.
+    let mut buildable_nodes: BTreeMap<NodeId, BuildableNode> = BTreeMap::new();
 
     let nodes = dataflow.resolve_aliases_and_set_defaults()?;
 
@@ -43,22 +66,79 @@ pub fn attach_dataflow(
         .to_owned();
 
     for node in nodes.into_values() {
-        match node.kind {
-            // Reloading Custom Nodes is not supported. See: https://github.com/dora-rs/dora/pull/239#discussion_r1154313139
-            CoreNodeKind::Custom(_cn) => (),
+        let node_working_dir = match node.deploy.as_ref().and_then(|d| d.working_dir.as_deref()) {
+            Some(dir) => working_dir.join(dir),
+            None => working_dir.clone(),
+        };
+        match &node.kind {
+            CoreNodeKind::Custom(cn) => {
+                if cn.source.is_git() {
+                    continue;
+                }
+                if let Some(path) = local_node_path(&cn.path, &node_working_dir) {
+                    if cn.build.is_some() {
+                        BuildableNode::add(&mut buildable_nodes, &node, path);
+                    } else {
+                        node_path_lookup
+                            .entry(path)
+                            .or_default()
+                            .push(ReloadTarget::Node(node.id.clone()));
+                    }
+                }
+            }
             CoreNodeKind::Runtime(rn) => {
                 for op in rn.operators.iter() {
-                    if let dora_core::descriptor::OperatorSource::Python(python_source) =
-                        &op.config.source
-                    {
-                        let path = resolve_path(&python_source.source, &working_dir)
-                            .wrap_err_with(|| {
-                                format!("failed to resolve node source `{}`", python_source.source)
-                            })?;
-                        node_path_lookup
-                            .insert(path, (dataflow_id, node.id.clone(), Some(op.id.clone())));
+                    match &op.config.source {
+                        OperatorSource::Python(python_source) => {
+                            let path = resolve_path(&python_source.source, &working_dir)
+                                .wrap_err_with(|| {
+                                    format!(
+                                        "failed to resolve node source `{}`",
+                                        python_source.source
+                                    )
+                                })?;
+                            node_path_lookup.entry(path).or_default().push(
+                                ReloadTarget::Operator {
+                                    node_id: node.id.clone(),
+                                    operator_id: op.id.clone(),
+                                },
+                            );
+                        }
+                        OperatorSource::SharedLibrary(source) => {
+                            if source_is_url(source) {
+                                continue;
+                            }
+                            let path = adjust_shared_library_path(Path::new(source))?;
+                            match dunce::canonicalize(node_working_dir.join(&path)) {
+                                Ok(path) if op.config.build.is_some() => {
+                                    BuildableNode::add(&mut buildable_nodes, &node, path)
+                                }
+                                Ok(path) => node_path_lookup
+                                    .entry(path)
+                                    .or_default()
+                                    .push(ReloadTarget::Node(node.id.clone())),
+                                Err(err) => warn!(
+                                    "not watching shared library `{}` of operator `{}/{}`: {err}",
+                                    path.display(),
+                                    node.id,
+                                    op.id
+                                ),
+                            }
+                        }
+                        OperatorSource::Wasm(source) => {
+                            if source_is_url(source) {
+                                continue;
+                            }
+                            if let Ok(path) = dunce::canonicalize(node_working_dir.join(source)) {
+                                node_path_lookup.entry(path).or_default().push(
+                                    ReloadTarget::Operator {
+                                        node_id: node.id.clone(),
+                                        operator_id: op.id.clone(),
+                                    },
+                                );
+                            }
+                        }
                     }
-                    // Reloading non-python operator is not supported. See: https://github.com/dora-rs/dora/pull/239#discussion_r1154313139
                 }
             }
         }
@@ -67,28 +147,113 @@ pub fn attach_dataflow(
     // Setup dataflow file watcher if reload option is set.
     let watcher_tx = tx.clone();
     let _watcher = if hot_reload {
-        let hash = node_path_lookup.clone();
-        let paths = hash.keys();
+        // Build tools and editors often replace files instead of modifying them, which
+        // would remove a watch on the file itself. So we watch the parent directories.
+        // The sources of nodes with a build command are expected in the dataflow directory.
+        let source_dir = (!buildable_nodes.is_empty()).then(|| working_dir.clone());
+        let watched_dirs: BTreeSet<PathBuf> = node_path_lookup
+            .keys()
+            .filter_map(|path| path.parent())
+            .filter(|dir| source_dir.as_ref().is_none_or(|src| !dir.starts_with(src)))
+            .map(|dir| dir.to_owned())
+            .collect();
+        let recursive_dir = source_dir.clone();
+        let buildable_node_ids: Vec<_> = buildable_nodes.keys().cloned().collect();
+
+        // Custom nodes and shared library operators can't be reloaded in place, so they are
+        // restarted instead, after rebuilding them if they have a build command.
+        let (restart_tx, restart_rx) = mpsc::channel();
+        if !buildable_nodes.is_empty()
+            || node_path_lookup
+                .values()
+                .flatten()
+                .any(|target| matches!(target, ReloadTarget::Node(_)))
+        {
+            let session_id = if buildable_nodes.is_empty() {
+                None
+            } else {
+                let session = DataflowSession::read_session(&dataflow_path)
+                    .context("failed to read DataflowSession")?;
+                Some(session.session_id)
+            };
+            let working_dir = working_dir.clone();
+            let control_tx = tx.clone();
+            std::thread::spawn(move || {
+                for node_id in changed_nodes(&restart_rx) {
+                    if let (Some(node), Some(session_id)) =
+                        (buildable_nodes.get_mut(&node_id), session_id)
+                    {
+                        info!("rebuilding node `{node_id}`");
+                        let result = build_node_locally(
+                            node.node.clone(),
+                            session_id,
+                            working_dir.clone(),
+                            uv,
+                        );
+                        // skip the file changes that were caused by the build itself
+                        while restart_rx.try_recv().is_ok() {}
+                        if let Err(err) = result {
+                            error!("failed to rebuild node `{node_id}`: {err:?}");
+                            continue;
+                        }
+                        if !node.update_modified() {
+                            info!("node `{node_id}` is up to date, not restarting it");
+                            continue;
+                        }
+                    }
+                    let request = ControlRequest::RestartNode {
+                        uuid: Some(dataflow_id),
+                        name: None,
+                        node_id,
+                        grace_duration: None,
+                    };
+                    if control_tx.send(AttachEvent::Control(request)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+
         let notifier = move |event| {
-            if let Ok(NotifyEvent {
-                paths,
-                kind: EventKind::Modify(ModifyKind::Data(_data)),
-                ..
-            }) = event
-            {
-                for path in paths {
-                    if let Some((dataflow_id, node_id, operator_id)) = node_path_lookup.get(&path) {
-                        watcher_tx
-                            .send(AttachEvent::Control(ControlRequest::Reload {
-                                dataflow_id: *dataflow_id,
-                                node_id: node_id.clone(),
-                                operator_id: operator_id.clone(),
-                            }))
-                            .context("Could not send reload request to the cli loop")
-                            .unwrap();
+            if let Ok(NotifyEvent { paths, kind, .. }) = event {
+                if !matches!(
+                    kind,
+                    EventKind::Create(_)
+                        | EventKind::Modify(ModifyKind::Data(_) | ModifyKind::Name(_))
+                ) {
+                    return;
+                }
+                if let Some(source_dir) = &source_dir {
+                    if paths.iter().any(|path| is_source_file(path, source_dir)) {
+                        for node_id in &buildable_node_ids {
+                            let _ = restart_tx.send(node_id.clone());
+                        }
+                    }
+                }
+                let targets = paths
+                    .iter()
+                    .filter_map(|path| node_path_lookup.get(path))
+                    .flatten();
+                for target in targets {
+                    match target {
+                        ReloadTarget::Operator {
+                            node_id,
+                            operator_id,
+                        } => {
+                            watcher_tx
+                                .send(AttachEvent::Control(ControlRequest::Reload {
+                                    dataflow_id,
+                                    node_id: node_id.clone(),
+                                    operator_id: Some(operator_id.clone()),
+                                }))
+                                .context("Could not send reload request to the cli loop")
+                                .unwrap();
+                        }
+                        ReloadTarget::Node(node_id) => {
+                            let _ = restart_tx.send(node_id.clone());
+                        }
                     }
                 }
-                // TODO: Manage different file event
             }
         };
 
@@ -97,8 +262,11 @@ pub fn attach_dataflow(
             Config::default().with_poll_interval(Duration::from_secs(1)),
         )?;
 
-        for path in paths {
-            watcher.watch(path, RecursiveMode::Recursive)?;
+        for dir in watched_dirs {
+            watcher.watch(&dir, RecursiveMode::NonRecursive)?;
+        }
+        if let Some(dir) = &recursive_dir {
+            watcher.watch(dir, RecursiveMode::Recursive)?;
         }
         Some(watcher)
     } else {
@@ -127,10 +295,8 @@ pub fn attach_dataflow(
     .wrap_err("failed to set ctrl-c handler")?;
 
     // subscribe to log messages
-    let mut log_session = TcpConnection {
-        stream: TcpStream::connect(coordinator_socket)
-            .wrap_err("failed to connect to dora coordinator")?,
-    };
+    let mut log_session = open_coordinator_connection(coordinator_socket, security)
+        .wrap_err("failed to connect to dora coordinator")?;
     log_session
         .send(
             &serde_json::to_vec(&ControlRequest::LogSubscribe {
@@ -180,6 +346,10 @@ pub fn attach_dataflow(
             ControlRequestReply::DataflowReloaded { uuid } => {
                 info!("dataflow {uuid} reloaded")
             }
+            ControlRequestReply::NodeRestarted { uuid: _, node_id } => {
+                info!("restarting node `{node_id}`")
+            }
+            ControlRequestReply::Error(err) => error!("{err}"),
             other => error!("Received unexpected Coordinator Reply: {:#?}", other),
         };
     }
@@ -189,3 +359,151 @@ enum AttachEvent {
     Control(ControlRequest),
     Log(eyre::Result<LogMessage>),
 }
+
+/// Describes how a dataflow is updated when a watched file changes.
+enum ReloadTarget {
+    /// Python and WASM operators are reloaded in place.
+    Operator {
+        node_id: NodeId,
+        operator_id: OperatorId,
+    },
+    /// Custom nodes and shared library operators are restarted.
+    Node(NodeId),
+}
+
+/// A custom node or runtime node with a build command.
+struct BuildableNode {
+    node: ResolvedNode,
+    /// Executables or shared libraries that are produced by the build.
+    artifacts: Vec<PathBuf>,
+    /// Modification times of the `artifacts` when the node was last (re)started.
+    modified: Vec<Option<SystemTime>>,
+}
+
+impl BuildableNode {
+    fn add(nodes: &mut BTreeMap<NodeId, BuildableNode>, node: &ResolvedNode, artifact: PathBuf) {
+        let entry = nodes
+            .entry(node.id.clone())
+            .or_insert_with(|| BuildableNode {
+                node: node.clone(),
+                artifacts: Vec::new(),
+                modified: Vec::new(),
+            });
+        entry.modified.push(modified_time(&artifact));
+        entry.artifacts.push(artifact);
+    }
+
+    /// Updates the modification times of the artifacts and returns whether they changed.
+    fn update_modified(&mut self) -> bool {
+        let modified: Vec<_> = self.artifacts.iter().map(|a| modified_time(a)).collect();
+        let changed = modified != self.modified;
+        self.modified = modified;
+        changed
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Checks whether a changed file might be a source file in the given directory.
+///
+/// Changes in hidden files and directories, e.g. editor swap files or `.git`, are ignored.
+fn is_source_file(path: &Path, source_dir: &Path) -> bool {
+    path.strip_prefix(source_dir).is_ok_and(|relative| {
+        !relative
+            .components()
+            .any(|c| c.as_os_str().to_string_lossy().starts_with('.'))
+    })
+}
+
+/// Returns the path of the executable of a custom node, if it is a local file.
+fn local_node_path(source: &str, working_dir: &Path) -> Option<PathBuf> {
+    if source == DYNAMIC_SOURCE || source == SHELL_SOURCE || source_is_url(source) {
+        return None;
+    }
+    let path = Path::new(source);
+    let path = if path.extension().is_none() {
+        path.with_extension(EXE_EXTENSION)
+    } else {
+        path.to_owned()
+    };
+    dunce::canonicalize(working_dir.join(path)).ok()
+}
+
+/// Yields the IDs of changed nodes, after waiting for the changes to settle.
+fn changed_nodes(rx: &mpsc::Receiver<NodeId>) -> impl Iterator<Item = NodeId> + '_ {
+    std::iter::from_fn(move || {
+        let first = rx.recv().ok()?;
+        let mut changed = BTreeSet::from([first]);
+        while let Ok(node_id) = rx.recv_timeout(RESTART_DEBOUNCE) {
+            changed.insert(node_id);
+        }
+        Some(changed)
+    })
+    .flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn debounce_changed_nodes() {
+        let (tx, rx) = mpsc::channel();
+        for node in ["b", "a", "b"] {
+            tx.send(NodeId::from(node.to_owned())).unwrap();
+        }
+        let sender = std::thread::spawn(move || {
+            std::thread::sleep(RESTART_DEBOUNCE * 3);
+            tx.send(NodeId::from("a".to_owned())).unwrap();
+        });
+
+        let changed: Vec<_> = changed_nodes(&rx).map(|id| id.to_string()).collect();
+        sender.join().unwrap();
+        assert_eq!(changed, ["a", "b", "a"]);
+    }
+
+    #[test]
+    fn detect_source_files() {
+        let dir = Path::new("/dataflow");
+        assert!(is_source_file(Path::new("/dataflow/src/main.rs"), dir));
+        assert!(is_source_file(Path::new("/dataflow/Cargo.toml"), dir));
+        assert!(!is_source_file(Path::new("/dataflow/.git/index"), dir));
+        assert!(!is_source_file(
+            Path::new("/dataflow/src/.main.rs.swp"),
+            dir
+        ));
+        assert!(!is_source_file(Path::new("/other/main.rs"), dir));
+    }
+
+    #[test]
+    fn restart_only_changed_artifacts() {
+        let descriptor = Descriptor::parse(
+            b"nodes:\n  - id: camera\n    path: camera\n    build: cargo build\n".to_vec(),
+        )
+        .unwrap();
+        let nodes = descriptor.resolve_aliases_and_set_defaults().unwrap();
+        let artifact = std::env::temp_dir().join(format!("dora-attach-{}", Uuid::new_v4()));
+        std::fs::write(&artifact, "v1").unwrap();
+
+        let mut buildable = BTreeMap::new();
+        for node in nodes.values() {
+            BuildableNode::add(&mut buildable, node, artifact.clone());
+        }
+        let node = buildable.values_mut().next().unwrap();
+        assert!(!node.update_modified());
+
+        let file = std::fs::File::options()
+            .write(true)
+            .open(&artifact)
+            .unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(10))
+            .unwrap();
+        assert!(node.update_modified());
+        assert!(!node.update_modified());
+
+        std::fs::remove_file(&artifact).unwrap();
+        assert!(node.update_modified());
+    }
+}
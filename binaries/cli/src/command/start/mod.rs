@@ -5,11 +5,14 @@
 use super::{Executable, default_tracing};
 use crate::{
     command::start::attach::attach_dataflow,
-    common::{connect_to_coordinator, local_working_dir, resolve_dataflow},
+    common::{
+        SecurityArgs, connect_to_coordinator, local_working_dir, open_coordinator_connection,
+        resolve_dataflow,
+    },
     output::print_log_message,
     session::DataflowSession,
 };
-use communication_layer_request_reply::{TcpConnection, TcpRequestReplyConnection};
+use communication_layer_request_reply::TcpRequestReplyConnection;
 use dora_core::{
     descriptor::{Descriptor, DescriptorExt},
     topics::{DORA_COORDINATOR_PORT_CONTROL_DEFAULT, LOCALHOST},
@@ -19,7 +22,7 @@ use dora_message::{
 };
 use eyre::{Context, bail};
 use std::{
-    net::{IpAddr, SocketAddr, TcpStream},
+    net::{IpAddr, SocketAddr},
     path::PathBuf,
 };
 use uuid::Uuid;
@@ -47,12 +50,19 @@ pub struct Start {
     /// Run the dataflow in background
     #[clap(long, action)]
     detach: bool,
-    /// Enable hot reloading (Python only)
+    /// Enable hot reloading
+    ///
+    /// Python and WASM operators are reloaded in place. Custom nodes and shared library operators
+    /// are restarted when their `path` changes. If they have a `build` command, they are rebuilt
+    /// when a file in the dataflow directory changes instead, and restarted if the build changed
+    /// their `path`.
     #[clap(long, action)]
     hot_reload: bool,
     // Use UV to run nodes.
     #[clap(long, action)]
     uv: bool,
+    #[clap(flatten)]
+    security: SecurityArgs,
 }
 
 impl Executable for Start {
@@ -60,8 +70,13 @@ impl Executable for Start {
         default_tracing()?;
         let coordinator_socket = (self.coordinator_addr, self.coordinator_port).into();
 
-        let (dataflow, dataflow_descriptor, mut session, dataflow_id) =
-            start_dataflow(self.dataflow, self.name, coordinator_socket, self.uv)?;
+        let (dataflow, dataflow_descriptor, mut session, dataflow_id) = start_dataflow(
+            self.dataflow,
+            self.name,
+            coordinator_socket,
+            &self.security,
+            self.uv,
+        )?;
 
         let attach = match (self.attach, self.detach) {
             (true, true) => eyre::bail!("both `--attach` and `--detach` are given"),
@@ -86,7 +101,9 @@ impl Executable for Start {
                 dataflow_id,
                 &mut *session,
                 self.hot_reload,
+                self.uv,
                 coordinator_socket,
+                &self.security,
                 log_level,
             )
         } else {
@@ -96,6 +113,7 @@ impl Executable for Start {
                 dataflow_id,
                 &mut session,
                 coordinator_socket,
+                &self.security,
                 log::LevelFilter::Info,
                 print_daemon_name,
             )
@@ -107,6 +125,7 @@ fn start_dataflow(
     dataflow: String,
     name: Option<String>,
     coordinator_socket: SocketAddr,
+    security: &SecurityArgs,
     uv: bool,
 ) -> Result<(PathBuf, Descriptor, Box<TcpRequestReplyConnection>, Uuid), eyre::Error> {
     let dataflow = resolve_dataflow(dataflow).context("could not resolve dataflow")?;
@@ -115,7 +134,7 @@ fn start_dataflow(
     let dataflow_session =
         DataflowSession::read_session(&dataflow).context("failed to read DataflowSession")?;
 
-    let mut session = connect_to_coordinator(coordinator_socket)
+    let mut session = connect_to_coordinator(coordinator_socket, security)
         .wrap_err("failed to connect to dora coordinator")?;
 
     let local_working_dir = local_working_dir(&dataflow, &dataflow_descriptor, &mut *session)?;
@@ -155,14 +174,13 @@ fn wait_until_dataflow_started(
     dataflow_id: Uuid,
     session: &mut Box<TcpRequestReplyConnection>,
     coordinator_addr: SocketAddr,
+    security: &SecurityArgs,
     log_level: log::LevelFilter,
     print_daemon_id: bool,
 ) -> eyre::Result<()> {
     // subscribe to log messages
-    let mut log_session = TcpConnection {
-        stream: TcpStream::connect(coordinator_addr)
-            .wrap_err("failed to connect to dora coordinator")?,
-    };
+    let mut log_session = open_coordinator_connection(coordinator_addr, security)
+        .wrap_err("failed to connect to dora coordinator")?;
     log_session
         .send(
             &serde_json::to_vec(&ControlRequest::LogSubscribe {
@@ -1,5 +1,7 @@
 use super::{Executable, default_tracing};
-use crate::common::{connect_to_coordinator, handle_dataflow_result, query_running_dataflows};
+use crate::common::{
+    SecurityArgs, connect_to_coordinator, handle_dataflow_result, query_running_dataflows,
+};
 use communication_layer_request_reply::TcpRequestReplyConnection;
 use dora_core::topics::{DORA_COORDINATOR_PORT_CONTROL_DEFAULT, LOCALHOST};
 use dora_message::cli_to_coordinator::ControlRequest;
@@ -28,14 +30,18 @@ pub struct Stop {
     /// Port number of the coordinator control server
     #[clap(long, value_name = "PORT", default_value_t = DORA_COORDINATOR_PORT_CONTROL_DEFAULT)]
     coordinator_port: u16,
+    #[clap(flatten)]
+    security: SecurityArgs,
 }
 
 impl Executable for Stop {
     fn execute(self) -> eyre::Result<()> {
         default_tracing()?;
-        let mut session =
-            connect_to_coordinator((self.coordinator_addr, self.coordinator_port).into())
-                .wrap_err("could not connect to dora coordinator")?;
+        let mut session = connect_to_coordinator(
+            (self.coordinator_addr, self.coordinator_port).into(),
+            &self.security,
+        )
+        .wrap_err("could not connect to dora coordinator")?;
         match (self.uuid, self.name) {
             (Some(uuid), _) => stop_dataflow(uuid, self.grace_duration, &mut *session),
             (None, Some(name)) => stop_dataflow_by_name(name, self.grace_duration, &mut *session),
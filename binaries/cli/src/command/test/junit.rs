@@ -0,0 +1,90 @@
+//! Minimal writer for JUnit XML reports, as understood by most CI systems.
+
+use super::TestCase;
+use std::{fmt::Write, time::Duration};
+
+pub fn report(suite: &str, cases: &[TestCase], duration: Duration) -> String {
+    let failures = cases.iter().filter(|c| c.failure.is_some()).count();
+    let time = duration.as_secs_f64();
+    let suite = escape(suite);
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(
+        xml,
+        "<testsuites tests=\"{}\" failures=\"{failures}\" time=\"{time:.3}\">",
+        cases.len()
+    );
+    let _ = writeln!(
+        xml,
+        "  <testsuite name=\"{suite}\" tests=\"{}\" failures=\"{failures}\" time=\"{time:.3}\">",
+        cases.len()
+    );
+    for case in cases {
+        let name = escape(&case.name);
+        match &case.failure {
+            None => {
+                let _ = writeln!(xml, "    <testcase classname=\"{suite}\" name=\"{name}\"/>");
+            }
+            Some(failure) => {
+                let _ = writeln!(xml, "    <testcase classname=\"{suite}\" name=\"{name}\">");
+                let _ = writeln!(
+                    xml,
+                    "      <failure message=\"{}\">{}</failure>",
+                    escape(failure.lines().next().unwrap_or_default()),
+                    escape(failure)
+                );
+                xml.push_str("    </testcase>\n");
+            }
+        }
+    }
+    xml.push_str("  </testsuite>\n</testsuites>\n");
+    xml
+}
+
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\n' | '\t' => escaped.push(c),
+            // not allowed in XML 1.0
+            c if c.is_control() => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape_xml() {
+        assert_eq!(
+            escape(r#"<a href="x">'&'</a>"#),
+            "&lt;a href=&quot;x&quot;&gt;&apos;&amp;&apos;&lt;/a&gt;"
+        );
+        assert_eq!(escape("line\n\tindented\u{1b}[31m"), "line\n\tindented[31m");
+    }
+
+    #[test]
+    fn report_failures() {
+        let cases = [
+            TestCase::new("node/a", vec![]),
+            TestCase::new("output/a<b>", vec!["first".into(), "second".into()]),
+        ];
+        let xml = report("suite", &cases, Duration::from_millis(1500));
+        assert!(xml.contains(r#"<testsuite name="suite" tests="2" failures="1" time="1.500">"#));
+        assert!(xml.contains(r#"<testcase classname="suite" name="node/a"/>"#));
+        assert!(xml.contains(
+            r#"<failure message="first">first
+second</failure>"#
+        ));
+        assert!(xml.contains(r#"name="output/a&lt;b&gt;""#));
+    }
+}
@@ -0,0 +1,646 @@
+//! The `dora test` command runs a dataflow locally as an integration test.
+//!
+//! The test is described by a spec file that lists fixture files for some of the dataflow
+//! inputs and the expected outputs and exit codes. A helper node is added to the dataflow
+//! that sends out the fixture messages and enforces the test timeout. All outputs of the
+//! dataflow are recorded (see `dora record`) and checked against the spec after the run.
+
+use super::{Executable, run::run_dataflow_modified};
+use colored::Colorize;
+use dora_core::{
+    config::{DataId, Input, InputMapping, NodeId, OperatorId, UserInputMapping},
+    descriptor::{CoreNodeKind, Descriptor, DescriptorExt, Node, SINGLE_OPERATOR_DEFAULT_ID},
+};
+use dora_daemon::record::{RECORD_FILE_EXTENSION, RecordedMessage, read_recorded_file};
+use dora_message::{
+    common::NodeExitStatus, coordinator_to_cli::DataflowResult, descriptor::EnvValue,
+};
+use dora_node_api::{
+    arrow::{
+        array::{RecordBatch, make_array},
+        buffer::Buffer,
+        json::ArrayWriter,
+    },
+    arrow_utils::buffer_into_arrow_array,
+    uhlc::NTP64,
+};
+use eyre::{Context, ContextCompat, bail};
+use spec::{Feed, HelperConfig, TestSpec, split_target};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+use uuid::Uuid;
+
+pub use node::TestNode;
+
+mod junit;
+mod node;
+mod spec;
+
+/// ID of the helper node that is added to the dataflow.
+const HELPER_NODE_ID: &str = "dora-test";
+const HELPER_CONFIG_ENV: &str = "DORA_TEST_NODE_CONFIG";
+/// Output on which the helper node sends an empty message once the dataflow started.
+///
+/// The recorded timestamp of this message is the reference time of `within` assertions.
+const STARTED_OUTPUT: &str = "started";
+/// Stop reasons reported by the helper node.
+const TIMEOUT_REASON: &str = "test timeout exceeded";
+const STOP_AFTER_REASON: &str = "`stop_after` duration elapsed";
+
+#[derive(Debug, clap::Args)]
+/// Run a dataflow as integration test.
+///
+/// Runs the given dataflow locally, feeds inputs from fixture files, and checks the outputs
+/// and exit codes of its nodes against the given test spec. Exits with an error if any check
+/// fails.
+pub struct Test {
+    /// Path to the dataflow descriptor file
+    #[clap(value_name = "PATH")]
+    dataflow: String,
+    /// Path to the test spec file
+    #[clap(value_name = "SPEC")]
+    spec: PathBuf,
+    /// Write a JUnit XML report to the given path
+    #[clap(long, value_name = "PATH")]
+    junit: Option<PathBuf>,
+    // Use UV to run nodes.
+    #[clap(long, action)]
+    uv: bool,
+}
+
+/// Result of a single check of a test run.
+struct TestCase {
+    name: String,
+    failure: Option<String>,
+}
+
+impl TestCase {
+    fn new(name: impl Into<String>, failures: Vec<String>) -> Self {
+        Self {
+            name: name.into(),
+            failure: (!failures.is_empty()).then(|| failures.join("\n")),
+        }
+    }
+}
+
+impl Executable for Test {
+    fn execute(self) -> eyre::Result<()> {
+        let spec = TestSpec::read(&self.spec)?;
+        let suite = match &spec.name {
+            Some(name) => name.clone(),
+            None => self
+                .spec
+                .file_stem()
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or_else(|| "dora-test".into()),
+        };
+        let spec_dir = self.spec.parent().unwrap_or(Path::new("")).to_owned();
+        let record_dir = std::env::temp_dir().join(format!("dora-test-{}", Uuid::new_v4()));
+
+        let start_time = SystemTime::now();
+        let start = Instant::now();
+        let mut single_operators = BTreeMap::new();
+        let result = run_dataflow_modified(self.dataflow, self.uv, |descriptor| {
+            prepare(
+                descriptor,
+                &spec,
+                &spec_dir,
+                &record_dir,
+                &mut single_operators,
+            )
+        });
+        let cases = match result {
+            Ok(result) => {
+                let run_dir = record_dir.join(result.uuid.to_string());
+                evaluate(&spec, &result, &run_dir, &single_operators, start_time)
+            }
+            Err(err) => vec![TestCase::new("dataflow", vec![format!("{err:?}")])],
+        };
+        let duration = start.elapsed();
+        if record_dir.exists() {
+            if let Err(err) = std::fs::remove_dir_all(&record_dir) {
+                tracing::warn!("failed to remove `{}`: {err}", record_dir.display());
+            }
+        }
+
+        if let Some(path) = &self.junit {
+            std::fs::write(path, junit::report(&suite, &cases, duration))
+                .wrap_err_with(|| format!("failed to write JUnit report `{}`", path.display()))?;
+        }
+
+        print_results(&cases, duration);
+        let failed = cases.iter().filter(|c| c.failure.is_some()).count();
+        if failed > 0 {
+            bail!("{failed} of {} checks failed", cases.len());
+        }
+        Ok(())
+    }
+}
+
+/// Rewires the fixture inputs to the helper node and enables recording.
+fn prepare(
+    descriptor: &mut Descriptor,
+    spec: &TestSpec,
+    spec_dir: &Path,
+    record_dir: &Path,
+    single_operators: &mut BTreeMap<NodeId, OperatorId>,
+) -> eyre::Result<()> {
+    if descriptor
+        .nodes
+        .iter()
+        .any(|n| n.id.as_ref() == HELPER_NODE_ID)
+    {
+        bail!("dataflow must not contain a node with ID `{HELPER_NODE_ID}`");
+    }
+
+    // watch all outputs to keep the helper node running until the dataflow is done
+    let mut watched = BTreeMap::new();
+    for node in descriptor.resolve_aliases_and_set_defaults()?.into_values() {
+        if node.replica.is_some() {
+            continue;
+        }
+        let outputs: Vec<String> = match &node.kind {
+            CoreNodeKind::Custom(custom) => custom
+                .run_config
+                .outputs
+                .iter()
+                .map(|o| o.id.to_string())
+                .collect(),
+            CoreNodeKind::Runtime(runtime) => runtime
+                .operators
+                .iter()
+                .flat_map(|op| {
+                    op.config
+                        .outputs
+                        .iter()
+                        .map(move |o| format!("{}/{}", op.id, o.id))
+                })
+                .collect(),
+        };
+        for output in outputs {
+            watched.insert(
+                format!("watch-{}", watched.len()),
+                format!("{}/{output}", node.id),
+            );
+        }
+    }
+    for node in &descriptor.nodes {
+        if let Some(operator) = &node.operator {
+            let operator_id = operator
+                .id
+                .clone()
+                .unwrap_or_else(|| SINGLE_OPERATOR_DEFAULT_ID.to_string().into());
+            single_operators.insert(node.id.clone(), operator_id);
+        }
+    }
+
+    let mut feeds = Vec::new();
+    for (i, fixture) in spec.inputs.iter().enumerate() {
+        let (node_id, input_id) = split_target(&fixture.target)?;
+        let node = descriptor
+            .nodes
+            .iter_mut()
+            .find(|n| n.id == node_id)
+            .wrap_err_with(|| format!("no node with ID `{node_id}` in dataflow"))?;
+        let input = node_input_mut(node, &input_id)
+            .wrap_err_with(|| format!("node `{node_id}` has no input `{input_id}`"))?;
+        let output = DataId::from(format!("feed-{i}"));
+        input.mapping = InputMapping::User(UserInputMapping {
+            source: HELPER_NODE_ID.to_string().into(),
+            output: output.clone(),
+        });
+
+        let path = spec_dir.join(&fixture.fixture);
+        let fixture_path = path
+            .canonicalize()
+            .wrap_err_with(|| format!("fixture `{}` not found", path.display()))?;
+        feeds.push(Feed {
+            output,
+            fixture: fixture_path,
+            data_type: fixture
+                .data_type
+                .clone()
+                .or_else(|| input.data_type.clone()),
+            interval: fixture.interval.unwrap_or_default(),
+        });
+    }
+
+    let outputs: Vec<_> = feeds
+        .iter()
+        .map(|f| f.output.to_string())
+        .chain([STARTED_OUTPUT.to_owned()])
+        .collect();
+    let config = HelperConfig {
+        timeout: spec.timeout,
+        stop_after: spec.stop_after,
+        feeds,
+    };
+    let current_exe = std::env::current_exe().context("failed to get current executable")?;
+    let helper: Node = serde_json::from_value(serde_json::json!({
+        "id": HELPER_NODE_ID,
+        "path": current_exe,
+        "args": "test-node",
+        "outputs": outputs,
+        "inputs": watched,
+    }))
+    .context("failed to create test helper node")?;
+    let helper = Node {
+        env: Some(BTreeMap::from([(
+            HELPER_CONFIG_ENV.to_owned(),
+            EnvValue::String(serde_json::to_string(&config)?),
+        )])),
+        ..helper
+    };
+    descriptor.nodes.push(helper);
+
+    descriptor.debug.record_dir = Some(record_dir.to_owned());
+    Ok(())
+}
+
+/// Returns the input with the given ID, for all kinds of nodes.
+///
+/// Inputs of runtime nodes are identified as `operator_id/input_id`.
+fn node_input_mut<'a>(node: &'a mut Node, input_id: &DataId) -> Option<&'a mut Input> {
+    if let Some(runtime) = &mut node.operators {
+        let (operator_id, input_id) = input_id.split_once('/')?;
+        let operator = runtime
+            .operators
+            .iter_mut()
+            .find(|op| op.id.as_ref() == operator_id)?;
+        operator.config.inputs.get_mut(input_id)
+    } else if let Some(operator) = &mut node.operator {
+        operator.config.inputs.get_mut(input_id)
+    } else if let Some(custom) = &mut node.custom {
+        custom.run_config.inputs.get_mut(input_id)
+    } else {
+        node.inputs.get_mut(input_id)
+    }
+}
+
+fn evaluate(
+    spec: &TestSpec,
+    result: &DataflowResult,
+    run_dir: &Path,
+    single_operators: &BTreeMap<NodeId, OperatorId>,
+    start_time: SystemTime,
+) -> Vec<TestCase> {
+    let mut cases = Vec::new();
+
+    // fall back to the start of the test command if the dataflow start was not recorded
+    let started = format!("{HELPER_NODE_ID}/{STARTED_OUTPUT}");
+    let start_time = match read_output(&started, run_dir, single_operators) {
+        Ok(messages) => messages
+            .first()
+            .map(|m| NTP64(m.timestamp).to_system_time())
+            .unwrap_or(start_time),
+        Err(err) => {
+            tracing::warn!("failed to read dataflow start time: {err:?}");
+            start_time
+        }
+    };
+
+    let mut failures = Vec::new();
+    if let Some(stop) = &result.stopped_by {
+        if stop.node_id.as_ref() == HELPER_NODE_ID && stop.reason.as_deref() == Some(TIMEOUT_REASON)
+        {
+            failures.push(format!(
+                "dataflow did not finish within {}",
+                humanize(spec.timeout)
+            ));
+        }
+    }
+    if let Some(Err(err)) = result
+        .node_results
+        .get(&NodeId::from(HELPER_NODE_ID.to_string()))
+    {
+        failures.push(format!("test helper node failed: {err}"));
+    }
+    cases.push(TestCase::new("dataflow", failures));
+
+    for (node_id, node_result) in &result.node_results {
+        if node_id.as_ref() == HELPER_NODE_ID {
+            continue;
+        }
+        let expected = spec.nodes.get(node_id).map(|n| n.exit_code).unwrap_or(0);
+        let failure = match node_result {
+            Ok(()) if expected == 0 => None,
+            Ok(()) => Some(format!("expected exit code {expected}, but node succeeded")),
+            Err(err) => match err.exit_status {
+                NodeExitStatus::ExitCode(code) if code == expected => None,
+                _ => Some(format!(
+                    "expected exit code {expected}, but node failed: {err}"
+                )),
+            },
+        };
+        cases.push(TestCase::new(
+            format!("node/{node_id}"),
+            failure.into_iter().collect(),
+        ));
+    }
+    for node_id in spec.nodes.keys() {
+        if !result.node_results.contains_key(node_id) {
+            cases.push(TestCase::new(
+                format!("node/{node_id}"),
+                vec![format!("no node with ID `{node_id}` in dataflow")],
+            ));
+        }
+    }
+
+    for assertion in &spec.outputs {
+        let failures = match read_output(&assertion.source, run_dir, single_operators) {
+            Ok(messages) => check_output(assertion, &messages, start_time),
+            Err(err) => vec![format!("{err:?}")],
+        };
+        cases.push(TestCase::new(
+            format!("output/{}", assertion.source),
+            failures,
+        ));
+    }
+
+    cases
+}
+
+/// Reads the recorded messages of the given `node_id/output_id`.
+fn read_output(
+    source: &str,
+    run_dir: &Path,
+    single_operators: &BTreeMap<NodeId, OperatorId>,
+) -> eyre::Result<Vec<RecordedMessage>> {
+    let (node_id, output_id) = split_target(source)?;
+    // outputs of single operator nodes are recorded as `operator_id/output_id`
+    let recorded_id = match single_operators.get(&node_id) {
+        Some(operator_id) if !output_id.contains('/') => {
+            DataId::from(format!("{operator_id}/{output_id}"))
+        }
+        _ => output_id,
+    };
+    let path = run_dir
+        .join(node_id.to_string())
+        .join(format!("{recorded_id}.{RECORD_FILE_EXTENSION}"));
+    if !path.exists() {
+        // no message was sent on this output
+        return Ok(Vec::new());
+    }
+    read_recorded_file(&path, recorded_id)
+}
+
+fn check_output(
+    assertion: &spec::OutputAssertion,
+    messages: &[RecordedMessage],
+    start_time: SystemTime,
+) -> Vec<String> {
+    let mut failures = Vec::new();
+    let received = messages.len();
+    if let Some(count) = assertion.count {
+        if received != count {
+            failures.push(format!("expected {count} messages, got {received}"));
+        }
+    }
+    if let Some(min_count) = assertion.min_count {
+        if received < min_count {
+            failures.push(format!(
+                "expected at least {min_count} messages, got {received}"
+            ));
+        }
+    }
+    if let Some(max_count) = assertion.max_count {
+        if received > max_count {
+            failures.push(format!(
+                "expected at most {max_count} messages, got {received}"
+            ));
+        }
+    }
+    if let Some(within) = assertion.within {
+        match messages.first() {
+            Some(first) => {
+                let elapsed = NTP64(first.timestamp)
+                    .to_system_time()
+                    .duration_since(start_time)
+                    .unwrap_or_default();
+                if elapsed > within {
+                    failures.push(format!(
+                        "expected first message within {}, got it after {}",
+                        humanize(within),
+                        humanize(elapsed)
+                    ));
+                }
+            }
+            None => failures.push(format!(
+                "expected first message within {}, got none",
+                humanize(within)
+            )),
+        }
+    }
+    if let Some(values) = &assertion.values {
+        if values.len() != received {
+            failures.push(format!(
+                "expected {} values, got {received} messages",
+                values.len()
+            ));
+        }
+        for (i, (expected, message)) in values.iter().zip(messages).enumerate() {
+            match message_values(message) {
+                Ok(actual) if values_match(expected, &actual) => {}
+                Ok(mut actual) => {
+                    let actual = match actual.len() {
+                        1 if !expected.is_array() => actual.remove(0),
+                        _ => serde_json::Value::Array(actual),
+                    };
+                    failures.push(format!(
+                        "message {i}: expected `{expected}`, got `{actual}`"
+                    ))
+                }
+                Err(err) => failures.push(format!("message {i}: {err}")),
+            }
+        }
+    }
+    failures
+}
+
+/// Converts the data of the given message to a list of JSON values.
+fn message_values(message: &RecordedMessage) -> eyre::Result<Vec<serde_json::Value>> {
+    let Some(data) = &message.data else {
+        return Ok(Vec::new());
+    };
+    let array = make_array(buffer_into_arrow_array(
+        &Buffer::from_slice_ref(data),
+        &message.metadata.type_info,
+    )?);
+    if array.is_empty() {
+        return Ok(Vec::new());
+    }
+    let batch =
+        RecordBatch::try_from_iter([("value", array)]).context("failed to create record batch")?;
+    let mut writer = ArrayWriter::new(Vec::new());
+    writer
+        .write(&batch)
+        .and_then(|()| writer.finish())
+        .context("failed to convert message data to JSON")?;
+    let rows: Vec<serde_json::Map<String, serde_json::Value>> =
+        serde_json::from_slice(&writer.into_inner())
+            .context("failed to parse converted message data")?;
+    // null values are omitted by the JSON writer
+    Ok(rows
+        .into_iter()
+        .map(|mut row| row.remove("value").unwrap_or_default())
+        .collect())
+}
+
+/// Checks whether the given expected value matches the values of a message.
+///
+/// Single values match single-element arrays.
+fn values_match(expected: &serde_json::Value, actual: &[serde_json::Value]) -> bool {
+    match expected {
+        serde_json::Value::Array(expected) => {
+            expected.len() == actual.len()
+                && expected.iter().zip(actual).all(|(e, a)| json_eq(e, a))
+        }
+        expected => matches!(actual, [actual] if json_eq(expected, actual)),
+    }
+}
+
+/// Compares JSON values, allowing for small floating point differences.
+fn json_eq(expected: &serde_json::Value, actual: &serde_json::Value) -> bool {
+    use serde_json::Value;
+
+    match (expected, actual) {
+        (Value::Number(e), Value::Number(a)) if e != a => match (e.as_f64(), a.as_f64()) {
+            (Some(e), Some(a)) => (e - a).abs() <= 1e-6 * e.abs().max(a.abs()).max(1.0),
+            _ => false,
+        },
+        (Value::Array(e), Value::Array(a)) => {
+            e.len() == a.len() && e.iter().zip(a).all(|(e, a)| json_eq(e, a))
+        }
+        (Value::Object(e), Value::Object(a)) => {
+            e.len() == a.len()
+                && e.iter()
+                    .all(|(key, e)| a.get(key).is_some_and(|a| json_eq(e, a)))
+        }
+        (e, a) => e == a,
+    }
+}
+
+fn print_results(cases: &[TestCase], duration: Duration) {
+    for case in cases {
+        match &case.failure {
+            None => println!("test {} ... {}", case.name, "ok".green()),
+            Some(_) => println!("test {} ... {}", case.name, "FAILED".red()),
+        }
+    }
+    let failed: Vec<_> = cases.iter().filter(|c| c.failure.is_some()).collect();
+    if !failed.is_empty() {
+        println!("\nfailures:");
+        for case in &failed {
+            println!("\n---- {} ----", case.name);
+            for line in case.failure.iter().flat_map(|f| f.lines()) {
+                println!("{line}");
+            }
+        }
+    }
+    let status = if failed.is_empty() {
+        "ok".green()
+    } else {
+        "FAILED".red()
+    };
+    println!(
+        "\ntest result: {status}. {} passed; {} failed; finished in {}",
+        cases.len() - failed.len(),
+        failed.len(),
+        humanize(duration)
+    );
+}
+
+fn humanize(duration: Duration) -> String {
+    format!("{:.2}s", duration.as_secs_f64())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dora_message::metadata::Metadata;
+    use dora_node_api::{
+        arrow::array::{Array, Float64Array, UInt32Array},
+        arrow_utils::{copy_array_into_sample, required_data_size},
+        uhlc::{ID, Timestamp},
+    };
+    use serde_json::json;
+
+    fn message(array: &dyn Array, time: SystemTime) -> RecordedMessage {
+        let array = array.to_data();
+        let mut data = vec![0; required_data_size(&array)];
+        let type_info = copy_array_into_sample(&mut data, &array);
+        let time = NTP64::from(time.duration_since(SystemTime::UNIX_EPOCH).unwrap());
+        RecordedMessage {
+            output_id: DataId::from("out".to_owned()),
+            timestamp: time.as_u64(),
+            metadata: Metadata::new(Timestamp::new(time, ID::from(Uuid::new_v4())), type_info),
+            data: Some(data),
+        }
+    }
+
+    #[test]
+    fn compare_json_values() {
+        assert!(json_eq(&json!(1.0), &json!(1)));
+        assert!(json_eq(&json!(0.1), &json!(0.1 + 1e-9)));
+        assert!(!json_eq(&json!(0.1), &json!(0.11)));
+        assert!(!json_eq(&json!(1), &json!("1")));
+        assert!(json_eq(
+            &json!({"x": [1.0, 2.0], "label": "a"}),
+            &json!({"label": "a", "x": [1, 2]})
+        ));
+        assert!(!json_eq(&json!({"x": 1}), &json!({"x": 1, "y": 2})));
+        assert!(!json_eq(&json!([1, 2]), &json!([1, 2, 3])));
+    }
+
+    #[test]
+    fn match_expected_values() {
+        // single values match single-element arrays
+        assert!(values_match(&json!(3), &[json!(3)]));
+        assert!(!values_match(&json!(3), &[json!(3), json!(3)]));
+        assert!(values_match(&json!([1, 2]), &[json!(1), json!(2)]));
+        assert!(!values_match(&json!([1, 2]), &[json!(1)]));
+        assert!(values_match(&json!([]), &[]));
+        assert!(!values_match(&json!(null), &[]));
+    }
+
+    #[test]
+    fn check_recorded_output() {
+        let assertion: spec::OutputAssertion = serde_yaml::from_str(
+            "{source: node/out, count: 2, min_count: 2, values: [[1, 2], 3.0000001], within: 1s}",
+        )
+        .unwrap();
+        let start = SystemTime::now();
+        let messages = [
+            message(
+                &UInt32Array::from(vec![1, 2]),
+                start + Duration::from_millis(500),
+            ),
+            message(
+                &Float64Array::from(vec![3.0]),
+                start + Duration::from_millis(600),
+            ),
+        ];
+        assert_eq!(
+            check_output(&assertion, &messages, start),
+            Vec::<String>::new()
+        );
+
+        let failures = check_output(&assertion, &messages[1..], start - Duration::from_secs(1));
+        assert_eq!(
+            failures,
+            [
+                "expected 2 messages, got 1",
+                "expected at least 2 messages, got 1",
+                "expected first message within 1.00s, got it after 1.60s",
+                "expected 2 values, got 1 messages",
+                "message 0: expected `[1,2]`, got `[3.0]`",
+            ]
+        );
+
+        let failures = check_output(&assertion, &[], start);
+        assert!(failures.contains(&"expected first message within 1.00s, got none".to_owned()));
+    }
+}
@@ -0,0 +1,167 @@
+use super::{
+    HELPER_CONFIG_ENV, STARTED_OUTPUT, STOP_AFTER_REASON, TIMEOUT_REASON,
+    spec::{Feed, HelperConfig},
+};
+use crate::command::{Executable, topic::json_to_arrow};
+use dora_daemon::record::{RecordedMessage, read_recorded_file};
+use dora_node_api::{
+    DoraNode, Event, StopCause,
+    arrow::array::{ArrayData, NullArray, make_array},
+    uhlc::NTP64,
+};
+use eyre::Context;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, clap::Args)]
+/// Run the test helper node (used by `dora test`)
+pub struct TestNode;
+
+impl Executable for TestNode {
+    fn execute(self) -> eyre::Result<()> {
+        let config = std::env::var(HELPER_CONFIG_ENV)
+            .wrap_err_with(|| format!("env variable {HELPER_CONFIG_ENV} must be set"))?;
+        let config: HelperConfig =
+            serde_json::from_str(&config).context("failed to parse test node config")?;
+
+        let mut messages = Vec::new();
+        for feed in &config.feeds {
+            load_feed(feed, &mut messages)?;
+        }
+        messages.sort_by_key(|m| m.offset);
+        let feed_outputs = config.feeds.into_iter().map(|f| f.output).collect();
+
+        let (mut node, mut events) = DoraNode::init_from_env()?;
+        node.send_output(
+            STARTED_OUTPUT.to_owned().into(),
+            Default::default(),
+            NullArray::new(0),
+        )?;
+
+        let start = Instant::now();
+        let timeout = start + config.timeout;
+        let stop_after = config.stop_after.map(|d| start + d);
+        let mut messages = messages.into_iter().peekable();
+        let mut feed_outputs = Some(feed_outputs);
+        let mut watched_closed = false;
+        let mut events_closed = false;
+        loop {
+            while let Some(message) = messages.next_if(|m| start + m.offset <= Instant::now()) {
+                message.send(&mut node)?;
+            }
+            if messages.peek().is_none() {
+                if let Some(outputs) = feed_outputs.take() {
+                    node.close_outputs(outputs)?;
+                }
+                if watched_closed {
+                    break;
+                }
+            }
+
+            let now = Instant::now();
+            if now >= timeout {
+                node.stop_dataflow(Some(TIMEOUT_REASON.into()))?;
+                break;
+            }
+            if stop_after.is_some_and(|deadline| now >= deadline) {
+                node.stop_dataflow(Some(STOP_AFTER_REASON.into()))?;
+                break;
+            }
+
+            let deadline = messages
+                .peek()
+                .map(|m| start + m.offset)
+                .into_iter()
+                .chain(stop_after)
+                .fold(timeout, Instant::min);
+            let remaining = deadline.saturating_duration_since(now);
+            if events_closed {
+                std::thread::sleep(remaining);
+                continue;
+            }
+            match events.recv_timeout(remaining) {
+                Some(Event::Stop(StopCause::Manual)) => break,
+                Some(Event::Stop(StopCause::AllInputsClosed)) => watched_closed = true,
+                Some(_) => {}
+                None => {
+                    events_closed = true;
+                    watched_closed = true;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+struct FeedMessage {
+    /// Send time, relative to the start of the test node.
+    offset: Duration,
+    output: dora_core::config::DataId,
+    data: FeedData,
+}
+
+enum FeedData {
+    Recorded(RecordedMessage),
+    Json(ArrayData),
+}
+
+impl FeedMessage {
+    fn send(self, node: &mut DoraNode) -> eyre::Result<()> {
+        match self.data {
+            FeedData::Recorded(message) => {
+                let type_info = message.metadata.type_info;
+                let parameters = message.metadata.parameters;
+                match message.data {
+                    Some(data) => node.send_typed_output(
+                        self.output,
+                        type_info,
+                        parameters,
+                        data.len(),
+                        |out| out.copy_from_slice(&data),
+                    ),
+                    None => node.send_output_sample(self.output, type_info, parameters, None),
+                }
+            }
+            FeedData::Json(data) => {
+                node.send_output(self.output, Default::default(), make_array(data))
+            }
+        }
+    }
+}
+
+fn load_feed(feed: &Feed, messages: &mut Vec<FeedMessage>) -> eyre::Result<()> {
+    if feed.fixture.extension().is_some_and(|ext| ext == "arrow") {
+        let recorded = read_recorded_file(&feed.fixture, feed.output.clone())?;
+        let Some(first_timestamp) = recorded.first().map(|m| m.timestamp) else {
+            return Ok(());
+        };
+        messages.extend(recorded.into_iter().map(|message| FeedMessage {
+            offset: NTP64(message.timestamp.saturating_sub(first_timestamp)).to_duration(),
+            output: feed.output.clone(),
+            data: FeedData::Recorded(message),
+        }));
+    } else {
+        let content = std::fs::read_to_string(&feed.fixture)
+            .wrap_err_with(|| format!("failed to read fixture `{}`", feed.fixture.display()))?;
+        let data_type = feed.data_type.as_ref().map(|t| t.to_arrow());
+        let mut index = 0;
+        for (line_number, line) in content.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let value = serde_json::from_str(line).wrap_err_with(|| {
+                format!(
+                    "failed to parse line {} of fixture `{}`",
+                    line_number + 1,
+                    feed.fixture.display()
+                )
+            })?;
+            messages.push(FeedMessage {
+                offset: feed.interval * index,
+                output: feed.output.clone(),
+                data: FeedData::Json(json_to_arrow(value, data_type.clone())?),
+            });
+            index += 1;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,134 @@
+use std::{collections::BTreeMap, path::PathBuf, time::Duration};
+
+use dora_core::config::{DataId, NodeId};
+use dora_message::config::DataTypeSpec;
+use duration_str::{deserialize_duration, deserialize_option_duration};
+use eyre::{Context, eyre};
+
+/// Default timeout of a test run.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Test specification, as read from the spec file given to `dora test`.
+#[derive(Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TestSpec {
+    /// Name of the test suite (defaults to the file name of the spec).
+    pub name: Option<String>,
+    /// Maximum duration of the test run, the test fails if the dataflow is still running afterwards.
+    #[serde(default = "default_timeout", deserialize_with = "deserialize_duration")]
+    pub timeout: Duration,
+    /// Stop the dataflow successfully after the given duration.
+    ///
+    /// Useful for dataflows that never finish on their own, e.g. because of timer inputs.
+    #[serde(default, deserialize_with = "deserialize_option_duration")]
+    pub stop_after: Option<Duration>,
+    /// Inputs that are fed from fixture files instead of their original source.
+    #[serde(default)]
+    pub inputs: Vec<InputFixture>,
+    /// Assertions on the outputs of the dataflow.
+    #[serde(default)]
+    pub outputs: Vec<OutputAssertion>,
+    /// Expected exit codes of nodes. Nodes that are not listed are expected to succeed.
+    #[serde(default)]
+    pub nodes: BTreeMap<NodeId, NodeAssertion>,
+}
+
+fn default_timeout() -> Duration {
+    DEFAULT_TIMEOUT
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct InputFixture {
+    /// Input to feed, in the form `node_id/input_id`.
+    pub target: String,
+    /// Path of the fixture file, relative to the spec file.
+    ///
+    /// Files with an `.arrow` extension are read as output recordings of `dora record` and
+    /// replayed with their original timing. All other files are read as JSON lines, with one
+    /// message per line.
+    pub fixture: PathBuf,
+    /// Arrow data type of JSON messages (defaults to the declared type of the input).
+    #[serde(default, rename = "type")]
+    pub data_type: Option<DataTypeSpec>,
+    /// Delay between two consecutive JSON messages.
+    #[serde(default, deserialize_with = "deserialize_option_duration")]
+    pub interval: Option<Duration>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OutputAssertion {
+    /// Output to check, in the form `node_id/output_id`.
+    pub source: String,
+    /// Exact number of expected messages.
+    pub count: Option<usize>,
+    pub min_count: Option<usize>,
+    pub max_count: Option<usize>,
+    /// Expected message data as JSON, one entry per message.
+    ///
+    /// Single values match single-element arrays.
+    pub values: Option<Vec<serde_json::Value>>,
+    /// The first message must be sent within the given duration after the dataflow started.
+    #[serde(default, deserialize_with = "deserialize_option_duration")]
+    pub within: Option<Duration>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NodeAssertion {
+    pub exit_code: i32,
+}
+
+/// Configuration of the test helper node, passed through an environment variable.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct HelperConfig {
+    pub timeout: Duration,
+    pub stop_after: Option<Duration>,
+    pub feeds: Vec<Feed>,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct Feed {
+    pub output: DataId,
+    /// Absolute path of the fixture file.
+    pub fixture: PathBuf,
+    pub data_type: Option<DataTypeSpec>,
+    pub interval: Duration,
+}
+
+/// Splits the given `node_id/data_id` string at the first slash.
+pub fn split_target(target: &str) -> eyre::Result<(NodeId, DataId)> {
+    let (node_id, data_id) = target
+        .split_once('/')
+        .ok_or_else(|| eyre!("invalid target `{target}`, expected `node_id/data_id`"))?;
+    Ok((node_id.to_owned().into(), data_id.to_owned().into()))
+}
+
+impl TestSpec {
+    pub fn read(path: &std::path::Path) -> eyre::Result<Self> {
+        let file = std::fs::read(path)
+            .wrap_err_with(|| format!("failed to read test spec `{}`", path.display()))?;
+        serde_yaml::from_slice(&file)
+            .wrap_err_with(|| format!("failed to parse test spec `{}`", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_targets() {
+        let (node_id, data_id) = split_target("camera/image").unwrap();
+        assert_eq!(node_id.as_ref(), "camera");
+        assert_eq!(data_id.as_str(), "image");
+
+        // operator outputs keep their slash
+        let (node_id, data_id) = split_target("runtime/op/out").unwrap();
+        assert_eq!(node_id.as_ref(), "runtime");
+        assert_eq!(data_id.as_str(), "op/out");
+
+        assert!(split_target("camera").is_err());
+    }
+}
@@ -0,0 +1,133 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use super::{DataflowSelector, MessageWindow, ZenohArgs, select_topics, subscribe, topic_name};
+use crate::command::Executable;
+use eyre::Context;
+use tokio::runtime::Builder;
+
+#[derive(Debug, clap::Args)]
+/// Measure the bandwidth used by outputs of a running dataflow.
+pub struct Bw {
+    /// Outputs to measure, in the form `node_id/output_id`. Measures all outputs if none are given.
+    #[clap(value_name = "OUTPUT")]
+    outputs: Vec<String>,
+    /// Number of most recent messages that the statistics are based on
+    #[clap(long, value_name = "N", default_value_t = 100)]
+    window: usize,
+    #[clap(flatten)]
+    dataflow: DataflowSelector,
+    #[clap(flatten)]
+    zenoh: ZenohArgs,
+}
+
+impl Executable for Bw {
+    fn execute(self) -> eyre::Result<()> {
+        let dataflow = self.dataflow.query_dataflow()?;
+        let topics = select_topics(dataflow.outputs, &self.outputs)?;
+
+        let rt = Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .context("tokio runtime failed")?;
+        rt.block_on(async {
+            let (_session, mut messages) = subscribe(
+                &self.zenoh,
+                self.dataflow.coordinator_addr,
+                dataflow.uuid,
+                &topics,
+            )
+            .await?;
+            let mut windows = BTreeMap::new();
+            let mut report = tokio::time::interval(Duration::from_secs(1));
+            report.tick().await;
+            loop {
+                tokio::select! {
+                    message = messages.recv() => {
+                        let Some(message) = message else { break };
+                        windows
+                            .entry(topic_name(&message.node_id, &message.output_id))
+                            .or_insert_with(|| MessageWindow::new(self.window))
+                            .push(message.len());
+                    }
+                    _ = report.tick() => {
+                        if windows.is_empty() {
+                            println!("no messages received yet");
+                        }
+                        for (name, window) in &windows {
+                            println!("{name}: {}", format_bandwidth(window));
+                        }
+                    }
+                }
+            }
+            Ok(())
+        })
+    }
+}
+
+fn format_bandwidth(window: &MessageWindow) -> String {
+    let Some(duration) = window.duration() else {
+        return "not enough messages".to_owned();
+    };
+    let sizes: Vec<_> = window.messages.iter().map(|(_, bytes)| *bytes).collect();
+    // the first message marks the start of the measured time span
+    let transferred: usize = sizes.iter().skip(1).sum();
+    let mean = sizes.iter().sum::<usize>() as f64 / sizes.len() as f64;
+    let min = sizes.iter().min().copied().unwrap_or_default();
+    let max = sizes.iter().max().copied().unwrap_or_default();
+    format!(
+        "{}/s (mean {}, min {}, max {}, window {})",
+        format_bytes(transferred as f64 / duration.as_secs_f64()),
+        format_bytes(mean),
+        format_bytes(min as f64),
+        format_bytes(max as f64),
+        sizes.len()
+    )
+}
+
+fn format_bytes(bytes: f64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1000. {
+        return format!("{bytes:.0} B");
+    }
+    let mut value = bytes;
+    let mut unit = "B";
+    for next in UNITS {
+        if value < 1000. {
+            break;
+        }
+        value /= 1000.;
+        unit = next;
+    }
+    format!("{value:.2} {unit}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::topic::tests::window;
+
+    #[test]
+    fn bandwidth() {
+        assert_eq!(
+            format_bandwidth(&window(10, &[(0, 1)])),
+            "not enough messages"
+        );
+
+        // the size of the first message is not counted for the transfer rate
+        let window = window(10, &[(0, 1000), (500, 2000), (1000, 3000)]);
+        assert_eq!(
+            format_bandwidth(&window),
+            "5.00 KB/s (mean 2.00 KB, min 1.00 KB, max 3.00 KB, window 3)"
+        );
+    }
+
+    #[test]
+    fn bytes() {
+        assert_eq!(format_bytes(0.), "0 B");
+        assert_eq!(format_bytes(999.), "999 B");
+        assert_eq!(format_bytes(1000.), "1.00 KB");
+        assert_eq!(format_bytes(1_500_000.), "1.50 MB");
+        assert_eq!(format_bytes(2e12), "2.00 TB");
+        assert_eq!(format_bytes(5e15), "5000.00 TB");
+    }
+}
@@ -0,0 +1,86 @@
+use super::{DataflowSelector, OutputMessage, ZenohArgs, select_topics, subscribe, topic_name};
+use crate::command::Executable;
+use dora_node_api::{
+    arrow::{
+        array::make_array,
+        util::display::{ArrayFormatter, FormatOptions},
+    },
+    arrow_utils::buffer_into_arrow_array,
+};
+use eyre::Context;
+use tokio::runtime::Builder;
+
+#[derive(Debug, clap::Args)]
+/// Print the messages sent on outputs of a running dataflow.
+pub struct Echo {
+    /// Outputs to print, in the form `node_id/output_id`. Prints all outputs if none are given.
+    #[clap(value_name = "OUTPUT")]
+    outputs: Vec<String>,
+    /// Maximum number of array values that are printed per message
+    #[clap(long, value_name = "N", default_value_t = 32)]
+    max_values: usize,
+    /// Exit after receiving the given number of messages
+    #[clap(long, short = 'n', value_name = "COUNT")]
+    count: Option<usize>,
+    #[clap(flatten)]
+    dataflow: DataflowSelector,
+    #[clap(flatten)]
+    zenoh: ZenohArgs,
+}
+
+impl Executable for Echo {
+    fn execute(self) -> eyre::Result<()> {
+        let dataflow = self.dataflow.query_dataflow()?;
+        let topics = select_topics(dataflow.outputs, &self.outputs)?;
+
+        let rt = Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .context("tokio runtime failed")?;
+        rt.block_on(async {
+            let (_session, mut messages) = subscribe(
+                &self.zenoh,
+                self.dataflow.coordinator_addr,
+                dataflow.uuid,
+                &topics,
+            )
+            .await?;
+            let mut received = 0;
+            while let Some(message) = messages.recv().await {
+                println!("{}", format_message(&message, self.max_values));
+                received += 1;
+                if self.count.is_some_and(|count| received >= count) {
+                    break;
+                }
+            }
+            Ok(())
+        })
+    }
+}
+
+fn format_message(message: &OutputMessage, max_values: usize) -> String {
+    let name = topic_name(&message.node_id, &message.output_id);
+    let timestamp = message.metadata.timestamp();
+    let time = timestamp.get_time();
+    let type_info = &message.metadata.type_info;
+    let header = format!("[{name}] {time:#} {}", type_info.data_type);
+
+    let Some(data) = &message.data else {
+        return format!("{header}: <no data>");
+    };
+    let array = match buffer_into_arrow_array(data, type_info) {
+        Ok(array) => make_array(array),
+        Err(err) => return format!("{header}: <failed to decode: {err}>"),
+    };
+    let formatter = match ArrayFormatter::try_new(&array, &FormatOptions::default()) {
+        Ok(formatter) => formatter,
+        Err(err) => return format!("{header}: <failed to format: {err}>"),
+    };
+    let mut values: Vec<_> = (0..array.len().min(max_values))
+        .map(|i| formatter.value(i).to_string())
+        .collect();
+    if array.len() > max_values {
+        values.push(format!("... ({} more)", array.len() - max_values));
+    }
+    format!("{header} ({} values): [{}]", array.len(), values.join(", "))
+}
@@ -0,0 +1,116 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use super::{DataflowSelector, MessageWindow, ZenohArgs, select_topics, subscribe, topic_name};
+use crate::command::Executable;
+use eyre::Context;
+use tokio::runtime::Builder;
+
+#[derive(Debug, clap::Args)]
+/// Measure the message frequency of outputs of a running dataflow.
+pub struct Hz {
+    /// Outputs to measure, in the form `node_id/output_id`. Measures all outputs if none are given.
+    #[clap(value_name = "OUTPUT")]
+    outputs: Vec<String>,
+    /// Number of most recent messages that the statistics are based on
+    #[clap(long, value_name = "N", default_value_t = 100)]
+    window: usize,
+    #[clap(flatten)]
+    dataflow: DataflowSelector,
+    #[clap(flatten)]
+    zenoh: ZenohArgs,
+}
+
+impl Executable for Hz {
+    fn execute(self) -> eyre::Result<()> {
+        let dataflow = self.dataflow.query_dataflow()?;
+        let topics = select_topics(dataflow.outputs, &self.outputs)?;
+
+        let rt = Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .context("tokio runtime failed")?;
+        rt.block_on(async {
+            let (_session, mut messages) = subscribe(
+                &self.zenoh,
+                self.dataflow.coordinator_addr,
+                dataflow.uuid,
+                &topics,
+            )
+            .await?;
+            let mut windows = BTreeMap::new();
+            let mut report = tokio::time::interval(Duration::from_secs(1));
+            report.tick().await;
+            loop {
+                tokio::select! {
+                    message = messages.recv() => {
+                        let Some(message) = message else { break };
+                        windows
+                            .entry(topic_name(&message.node_id, &message.output_id))
+                            .or_insert_with(|| MessageWindow::new(self.window))
+                            .push(message.len());
+                    }
+                    _ = report.tick() => {
+                        if windows.is_empty() {
+                            println!("no messages received yet");
+                        }
+                        for (name, window) in &windows {
+                            println!("{name}: {}", format_frequency(window));
+                        }
+                    }
+                }
+            }
+            Ok(())
+        })
+    }
+}
+
+fn format_frequency(window: &MessageWindow) -> String {
+    let Some(duration) = window.duration() else {
+        return "not enough messages".to_owned();
+    };
+    let intervals: Vec<_> = window.intervals().collect();
+    let count = intervals.len() as f64;
+    let mean = duration.as_secs_f64() / count;
+    let variance = intervals
+        .iter()
+        .map(|i| (i.as_secs_f64() - mean).powi(2))
+        .sum::<f64>()
+        / count;
+    let min = intervals.iter().min().copied().unwrap_or_default();
+    let max = intervals.iter().max().copied().unwrap_or_default();
+    format!(
+        "{:.2} Hz (min {:.2?}, max {:.2?}, std dev {:.2?}, window {})",
+        1. / mean,
+        min,
+        max,
+        Duration::from_secs_f64(variance.sqrt()),
+        intervals.len() + 1
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::topic::tests::window;
+
+    #[test]
+    fn frequency() {
+        assert_eq!(
+            format_frequency(&window(10, &[(0, 1)])),
+            "not enough messages"
+        );
+
+        let steady = window(10, &[(0, 1), (100, 1), (200, 1), (300, 1)]);
+        assert_eq!(
+            format_frequency(&steady),
+            "10.00 Hz (min 100.00ms, max 100.00ms, std dev 0.00ns, window 4)"
+        );
+
+        // intervals of 100ms and 300ms
+        let jittery = window(10, &[(0, 1), (100, 1), (400, 1)]);
+        assert_eq!(
+            format_frequency(&jittery),
+            "5.00 Hz (min 100.00ms, max 300.00ms, std dev 100.00ms, window 3)"
+        );
+    }
+}
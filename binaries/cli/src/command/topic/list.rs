@@ -0,0 +1,35 @@
+use std::io::Write;
+
+use super::{DataflowSelector, topic_name};
+use crate::command::Executable;
+use tabwriter::TabWriter;
+
+#[derive(Debug, clap::Args)]
+/// List the outputs of a running dataflow, together with their declared types.
+pub struct List {
+    #[clap(flatten)]
+    dataflow: DataflowSelector,
+}
+
+impl Executable for List {
+    fn execute(self) -> eyre::Result<()> {
+        let dataflow = self.dataflow.query_dataflow()?;
+
+        let mut tw = TabWriter::new(vec![]);
+        tw.write_all(b"Output\tType\n")?;
+        for topic in dataflow.outputs {
+            let name = topic_name(&topic.node_id, &topic.output_id);
+            let data_type = topic
+                .data_type
+                .map(|t| t.to_string())
+                .unwrap_or_else(|| "-".to_owned());
+            tw.write_all(format!("{name}\t{data_type}\n").as_bytes())?;
+        }
+        tw.flush()?;
+        let formatted = String::from_utf8(tw.into_inner()?)?;
+
+        println!("{formatted}");
+
+        Ok(())
+    }
+}
@@ -0,0 +1,347 @@
+//! The `dora topic` commands inspect the outputs of a running dataflow and inject inputs into it.
+//!
+//! Daemons publish the outputs of their nodes through zenoh as soon as there is a subscriber
+//! for them, so the commands work on any running dataflow without restarting it.
+
+use std::{
+    collections::VecDeque,
+    net::{IpAddr, SocketAddr},
+    time::{Duration, Instant},
+};
+
+use super::{DataflowSelector, Executable, default_tracing};
+use clap::Subcommand;
+use dora_core::{
+    config::{DataId, NodeId},
+    topics::zenoh_output_publish_topic,
+};
+use dora_message::{
+    cli_to_coordinator::ControlRequest,
+    coordinator_to_cli::{ControlRequestReply, InputInfo, TopicInfo},
+    daemon_to_daemon::InterDaemonEvent,
+    metadata::Metadata,
+    node_to_daemon::Timestamped,
+};
+use dora_node_api::arrow::buffer::Buffer;
+use eyre::{Context, bail, eyre};
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+mod bw;
+mod echo;
+mod hz;
+mod list;
+mod publish;
+
+pub(crate) use publish::json_to_arrow;
+
+/// Port of the zenoh peer endpoint that daemons listen on.
+const DAEMON_ZENOH_PORT: u16 = 5456;
+
+#[derive(Debug, Subcommand)]
+/// Inspect the outputs of a running dataflow or publish messages to its inputs.
+pub enum TopicCommand {
+    List(list::List),
+    Echo(echo::Echo),
+    Hz(hz::Hz),
+    Bw(bw::Bw),
+    #[command(name = "pub")]
+    Pub(publish::Pub),
+}
+
+impl Executable for TopicCommand {
+    fn execute(self) -> eyre::Result<()> {
+        default_tracing()?;
+
+        match self {
+            TopicCommand::List(args) => args.execute(),
+            TopicCommand::Echo(args) => args.execute(),
+            TopicCommand::Hz(args) => args.execute(),
+            TopicCommand::Bw(args) => args.execute(),
+            TopicCommand::Pub(args) => args.execute(),
+        }
+    }
+}
+
+impl DataflowSelector {
+    /// Queries the outputs and inputs of the selected dataflow from the coordinator.
+    ///
+    /// Asks the user to choose a dataflow if none was given and multiple dataflows are running.
+    fn query_dataflow(&self) -> eyre::Result<DataflowInfo> {
+        let (mut session, uuid, name) = self.connect()?;
+
+        let reply_raw = session
+            .request(&serde_json::to_vec(&ControlRequest::Topics { uuid, name }).unwrap())
+            .wrap_err("failed to send topics request")?;
+        let reply: ControlRequestReply =
+            serde_json::from_slice(&reply_raw).wrap_err("failed to parse reply")?;
+        match reply {
+            ControlRequestReply::Topics {
+                uuid,
+                topics,
+                inputs,
+            } => Ok(DataflowInfo {
+                uuid,
+                outputs: topics,
+                inputs,
+            }),
+            ControlRequestReply::Error(err) => bail!("{err}"),
+            other => bail!("unexpected topics reply: {other:?}"),
+        }
+    }
+}
+
+struct DataflowInfo {
+    uuid: Uuid,
+    outputs: Vec<TopicInfo>,
+    inputs: Vec<InputInfo>,
+}
+
+/// Returns the topics that match the given `node_id/output_id` pairs.
+///
+/// Returns all topics if no outputs are given.
+fn select_topics(topics: Vec<TopicInfo>, outputs: &[String]) -> eyre::Result<Vec<TopicInfo>> {
+    if outputs.is_empty() {
+        return Ok(topics);
+    }
+    outputs
+        .iter()
+        .map(|output| {
+            let (node_id, output_id) = output
+                .split_once('/')
+                .ok_or_else(|| eyre!("invalid output `{output}`, expected `node_id/output_id`"))?;
+            topics
+                .iter()
+                .find(|t| t.node_id.as_ref() == node_id && t.output_id.as_str() == output_id)
+                .cloned()
+                .ok_or_else(|| eyre!("dataflow has no output `{output}`"))
+        })
+        .collect()
+}
+
+/// An output message that was received from a daemon.
+struct OutputMessage {
+    node_id: NodeId,
+    output_id: DataId,
+    metadata: Metadata,
+    data: Option<Buffer>,
+}
+
+impl OutputMessage {
+    fn len(&self) -> usize {
+        self.data.as_ref().map(|d| d.len()).unwrap_or_default()
+    }
+}
+
+/// Subscribes to the given outputs of a running dataflow.
+///
+/// The returned session must be kept alive while receiving messages.
+async fn subscribe(
+    zenoh: &ZenohArgs,
+    coordinator_addr: IpAddr,
+    dataflow_id: Uuid,
+    topics: &[TopicInfo],
+) -> eyre::Result<(zenoh::Session, mpsc::UnboundedReceiver<OutputMessage>)> {
+    let session = zenoh.open_session(coordinator_addr).await?;
+    let (tx, rx) = mpsc::unbounded_channel();
+    for topic in topics {
+        let tx = tx.clone();
+        session
+            .declare_subscriber(zenoh_output_publish_topic(
+                dataflow_id,
+                &topic.node_id,
+                &topic.output_id,
+            ))
+            .callback(move |sample| {
+                match Timestamped::deserialize_inter_daemon_event(&sample.payload().to_bytes()) {
+                    Ok(Timestamped {
+                        inner:
+                            InterDaemonEvent::Output {
+                                node_id,
+                                output_id,
+                                metadata,
+                                data,
+                                ..
+                            },
+                        ..
+                    }) => {
+                        let _ = tx.send(OutputMessage {
+                            node_id,
+                            output_id,
+                            metadata,
+                            data: data.map(|d| Buffer::from_slice_ref(&d[..])),
+                        });
+                    }
+                    Ok(_) => {}
+                    Err(err) => tracing::warn!("{err:?}"),
+                }
+            })
+            .background()
+            .await
+            .map_err(|e| eyre!(e))
+            .wrap_err_with(|| {
+                format!(
+                    "failed to subscribe to {}/{}",
+                    topic.node_id, topic.output_id
+                )
+            })?;
+    }
+    Ok((session, rx))
+}
+
+/// Options for connecting to the zenoh network that the daemons publish outputs on.
+#[derive(Debug, clap::Args)]
+struct ZenohArgs {
+    /// Zenoh endpoint to connect to, e.g. `tcp/192.168.0.2:5456`
+    ///
+    /// Defaults to the zenoh config file given through the `ZENOH_CONFIG` env variable or,
+    /// if not set, to the daemon that runs on the coordinator machine.
+    #[clap(long, value_name = "ENDPOINT", env = "DORA_ZENOH_ENDPOINT")]
+    zenoh_endpoint: Option<String>,
+}
+
+impl ZenohArgs {
+    async fn open_session(&self, coordinator_addr: IpAddr) -> eyre::Result<zenoh::Session> {
+        let config_path = match std::env::var(zenoh::Config::DEFAULT_CONFIG_PATH_ENV) {
+            Ok(path) => Some(path),
+            Err(std::env::VarError::NotPresent) => None,
+            Err(std::env::VarError::NotUnicode(_)) => bail!(
+                "{} env variable is not valid unicode",
+                zenoh::Config::DEFAULT_CONFIG_PATH_ENV
+            ),
+        };
+        let (zenoh_config, target) = match (&self.zenoh_endpoint, config_path) {
+            (None, Some(path)) => {
+                let config = zenoh::Config::from_file(&path)
+                    .map_err(|e| eyre!(e))
+                    .wrap_err_with(|| format!("failed to read zenoh config from {path}"))?;
+                (config, format!("zenoh config `{path}`"))
+            }
+            (endpoint, _) => {
+                // by default, connect to the daemon that runs on the coordinator machine, it
+                // routes the messages of the other daemons
+                let endpoint = zenoh_endpoint(endpoint.as_deref(), coordinator_addr);
+                let mut zenoh_config = zenoh::Config::default();
+                zenoh_config
+                    .insert_json5("mode", r#""client""#)
+                    .map_err(|e| eyre!(e))?;
+                zenoh_config
+                    .insert_json5("connect/endpoints", &format!(r#"["{endpoint}"]"#))
+                    .map_err(|e| eyre!(e))
+                    .wrap_err_with(|| format!("invalid zenoh endpoint `{endpoint}`"))?;
+                (zenoh_config, format!("endpoint `{endpoint}`"))
+            }
+        };
+        zenoh::open(zenoh_config)
+            .await
+            .map_err(|e| eyre!(e))
+            .wrap_err_with(|| {
+                format!(
+                    "failed to open zenoh session using {target} (use `--zenoh-endpoint` to \
+                    connect to a different daemon)"
+                )
+            })
+    }
+}
+
+/// Returns the zenoh endpoint to connect to, defaulting to the daemon on the coordinator machine.
+fn zenoh_endpoint(endpoint: Option<&str>, coordinator_addr: IpAddr) -> String {
+    match endpoint {
+        Some(endpoint) => endpoint.to_owned(),
+        None => format!(
+            "tcp/{}",
+            SocketAddr::new(coordinator_addr, DAEMON_ZENOH_PORT)
+        ),
+    }
+}
+
+/// Sliding window over the most recent messages of an output.
+struct MessageWindow {
+    size: usize,
+    /// Receive time and data size of the messages.
+    messages: VecDeque<(Instant, usize)>,
+}
+
+impl MessageWindow {
+    fn new(size: usize) -> Self {
+        Self {
+            size: size.max(2),
+            messages: VecDeque::new(),
+        }
+    }
+
+    fn push(&mut self, bytes: usize) {
+        self.push_at(Instant::now(), bytes);
+    }
+
+    fn push_at(&mut self, time: Instant, bytes: usize) {
+        if self.messages.len() >= self.size {
+            self.messages.pop_front();
+        }
+        self.messages.push_back((time, bytes));
+    }
+
+    /// Time span between the oldest and the newest message.
+    fn duration(&self) -> Option<Duration> {
+        let (first, _) = self.messages.front()?;
+        let (last, _) = self.messages.back()?;
+        Some(*last - *first).filter(|d| !d.is_zero())
+    }
+
+    fn intervals(&self) -> impl Iterator<Item = Duration> + '_ {
+        self.messages
+            .iter()
+            .zip(self.messages.iter().skip(1))
+            .map(|((a, _), (b, _))| *b - *a)
+    }
+}
+
+fn topic_name(node_id: &NodeId, output_id: &DataId) -> String {
+    format!("{node_id}/{output_id}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Creates a window with messages of the given sizes, received at the given milliseconds.
+    pub(super) fn window(size: usize, messages: &[(u64, usize)]) -> MessageWindow {
+        let start = Instant::now();
+        let mut window = MessageWindow::new(size);
+        for (millis, bytes) in messages {
+            window.push_at(start + Duration::from_millis(*millis), *bytes);
+        }
+        window
+    }
+
+    #[test]
+    fn default_zenoh_endpoint() {
+        let ip = |s: &str| s.parse::<IpAddr>().unwrap();
+        assert_eq!(zenoh_endpoint(None, ip("10.0.0.1")), "tcp/10.0.0.1:5456");
+        assert_eq!(zenoh_endpoint(None, ip("::1")), "tcp/[::1]:5456");
+        assert_eq!(
+            zenoh_endpoint(Some("udp/10.0.0.2:7447"), ip("10.0.0.1")),
+            "udp/10.0.0.2:7447"
+        );
+    }
+
+    #[test]
+    fn message_window() {
+        assert!(window(10, &[]).duration().is_none());
+        assert!(window(10, &[(0, 1)]).duration().is_none());
+        assert!(window(10, &[(5, 1), (5, 1)]).duration().is_none());
+
+        // the window keeps at least two messages
+        assert_eq!(window(1, &[(0, 1), (10, 1), (30, 1)]).messages.len(), 2);
+
+        let window = window(3, &[(0, 1), (10, 2), (30, 3), (60, 4)]);
+        let sizes: Vec<_> = window.messages.iter().map(|(_, bytes)| *bytes).collect();
+        assert_eq!(sizes, [2, 3, 4]);
+        assert_eq!(window.duration(), Some(Duration::from_millis(50)));
+        let intervals: Vec<_> = window.intervals().collect();
+        assert_eq!(
+            intervals,
+            [Duration::from_millis(20), Duration::from_millis(30)]
+        );
+    }
+}
@@ -0,0 +1,223 @@
+use std::{
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use super::{DataflowSelector, ZenohArgs};
+use crate::command::Executable;
+use aligned_vec::{AVec, ConstAlign};
+use dora_core::{
+    topics::{INJECTED_INPUTS_SOURCE, injected_input_output_id, zenoh_input_publish_topic},
+    uhlc::HLC,
+};
+use dora_message::{
+    daemon_to_daemon::InterDaemonEvent, id::NodeId, metadata::Metadata, node_to_daemon::Timestamped,
+};
+use dora_node_api::{
+    arrow::{
+        array::{Array, ArrayData, UInt8Array},
+        datatypes::{DataType, Field, Schema},
+        json::{ReaderBuilder, reader::infer_json_schema_from_iterator},
+    },
+    arrow_utils::{copy_array_into_sample, required_data_size},
+};
+use eyre::{Context, ContextCompat, bail, eyre};
+use tokio::runtime::Builder;
+
+/// Maximum time to wait for the daemon of the receiving node.
+const SUBSCRIBER_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, clap::Args)]
+/// Publish a message to an input of a running dataflow.
+///
+/// The message is delivered to the receiving node as a normal input event. This requires
+/// `allow_input_injection: true` in the `_unstable_debug` section of the dataflow.
+pub struct Pub {
+    /// Input to publish to, in the form `node_id/input_id`
+    #[clap(value_name = "INPUT")]
+    input: String,
+    /// Message data as JSON. Arrays are sent as Arrow arrays, other values as single-element arrays.
+    #[clap(
+        value_name = "JSON",
+        conflicts_with = "file",
+        required_unless_present = "file"
+    )]
+    data: Option<String>,
+    /// Read the message data from the given file. Files with a `.json` extension are parsed as
+    /// JSON, the content of other files is sent as `uint8` array.
+    #[clap(long, value_name = "PATH")]
+    file: Option<PathBuf>,
+    /// Publish the message repeatedly at the given frequency (in Hz)
+    #[clap(long, value_name = "HZ")]
+    rate: Option<f64>,
+    /// Stop after publishing the given number of messages (requires `--rate`)
+    #[clap(long, short = 'n', value_name = "COUNT", requires = "rate")]
+    count: Option<usize>,
+    #[clap(flatten)]
+    dataflow: DataflowSelector,
+    #[clap(flatten)]
+    zenoh: ZenohArgs,
+}
+
+impl Executable for Pub {
+    fn execute(self) -> eyre::Result<()> {
+        let dataflow = self.dataflow.query_dataflow()?;
+        let (node_id, input_id) = self.input.split_once('/').ok_or_else(|| {
+            eyre!(
+                "invalid input `{}`, expected `node_id/input_id`",
+                self.input
+            )
+        })?;
+        let input = dataflow
+            .inputs
+            .into_iter()
+            .find(|i| i.node_id.as_ref() == node_id && i.input_id.as_str() == input_id)
+            .ok_or_else(|| eyre!("dataflow has no input `{}`", self.input))?;
+        let data_type = input.data_type.map(|t| t.to_arrow());
+
+        let array = match (self.data, self.file) {
+            (Some(json), _) => {
+                let value = serde_json::from_str(&json).context("failed to parse JSON data")?;
+                json_to_arrow(value, data_type)?
+            }
+            (None, Some(path)) => {
+                let content = std::fs::read(&path)
+                    .wrap_err_with(|| format!("failed to read `{}`", path.display()))?;
+                if path.extension().is_some_and(|ext| ext == "json") {
+                    let value = serde_json::from_slice(&content)
+                        .wrap_err_with(|| format!("failed to parse `{}`", path.display()))?;
+                    json_to_arrow(value, data_type)?
+                } else {
+                    UInt8Array::from(content).into_data()
+                }
+            }
+            (None, None) => bail!("no message data given"),
+        };
+        let data_len = required_data_size(&array);
+        let mut data: AVec<u8, ConstAlign<128>> = AVec::__from_elem(128, 0, data_len);
+        let type_info = copy_array_into_sample(&mut data, &array);
+
+        let interval = self
+            .rate
+            .map(|rate| {
+                Duration::try_from_secs_f64(1. / rate).map_err(|_| eyre!("invalid rate `{rate}`"))
+            })
+            .transpose()?;
+        let count = match interval {
+            Some(_) => self.count,
+            None => Some(1),
+        };
+
+        let rt = Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .context("tokio runtime failed")?;
+        rt.block_on(async {
+            let session = self
+                .zenoh
+                .open_session(self.dataflow.coordinator_addr)
+                .await?;
+            let publisher = session
+                .declare_publisher(zenoh_input_publish_topic(
+                    dataflow.uuid,
+                    &input.node_id,
+                    &input.input_id,
+                ))
+                .await
+                .map_err(|e| eyre!(e))
+                .context("failed to create zenoh publisher")?;
+
+            // messages are dropped if the daemon has not subscribed yet
+            let start = Instant::now();
+            while !publisher
+                .matching_status()
+                .await
+                .map_err(|e| eyre!(e))?
+                .matching()
+            {
+                if start.elapsed() > SUBSCRIBER_TIMEOUT {
+                    bail!(
+                        "no daemon accepts inputs for node `{}` (is `_unstable_debug.\
+                        allow_input_injection` enabled for the dataflow?)",
+                        input.node_id
+                    );
+                }
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+
+            let clock = HLC::default();
+            let mut ticker = interval.map(tokio::time::interval);
+            let mut sent = 0;
+            while count.is_none_or(|count| sent < count) {
+                if let Some(ticker) = &mut ticker {
+                    ticker.tick().await;
+                }
+                // injected messages are delivered like outputs of a pseudo node
+                let event = InterDaemonEvent::Output {
+                    dataflow_id: dataflow.uuid,
+                    node_id: NodeId::from(INJECTED_INPUTS_SOURCE.to_owned()),
+                    output_id: injected_input_output_id(&input.node_id, &input.input_id),
+                    metadata: Metadata::new(clock.new_timestamp(), type_info.clone()),
+                    data: Some(data.clone()),
+                };
+                let serialized = Timestamped {
+                    inner: event,
+                    timestamp: clock.new_timestamp(),
+                }
+                .serialize();
+                publisher
+                    .put(serialized)
+                    .await
+                    .map_err(|e| eyre!(e))
+                    .context("zenoh put failed")?;
+                sent += 1;
+            }
+
+            session
+                .close()
+                .await
+                .map_err(|e| eyre!(e))
+                .context("failed to close zenoh session")
+        })
+    }
+}
+
+/// Converts the given JSON value to an Arrow array.
+///
+/// JSON arrays are converted element-wise, other values result in a single-element array. The
+/// data type is inferred from the JSON value if no `data_type` is given.
+pub(crate) fn json_to_arrow(
+    value: serde_json::Value,
+    data_type: Option<DataType>,
+) -> eyre::Result<ArrayData> {
+    let values = match value {
+        serde_json::Value::Array(values) => values,
+        other => vec![other],
+    };
+    let rows: Vec<_> = values
+        .into_iter()
+        .map(|value| serde_json::json!({ "value": value }))
+        .collect();
+    let field = match data_type {
+        Some(data_type) => Field::new("value", data_type, true),
+        None => infer_json_schema_from_iterator(rows.iter().map(Ok))
+            .context("failed to infer data type")?
+            .field_with_name("value")
+            .cloned()
+            .unwrap_or_else(|_| Field::new("value", DataType::Null, true)),
+    };
+
+    let mut decoder = ReaderBuilder::new(Arc::new(Schema::new(vec![field])))
+        .with_batch_size(rows.len().max(1))
+        .build_decoder()
+        .context("failed to create JSON decoder")?;
+    decoder
+        .serialize(&rows)
+        .context("failed to convert JSON data to the input type")?;
+    let batch = decoder
+        .flush()
+        .context("failed to convert JSON data to the input type")?
+        .context("no data")?;
+    Ok(batch.column(0).to_data())
+}
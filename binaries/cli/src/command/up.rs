@@ -1,6 +1,9 @@
 use super::check::daemon_running;
 use super::{Executable, default_tracing};
-use crate::{LOCALHOST, common::connect_to_coordinator};
+use crate::{
+    LOCALHOST,
+    common::{SecurityArgs, connect_to_coordinator},
+};
 use dora_core::topics::DORA_COORDINATOR_PORT_CONTROL_DEFAULT;
 use dora_message::{cli_to_coordinator::ControlRequest, coordinator_to_cli::ControlRequestReply};
 use eyre::{Context, ContextCompat, bail};
@@ -13,28 +16,30 @@ pub struct Up {
     /// Use a custom configuration
     #[clap(long, hide = true, value_name = "PATH", value_hint = clap::ValueHint::FilePath)]
     config: Option<PathBuf>,
+    #[clap(flatten)]
+    security: SecurityArgs,
 }
 
 impl Executable for Up {
     fn execute(self) -> eyre::Result<()> {
         default_tracing()?;
-        up(self.config.as_deref())
+        up(self.config.as_deref(), &self.security)
     }
 }
 
 #[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
 struct UpConfig {}
 
-pub(crate) fn up(config_path: Option<&Path>) -> eyre::Result<()> {
+pub(crate) fn up(config_path: Option<&Path>, security: &SecurityArgs) -> eyre::Result<()> {
     let UpConfig {} = parse_dora_config(config_path)?;
     let coordinator_addr = (LOCALHOST, DORA_COORDINATOR_PORT_CONTROL_DEFAULT).into();
-    let mut session = match connect_to_coordinator(coordinator_addr) {
+    let mut session = match connect_to_coordinator(coordinator_addr, security) {
         Ok(session) => session,
         Err(_) => {
-            start_coordinator().wrap_err("failed to start dora-coordinator")?;
+            start_coordinator(security).wrap_err("failed to start dora-coordinator")?;
 
             loop {
-                match connect_to_coordinator(coordinator_addr) {
+                match connect_to_coordinator(coordinator_addr, security) {
                     Ok(session) => break session,
                     Err(_) => {
                         // sleep a bit until the coordinator accepts connections
@@ -46,7 +51,7 @@ pub(crate) fn up(config_path: Option<&Path>) -> eyre::Result<()> {
     };
 
     if !daemon_running(&mut *session)? {
-        start_daemon().wrap_err("failed to start dora-daemon")?;
+        start_daemon(security).wrap_err("failed to start dora-daemon")?;
 
         // wait a bit until daemon is connected
         let mut i = 0;
@@ -69,9 +74,10 @@ pub(crate) fn up(config_path: Option<&Path>) -> eyre::Result<()> {
 pub(crate) fn destroy(
     config_path: Option<&Path>,
     coordinator_addr: SocketAddr,
+    security: &SecurityArgs,
 ) -> Result<(), eyre::ErrReport> {
     let UpConfig {} = parse_dora_config(config_path)?;
-    match connect_to_coordinator(coordinator_addr) {
+    match connect_to_coordinator(coordinator_addr, security) {
         Ok(mut session) => {
             // send destroy command to dora-coordinator
             let reply_raw = session
@@ -113,7 +119,7 @@ fn parse_dora_config(config_path: Option<&Path>) -> Result<UpConfig, eyre::ErrRe
     Ok(config)
 }
 
-fn start_coordinator() -> eyre::Result<()> {
+fn start_coordinator(security: &SecurityArgs) -> eyre::Result<()> {
     let path = if cfg!(feature = "python") {
         std::env::args_os()
             .nth(1)
@@ -126,6 +132,7 @@ fn start_coordinator() -> eyre::Result<()> {
     let mut cmd = Command::new(path);
     cmd.arg("coordinator");
     cmd.arg("--quiet");
+    security.apply_to(&mut cmd);
     cmd.spawn().wrap_err("failed to run `dora coordinator`")?;
 
     println!("started dora coordinator");
@@ -133,7 +140,7 @@ fn start_coordinator() -> eyre::Result<()> {
     Ok(())
 }
 
-fn start_daemon() -> eyre::Result<()> {
+fn start_daemon(security: &SecurityArgs) -> eyre::Result<()> {
     let path = if cfg!(feature = "python") {
         std::env::args_os()
             .nth(1)
@@ -146,6 +153,7 @@ fn start_daemon() -> eyre::Result<()> {
     let mut cmd = Command::new(path);
     cmd.arg("daemon");
     cmd.arg("--quiet");
+    security.apply_to(&mut cmd);
     cmd.spawn().wrap_err("failed to run `dora daemon`")?;
 
     println!("started dora daemon");
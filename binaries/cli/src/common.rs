@@ -1,6 +1,9 @@
 use crate::formatting::FormatDataflowError;
-use communication_layer_request_reply::{RequestReplyLayer, TcpLayer, TcpRequestReplyConnection};
-use dora_core::descriptor::{Descriptor, source_is_url};
+use communication_layer_request_reply::{TcpConnection, TcpRequestReplyConnection};
+use dora_core::{
+    descriptor::{Descriptor, source_is_url},
+    security::{BlockingMaybeTlsStream, ClientSecurity, DORA_TOKEN_ENV},
+};
 use dora_download::download_file;
 use dora_message::{
     cli_to_coordinator::ControlRequest,
@@ -9,8 +12,9 @@ use dora_message::{
 use eyre::{Context, ContextCompat, bail};
 use std::{
     env::current_dir,
-    net::SocketAddr,
+    net::{SocketAddr, TcpStream},
     path::{Path, PathBuf},
+    process::Command,
 };
 use tokio::runtime::Builder;
 use uuid::Uuid;
@@ -19,6 +23,12 @@ pub(crate) fn handle_dataflow_result(
     result: DataflowResult,
     uuid: Option<Uuid>,
 ) -> Result<(), eyre::Error> {
+    if let Some(stop_request) = &result.stopped_by {
+        match uuid {
+            Some(uuid) => eprintln!("Dataflow {uuid} was {stop_request}"),
+            None => eprintln!("Dataflow was {stop_request}"),
+        }
+    }
     if result.is_ok() {
         Ok(())
     } else {
@@ -50,10 +60,62 @@ pub(crate) fn query_running_dataflows(
     Ok(ids)
 }
 
+/// Options for securing the connections to the coordinator.
+#[derive(Debug, Clone, Default, clap::Args)]
+pub struct SecurityArgs {
+    /// Connect to the coordinator using TLS, trusting the given CA certificate (PEM)
+    #[clap(long, value_name = "PATH", env = "DORA_TLS_CA")]
+    pub tls_ca: Option<PathBuf>,
+    /// Name to check the TLS certificate of the coordinator against [default: coordinator IP]
+    #[clap(long, value_name = "NAME", env = "DORA_TLS_SERVER_NAME")]
+    pub tls_server_name: Option<String>,
+    /// Token to authenticate at the coordinator
+    #[clap(long, env = DORA_TOKEN_ENV, hide_env_values = true)]
+    pub token: Option<String>,
+}
+
+impl SecurityArgs {
+    pub(crate) fn client_security(&self) -> eyre::Result<ClientSecurity> {
+        let mut security = ClientSecurity::default().with_token(self.token.clone());
+        if let Some(ca) = &self.tls_ca {
+            security = security.with_tls(ca, self.tls_server_name.clone())?;
+        }
+        Ok(security)
+    }
+
+    /// Passes the options to a spawned `dora` process.
+    pub(crate) fn apply_to(&self, cmd: &mut Command) {
+        if let Some(ca) = &self.tls_ca {
+            cmd.env("DORA_TLS_CA", ca);
+        }
+        if let Some(name) = &self.tls_server_name {
+            cmd.env("DORA_TLS_SERVER_NAME", name);
+        }
+        if let Some(token) = &self.token {
+            cmd.env(DORA_TOKEN_ENV, token);
+        }
+    }
+}
+
 pub(crate) fn connect_to_coordinator(
     coordinator_addr: SocketAddr,
-) -> std::io::Result<Box<TcpRequestReplyConnection>> {
-    TcpLayer::new().connect(coordinator_addr)
+    security: &SecurityArgs,
+) -> eyre::Result<Box<TcpRequestReplyConnection>> {
+    Ok(Box::new(open_coordinator_connection(
+        coordinator_addr,
+        security,
+    )?))
+}
+
+/// Opens a connection to the coordinator, e.g. for subscribing to log messages.
+pub(crate) fn open_coordinator_connection(
+    coordinator_addr: SocketAddr,
+    security: &SecurityArgs,
+) -> eyre::Result<TcpConnection<BlockingMaybeTlsStream>> {
+    let stream = TcpStream::connect(coordinator_addr)
+        .wrap_err_with(|| format!("failed to connect to {coordinator_addr}"))?;
+    let stream = security.client_security()?.connect_blocking(stream)?;
+    Ok(TcpConnection { stream })
 }
 
 pub(crate) fn resolve_dataflow(dataflow: String) -> eyre::Result<PathBuf> {
@@ -2,6 +2,7 @@ use crate::{
     Event,
     tcp_utils::{tcp_receive, tcp_send},
 };
+use dora_core::security::{MaybeTlsStream, ServerSecurity};
 use dora_message::{
     BuildId, cli_to_coordinator::ControlRequest, coordinator_to_cli::ControlRequestReply,
 };
@@ -23,12 +24,18 @@ use uuid::Uuid;
 
 pub(crate) async fn control_events(
     control_listen_addr: SocketAddr,
+    security: ServerSecurity,
     tasks: &FuturesUnordered<JoinHandle<()>>,
 ) -> eyre::Result<impl Stream<Item = Event> + use<>> {
     let (tx, rx) = mpsc::channel(10);
 
     let (finish_tx, mut finish_rx) = mpsc::channel(1);
-    tasks.push(tokio::spawn(listen(control_listen_addr, tx, finish_tx)));
+    tasks.push(tokio::spawn(listen(
+        control_listen_addr,
+        security,
+        tx,
+        finish_tx,
+    )));
     tasks.push(tokio::spawn(async move {
         while let Some(()) = finish_rx.recv().await {}
     }));
@@ -38,6 +45,7 @@ pub(crate) async fn control_events(
 
 async fn listen(
     control_listen_addr: SocketAddr,
+    security: ServerSecurity,
     tx: mpsc::Sender<ControlEvent>,
     _finish_tx: mpsc::Sender<()>,
 ) {
@@ -65,7 +73,12 @@ async fn listen(
         match connection.wrap_err("failed to connect") {
             Ok((connection, _)) => {
                 let tx = tx.clone();
-                tokio::spawn(handle_requests(connection, tx, _finish_tx.clone()));
+                tokio::spawn(handle_requests(
+                    connection,
+                    security.clone(),
+                    tx,
+                    _finish_tx.clone(),
+                ));
             }
             Err(err) => {
                 if tx.blocking_send(err.into()).is_err() {
@@ -77,10 +90,18 @@ async fn listen(
 }
 
 async fn handle_requests(
-    mut connection: TcpStream,
+    connection: TcpStream,
+    security: ServerSecurity,
     tx: mpsc::Sender<ControlEvent>,
     _finish_tx: mpsc::Sender<()>,
 ) {
+    let mut connection = match security.accept(connection).await {
+        Ok(connection) => connection,
+        Err(err) => {
+            tracing::warn!("{:?}", err.wrap_err("failed to accept control connection"));
+            return;
+        }
+    };
     let peer_addr = connection.peer_addr().ok();
     loop {
         let next_request = tcp_receive(&mut connection).map(Either::Left);
@@ -190,6 +211,7 @@ async fn handle_request(
 }
 
 #[derive(Debug)]
+#[allow(clippy::large_enum_variant)]
 pub enum ControlEvent {
     IncomingRequest {
         request: ControlRequest,
@@ -198,12 +220,12 @@ pub enum ControlEvent {
     LogSubscribe {
         dataflow_id: Uuid,
         level: log::LevelFilter,
-        connection: TcpStream,
+        connection: MaybeTlsStream,
     },
     BuildLogSubscribe {
         build_id: BuildId,
         level: log::LevelFilter,
-        connection: TcpStream,
+        connection: MaybeTlsStream,
     },
     Error(eyre::Report),
 }
@@ -5,30 +5,31 @@ use crate::{
 pub use control::ControlEvent;
 use dora_core::{
     config::{NodeId, OperatorId},
-    descriptor::DescriptorExt,
+    descriptor::{DescriptorExt, check_blocking_inputs, check_node_inputs},
+    security::{MaybeTlsStream, ServerSecurity},
     uhlc::{self, HLC},
 };
 use dora_message::{
     BuildId, DataflowId, SessionId,
     cli_to_coordinator::ControlRequest,
-    common::{DaemonId, GitSource},
+    common::{DaemonId, DataflowStopRequest, GitSource, NodeError, NodeErrorCause, NodeExitStatus},
     coordinator_to_cli::{
         ControlRequestReply, DataflowIdAndName, DataflowList, DataflowListEntry, DataflowResult,
-        DataflowStatus, LogLevel, LogMessage,
+        DataflowStatus, InputInfo, LogLevel, LogMessage, TopicInfo,
     },
     coordinator_to_daemon::{
-        BuildDataflowNodes, DaemonCoordinatorEvent, RegisterResult, Timestamped,
+        AddDataflowNodes, BuildDataflowNodes, DaemonCoordinatorEvent, RegisterResult, Timestamped,
     },
-    daemon_to_coordinator::{DaemonCoordinatorReply, DataflowDaemonResult},
-    descriptor::{Descriptor, ResolvedNode},
+    daemon_to_coordinator::{DaemonCoordinatorReply, DaemonReconnect, DataflowDaemonResult},
+    descriptor::{CoreNodeKind, Deploy, Descriptor, Node, ResolvedNode},
 };
 use eyre::{ContextCompat, Result, WrapErr, bail, eyre};
 use futures::{Future, Stream, StreamExt, future::join_all, stream::FuturesUnordered};
 use futures_concurrency::stream::Merge;
-use itertools::Itertools;
 use log_subscriber::LogSubscriber;
 use petname::petname;
 use run::SpawnedDataflow;
+use state::{CoordinatorState, StateFile};
 use std::{
     collections::{BTreeMap, BTreeSet, HashMap},
     net::SocketAddr,
@@ -37,37 +38,50 @@ use std::{
     time::{Duration, Instant},
 };
 use tokio::{
-    net::TcpStream,
     sync::{mpsc, oneshot},
     task::JoinHandle,
 };
-use tokio_stream::wrappers::{ReceiverStream, TcpListenerStream};
+use tokio_stream::wrappers::ReceiverStream;
 use uuid::Uuid;
 
 mod control;
 mod listener;
 mod log_subscriber;
 mod run;
+mod state;
 mod tcp_utils;
 
+/// Time that daemons have to reconnect after the coordinator restored its state.
+const DAEMON_RECONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Starts the coordinator.
+///
+/// If a `state_file` is given, the coordinator persists its state to that file and restores
+/// it on start. This way, dataflows keep running when the coordinator is restarted.
+///
+/// The given `security` settings apply to both daemon and control connections.
 pub async fn start(
     bind: SocketAddr,
     bind_control: SocketAddr,
     external_events: impl Stream<Item = Event> + Unpin,
+    state_file: Option<PathBuf>,
+    security: ServerSecurity,
 ) -> Result<(u16, impl Future<Output = eyre::Result<()>>), eyre::ErrReport> {
     let listener = listener::create_listener(bind).await?;
     let port = listener
         .local_addr()
         .wrap_err("failed to get local addr of listener")?
         .port();
-    let new_daemon_connections = TcpListenerStream::new(listener).map(|c| {
-        c.map(Event::NewDaemonConnection)
-            .wrap_err("failed to open connection")
-            .unwrap_or_else(Event::DaemonConnectError)
-    });
+    let (new_daemon_connections_tx, new_daemon_connections) = mpsc::channel(10);
+    tokio::spawn(listener::accept_connections(
+        listener,
+        security.clone(),
+        new_daemon_connections_tx,
+    ));
+    let new_daemon_connections = ReceiverStream::new(new_daemon_connections);
 
     let mut tasks = FuturesUnordered::new();
-    let control_events = control::control_events(bind_control, &tasks)
+    let control_events = control::control_events(bind_control, security.clone(), &tasks)
         .await
         .wrap_err("failed to create control events")?;
 
@@ -83,7 +97,7 @@ pub async fn start(
         .merge();
 
     let future = async move {
-        start_inner(events, &tasks).await?;
+        start_inner(events, &tasks, state_file).await?;
 
         tracing::debug!("coordinator main loop finished, waiting on spawned tasks");
         while let Some(join_result) = tasks.next().await {
@@ -97,6 +111,61 @@ pub async fn start(
     Ok((port, future))
 }
 
+fn dataflow_topics<'a>(nodes: impl Iterator<Item = &'a ResolvedNode>) -> Vec<TopicInfo> {
+    let mut topics = Vec::new();
+    // replicas share the outputs of the replicated node
+    let nodes = nodes.filter(|n| n.replica.as_ref().is_none_or(|r| r.index == 0));
+    for node in nodes {
+        let node_id = node.output_node_id();
+        match &node.kind {
+            CoreNodeKind::Custom(n) => {
+                topics.extend(n.run_config.outputs.iter().map(|output| TopicInfo {
+                    node_id: node_id.clone(),
+                    output_id: output.id.clone(),
+                    data_type: output.data_type.clone(),
+                }))
+            }
+            CoreNodeKind::Runtime(n) => {
+                for operator in &n.operators {
+                    topics.extend(operator.config.outputs.iter().map(|output| TopicInfo {
+                        node_id: node_id.clone(),
+                        output_id: format!("{}/{}", operator.id, output.id).into(),
+                        data_type: output.data_type.clone(),
+                    }))
+                }
+            }
+        }
+    }
+    topics
+}
+
+fn dataflow_inputs<'a>(nodes: impl Iterator<Item = &'a ResolvedNode>) -> Vec<InputInfo> {
+    let mut inputs = Vec::new();
+    for node in nodes {
+        match &node.kind {
+            CoreNodeKind::Custom(n) => inputs.extend(n.run_config.inputs.iter().map(
+                |(input_id, input)| InputInfo {
+                    node_id: node.id.clone(),
+                    input_id: input_id.clone(),
+                    data_type: input.data_type.clone(),
+                },
+            )),
+            CoreNodeKind::Runtime(n) => {
+                for operator in &n.operators {
+                    inputs.extend(operator.config.inputs.iter().map(|(input_id, input)| {
+                        InputInfo {
+                            node_id: node.id.clone(),
+                            input_id: format!("{}/{input_id}", operator.id).into(),
+                            data_type: input.data_type.clone(),
+                        }
+                    }))
+                }
+            }
+        }
+    }
+    inputs
+}
+
 // Resolve the dataflow name.
 fn resolve_name(
     name: String,
@@ -161,6 +230,24 @@ impl DaemonConnections {
             .find(|id| id.matches_machine_id(machine_id))
     }
 
+    /// Returns a daemon that fulfills the machine and label constraints of the given deploy
+    /// section.
+    ///
+    /// Daemons are checked in a stable order, so that the build and spawn of a dataflow choose
+    /// the same daemon.
+    fn find_matching_deploy(&self, deploy: &Deploy) -> Option<&DaemonId> {
+        self.daemons
+            .iter()
+            .filter(|(id, _)| {
+                deploy
+                    .machine
+                    .as_deref()
+                    .is_none_or(|machine| id.matches_machine_id(machine))
+            })
+            .find(|(_, connection)| deploy.matches_labels(&connection.labels))
+            .map(|(id, _)| id)
+    }
+
     fn drain(&mut self) -> impl Iterator<Item = (DaemonId, DaemonConnection)> {
         std::mem::take(&mut self.daemons).into_iter()
     }
@@ -189,6 +276,7 @@ impl DaemonConnections {
 async fn start_inner(
     events: impl Stream<Item = Event> + Unpin,
     tasks: &FuturesUnordered<JoinHandle<()>>,
+    state_file: Option<PathBuf>,
 ) -> eyre::Result<()> {
     let clock = Arc::new(HLC::default());
 
@@ -215,17 +303,45 @@ async fn start_inner(
     let mut archived_dataflows: HashMap<DataflowId, ArchivedDataflow> = HashMap::new();
     let mut daemon_connections = DaemonConnections::default();
 
+    // daemons of restored dataflows that did not reconnect yet
+    let mut reconnecting_daemons = BTreeSet::new();
+    let reconnect_deadline = Instant::now() + DAEMON_RECONNECT_TIMEOUT;
+    let mut state_file = match state_file {
+        Some(path) => {
+            let (state_file, state) = StateFile::load(path).await?;
+            if !state.running_dataflows.is_empty() {
+                tracing::info!(
+                    "restored {} running dataflows, waiting for daemons to reconnect",
+                    state.running_dataflows.len()
+                );
+            }
+            for dataflow in state.running_dataflows {
+                reconnecting_daemons.extend(dataflow.daemons.iter().cloned());
+                running_dataflows.insert(dataflow.uuid, dataflow.into());
+            }
+            archived_dataflows.extend(state.archived_dataflows);
+            dataflow_results.extend(
+                state
+                    .dataflow_results
+                    .into_iter()
+                    .map(|(uuid, results)| (uuid, results.into_iter().collect())),
+            );
+            Some(state_file)
+        }
+        None => None,
+    };
+
     while let Some(event) = events.next().await {
         // used below for measuring the event handling duration
         let start = Instant::now();
         let event_kind = event.kind();
+        let mut changes_state = event.changes_state();
 
         if event.log() {
             tracing::trace!("Handling event {event:?}");
         }
         match event {
             Event::NewDaemonConnection(connection) => {
-                connection.set_nodelay(true)?;
                 let events_tx = daemon_events_tx.clone();
                 if let Some(events_tx) = events_tx {
                     let task = tokio::spawn(listener::handle_connection(
@@ -246,13 +362,18 @@ async fn start_inner(
             Event::Daemon(event) => match event {
                 DaemonRequest::Register {
                     machine_id,
+                    labels,
                     mut connection,
                     version_check_result,
+                    reconnect,
                 } => {
                     let existing = match &machine_id {
                         Some(id) => daemon_connections.get_matching_daemon_id(id),
                         None => daemon_connections.unnamed().next(),
                     };
+                    // a reconnecting daemon replaces its previous connection
+                    let existing = existing
+                        .filter(|id| reconnect.as_ref().is_none_or(|r| &&r.daemon_id != id));
                     let existing_result = if existing.is_some() {
                         Err(format!(
                             "There is already a connected daemon with machine ID `{machine_id:?}`"
@@ -261,8 +382,11 @@ async fn start_inner(
                         Ok(())
                     };
 
-                    // assign a unique ID to the daemon
-                    let daemon_id = DaemonId::new(machine_id);
+                    // assign a unique ID to the daemon, reconnecting daemons keep their ID
+                    let daemon_id = match &reconnect {
+                        Some(reconnect) => reconnect.daemon_id.clone(),
+                        None => DaemonId::new(machine_id),
+                    };
 
                     let reply: Timestamped<RegisterResult> = Timestamped {
                         inner: match version_check_result.as_ref().and(existing_result.as_ref()) {
@@ -284,8 +408,21 @@ async fn start_inner(
                                 DaemonConnection {
                                     stream: connection,
                                     last_heartbeat: Instant::now(),
+                                    labels,
                                 },
                             );
+                            if let Some(reconnect) = reconnect {
+                                tracing::info!("daemon `{daemon_id}` reconnected");
+                                reconnecting_daemons.remove(&daemon_id);
+                                handle_daemon_reconnect(
+                                    reconnect,
+                                    &mut running_dataflows,
+                                    &mut dataflow_results,
+                                    &mut archived_dataflows,
+                                    &clock,
+                                )
+                                .await;
+                            }
                         }
                         Err(err) => {
                             tracing::warn!(
@@ -346,69 +483,32 @@ async fn start_inner(
                     }
                 }
                 DataflowEvent::DataflowFinishedOnDaemon { daemon_id, result } => {
-                    tracing::debug!(
-                        "coordinator received DataflowFinishedOnDaemon ({daemon_id:?}, result: {result:?})"
+                    handle_dataflow_finished_on_daemon(
+                        uuid,
+                        daemon_id,
+                        result,
+                        &mut running_dataflows,
+                        &mut dataflow_results,
+                        &mut archived_dataflows,
+                        &clock,
+                    )
+                    .await;
+                }
+                DataflowEvent::StopRequested { request } => {
+                    tracing::info!(
+                        "node `{}` requested to stop dataflow `{uuid}`",
+                        request.node_id
                     );
-                    match running_dataflows.entry(uuid) {
-                        std::collections::hash_map::Entry::Occupied(mut entry) => {
-                            let dataflow = entry.get_mut();
-                            dataflow.daemons.remove(&daemon_id);
-                            tracing::info!(
-                                "removed machine id: {daemon_id} from dataflow: {:#?}",
-                                dataflow.uuid
-                            );
-                            dataflow_results
-                                .entry(uuid)
-                                .or_default()
-                                .insert(daemon_id, result);
-
-                            if dataflow.daemons.is_empty() {
-                                // Archive finished dataflow
-                                archived_dataflows
-                                    .entry(uuid)
-                                    .or_insert_with(|| ArchivedDataflow::from(entry.get()));
-                                let mut finished_dataflow = entry.remove();
-                                let dataflow_id = finished_dataflow.uuid;
-                                send_log_message(
-                                    &mut finished_dataflow.log_subscribers,
-                                    &LogMessage {
-                                        build_id: None,
-                                        dataflow_id: Some(dataflow_id),
-                                        node_id: None,
-                                        daemon_id: None,
-                                        level: LogLevel::Info.into(),
-                                        target: Some("coordinator".into()),
-                                        module_path: None,
-                                        file: None,
-                                        line: None,
-                                        message: "dataflow finished".into(),
-                                    },
-                                )
-                                .await;
-
-                                let reply = ControlRequestReply::DataflowStopped {
-                                    uuid,
-                                    result: dataflow_results
-                                        .get(&uuid)
-                                        .map(|r| dataflow_result(r, uuid, &clock))
-                                        .unwrap_or_else(|| {
-                                            DataflowResult::ok_empty(uuid, clock.new_timestamp())
-                                        }),
-                                };
-                                for sender in finished_dataflow.stop_reply_senders {
-                                    let _ = sender.send(Ok(reply.clone()));
-                                }
-                                if !matches!(
-                                    finished_dataflow.spawn_result,
-                                    CachedResult::Cached { .. }
-                                ) {
-                                    log::error!("pending spawn result on dataflow finish");
-                                }
-                            }
-                        }
-                        std::collections::hash_map::Entry::Vacant(_) => {
-                            tracing::warn!("dataflow not running on DataflowFinishedOnDaemon");
-                        }
+                    if let Err(err) = stop_dataflow(
+                        &mut running_dataflows,
+                        uuid,
+                        &mut daemon_connections,
+                        clock.new_timestamp(),
+                        None,
+                    )
+                    .await
+                    {
+                        tracing::warn!("failed to stop dataflow `{uuid}`: {err:?}");
                     }
                 }
             },
@@ -662,6 +762,108 @@ async fn start_inner(
                                 }
                             }
                         }
+                        ControlRequest::Topics { uuid, name } => {
+                            let dataflow_uuid = if let Some(uuid) = uuid {
+                                Ok(uuid)
+                            } else if let Some(name) = name {
+                                resolve_name(name, &running_dataflows, &archived_dataflows)
+                            } else {
+                                Err(eyre!("No uuid"))
+                            };
+                            let reply = dataflow_uuid.and_then(|uuid| {
+                                let dataflow =
+                                    running_dataflows.get(&uuid).wrap_err_with(|| {
+                                        format!("no running dataflow with ID `{uuid}`")
+                                    })?;
+                                Ok(ControlRequestReply::Topics {
+                                    uuid,
+                                    topics: dataflow_topics(dataflow.nodes.values()),
+                                    inputs: dataflow_inputs(dataflow.nodes.values()),
+                                })
+                            });
+                            let _ = reply_sender.send(reply);
+                        }
+                        ControlRequest::AddNode { uuid, name, node } => {
+                            let dataflow_uuid = if let Some(uuid) = uuid {
+                                Ok(uuid)
+                            } else if let Some(name) = name {
+                                resolve_name(name, &running_dataflows, &archived_dataflows)
+                            } else {
+                                Err(eyre!("No uuid"))
+                            };
+                            let reply = match dataflow_uuid {
+                                Ok(uuid) => {
+                                    let node_id = node.id.clone();
+                                    add_node(
+                                        &mut running_dataflows,
+                                        uuid,
+                                        *node,
+                                        &mut daemon_connections,
+                                        clock.new_timestamp(),
+                                    )
+                                    .await
+                                    .map(|()| ControlRequestReply::NodeAdded { uuid, node_id })
+                                }
+                                Err(err) => Err(err),
+                            };
+                            let _ = reply_sender.send(reply);
+                        }
+                        ControlRequest::RemoveNode {
+                            uuid,
+                            name,
+                            node_id,
+                            grace_duration,
+                        } => {
+                            let dataflow_uuid = if let Some(uuid) = uuid {
+                                Ok(uuid)
+                            } else if let Some(name) = name {
+                                resolve_name(name, &running_dataflows, &archived_dataflows)
+                            } else {
+                                Err(eyre!("No uuid"))
+                            };
+                            let reply = match dataflow_uuid {
+                                Ok(uuid) => remove_node(
+                                    &mut running_dataflows,
+                                    uuid,
+                                    node_id.clone(),
+                                    grace_duration,
+                                    &mut daemon_connections,
+                                    clock.new_timestamp(),
+                                )
+                                .await
+                                .map(|()| ControlRequestReply::NodeRemoved { uuid, node_id }),
+                                Err(err) => Err(err),
+                            };
+                            let _ = reply_sender.send(reply);
+                        }
+                        ControlRequest::RestartNode {
+                            uuid,
+                            name,
+                            node_id,
+                            grace_duration,
+                        } => {
+                            let dataflow_uuid = if let Some(uuid) = uuid {
+                                Ok(uuid)
+                            } else if let Some(name) = name {
+                                resolve_name(name, &running_dataflows, &archived_dataflows)
+                            } else {
+                                Err(eyre!("No uuid"))
+                            };
+                            let reply = match dataflow_uuid {
+                                Ok(uuid) => restart_node(
+                                    &running_dataflows,
+                                    uuid,
+                                    node_id.clone(),
+                                    grace_duration,
+                                    &mut daemon_connections,
+                                    clock.new_timestamp(),
+                                )
+                                .await
+                                .map(|()| ControlRequestReply::NodeRestarted { uuid, node_id }),
+                                Err(err) => Err(err),
+                            };
+                            let _ = reply_sender.send(reply);
+                        }
                         ControlRequest::Destroy => {
                             tracing::info!("Received destroy command");
 
@@ -810,6 +1012,30 @@ async fn start_inner(
                         daemon_connections.remove(&machine_id);
                     }
                 }
+                if !reconnecting_daemons.is_empty() && Instant::now() > reconnect_deadline {
+                    for daemon_id in std::mem::take(&mut reconnecting_daemons) {
+                        tracing::error!(
+                            "daemon `{daemon_id}` did not reconnect after coordinator restart"
+                        );
+                        let dataflows: Vec<_> = running_dataflows
+                            .values()
+                            .filter(|d| d.daemons.contains(&daemon_id))
+                            .map(|d| d.uuid)
+                            .collect();
+                        for uuid in dataflows {
+                            changes_state = true;
+                            handle_daemon_lost(
+                                uuid,
+                                daemon_id.clone(),
+                                &mut running_dataflows,
+                                &mut dataflow_results,
+                                &mut archived_dataflows,
+                                &clock,
+                            )
+                            .await;
+                        }
+                    }
+                }
             }
             Event::CtrlC => {
                 tracing::info!("Destroying coordinator after receiving Ctrl-C signal");
@@ -919,6 +1145,14 @@ async fn start_inner(
             },
         }
 
+        if let Some(state_file) = state_file.as_mut().filter(|_| changes_state) {
+            let state =
+                CoordinatorState::new(&running_dataflows, &archived_dataflows, &dataflow_results);
+            if let Err(err) = state_file.store(&state).await {
+                tracing::error!("failed to persist coordinator state: {err:?}");
+            }
+        }
+
         // warn if event handling took too long -> the main loop should never be blocked for too long
         let elapsed = start.elapsed();
         if elapsed > Duration::from_millis(100) {
@@ -929,11 +1163,177 @@ async fn start_inner(
         }
     }
 
+    if let Some(state_file) = &mut state_file {
+        // all dataflows were stopped on destroy
+        for (uuid, dataflow) in running_dataflows.drain() {
+            archived_dataflows
+                .entry(uuid)
+                .or_insert_with(|| ArchivedDataflow::from(&dataflow));
+        }
+        let state =
+            CoordinatorState::new(&running_dataflows, &archived_dataflows, &dataflow_results);
+        if let Err(err) = state_file.store(&state).await {
+            tracing::error!("failed to persist coordinator state: {err:?}");
+        }
+    }
+
     tracing::info!("stopped");
 
     Ok(())
 }
 
+async fn handle_dataflow_finished_on_daemon(
+    uuid: Uuid,
+    daemon_id: DaemonId,
+    result: DataflowDaemonResult,
+    running_dataflows: &mut HashMap<Uuid, RunningDataflow>,
+    dataflow_results: &mut HashMap<Uuid, BTreeMap<DaemonId, DataflowDaemonResult>>,
+    archived_dataflows: &mut HashMap<Uuid, ArchivedDataflow>,
+    clock: &HLC,
+) {
+    tracing::debug!(
+        "coordinator received DataflowFinishedOnDaemon ({daemon_id:?}, result: {result:?})"
+    );
+    match running_dataflows.entry(uuid) {
+        std::collections::hash_map::Entry::Occupied(mut entry) => {
+            let dataflow = entry.get_mut();
+            dataflow.daemons.remove(&daemon_id);
+            tracing::info!(
+                "removed machine id: {daemon_id} from dataflow: {:#?}",
+                dataflow.uuid
+            );
+            dataflow_results
+                .entry(uuid)
+                .or_default()
+                .insert(daemon_id, result);
+
+            if dataflow.daemons.is_empty() {
+                // Archive finished dataflow
+                archived_dataflows
+                    .entry(uuid)
+                    .or_insert_with(|| ArchivedDataflow::from(entry.get()));
+                let mut finished_dataflow = entry.remove();
+                let dataflow_id = finished_dataflow.uuid;
+                send_log_message(
+                    &mut finished_dataflow.log_subscribers,
+                    &LogMessage {
+                        build_id: None,
+                        dataflow_id: Some(dataflow_id),
+                        node_id: None,
+                        daemon_id: None,
+                        level: LogLevel::Info.into(),
+                        target: Some("coordinator".into()),
+                        module_path: None,
+                        file: None,
+                        line: None,
+                        message: "dataflow finished".into(),
+                    },
+                )
+                .await;
+
+                let reply = ControlRequestReply::DataflowStopped {
+                    uuid,
+                    result: dataflow_results
+                        .get(&uuid)
+                        .map(|r| dataflow_result(r, uuid, clock))
+                        .unwrap_or_else(|| DataflowResult::ok_empty(uuid, clock.new_timestamp())),
+                };
+                for sender in finished_dataflow.stop_reply_senders {
+                    let _ = sender.send(Ok(reply.clone()));
+                }
+                if !matches!(finished_dataflow.spawn_result, CachedResult::Cached { .. }) {
+                    log::error!("pending spawn result on dataflow finish");
+                }
+            }
+        }
+        std::collections::hash_map::Entry::Vacant(_) => {
+            tracing::warn!("dataflow not running on DataflowFinishedOnDaemon");
+        }
+    }
+}
+
+/// Updates the dataflows of a daemon that registered again after losing its connection.
+///
+/// Dataflows that are no longer running on the daemon are considered as finished, with an
+/// unknown result for their nodes.
+async fn handle_daemon_reconnect(
+    reconnect: DaemonReconnect,
+    running_dataflows: &mut HashMap<Uuid, RunningDataflow>,
+    dataflow_results: &mut HashMap<Uuid, BTreeMap<DaemonId, DataflowDaemonResult>>,
+    archived_dataflows: &mut HashMap<Uuid, ArchivedDataflow>,
+    clock: &HLC,
+) {
+    let DaemonReconnect {
+        daemon_id,
+        running_dataflows: still_running,
+    } = reconnect;
+    for uuid in &still_running {
+        if !running_dataflows.contains_key(uuid) {
+            tracing::warn!("daemon `{daemon_id}` runs unknown dataflow `{uuid}`");
+        }
+    }
+    let lost: Vec<_> = running_dataflows
+        .values()
+        .filter(|d| d.daemons.contains(&daemon_id) && !still_running.contains(&d.uuid))
+        .map(|d| d.uuid)
+        .collect();
+    for uuid in lost {
+        handle_daemon_lost(
+            uuid,
+            daemon_id.clone(),
+            running_dataflows,
+            dataflow_results,
+            archived_dataflows,
+            clock,
+        )
+        .await;
+    }
+}
+
+/// Marks the nodes of the given dataflow that ran on the lost daemon as failed.
+async fn handle_daemon_lost(
+    uuid: Uuid,
+    daemon_id: DaemonId,
+    running_dataflows: &mut HashMap<Uuid, RunningDataflow>,
+    dataflow_results: &mut HashMap<Uuid, BTreeMap<DaemonId, DataflowDaemonResult>>,
+    archived_dataflows: &mut HashMap<Uuid, ArchivedDataflow>,
+    clock: &HLC,
+) {
+    let Some(dataflow) = running_dataflows.get(&uuid) else {
+        return;
+    };
+    tracing::warn!("dataflow `{uuid}` is no longer running on daemon `{daemon_id}`");
+    let timestamp = clock.new_timestamp();
+    let node_results = dataflow
+        .node_daemons
+        .iter()
+        .filter(|(_, node_daemon)| **node_daemon == daemon_id)
+        .map(|(node_id, _)| {
+            let error = NodeError {
+                timestamp,
+                cause: NodeErrorCause::DaemonLost,
+                exit_status: NodeExitStatus::Unknown,
+            };
+            (node_id.clone(), Err(error))
+        })
+        .collect();
+    let result = DataflowDaemonResult {
+        timestamp,
+        node_results,
+        stop_request: None,
+    };
+    handle_dataflow_finished_on_daemon(
+        uuid,
+        daemon_id,
+        result,
+        running_dataflows,
+        dataflow_results,
+        archived_dataflows,
+        clock,
+    )
+    .await;
+}
+
 async fn send_log_message(log_subscribers: &mut Vec<LogSubscriber>, message: &LogMessage) {
     for subscriber in log_subscribers.iter_mut() {
         let send_result =
@@ -952,8 +1352,12 @@ fn dataflow_result(
     clock: &uhlc::HLC,
 ) -> DataflowResult {
     let mut node_results = BTreeMap::new();
+    let mut stopped_by = None;
     for result in results.values() {
         node_results.extend(result.node_results.clone());
+        if stopped_by.is_none() {
+            stopped_by.clone_from(&result.stop_request);
+        }
         if let Err(err) = clock.update_with_timestamp(&result.timestamp) {
             tracing::warn!("failed to update HLC: {err}");
         }
@@ -963,12 +1367,15 @@ fn dataflow_result(
         uuid: dataflow_uuid,
         timestamp: clock.new_timestamp(),
         node_results,
+        stopped_by,
     }
 }
 
 struct DaemonConnection {
-    stream: TcpStream,
+    stream: MaybeTlsStream,
     last_heartbeat: Instant,
+    /// Labels reported by the daemon on registration.
+    labels: BTreeMap<String, String>,
 }
 
 async fn handle_destroy(
@@ -996,7 +1403,7 @@ async fn handle_destroy(
 }
 
 async fn send_heartbeat_message(
-    connection: &mut TcpStream,
+    connection: &mut MaybeTlsStream,
     timestamp: uhlc::Timestamp,
 ) -> eyre::Result<()> {
     let message = serde_json::to_vec(&Timestamped {
@@ -1030,6 +1437,10 @@ struct RunningDataflow {
     pending_daemons: BTreeSet<DaemonId>,
     exited_before_subscribe: Vec<NodeId>,
     nodes: BTreeMap<NodeId, ResolvedNode>,
+    /// The daemon that each node runs on.
+    node_daemons: BTreeMap<NodeId, DaemonId>,
+    /// The descriptor of the dataflow, updated when nodes are added or removed.
+    descriptor: Descriptor,
 
     spawn_result: CachedResult,
     stop_reply_senders: Vec<tokio::sync::oneshot::Sender<eyre::Result<ControlRequestReply>>>,
@@ -1095,9 +1506,13 @@ impl CachedResult {
     }
 }
 
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 struct ArchivedDataflow {
     name: Option<String>,
     nodes: BTreeMap<NodeId, ResolvedNode>,
+    /// The daemon that each node ran on.
+    #[serde(default)]
+    node_daemons: BTreeMap<NodeId, DaemonId>,
 }
 
 impl From<&RunningDataflow> for ArchivedDataflow {
@@ -1105,6 +1520,7 @@ impl From<&RunningDataflow> for ArchivedDataflow {
         ArchivedDataflow {
             name: dataflow.name.clone(),
             nodes: dataflow.nodes.clone(),
+            node_daemons: dataflow.node_daemons.clone(),
         }
     }
 }
@@ -1209,6 +1625,233 @@ async fn reload_dataflow(
     Ok(())
 }
 
+/// Adds the given node to a running dataflow.
+///
+/// The node is placed like the nodes of a newly started dataflow, but only daemons that
+/// already run the dataflow are accepted.
+async fn add_node(
+    running_dataflows: &mut HashMap<Uuid, RunningDataflow>,
+    dataflow_id: Uuid,
+    node: Node,
+    daemon_connections: &mut DaemonConnections,
+    timestamp: uhlc::Timestamp,
+) -> eyre::Result<()> {
+    let Some(dataflow) = running_dataflows.get_mut(&dataflow_id) else {
+        bail!("No running dataflow found with UUID `{dataflow_id}`")
+    };
+    if !dataflow.pending_spawn_results.is_empty() {
+        bail!("dataflow `{dataflow_id}` is still being spawned");
+    }
+    if node.include.is_some() {
+        bail!("nodes that include another dataflow cannot be added to a running dataflow");
+    }
+    let node_id = node.id.clone();
+    if dataflow.descriptor.nodes.iter().any(|n| n.id == node_id)
+        || dataflow
+            .nodes
+            .values()
+            .any(|n| n.output_node_id() == &node_id)
+    {
+        bail!("dataflow `{dataflow_id}` already has a node `{node_id}`");
+    }
+
+    let mut descriptor = dataflow.descriptor.clone();
+    descriptor.nodes.push(node);
+    let new_nodes: BTreeMap<_, _> = descriptor
+        .resolve_aliases_and_set_defaults()?
+        .into_iter()
+        .filter(|(_, n)| n.output_node_id() == &node_id)
+        .collect();
+    if let Some(existing) = new_nodes.keys().find(|id| dataflow.nodes.contains_key(*id)) {
+        bail!("dataflow `{dataflow_id}` already has a node `{existing}`");
+    }
+    let mut nodes = dataflow.nodes.clone();
+    nodes.extend(new_nodes.clone());
+    for node in new_nodes.values() {
+        check_node_inputs(node, &nodes)?;
+    }
+
+    let mut spawn_nodes: BTreeMap<DaemonId, BTreeSet<NodeId>> = BTreeMap::new();
+    let mut node_daemons = BTreeMap::new();
+    for node in new_nodes.values() {
+        let daemon_id = run::node_daemon(node, daemon_connections)?;
+        if !dataflow.daemons.contains(&daemon_id) {
+            bail!(
+                "node `{}` would run on daemon `{daemon_id}`, which is not part of \
+                dataflow `{dataflow_id}`",
+                node.id
+            );
+        }
+        node_daemons.insert(node.id.clone(), daemon_id.clone());
+        spawn_nodes
+            .entry(daemon_id)
+            .or_default()
+            .insert(node.id.clone());
+    }
+    let mut placement = dataflow.node_daemons.clone();
+    placement.extend(node_daemons.clone());
+    check_blocking_inputs(&nodes, &placement)?;
+
+    // all daemons need to know about the new nodes to forward outputs to them
+    for daemon_id in &dataflow.daemons {
+        let message = serde_json::to_vec(&Timestamped {
+            inner: DaemonCoordinatorEvent::AddNodes(AddDataflowNodes {
+                dataflow_id,
+                nodes: new_nodes.clone(),
+                dataflow_descriptor: descriptor.clone(),
+                spawn_nodes: spawn_nodes.remove(daemon_id).unwrap_or_default(),
+            }),
+            timestamp,
+        })?;
+        let daemon_connection = daemon_connections
+            .get_mut(daemon_id)
+            .wrap_err("no daemon connection")?;
+        tcp_send(&mut daemon_connection.stream, &message)
+            .await
+            .wrap_err("failed to send add nodes message to daemon")?;
+
+        // wait for reply
+        let reply_raw = tcp_receive(&mut daemon_connection.stream)
+            .await
+            .wrap_err("failed to receive add nodes reply from daemon")?;
+        match serde_json::from_slice(&reply_raw)
+            .wrap_err("failed to deserialize add nodes reply from daemon")?
+        {
+            DaemonCoordinatorReply::AddNodesResult(result) => result
+                .map_err(|e| eyre!(e))
+                .wrap_err_with(|| format!("failed to add node on daemon `{daemon_id}`"))?,
+            other => bail!("unexpected reply after sending add nodes: {other:?}"),
+        }
+    }
+
+    dataflow.nodes = nodes;
+    dataflow.node_daemons.extend(node_daemons);
+    dataflow.descriptor = descriptor;
+    tracing::info!("added node `{node_id}` to dataflow `{dataflow_id}`");
+
+    Ok(())
+}
+
+/// Stops the given node of a running dataflow and removes it from the dataflow.
+///
+/// Replicated nodes are removed together with all their replicas.
+async fn remove_node(
+    running_dataflows: &mut HashMap<Uuid, RunningDataflow>,
+    dataflow_id: Uuid,
+    node_id: NodeId,
+    grace_duration: Option<Duration>,
+    daemon_connections: &mut DaemonConnections,
+    timestamp: uhlc::Timestamp,
+) -> eyre::Result<()> {
+    let Some(dataflow) = running_dataflows.get_mut(&dataflow_id) else {
+        bail!("No running dataflow found with UUID `{dataflow_id}`")
+    };
+    let node_ids: BTreeSet<_> = dataflow
+        .nodes
+        .values()
+        .filter(|n| n.output_node_id() == &node_id)
+        .map(|n| n.id.clone())
+        .collect();
+    if node_ids.is_empty() {
+        bail!("dataflow `{dataflow_id}` has no node `{node_id}`");
+    }
+
+    let message = serde_json::to_vec(&Timestamped {
+        inner: DaemonCoordinatorEvent::RemoveNodes {
+            dataflow_id,
+            node_ids: node_ids.clone(),
+            grace_duration,
+        },
+        timestamp,
+    })?;
+    for daemon_id in &dataflow.daemons {
+        let daemon_connection = daemon_connections
+            .get_mut(daemon_id)
+            .wrap_err("no daemon connection")?;
+        tcp_send(&mut daemon_connection.stream, &message)
+            .await
+            .wrap_err("failed to send remove nodes message to daemon")?;
+
+        // wait for reply
+        let reply_raw = tcp_receive(&mut daemon_connection.stream)
+            .await
+            .wrap_err("failed to receive remove nodes reply from daemon")?;
+        match serde_json::from_slice(&reply_raw)
+            .wrap_err("failed to deserialize remove nodes reply from daemon")?
+        {
+            DaemonCoordinatorReply::RemoveNodesResult(result) => result
+                .map_err(|e| eyre!(e))
+                .wrap_err_with(|| format!("failed to remove node on daemon `{daemon_id}`"))?,
+            other => bail!("unexpected reply after sending remove nodes: {other:?}"),
+        }
+    }
+
+    dataflow.nodes.retain(|id, _| !node_ids.contains(id));
+    dataflow.node_daemons.retain(|id, _| !node_ids.contains(id));
+    dataflow.descriptor.nodes.retain(|n| n.id != node_id);
+    tracing::info!("removed node `{node_id}` from dataflow `{dataflow_id}`");
+
+    Ok(())
+}
+
+/// Restarts the given node of a running dataflow.
+///
+/// Replicated nodes are restarted together with all their replicas.
+async fn restart_node(
+    running_dataflows: &HashMap<Uuid, RunningDataflow>,
+    dataflow_id: Uuid,
+    node_id: NodeId,
+    grace_duration: Option<Duration>,
+    daemon_connections: &mut DaemonConnections,
+    timestamp: uhlc::Timestamp,
+) -> eyre::Result<()> {
+    let Some(dataflow) = running_dataflows.get(&dataflow_id) else {
+        bail!("No running dataflow found with UUID `{dataflow_id}`")
+    };
+    let node_ids: BTreeSet<_> = dataflow
+        .nodes
+        .values()
+        .filter(|n| n.id == node_id || n.output_node_id() == &node_id)
+        .map(|n| n.id.clone())
+        .collect();
+    if node_ids.is_empty() {
+        bail!("dataflow `{dataflow_id}` has no node `{node_id}`");
+    }
+
+    let message = serde_json::to_vec(&Timestamped {
+        inner: DaemonCoordinatorEvent::RestartNodes {
+            dataflow_id,
+            node_ids,
+            grace_duration,
+        },
+        timestamp,
+    })?;
+    for daemon_id in &dataflow.daemons {
+        let daemon_connection = daemon_connections
+            .get_mut(daemon_id)
+            .wrap_err("no daemon connection")?;
+        tcp_send(&mut daemon_connection.stream, &message)
+            .await
+            .wrap_err("failed to send restart message to daemon")?;
+
+        // wait for reply
+        let reply_raw = tcp_receive(&mut daemon_connection.stream)
+            .await
+            .wrap_err("failed to receive restart reply from daemon")?;
+        match serde_json::from_slice(&reply_raw)
+            .wrap_err("failed to deserialize restart reply from daemon")?
+        {
+            DaemonCoordinatorReply::RestartNodesResult(result) => result
+                .map_err(|e| eyre!(e))
+                .wrap_err_with(|| format!("failed to restart node on daemon `{daemon_id}`"))?,
+            other => bail!("unexpected reply after sending restart: {other:?}"),
+        }
+    }
+    tracing::info!("triggered restart of node `{node_id}` of dataflow `{dataflow_id}`");
+
+    Ok(())
+}
+
 async fn retrieve_logs(
     running_dataflows: &HashMap<Uuid, RunningDataflow>,
     archived_dataflows: &HashMap<Uuid, ArchivedDataflow>,
@@ -1217,10 +1860,10 @@ async fn retrieve_logs(
     daemon_connections: &mut DaemonConnections,
     timestamp: uhlc::Timestamp,
 ) -> eyre::Result<Vec<u8>> {
-    let nodes = if let Some(dataflow) = archived_dataflows.get(&dataflow_id) {
-        dataflow.nodes.clone()
+    let (nodes, node_daemons) = if let Some(dataflow) = archived_dataflows.get(&dataflow_id) {
+        (&dataflow.nodes, &dataflow.node_daemons)
     } else if let Some(dataflow) = running_dataflows.get(&dataflow_id) {
-        dataflow.nodes.clone()
+        (&dataflow.nodes, &dataflow.node_daemons)
     } else {
         bail!("No dataflow found with UUID `{dataflow_id}`")
     };
@@ -1233,35 +1876,43 @@ async fn retrieve_logs(
         timestamp,
     })?;
 
-    let machine_ids: Vec<Option<String>> = nodes
-        .values()
-        .filter(|node| node.id == node_id)
-        .map(|node| node.deploy.as_ref().and_then(|d| d.machine.clone()))
-        .collect();
-
-    let machine_id = if let [machine_id] = &machine_ids[..] {
-        machine_id
-    } else if machine_ids.is_empty() {
-        bail!("No machine contains {}/{}", dataflow_id, node_id)
-    } else {
-        bail!(
-            "More than one machine contains {}/{}. However, it should only be present on one.",
-            dataflow_id,
-            node_id
-        )
-    };
-
-    let daemon_ids: Vec<_> = match machine_id {
-        None => daemon_connections.unnamed().collect(),
-        Some(machine_id) => daemon_connections
-            .get_matching_daemon_id(machine_id)
-            .into_iter()
-            .collect(),
-    };
-    let daemon_id = match &daemon_ids[..] {
-        [id] => (*id).clone(),
-        [] => eyre::bail!("no matching daemon connections for machine ID `{machine_id:?}`"),
-        _ => eyre::bail!("multiple matching daemon connections for machine ID `{machine_id:?}`"),
+    let daemon_id = match node_daemons.get(&node_id) {
+        Some(daemon_id) => daemon_id.clone(),
+        // dataflows archived by older versions don't record the node daemons
+        None => {
+            let machine_ids: Vec<Option<String>> = nodes
+                .values()
+                .filter(|node| node.id == node_id)
+                .map(|node| node.deploy.as_ref().and_then(|d| d.machine.clone()))
+                .collect();
+
+            let machine_id = if let [machine_id] = &machine_ids[..] {
+                machine_id
+            } else if machine_ids.is_empty() {
+                bail!("No machine contains {}/{}", dataflow_id, node_id)
+            } else {
+                bail!(
+                    "More than one machine contains {}/{}. However, it should only be present on one.",
+                    dataflow_id,
+                    node_id
+                )
+            };
+
+            let daemon_ids: Vec<_> = match machine_id {
+                None => daemon_connections.unnamed().collect(),
+                Some(machine_id) => daemon_connections
+                    .get_matching_daemon_id(machine_id)
+                    .into_iter()
+                    .collect(),
+            };
+            match &daemon_ids[..] {
+                [id] => (*id).clone(),
+                [] => eyre::bail!("no matching daemon connections for machine ID `{machine_id:?}`"),
+                _ => eyre::bail!(
+                    "multiple matching daemon connections for machine ID `{machine_id:?}`"
+                ),
+            }
+        }
     };
     let daemon_connection = daemon_connections
         .get_mut(&daemon_id)
@@ -1299,42 +1950,44 @@ async fn build_dataflow(
     daemon_connections: &mut DaemonConnections,
 ) -> eyre::Result<RunningBuild> {
     let nodes = dataflow.resolve_aliases_and_set_defaults()?;
-
-    let mut git_sources_by_daemon = git_sources
-        .into_iter()
-        .into_grouping_map_by(|(id, _)| {
-            nodes
-                .get(id)
-                .and_then(|n| n.deploy.as_ref().and_then(|d| d.machine.as_ref()))
-        })
-        .collect();
-    let mut prev_git_sources_by_daemon = prev_git_sources
-        .into_iter()
-        .into_grouping_map_by(|(id, _)| {
-            nodes
-                .get(id)
-                .and_then(|n| n.deploy.as_ref().and_then(|d| d.machine.as_ref()))
-        })
-        .collect();
-
-    let nodes_by_daemon = nodes
-        .values()
-        .into_group_map_by(|n| n.deploy.as_ref().and_then(|d| d.machine.as_ref()));
+    let node_daemons = run::assign_daemons(&nodes, daemon_connections)?;
+
+    let group_by_daemon = |sources: BTreeMap<NodeId, GitSource>| {
+        let mut grouped: BTreeMap<_, BTreeMap<_, _>> = BTreeMap::new();
+        for (node_id, source) in sources {
+            if let Some(daemon_id) = node_daemons.get(&node_id) {
+                grouped
+                    .entry(daemon_id)
+                    .or_default()
+                    .insert(node_id, source);
+            }
+        }
+        grouped
+    };
+    let mut git_sources_by_daemon = group_by_daemon(git_sources);
+    let mut prev_git_sources_by_daemon = group_by_daemon(prev_git_sources);
+
+    let mut nodes_by_daemon: BTreeMap<_, BTreeSet<_>> = BTreeMap::new();
+    for (node_id, daemon_id) in &node_daemons {
+        nodes_by_daemon
+            .entry(daemon_id)
+            .or_default()
+            .insert(node_id.clone());
+    }
 
     let mut daemons = BTreeSet::new();
-    for (machine, nodes_on_machine) in &nodes_by_daemon {
-        let nodes_on_machine = nodes_on_machine.iter().map(|n| n.id.clone()).collect();
+    for (daemon_id, nodes_on_machine) in nodes_by_daemon {
         tracing::debug!(
-            "Running dataflow build `{build_id}` on machine `{machine:?}` (nodes: {nodes_on_machine:?})"
+            "Running dataflow build `{build_id}` on daemon `{daemon_id}` (nodes: {nodes_on_machine:?})"
         );
 
         let build_command = BuildDataflowNodes {
             build_id,
             session_id,
             local_working_dir: local_working_dir.clone(),
-            git_sources: git_sources_by_daemon.remove(machine).unwrap_or_default(),
+            git_sources: git_sources_by_daemon.remove(daemon_id).unwrap_or_default(),
             prev_git_sources: prev_git_sources_by_daemon
-                .remove(machine)
+                .remove(daemon_id)
                 .unwrap_or_default(),
             dataflow_descriptor: dataflow.clone(),
             nodes_on_machine,
@@ -1345,11 +1998,10 @@ async fn build_dataflow(
             timestamp: clock.new_timestamp(),
         })?;
 
-        let daemon_id =
-            build_dataflow_on_machine(daemon_connections, machine.map(|s| s.as_str()), &message)
-                .await
-                .wrap_err_with(|| format!("failed to build dataflow on machine `{machine:?}`"))?;
-        daemons.insert(daemon_id);
+        build_dataflow_on_daemon(daemon_connections, daemon_id, &message)
+            .await
+            .wrap_err_with(|| format!("failed to build dataflow on daemon `{daemon_id}`"))?;
+        daemons.insert(daemon_id.clone());
     }
 
     tracing::info!("successfully triggered dataflow build `{build_id}`",);
@@ -1363,25 +2015,13 @@ async fn build_dataflow(
     })
 }
 
-async fn build_dataflow_on_machine(
+async fn build_dataflow_on_daemon(
     daemon_connections: &mut DaemonConnections,
-    machine: Option<&str>,
+    daemon_id: &DaemonId,
     message: &[u8],
-) -> Result<DaemonId, eyre::ErrReport> {
-    let daemon_id = match machine {
-        Some(machine) => daemon_connections
-            .get_matching_daemon_id(machine)
-            .wrap_err_with(|| format!("no matching daemon for machine id {machine:?}"))?
-            .clone(),
-        None => daemon_connections
-            .unnamed()
-            .next()
-            .wrap_err("no unnamed daemon connections")?
-            .clone(),
-    };
-
+) -> eyre::Result<()> {
     let daemon_connection = daemon_connections
-        .get_mut(&daemon_id)
+        .get_mut(daemon_id)
         .wrap_err_with(|| format!("no daemon connection for daemon `{daemon_id}`"))?;
     tcp_send(&mut daemon_connection.stream, message)
         .await
@@ -1398,7 +2038,7 @@ async fn build_dataflow_on_machine(
             .wrap_err("daemon returned an error")?,
         _ => bail!("unexpected reply"),
     }
-    Ok(daemon_id)
+    Ok(())
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -1415,11 +2055,12 @@ async fn start_dataflow(
     let SpawnedDataflow {
         uuid,
         daemons,
+        node_daemons,
         nodes,
     } = spawn_dataflow(
         build_id,
         session_id,
-        dataflow,
+        dataflow.clone(),
         local_working_dir,
         daemon_connections,
         clock,
@@ -1437,6 +2078,8 @@ async fn start_dataflow(
         exited_before_subscribe: Default::default(),
         daemons: daemons.clone(),
         nodes,
+        node_daemons,
+        descriptor: dataflow,
         spawn_result: CachedResult::default(),
         stop_reply_senders: Vec::new(),
         buffered_log_messages: Vec::new(),
@@ -1499,7 +2142,7 @@ async fn destroy_daemons(
 
 #[derive(Debug)]
 pub enum Event {
-    NewDaemonConnection(TcpStream),
+    NewDaemonConnection(MaybeTlsStream),
     DaemonConnectError(eyre::Report),
     DaemonHeartbeat {
         daemon_id: DaemonId,
@@ -1529,6 +2172,37 @@ pub enum Event {
 }
 
 impl Event {
+    /// Whether handling this event might modify the state that is persisted in the state file.
+    ///
+    /// The [`DaemonHeartbeatInterval`][Event::DaemonHeartbeatInterval] event only changes the
+    /// state if a daemon is considered lost, which is tracked separately in the event loop.
+    fn changes_state(&self) -> bool {
+        match self {
+            Event::Control(ControlEvent::IncomingRequest { request, .. }) => matches!(
+                request,
+                ControlRequest::Start { .. }
+                    | ControlRequest::Stop { .. }
+                    | ControlRequest::StopByName { .. }
+                    | ControlRequest::AddNode { .. }
+                    | ControlRequest::RemoveNode { .. }
+                    | ControlRequest::Destroy
+            ),
+            Event::Dataflow { event, .. } => {
+                matches!(event, DataflowEvent::DataflowFinishedOnDaemon { .. })
+            }
+            Event::Daemon(DaemonRequest::Register { reconnect, .. }) => reconnect.is_some(),
+            Event::CtrlC | Event::DataflowSpawnResult { .. } => true,
+            Event::Control(_)
+            | Event::NewDaemonConnection(_)
+            | Event::DaemonConnectError(_)
+            | Event::DaemonHeartbeat { .. }
+            | Event::DaemonHeartbeatInterval
+            | Event::Log(_)
+            | Event::DaemonExit { .. }
+            | Event::DataflowBuildResult { .. } => false,
+        }
+    }
+
     /// Whether this event should be logged.
     #[allow(clippy::match_like_matches_macro)]
     pub fn log(&self) -> bool {
@@ -1566,6 +2240,9 @@ pub enum DataflowEvent {
         daemon_id: DaemonId,
         exited_before_subscribe: Vec<NodeId>,
     },
+    StopRequested {
+        request: DataflowStopRequest,
+    },
 }
 
 #[derive(Debug)]
@@ -1573,7 +2250,9 @@ pub enum DaemonRequest {
     Register {
         version_check_result: Result<(), String>,
         machine_id: Option<String>,
-        connection: TcpStream,
+        labels: BTreeMap<String, String>,
+        connection: MaybeTlsStream,
+        reconnect: Option<DaemonReconnect>,
     },
 }
 
@@ -1598,3 +2277,139 @@ fn set_up_ctrlc_handler() -> Result<impl Stream<Item = Event>, eyre::ErrReport>
 
     Ok(ReceiverStream::new(ctrlc_rx))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dora_message::daemon_to_coordinator::DaemonEvent;
+
+    fn stop_request() -> DataflowStopRequest {
+        DataflowStopRequest {
+            node_id: NodeId::from("plot".to_owned()),
+            reason: Some("window closed".into()),
+        }
+    }
+
+    async fn daemon_connection(labels: &[(&str, &str)]) -> DaemonConnection {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let stream = tokio::net::TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+        DaemonConnection {
+            stream: MaybeTlsStream::Plain(stream),
+            last_heartbeat: Instant::now(),
+            labels: labels
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+        }
+    }
+
+    #[tokio::test]
+    async fn place_nodes_by_labels() {
+        let big = DaemonId::new(Some("big".into()));
+        let small = DaemonId::new(Some("small".into()));
+        let mut daemon_connections = DaemonConnections::default();
+        daemon_connections.add(
+            big.clone(),
+            daemon_connection(&[("cpus", "16"), ("gpu", "true"), ("os", "linux")]).await,
+        );
+        daemon_connections.add(
+            small.clone(),
+            daemon_connection(&[("cpus", "2"), ("os", "linux")]).await,
+        );
+
+        let descriptor = Descriptor::parse(
+            br#"
+nodes:
+  - id: trainer
+    path: trainer
+    _unstable_deploy:
+      labels: {gpu: true, cpus: ">=8"}
+  - id: preprocess
+    path: preprocess
+    _unstable_deploy:
+      labels: {cpus: ">=2"}
+  - id: logger
+    path: logger
+    _unstable_deploy:
+      labels: {cpus: "<4"}
+  - id: viewer
+    path: viewer
+    _unstable_deploy:
+      machine: small
+      labels: {os: linux}
+  - id: simulator
+    path: simulator
+    _unstable_deploy:
+      labels: {cpus: ">=64"}
+"#
+            .to_vec(),
+        )
+        .unwrap();
+        let mut nodes = descriptor.resolve_aliases_and_set_defaults().unwrap();
+        let simulator = nodes.remove(&NodeId::from("simulator".to_owned())).unwrap();
+
+        let placement = run::assign_daemons(&nodes, &daemon_connections).unwrap();
+        let daemon_of = |node: &str| &placement[&NodeId::from(node.to_owned())];
+        assert_eq!(daemon_of("trainer"), &big);
+        // both daemons match -> the first one is chosen
+        assert_eq!(daemon_of("preprocess"), &big);
+        assert_eq!(daemon_of("logger"), &small);
+        assert_eq!(daemon_of("viewer"), &small);
+
+        let err = run::node_daemon(&simulator, &daemon_connections).unwrap_err();
+        assert!(
+            err.to_string().contains("no connected daemon matches"),
+            "{err:?}"
+        );
+    }
+
+    #[test]
+    fn stop_request_round_trip() {
+        let dataflow_uuid = Uuid::new_v4();
+        let event = DaemonEvent::StopDataflowRequested {
+            dataflow_id: dataflow_uuid,
+            request: stop_request(),
+        };
+        let serialized = serde_json::to_vec(&event).unwrap();
+        let DaemonEvent::StopDataflowRequested {
+            dataflow_id,
+            request,
+        } = serde_json::from_slice(&serialized).unwrap()
+        else {
+            panic!("unexpected event");
+        };
+        assert_eq!(dataflow_id, dataflow_uuid);
+        assert_eq!(request.node_id, stop_request().node_id);
+        assert_eq!(request.reason, stop_request().reason);
+        assert_eq!(request.to_string(), "stopped by node `plot`: window closed");
+    }
+
+    #[test]
+    fn dataflow_result_reports_stop_request() {
+        let clock = HLC::default();
+        let daemon_result = |node: &str, stop_request| DataflowDaemonResult {
+            timestamp: clock.new_timestamp(),
+            node_results: [(NodeId::from(node.to_owned()), Ok(()))].into(),
+            stop_request,
+        };
+        let mut results = BTreeMap::from([(
+            DaemonId::new(Some("a".into())),
+            daemon_result("camera", None),
+        )]);
+
+        let result = dataflow_result(&results, Uuid::new_v4(), &clock);
+        assert!(result.stopped_by.is_none());
+
+        results.insert(
+            DaemonId::new(Some("b".into())),
+            daemon_result("plot", Some(stop_request())),
+        );
+        let result = dataflow_result(&results, Uuid::new_v4(), &clock);
+        assert_eq!(result.node_results.len(), 2);
+        let stopped_by = result.stopped_by.expect("stop request missing");
+        assert_eq!(stopped_by.node_id, stop_request().node_id);
+        assert_eq!(stopped_by.reason, stop_request().reason);
+    }
+}
@@ -1,12 +1,12 @@
 use crate::{DaemonRequest, DataflowEvent, Event, tcp_utils::tcp_receive};
-use dora_core::uhlc::HLC;
+use dora_core::security::MaybeTlsStream;
+use dora_core::{security::ServerSecurity, uhlc::HLC};
 use dora_message::daemon_to_coordinator::{CoordinatorRequest, DaemonEvent, Timestamped};
 use eyre::Context;
+use futures::{FutureExt, future::Either};
+use futures_concurrency::future::Race;
 use std::{io::ErrorKind, net::SocketAddr, sync::Arc};
-use tokio::{
-    net::{TcpListener, TcpStream},
-    sync::mpsc,
-};
+use tokio::{net::TcpListener, sync::mpsc};
 
 pub async fn create_listener(bind: SocketAddr) -> eyre::Result<TcpListener> {
     let socket = match TcpListener::bind(bind).await {
@@ -18,8 +18,58 @@ pub async fn create_listener(bind: SocketAddr) -> eyre::Result<TcpListener> {
     Ok(socket)
 }
 
+/// Accepts new daemon connections and reports them as [`Event::NewDaemonConnection`].
+///
+/// The TLS and authentication handshakes are done in separate tasks, independent of the
+/// coordinator event loop. This is required because daemons might open additional
+/// connections while the event loop waits for their reply.
+pub async fn accept_connections(
+    listener: TcpListener,
+    security: ServerSecurity,
+    tx: mpsc::Sender<Event>,
+) {
+    loop {
+        let new_connection = listener.accept().map(Either::Left);
+        let coordinator_stop = tx.closed().map(Either::Right);
+        let connection = match (new_connection, coordinator_stop).race().await {
+            Either::Left(connection) => connection,
+            Either::Right(()) => {
+                // coordinator was stopped
+                break;
+            }
+        };
+        match connection.wrap_err("failed to open connection") {
+            Ok((connection, _)) => {
+                let security = security.clone();
+                let tx = tx.clone();
+                tokio::spawn(async move {
+                    if let Err(err) = connection.set_nodelay(true) {
+                        tracing::warn!("failed to set TCP_NODELAY: {err}");
+                    }
+                    match security.accept(connection).await {
+                        Ok(connection) => {
+                            let _ = tx.send(Event::NewDaemonConnection(connection)).await;
+                        }
+                        Err(err) => {
+                            tracing::warn!(
+                                "{:?}",
+                                err.wrap_err("failed to accept daemon connection")
+                            );
+                        }
+                    }
+                });
+            }
+            Err(err) => {
+                if tx.send(Event::DaemonConnectError(err)).await.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+}
+
 pub async fn handle_connection(
-    mut connection: TcpStream,
+    mut connection: MaybeTlsStream,
     events_tx: mpsc::Sender<Event>,
     clock: Arc<HLC>,
 ) {
@@ -62,6 +112,8 @@ pub async fn handle_connection(
                     connection,
                     version_check_result: register_request.check_version(),
                     machine_id: register_request.machine_id,
+                    labels: register_request.labels,
+                    reconnect: register_request.reconnect,
                 };
                 let _ = events_tx.send(Event::Daemon(event)).await;
                 break;
@@ -94,6 +146,18 @@ pub async fn handle_connection(
                         break;
                     }
                 }
+                DaemonEvent::StopDataflowRequested {
+                    dataflow_id,
+                    request,
+                } => {
+                    let event = Event::Dataflow {
+                        uuid: dataflow_id,
+                        event: DataflowEvent::StopRequested { request },
+                    };
+                    if events_tx.send(event).await.is_err() {
+                        break;
+                    }
+                }
                 DaemonEvent::Heartbeat => {
                     let event = Event::DaemonHeartbeat { daemon_id };
                     if events_tx.send(event).await.is_err() {
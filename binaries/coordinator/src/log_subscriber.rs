@@ -1,3 +1,4 @@
+use dora_core::security::MaybeTlsStream;
 use dora_message::coordinator_to_cli::LogMessage;
 use eyre::{Context, ContextCompat};
 
@@ -5,11 +6,11 @@ use crate::tcp_utils::tcp_send;
 
 pub struct LogSubscriber {
     pub level: log::LevelFilter,
-    connection: Option<tokio::net::TcpStream>,
+    connection: Option<MaybeTlsStream>,
 }
 
 impl LogSubscriber {
-    pub fn new(level: log::LevelFilter, connection: tokio::net::TcpStream) -> Self {
+    pub fn new(level: log::LevelFilter, connection: MaybeTlsStream) -> Self {
         Self {
             level,
             connection: Some(connection),
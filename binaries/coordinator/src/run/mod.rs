@@ -3,7 +3,10 @@ use crate::{
     tcp_utils::{tcp_receive, tcp_send},
 };
 
-use dora_core::{descriptor::DescriptorExt, uhlc::HLC};
+use dora_core::{
+    descriptor::{DescriptorExt, check_blocking_inputs},
+    uhlc::HLC,
+};
 use dora_message::{
     BuildId, SessionId,
     common::DaemonId,
@@ -31,17 +34,22 @@ pub(super) async fn spawn_dataflow(
     uv: bool,
 ) -> eyre::Result<SpawnedDataflow> {
     let nodes = dataflow.resolve_aliases_and_set_defaults()?;
+    let node_daemons = assign_daemons(&nodes, daemon_connections)?;
+    check_blocking_inputs(&nodes, &node_daemons)?;
     let uuid = Uuid::new_v7(Timestamp::now(NoContext));
 
-    let nodes_by_daemon = nodes
-        .values()
-        .into_group_map_by(|n| n.deploy.as_ref().and_then(|d| d.machine.as_ref()));
+    let mut nodes_by_daemon: BTreeMap<_, BTreeSet<_>> = BTreeMap::new();
+    for (node_id, daemon_id) in &node_daemons {
+        nodes_by_daemon
+            .entry(daemon_id)
+            .or_default()
+            .insert(node_id.clone());
+    }
 
     let mut daemons = BTreeSet::new();
-    for (machine, nodes_on_machine) in &nodes_by_daemon {
-        let spawn_nodes = nodes_on_machine.iter().map(|n| n.id.clone()).collect();
+    for (daemon_id, spawn_nodes) in nodes_by_daemon {
         tracing::debug!(
-            "Spawning dataflow `{uuid}` on machine `{machine:?}` (nodes: {spawn_nodes:?})"
+            "Spawning dataflow `{uuid}` on daemon `{daemon_id}` (nodes: {spawn_nodes:?})"
         );
 
         let spawn_command = SpawnDataflowNodes {
@@ -59,11 +67,10 @@ pub(super) async fn spawn_dataflow(
             timestamp: clock.new_timestamp(),
         })?;
 
-        let daemon_id =
-            spawn_dataflow_on_machine(daemon_connections, machine.map(|m| m.as_str()), &message)
-                .await
-                .wrap_err_with(|| format!("failed to spawn dataflow on machine `{machine:?}`"))?;
-        daemons.insert(daemon_id);
+        spawn_dataflow_on_daemon(daemon_connections, daemon_id, &message)
+            .await
+            .wrap_err_with(|| format!("failed to spawn dataflow on daemon `{daemon_id}`"))?;
+        daemons.insert(daemon_id.clone());
     }
 
     tracing::info!("successfully triggered dataflow spawn `{uuid}`",);
@@ -71,29 +78,82 @@ pub(super) async fn spawn_dataflow(
     Ok(SpawnedDataflow {
         uuid,
         daemons,
+        node_daemons,
         nodes,
     })
 }
 
-async fn spawn_dataflow_on_machine(
-    daemon_connections: &mut DaemonConnections,
+/// Chooses the daemon that each of the given nodes should run on.
+///
+/// Nodes with label constraints in their deploy section run on a matching daemon, all other
+/// nodes on the daemon of their `machine` (see [`daemon_for_machine`]).
+pub(crate) fn assign_daemons(
+    nodes: &BTreeMap<NodeId, ResolvedNode>,
+    daemon_connections: &DaemonConnections,
+) -> eyre::Result<BTreeMap<NodeId, DaemonId>> {
+    nodes
+        .values()
+        .map(|node| Ok((node.id.clone(), node_daemon(node, daemon_connections)?)))
+        .collect()
+}
+
+/// Chooses the daemon that the given node should run on.
+///
+/// See [`assign_daemons`] for details.
+pub(crate) fn node_daemon(
+    node: &ResolvedNode,
+    daemon_connections: &DaemonConnections,
+) -> eyre::Result<DaemonId> {
+    let deploy = match &node.deploy {
+        Some(deploy) if !deploy.labels.is_empty() => deploy,
+        deploy => {
+            let machine = deploy.as_ref().and_then(|d| d.machine.as_deref());
+            return daemon_for_machine(daemon_connections, machine);
+        }
+    };
+    let daemon_id = daemon_connections
+        .find_matching_deploy(deploy)
+        .wrap_err_with(|| {
+            let labels = deploy
+                .labels
+                .iter()
+                .map(|(key, value)| format!("{key}={value}"))
+                .join(", ");
+            format!(
+                "no connected daemon matches the deploy labels of node `{}` ({labels})",
+                node.id
+            )
+        })?;
+    tracing::debug!("deploying node `{}` on daemon `{daemon_id}`", node.id);
+    Ok(daemon_id.clone())
+}
+
+/// Returns the ID of the daemon that nodes deployed on the given machine should run on.
+///
+/// Nodes without a `machine` run on an unnamed daemon.
+fn daemon_for_machine(
+    daemon_connections: &DaemonConnections,
     machine: Option<&str>,
-    message: &[u8],
-) -> Result<DaemonId, eyre::ErrReport> {
+) -> eyre::Result<DaemonId> {
     let daemon_id = match machine {
         Some(machine) => daemon_connections
             .get_matching_daemon_id(machine)
-            .wrap_err_with(|| format!("no matching daemon for machine id {machine:?}"))?
-            .clone(),
+            .wrap_err_with(|| format!("no matching daemon for machine id {machine:?}"))?,
         None => daemon_connections
             .unnamed()
             .next()
-            .wrap_err("no unnamed daemon connections")?
-            .clone(),
+            .wrap_err("no unnamed daemon connections")?,
     };
+    Ok(daemon_id.clone())
+}
 
+async fn spawn_dataflow_on_daemon(
+    daemon_connections: &mut DaemonConnections,
+    daemon_id: &DaemonId,
+    message: &[u8],
+) -> eyre::Result<()> {
     let daemon_connection = daemon_connections
-        .get_mut(&daemon_id)
+        .get_mut(daemon_id)
         .wrap_err_with(|| format!("no daemon connection for daemon `{daemon_id}`"))?;
     tcp_send(&mut daemon_connection.stream, message)
         .await
@@ -110,11 +170,13 @@ async fn spawn_dataflow_on_machine(
             .wrap_err("daemon returned an error")?,
         _ => bail!("unexpected reply"),
     }
-    Ok(daemon_id)
+    Ok(())
 }
 
 pub struct SpawnedDataflow {
     pub uuid: Uuid,
     pub daemons: BTreeSet<DaemonId>,
+    /// The daemon that each node was spawned on.
+    pub node_daemons: BTreeMap<NodeId, DaemonId>,
     pub nodes: BTreeMap<NodeId, ResolvedNode>,
 }
@@ -0,0 +1,262 @@
+//! Persistence of the coordinator state.
+//!
+//! Allows restarting the coordinator without losing track of the running dataflows. The
+//! daemons reconnect after the restart and report which dataflows are still running on them.
+
+use crate::{ArchivedDataflow, CachedResult, RunningDataflow};
+use dora_message::{
+    DataflowId,
+    common::DaemonId,
+    coordinator_to_cli::ControlRequestReply,
+    daemon_to_coordinator::DataflowDaemonResult,
+    descriptor::{Descriptor, ResolvedNode},
+    id::NodeId,
+};
+use eyre::Context;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    path::PathBuf,
+};
+use tokio::io::AsyncWriteExt;
+
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+pub struct CoordinatorState {
+    pub running_dataflows: Vec<PersistedDataflow>,
+    pub archived_dataflows: BTreeMap<DataflowId, ArchivedDataflow>,
+    pub dataflow_results: BTreeMap<DataflowId, Vec<(DaemonId, DataflowDaemonResult)>>,
+}
+
+impl CoordinatorState {
+    pub fn new(
+        running_dataflows: &HashMap<DataflowId, RunningDataflow>,
+        archived_dataflows: &HashMap<DataflowId, ArchivedDataflow>,
+        dataflow_results: &HashMap<DataflowId, BTreeMap<DaemonId, DataflowDaemonResult>>,
+    ) -> Self {
+        let mut running_dataflows: Vec<_> = running_dataflows
+            .values()
+            .map(|dataflow| PersistedDataflow {
+                uuid: dataflow.uuid,
+                name: dataflow.name.clone(),
+                daemons: dataflow.daemons.clone(),
+                nodes: dataflow.nodes.clone(),
+                node_daemons: dataflow.node_daemons.clone(),
+                descriptor: dataflow.descriptor.clone(),
+            })
+            .collect();
+        running_dataflows.sort_by_key(|d| d.uuid);
+
+        Self {
+            running_dataflows,
+            archived_dataflows: archived_dataflows
+                .iter()
+                .map(|(uuid, dataflow)| (*uuid, dataflow.clone()))
+                .collect(),
+            dataflow_results: dataflow_results
+                .iter()
+                .map(|(uuid, results)| {
+                    let results = results
+                        .iter()
+                        .map(|(daemon_id, result)| (daemon_id.clone(), result.clone()))
+                        .collect();
+                    (*uuid, results)
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct PersistedDataflow {
+    pub uuid: DataflowId,
+    pub name: Option<String>,
+    pub daemons: BTreeSet<DaemonId>,
+    pub nodes: BTreeMap<NodeId, ResolvedNode>,
+    pub node_daemons: BTreeMap<NodeId, DaemonId>,
+    pub descriptor: Descriptor,
+}
+
+impl From<PersistedDataflow> for RunningDataflow {
+    fn from(dataflow: PersistedDataflow) -> Self {
+        let PersistedDataflow {
+            uuid,
+            name,
+            daemons,
+            nodes,
+            node_daemons,
+            descriptor,
+        } = dataflow;
+        RunningDataflow {
+            name,
+            uuid,
+            daemons,
+            pending_daemons: BTreeSet::new(),
+            exited_before_subscribe: Vec::new(),
+            nodes,
+            node_daemons,
+            descriptor,
+            // the dataflow was spawned before the coordinator restart
+            spawn_result: CachedResult::Cached {
+                result: Ok(ControlRequestReply::DataflowSpawned { uuid }),
+            },
+            stop_reply_senders: Vec::new(),
+            buffered_log_messages: Vec::new(),
+            log_subscribers: Vec::new(),
+            pending_spawn_results: BTreeSet::new(),
+        }
+    }
+}
+
+/// File that the coordinator state is written to.
+pub struct StateFile {
+    path: PathBuf,
+    last_written: Vec<u8>,
+}
+
+impl StateFile {
+    /// Loads the state stored in the given file.
+    ///
+    /// Returns an empty state if the file does not exist yet.
+    pub async fn load(path: PathBuf) -> eyre::Result<(Self, CoordinatorState)> {
+        let (state, last_written) = match tokio::fs::read(&path).await {
+            Ok(raw) => {
+                let state = serde_json::from_slice(&raw).wrap_err_with(|| {
+                    format!(
+                        "failed to parse coordinator state file `{}`",
+                        path.display()
+                    )
+                })?;
+                (state, raw)
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                (CoordinatorState::default(), Vec::new())
+            }
+            Err(err) => {
+                return Err(err).wrap_err_with(|| {
+                    format!("failed to read coordinator state file `{}`", path.display())
+                });
+            }
+        };
+        Ok((Self { path, last_written }, state))
+    }
+
+    /// Writes the given state to the file, unless it is unchanged.
+    ///
+    /// The state is written to a temporary file first, which then replaces the state file. This
+    /// way, the state file stays valid if the coordinator crashes while writing.
+    pub async fn store(&mut self, state: &CoordinatorState) -> eyre::Result<()> {
+        let serialized =
+            serde_json::to_vec(state).context("failed to serialize coordinator state")?;
+        if serialized == self.last_written {
+            return Ok(());
+        }
+
+        let tmp_path = self.path.with_extension("tmp");
+        let mut file = tokio::fs::File::create(&tmp_path)
+            .await
+            .wrap_err_with(|| format!("failed to create `{}`", tmp_path.display()))?;
+        file.write_all(&serialized)
+            .await
+            .wrap_err_with(|| format!("failed to write `{}`", tmp_path.display()))?;
+        file.sync_all()
+            .await
+            .wrap_err_with(|| format!("failed to sync `{}`", tmp_path.display()))?;
+        tokio::fs::rename(&tmp_path, &self.path)
+            .await
+            .wrap_err_with(|| format!("failed to replace `{}`", self.path.display()))?;
+
+        self.last_written = serialized;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dora_core::descriptor::DescriptorExt;
+    use dora_message::daemon_to_coordinator::{NodeError, NodeErrorCause, NodeExitStatus};
+
+    const DATAFLOW: &str = r#"
+nodes:
+  - id: camera
+    path: camera
+    outputs: [image]
+    _unstable_deploy:
+      machine: a
+  - id: plot
+    path: plot
+    inputs:
+      image: camera/image
+    _unstable_deploy:
+      machine: b
+"#;
+
+    fn running_dataflow(daemon_a: &DaemonId, daemon_b: &DaemonId) -> RunningDataflow {
+        let descriptor = Descriptor::parse(DATAFLOW.as_bytes().to_vec()).unwrap();
+        let nodes = descriptor.resolve_aliases_and_set_defaults().unwrap();
+        PersistedDataflow {
+            uuid: DataflowId::new_v4(),
+            name: Some("test".into()),
+            daemons: [daemon_a.clone(), daemon_b.clone()].into(),
+            nodes,
+            node_daemons: [
+                (NodeId::from("camera".to_owned()), daemon_a.clone()),
+                (NodeId::from("plot".to_owned()), daemon_b.clone()),
+            ]
+            .into(),
+            descriptor,
+        }
+        .into()
+    }
+
+    #[tokio::test]
+    async fn store_and_load_state() -> eyre::Result<()> {
+        let dir = std::env::temp_dir().join(format!("dora-state-{}", uuid::Uuid::new_v4()));
+        tokio::fs::create_dir_all(&dir).await?;
+        let path = dir.join("state.json");
+
+        let (mut state_file, state) = StateFile::load(path.clone()).await?;
+        assert!(state.running_dataflows.is_empty());
+        assert!(state.archived_dataflows.is_empty());
+
+        let (daemon_a, daemon_b) = (DaemonId::new(Some("a".into())), DaemonId::new(None));
+        let running = running_dataflow(&daemon_a, &daemon_b);
+        let finished = running_dataflow(&daemon_a, &daemon_b);
+        let result = DataflowDaemonResult {
+            timestamp: dora_core::uhlc::HLC::default().new_timestamp(),
+            node_results: [(
+                NodeId::from("plot".to_owned()),
+                Err(NodeError {
+                    timestamp: dora_core::uhlc::HLC::default().new_timestamp(),
+                    cause: NodeErrorCause::DaemonLost,
+                    exit_status: NodeExitStatus::Unknown,
+                }),
+            )]
+            .into(),
+            stop_request: None,
+        };
+        let state = CoordinatorState::new(
+            &[(running.uuid, running)].into(),
+            &[(finished.uuid, ArchivedDataflow::from(&finished))].into(),
+            &[(finished.uuid, [(daemon_b.clone(), result)].into())].into(),
+        );
+        state_file.store(&state).await?;
+
+        // the state is stored as compact JSON
+        let raw = tokio::fs::read(&path).await?;
+        assert!(!raw.contains(&b'\n'));
+
+        let (_, loaded) = StateFile::load(path).await?;
+        assert_eq!(serde_json::to_vec(&loaded)?, raw);
+
+        let [restored] = <[PersistedDataflow; 1]>::try_from(loaded.running_dataflows).unwrap();
+        let restored = RunningDataflow::from(restored);
+        assert_eq!(
+            restored.node_daemons.get(&NodeId::from("plot".to_owned())),
+            Some(&daemon_b)
+        );
+        assert!(restored.pending_daemons.is_empty());
+
+        tokio::fs::remove_dir_all(&dir).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,505 @@
+//! Resource limits for nodes, based on Linux control groups (cgroup v2).
+//!
+//! Each node with [`NodeResources`] is placed in its own cgroup, which is created as a
+//! child of the cgroup of the daemon. Because cgroup v2 does not allow processes in
+//! inner nodes of the hierarchy, the daemon moves itself into a `dora-daemon` leaf
+//! cgroup if needed.
+
+use dora_message::{
+    DataflowId,
+    common::ResourceLimit,
+    descriptor::{MemorySize, NodeResources},
+    id::NodeId,
+};
+use eyre::{Context, ContextCompat, bail, eyre};
+use std::{
+    fs::File,
+    io,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+    time::Duration,
+};
+
+/// Period of the CPU bandwidth controller in microseconds (kernel default).
+const CPU_PERIOD_US: u64 = 100_000;
+/// Name of the leaf cgroup that the daemon moves itself into.
+const DAEMON_LEAF: &str = "dora-daemon";
+/// How often to check whether killed processes exited before removing a cgroup.
+const KILL_WAIT_ATTEMPTS: u32 = 50;
+const KILL_WAIT_INTERVAL: Duration = Duration::from_millis(10);
+
+/// The cgroup that contains the process of a single node.
+///
+/// The cgroup is removed when this value is dropped.
+pub struct NodeCgroup {
+    path: PathBuf,
+    resources: NodeResources,
+}
+
+impl NodeCgroup {
+    /// Creates a new cgroup for the given node and configures the given limits.
+    pub fn create(
+        dataflow_id: DataflowId,
+        node_id: &NodeId,
+        resources: &NodeResources,
+    ) -> eyre::Result<Self> {
+        if !cfg!(target_os = "linux") {
+            bail!("resource limits are only supported on Linux");
+        }
+
+        let parent = daemon_cgroup().map_err(|err| eyre!("{err}"))?;
+        let controllers: Vec<_> = [
+            ("memory", resources.memory_max.is_some()),
+            ("cpu", resources.cpu_quota.is_some()),
+            ("pids", resources.pids_max.is_some()),
+        ]
+        .into_iter()
+        .filter(|(_, needed)| *needed)
+        .map(|(controller, _)| controller)
+        .collect();
+        enable_controllers(parent, &controllers)?;
+
+        let path = parent.join(format!("dora-{dataflow_id}-{node_id}"));
+        create_node_cgroup_dir(&path)?;
+        let cgroup = Self {
+            path,
+            resources: resources.clone(),
+        };
+        cgroup.write_limits()?;
+
+        Ok(cgroup)
+    }
+
+    fn write_limits(&self) -> eyre::Result<()> {
+        if let Some(MemorySize(max)) = self.resources.memory_max {
+            self.write("memory.max", &max.to_string())?;
+        }
+        if let Some(quota) = self.resources.cpu_quota {
+            let quota_us = ((quota * CPU_PERIOD_US as f64) as u64).max(1000);
+            self.write("cpu.max", &format!("{quota_us} {CPU_PERIOD_US}"))?;
+        }
+        if let Some(max) = self.resources.pids_max {
+            self.write("pids.max", &max.to_string())?;
+        }
+        Ok(())
+    }
+
+    /// Moves the process spawned by the given command into this cgroup before it starts
+    /// executing the node.
+    pub fn apply(&self, command: &mut tokio::process::Command) -> eyre::Result<()> {
+        let procs_path = self.path.join("cgroup.procs");
+        let procs = File::options()
+            .write(true)
+            .open(&procs_path)
+            .wrap_err_with(|| format!("failed to open `{}`", procs_path.display()))?;
+
+        #[cfg(target_os = "linux")]
+        {
+            use std::io::Write;
+
+            // SAFETY: the closure only performs a single `write` syscall on an already opened
+            // file, which is async-signal-safe. Writing `0` moves the calling process.
+            unsafe {
+                command.pre_exec(move || (&procs).write_all(b"0"));
+            }
+        }
+        #[cfg(not(target_os = "linux"))]
+        let _ = (command, procs);
+
+        Ok(())
+    }
+
+    /// Returns the limit that caused the node to fail, if any, and removes the cgroup.
+    ///
+    /// Must be called after the node process exited.
+    pub fn finish(self) -> Option<ResourceLimit> {
+        if self.event_count("memory.events", "oom_kill") > 0 {
+            self.resources
+                .memory_max
+                .map(|max| ResourceLimit::Memory { max })
+        } else if self.event_count("pids.events", "max") > 0 {
+            self.resources
+                .pids_max
+                .map(|max| ResourceLimit::Pids { max })
+        } else {
+            None
+        }
+    }
+
+    fn write(&self, file: &str, value: &str) -> eyre::Result<()> {
+        let path = self.path.join(file);
+        std::fs::write(&path, value)
+            .wrap_err_with(|| format!("failed to write `{value}` to `{}`", path.display()))
+    }
+
+    /// Returns the IDs of the processes that are in the cgroup.
+    fn processes(&self) -> Vec<String> {
+        std::fs::read_to_string(self.path.join("cgroup.procs"))
+            .map(|procs| procs.split_whitespace().map(str::to_owned).collect())
+            .unwrap_or_default()
+    }
+
+    /// Kills the processes that are left in the cgroup, e.g. children of the node process.
+    ///
+    /// Returns `false` if the cgroup contained no processes.
+    fn kill_remaining_processes(&self) -> bool {
+        let procs = self.processes();
+        if procs.is_empty() {
+            return false;
+        }
+        // `cgroup.kill` is only available since Linux 5.14
+        if let Err(err) = self.write("cgroup.kill", "1") {
+            tracing::debug!("{err:?}, killing remaining processes one by one");
+            for pid in procs.iter().filter_map(|pid| pid.parse().ok()) {
+                kill_process(pid);
+            }
+        }
+        true
+    }
+
+    /// Reads the given counter from a cgroup `*.events` file.
+    fn event_count(&self, file: &str, key: &str) -> u64 {
+        std::fs::read_to_string(self.path.join(file))
+            .ok()
+            .and_then(|events| {
+                events.lines().find_map(|line| {
+                    let (k, v) = line.split_once(' ')?;
+                    (k == key).then(|| v.trim().parse().ok()).flatten()
+                })
+            })
+            .unwrap_or_default()
+    }
+}
+
+impl Drop for NodeCgroup {
+    fn drop(&mut self) {
+        // a cgroup can only be removed after all of its processes exited
+        if self.kill_remaining_processes() {
+            for _ in 0..KILL_WAIT_ATTEMPTS {
+                if self.processes().is_empty() {
+                    break;
+                }
+                std::thread::sleep(KILL_WAIT_INTERVAL);
+            }
+        }
+        if let Err(err) = std::fs::remove_dir(&self.path) {
+            tracing::warn!("failed to remove cgroup `{}`: {err}", self.path.display());
+        }
+    }
+}
+
+/// Creates the directory of a new node cgroup.
+///
+/// Fails if the directory exists already, as its limits and processes would be unrelated to
+/// the new node.
+fn create_node_cgroup_dir(path: &Path) -> eyre::Result<()> {
+    match std::fs::create_dir(path) {
+        Ok(()) => Ok(()),
+        Err(err) if err.kind() == io::ErrorKind::AlreadyExists => bail!(
+            "cgroup `{}` exists already (it might be left over from a previous run)",
+            path.display()
+        ),
+        Err(err) => {
+            Err(err).wrap_err_with(|| format!("failed to create cgroup `{}`", path.display()))
+        }
+    }
+}
+
+/// Returns the cgroup that the node cgroups are created in.
+///
+/// This is the cgroup that the daemon was started in.
+fn daemon_cgroup() -> Result<&'static Path, &'static str> {
+    static CGROUP: OnceLock<Result<PathBuf, String>> = OnceLock::new();
+    CGROUP
+        .get_or_init(|| find_own_cgroup().map_err(|err| format!("{err:?}")))
+        .as_deref()
+        .map_err(|err| err.as_str())
+}
+
+fn find_own_cgroup() -> eyre::Result<PathBuf> {
+    // cgroup v2 uses a single hierarchy with ID 0
+    let own = std::fs::read_to_string("/proc/self/cgroup")
+        .context("failed to read `/proc/self/cgroup`")?
+        .lines()
+        .find_map(|line| line.strip_prefix("0::").map(|path| path.to_owned()))
+        .context("cgroup v2 is not available")?;
+
+    // the cgroup path is relative to the root of the cgroup2 mount
+    let mountinfo = std::fs::read_to_string("/proc/self/mountinfo")
+        .context("failed to read `/proc/self/mountinfo`")?;
+    let (mount_root, mount_point) = mountinfo
+        .lines()
+        .find_map(|line| {
+            let (fields, fs) = line.split_once(" - ")?;
+            if fs.split(' ').next() != Some("cgroup2") {
+                return None;
+            }
+            let mut fields = fields.split(' ').skip(3);
+            Some((fields.next()?.to_owned(), fields.next()?.to_owned()))
+        })
+        .context("no cgroup2 file system is mounted")?;
+
+    let relative = Path::new(&own)
+        .strip_prefix(&mount_root)
+        .unwrap_or(Path::new(&own));
+    let relative = relative.strip_prefix("/").unwrap_or(relative);
+    let mut path = Path::new(&mount_point).join(relative);
+
+    // the daemon might have been started by a process in the leaf cgroup of another daemon
+    if path.file_name().is_some_and(|name| name == DAEMON_LEAF) {
+        path.pop();
+    }
+    Ok(path)
+}
+
+/// Enables the given controllers for the child cgroups of `parent`.
+fn enable_controllers(parent: &Path, controllers: &[&str]) -> eyre::Result<()> {
+    if controllers.is_empty() {
+        return Ok(());
+    }
+    let available =
+        std::fs::read_to_string(parent.join("cgroup.controllers")).wrap_err_with(|| {
+            format!(
+                "failed to read controllers of cgroup `{}`",
+                parent.display()
+            )
+        })?;
+    for controller in controllers {
+        if !available.split_whitespace().any(|c| c == *controller) {
+            bail!(
+                "the `{controller}` cgroup controller is not available in `{}`",
+                parent.display()
+            );
+        }
+    }
+
+    let request = controllers
+        .iter()
+        .map(|c| format!("+{c}"))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let subtree_control = parent.join("cgroup.subtree_control");
+    let result = match std::fs::write(&subtree_control, &request) {
+        Err(err) if is_busy(&err) => {
+            // controllers can only be enabled for cgroups that contain no processes
+            move_procs_into_leaf(parent)?;
+            std::fs::write(&subtree_control, &request)
+        }
+        other => other,
+    };
+    result.wrap_err_with(|| {
+        format!(
+            "failed to enable cgroup controllers in `{}` (the daemon needs to run in a \
+            delegated cgroup, e.g. through `systemd-run --user --scope -p Delegate=yes`)",
+            parent.display()
+        )
+    })
+}
+
+/// Moves all processes of the `parent` cgroup into the `dora-daemon` leaf cgroup.
+///
+/// Besides the daemon, the cgroup might contain other processes, e.g. the shell that
+/// started the daemon.
+fn move_procs_into_leaf(parent: &Path) -> eyre::Result<()> {
+    let leaf = parent.join(DAEMON_LEAF);
+    match std::fs::create_dir(&leaf) {
+        Ok(()) => {}
+        Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {}
+        Err(err) => {
+            return Err(err)
+                .wrap_err_with(|| format!("failed to create cgroup `{}`", leaf.display()));
+        }
+    }
+
+    let procs = std::fs::read_to_string(parent.join("cgroup.procs"))
+        .wrap_err_with(|| format!("failed to read processes of cgroup `{}`", parent.display()))?;
+    // the kernel only accepts a single PID per write
+    for pid in procs.lines().map(str::trim).filter(|pid| !pid.is_empty()) {
+        match std::fs::write(leaf.join("cgroup.procs"), pid) {
+            Ok(()) => {}
+            // the process exited in the meantime
+            Err(err) if is_no_such_process(&err) => {}
+            Err(err) => {
+                return Err(err).wrap_err_with(|| {
+                    format!("failed to move process {pid} into `{}`", leaf.display())
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn is_busy(err: &io::Error) -> bool {
+    err.raw_os_error() == Some(libc::EBUSY)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn is_busy(_err: &io::Error) -> bool {
+    false
+}
+
+#[cfg(target_os = "linux")]
+fn kill_process(pid: libc::pid_t) {
+    // SAFETY: `kill` has no memory safety requirements
+    if unsafe { libc::kill(pid, libc::SIGKILL) } != 0 {
+        let err = io::Error::last_os_error();
+        if !is_no_such_process(&err) {
+            tracing::warn!("failed to kill process {pid}: {err}");
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn kill_process(_pid: i32) {}
+
+#[cfg(target_os = "linux")]
+fn is_no_such_process(err: &io::Error) -> bool {
+    err.raw_os_error() == Some(libc::ESRCH)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn is_no_such_process(_err: &io::Error) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_resources(yaml: &str) -> Result<NodeResources, serde_yaml::Error> {
+        serde_yaml::from_str(yaml)
+    }
+
+    /// Creates a `NodeCgroup` backed by a plain temporary directory.
+    fn temp_cgroup(name: &str, resources: NodeResources) -> NodeCgroup {
+        let path =
+            std::env::temp_dir().join(format!("dora-cgroup-test-{name}-{}", std::process::id()));
+        std::fs::create_dir_all(&path).unwrap();
+        NodeCgroup { path, resources }
+    }
+
+    fn remove(cgroup: NodeCgroup) {
+        let path = cgroup.path.clone();
+        std::fs::remove_dir_all(&path).unwrap();
+        drop(cgroup);
+    }
+
+    #[test]
+    fn parse_limits() {
+        let resources = parse_resources("memory_max: 512M\ncpu_quota: 1.5\npids_max: 64").unwrap();
+        assert_eq!(resources.memory_max, Some(MemorySize(512 << 20)));
+        assert_eq!(resources.cpu_quota, Some(1.5));
+        assert_eq!(resources.pids_max, Some(64));
+
+        let sizes = [
+            ("1024", 1024),
+            ("2K", 2 << 10),
+            ("3 MiB", 3 << 20),
+            ("1gb", 1 << 30),
+            ("2T", 2 << 40),
+        ];
+        for (input, expected) in sizes {
+            let resources = parse_resources(&format!("memory_max: {input}")).unwrap();
+            assert_eq!(resources.memory_max, Some(MemorySize(expected)), "{input}");
+        }
+
+        assert!(parse_resources("memory_max: 12X").is_err());
+        assert!(parse_resources("memory_max: M").is_err());
+        assert!(parse_resources("memory_max: 99999999999T").is_err());
+        assert!(parse_resources("memory: 1G").is_err());
+        assert!(parse_resources("{}").unwrap().is_empty());
+    }
+
+    #[test]
+    fn write_limits() {
+        let resources = parse_resources("memory_max: 512M\ncpu_quota: 0.5\npids_max: 64").unwrap();
+        let cgroup = temp_cgroup("limits", resources);
+        cgroup.write_limits().unwrap();
+
+        let read = |file: &str| std::fs::read_to_string(cgroup.path.join(file)).unwrap();
+        assert_eq!(read("memory.max"), (512u64 << 20).to_string());
+        assert_eq!(read("cpu.max"), "50000 100000");
+        assert_eq!(read("pids.max"), "64");
+        remove(cgroup);
+    }
+
+    #[test]
+    fn write_minimal_cpu_quota() {
+        let resources = parse_resources("cpu_quota: 0.001").unwrap();
+        let cgroup = temp_cgroup("min-quota", resources);
+        cgroup.write_limits().unwrap();
+
+        let read = |file: &str| std::fs::read_to_string(cgroup.path.join(file)).unwrap();
+        assert_eq!(read("cpu.max"), "1000 100000");
+        assert!(!cgroup.path.join("memory.max").exists());
+        assert!(!cgroup.path.join("pids.max").exists());
+        remove(cgroup);
+    }
+
+    #[test]
+    fn report_exceeded_limit() {
+        let resources = parse_resources("memory_max: 1G\npids_max: 8").unwrap();
+
+        let cgroup = temp_cgroup("oom", resources.clone());
+        std::fs::write(
+            cgroup.path.join("memory.events"),
+            "low 0\nhigh 0\nmax 3\noom 1\noom_kill 1\n",
+        )
+        .unwrap();
+        let path = cgroup.path.clone();
+        assert!(matches!(
+            cgroup.finish(),
+            Some(ResourceLimit::Memory { max: MemorySize(max) }) if max == 1 << 30
+        ));
+        std::fs::remove_dir_all(path).unwrap();
+
+        let cgroup = temp_cgroup("pids", resources.clone());
+        std::fs::write(cgroup.path.join("memory.events"), "oom_kill 0\n").unwrap();
+        std::fs::write(cgroup.path.join("pids.events"), "max 2\n").unwrap();
+        let path = cgroup.path.clone();
+        assert!(matches!(
+            cgroup.finish(),
+            Some(ResourceLimit::Pids { max: 8 })
+        ));
+        std::fs::remove_dir_all(path).unwrap();
+
+        let cgroup = temp_cgroup("none", resources);
+        assert!(cgroup.finish().is_none());
+    }
+
+    #[test]
+    fn reject_existing_cgroup_dir() {
+        let cgroup = temp_cgroup("existing", NodeResources::default());
+        let err = create_node_cgroup_dir(&cgroup.path).unwrap_err();
+        assert!(err.to_string().contains("exists already"), "{err:?}");
+        drop(cgroup);
+    }
+
+    #[test]
+    fn kill_remaining_processes() {
+        let cgroup = temp_cgroup("kill", NodeResources::default());
+        assert!(!cgroup.kill_remaining_processes());
+        assert!(!cgroup.path.join("cgroup.kill").exists());
+
+        std::fs::write(cgroup.path.join("cgroup.procs"), "").unwrap();
+        assert!(!cgroup.kill_remaining_processes());
+
+        std::fs::write(cgroup.path.join("cgroup.procs"), "4242\n4243\n").unwrap();
+        assert_eq!(cgroup.processes(), ["4242", "4243"]);
+        assert!(cgroup.kill_remaining_processes());
+        assert_eq!(
+            std::fs::read_to_string(cgroup.path.join("cgroup.kill")).unwrap(),
+            "1"
+        );
+        remove(cgroup);
+    }
+
+    #[test]
+    fn remove_cgroup_on_drop() {
+        let cgroup = temp_cgroup("drop", NodeResources::default());
+        let path = cgroup.path.clone();
+        drop(cgroup);
+        assert!(!path.exists());
+    }
+}
@@ -1,15 +1,23 @@
 use crate::{
-    DaemonCoordinatorEvent,
+    DaemonCoordinatorEvent, Event,
     socket_stream_utils::{socket_stream_receive, socket_stream_send},
 };
-use dora_core::uhlc::HLC;
+use dora_core::{
+    security::{ClientSecurity, MaybeTlsStream},
+    uhlc::HLC,
+};
 use dora_message::{
     common::{DaemonId, Timestamped},
     coordinator_to_daemon::RegisterResult,
-    daemon_to_coordinator::{CoordinatorRequest, DaemonCoordinatorReply, DaemonRegisterRequest},
+    daemon_to_coordinator::{
+        CoordinatorRequest, DaemonCoordinatorReply, DaemonReconnect, DaemonRegisterRequest,
+    },
 };
 use eyre::{Context, eyre};
-use std::{io::ErrorKind, net::SocketAddr, time::Duration};
+use futures::StreamExt;
+use std::{
+    collections::BTreeMap, io::ErrorKind, net::SocketAddr, pin::pin, sync::Arc, time::Duration,
+};
 use tokio::{
     net::TcpStream,
     sync::{mpsc, oneshot},
@@ -29,9 +37,12 @@ pub struct CoordinatorEvent {
 pub async fn register(
     addr: SocketAddr,
     machine_id: Option<String>,
+    labels: BTreeMap<String, String>,
+    reconnect: Option<DaemonReconnect>,
+    security: &ClientSecurity,
     clock: &HLC,
 ) -> eyre::Result<(DaemonId, impl Stream<Item = Timestamped<CoordinatorEvent>>)> {
-    let mut stream = loop {
+    let stream = loop {
         match TcpStream::connect(addr)
             .await
             .wrap_err("failed to connect to dora-coordinator")
@@ -50,8 +61,14 @@ pub async fn register(
     stream
         .set_nodelay(true)
         .wrap_err("failed to set TCP_NODELAY")?;
+    let mut stream = security
+        .connect(stream)
+        .await
+        .wrap_err("failed to establish connection to dora-coordinator")?;
     let register = serde_json::to_vec(&Timestamped {
-        inner: CoordinatorRequest::Register(DaemonRegisterRequest::new(machine_id)),
+        inner: CoordinatorRequest::Register(DaemonRegisterRequest::new(
+            machine_id, labels, reconnect,
+        )),
         timestamp: clock.new_timestamp(),
     })?;
     socket_stream_send(&mut stream, &register)
@@ -82,7 +99,16 @@ pub async fn register(
                         continue;
                     }
                 },
-                Err(err) if err.kind() == ErrorKind::UnexpectedEof => break,
+                Err(err)
+                    if matches!(
+                        err.kind(),
+                        ErrorKind::UnexpectedEof
+                            | ErrorKind::ConnectionAborted
+                            | ErrorKind::ConnectionReset
+                    ) =>
+                {
+                    break;
+                }
                 Err(err) => {
                     let err = eyre!(err).wrap_err("failed to receive incoming event");
                     tracing::warn!("{err:?}");
@@ -138,3 +164,81 @@ pub async fn register(
 
     Ok((daemon_id, ReceiverStream::new(rx)))
 }
+
+/// Registers the daemon again after the connection to the coordinator was lost.
+///
+/// Retries until the coordinator is reachable again. Then, the new connections are reported
+/// through an [`Event::CoordinatorReconnected`] and the incoming coordinator events are
+/// forwarded to the given channel.
+pub async fn reconnect(
+    addr: SocketAddr,
+    machine_id: Option<String>,
+    labels: BTreeMap<String, String>,
+    reconnect: DaemonReconnect,
+    security: ClientSecurity,
+    clock: Arc<HLC>,
+    events_tx: mpsc::Sender<Timestamped<Event>>,
+) {
+    let (events, connection, log_connection) = loop {
+        let result = async {
+            let (_daemon_id, events) = register(
+                addr,
+                machine_id.clone(),
+                labels.clone(),
+                Some(reconnect.clone()),
+                &security,
+                &clock,
+            )
+            .await?;
+            let connection = connect(addr, &security).await?;
+            let log_connection = connect(addr, &security)
+                .await
+                .wrap_err("failed to connect log to dora-coordinator")?;
+            eyre::Ok((events, connection, log_connection))
+        };
+        match result.await {
+            Ok(connections) => break connections,
+            Err(err) => {
+                warn!(
+                    "failed to reconnect to dora-coordinator: {err:?}. Retrying in {DAEMON_COORDINATOR_RETRY_INTERVAL:#?}.."
+                );
+                sleep(DAEMON_COORDINATOR_RETRY_INTERVAL).await;
+            }
+        }
+    };
+
+    let reconnected = Timestamped {
+        inner: Event::CoordinatorReconnected {
+            connection,
+            log_connection,
+        },
+        timestamp: clock.new_timestamp(),
+    };
+    if events_tx.send(reconnected).await.is_err() {
+        return;
+    }
+    let mut events = pin!(events);
+    while let Some(Timestamped { inner, timestamp }) = events.next().await {
+        let event = Timestamped {
+            inner: Event::Coordinator(inner),
+            timestamp,
+        };
+        if events_tx.send(event).await.is_err() {
+            break;
+        }
+    }
+}
+
+/// Opens an additional connection to the coordinator.
+pub async fn connect(addr: SocketAddr, security: &ClientSecurity) -> eyre::Result<MaybeTlsStream> {
+    let stream = TcpStream::connect(addr)
+        .await
+        .wrap_err("failed to connect to dora-coordinator")?;
+    stream
+        .set_nodelay(true)
+        .wrap_err("failed to set TCP_NODELAY")?;
+    security
+        .connect(stream)
+        .await
+        .wrap_err("failed to establish connection to dora-coordinator")
+}
@@ -3,38 +3,52 @@ use coordinator::CoordinatorEvent;
 use crossbeam::queue::ArrayQueue;
 use dora_core::{
     build::{self, BuildInfo, GitManager, PrevGitSource},
-    config::{DataId, Input, InputMapping, NodeId, NodeRunConfig, OperatorId},
+    config::{
+        DataId, DataTypeSpec, Input, InputMapping, NodeId, NodeRunConfig, OperatorId, Output,
+        QueuePolicy,
+    },
     descriptor::{
         CoreNodeKind, DYNAMIC_SOURCE, Descriptor, DescriptorExt, ResolvedNode, RuntimeNode,
         read_as_descriptor,
     },
-    topics::LOCALHOST,
+    metadata::ArrowTypeInfoExt,
+    security::{ClientSecurity, MaybeTlsStream},
+    topics::{
+        INJECTED_INPUTS_SOURCE, LOCALHOST, injected_input_output_id, zenoh_input_publish_topic,
+        zenoh_output_publish_topic,
+    },
     uhlc::{self, HLC},
 };
 use dora_message::{
     BuildId, DataflowId, SessionId,
     common::{
-        DaemonId, DataMessage, DropToken, GitSource, LogLevel, NodeError, NodeErrorCause,
-        NodeExitStatus,
+        DaemonId, DataMessage, DataflowStopRequest, DropToken, GitSource, LogLevel, NodeError,
+        NodeErrorCause, NodeExitStatus, ResourceLimit,
     },
     coordinator_to_cli::DataflowResult,
-    coordinator_to_daemon::{BuildDataflowNodes, DaemonCoordinatorEvent, SpawnDataflowNodes},
+    coordinator_to_daemon::{
+        AddDataflowNodes, BuildDataflowNodes, DaemonCoordinatorEvent, SpawnDataflowNodes,
+    },
     daemon_to_coordinator::{
-        CoordinatorRequest, DaemonCoordinatorReply, DaemonEvent, DataflowDaemonResult,
+        CoordinatorRequest, DaemonCoordinatorReply, DaemonEvent, DaemonReconnect,
+        DataflowDaemonResult,
     },
     daemon_to_daemon::InterDaemonEvent,
     daemon_to_node::{DaemonReply, NodeConfig, NodeDropEvent, NodeEvent},
-    descriptor::NodeSource,
+    descriptor::{NodeSource, RestartMode},
     metadata::{self, ArrowTypeInfo},
-    node_to_daemon::{DynamicNodeEvent, Timestamped},
+    node_to_daemon::{DynamicNodeEvent, InputStats, Timestamped},
 };
-use dora_node_api::{Parameter, arrow::datatypes::DataType};
+use dora_node_api::Parameter;
 use eyre::{Context, ContextCompat, Result, bail, eyre};
 use futures::{FutureExt, TryFutureExt, future, stream};
 use futures_concurrency::stream::Merge;
 use local_listener::DynamicNodeEventWrapper;
 use log::{DaemonLogger, DataflowLogger, Logger};
+use metrics::DaemonMetrics;
 use pending::PendingNodes;
+use record::Recorder;
+use replicas::ReplicaGroups;
 use shared_memory_server::ShmemConf;
 use socket_stream_utils::socket_stream_send;
 use spawn::Spawner;
@@ -42,17 +56,20 @@ use std::{
     collections::{BTreeMap, BTreeSet, HashMap},
     env::current_dir,
     future::Future,
+    mem,
     net::SocketAddr,
     path::{Path, PathBuf},
     pin::pin,
-    sync::Arc,
+    sync::{
+        Arc,
+        atomic::{self, AtomicBool},
+    },
     time::{Duration, Instant},
 };
 use sysinfo::Pid;
 use tokio::{
     fs::File,
     io::AsyncReadExt,
-    net::TcpStream,
     sync::{
         broadcast,
         mpsc::{self, UnboundedSender},
@@ -66,11 +83,16 @@ use uuid::{NoContext, Timestamp, Uuid};
 pub use flume;
 pub use log::LogDestination;
 
+mod cgroup;
 mod coordinator;
 mod local_listener;
 mod log;
+mod metrics;
 mod node_communication;
 mod pending;
+pub mod record;
+mod replicas;
+mod scheduling;
 mod socket_stream_utils;
 mod spawn;
 
@@ -89,16 +111,33 @@ pub struct Daemon {
 
     events_tx: mpsc::Sender<Timestamped<Event>>,
 
-    coordinator_connection: Option<TcpStream>,
+    coordinator_addr: Option<SocketAddr>,
+    coordinator_security: ClientSecurity,
+    coordinator_connection: Option<MaybeTlsStream>,
     last_coordinator_heartbeat: Instant,
+    /// Set while the daemon tries to reconnect to the coordinator.
+    coordinator_reconnecting: bool,
+    /// Events that could not be sent because the coordinator connection was lost.
+    ///
+    /// They are sent after the daemon reconnected.
+    pending_coordinator_events: Vec<DaemonEvent>,
     daemon_id: DaemonId,
+    machine_id: Option<String>,
+    /// Labels that are reported to the coordinator on registration.
+    labels: BTreeMap<String, String>,
 
     /// used for testing and examples
     exit_when_done: Option<BTreeSet<(Uuid, NodeId)>>,
     /// set on ctrl-c
     exit_when_all_finished: bool,
+    /// Finished dataflows whose record files are still being written.
+    ///
+    /// They are reported as finished once the recording is complete.
+    finishing_recordings: BTreeSet<Uuid>,
     /// used to record results of local nodes
     dataflow_node_results: BTreeMap<Uuid, BTreeMap<NodeId, Result<(), NodeError>>>,
+    /// the first request of a local node to stop its dataflow
+    dataflow_stop_requests: BTreeMap<Uuid, DataflowStopRequest>,
 
     clock: Arc<uhlc::HLC>,
 
@@ -110,9 +149,15 @@ pub struct Daemon {
     sessions: BTreeMap<SessionId, BuildId>,
     builds: BTreeMap<BuildId, BuildInfo>,
     git_manager: GitManager,
+
+    /// Collects message metrics if the metrics endpoint is enabled.
+    metrics: Option<DaemonMetrics>,
 }
 
-type DaemonRunResult = BTreeMap<Uuid, BTreeMap<NodeId, Result<(), NodeError>>>;
+struct DaemonRunResult {
+    node_results: BTreeMap<Uuid, BTreeMap<NodeId, Result<(), NodeError>>>,
+    stop_requests: BTreeMap<Uuid, DataflowStopRequest>,
+}
 
 struct NodeBuildTask<F> {
     node_id: NodeId,
@@ -124,9 +169,25 @@ impl Daemon {
     pub async fn run(
         coordinator_addr: SocketAddr,
         machine_id: Option<String>,
+        labels: BTreeMap<String, String>,
         local_listen_port: u16,
+        metrics_addr: Option<SocketAddr>,
+        security: ClientSecurity,
     ) -> eyre::Result<()> {
         let clock = Arc::new(HLC::default());
+        // explicitly given labels take precedence over the detected ones
+        let labels: BTreeMap<_, _> = system_labels().into_iter().chain(labels).collect();
+
+        let metrics = metrics_addr.map(|addr| {
+            let metrics = DaemonMetrics::default();
+            let server = metrics.clone().serve(addr);
+            tokio::spawn(async move {
+                if let Err(err) = server.await {
+                    tracing::error!("metrics server failed: {err:?}");
+                }
+            });
+            metrics
+        });
 
         let mut ctrlc_events = set_up_ctrlc_handler(clock.clone())?;
         let (remote_daemon_events_tx, remote_daemon_events_rx) = flume::bounded(10);
@@ -134,6 +195,8 @@ impl Daemon {
             let incoming_events = set_up_event_stream(
                 coordinator_addr,
                 &machine_id,
+                &labels,
+                &security,
                 &clock,
                 remote_daemon_events_rx,
                 local_listen_port,
@@ -152,26 +215,28 @@ impl Daemon {
 
         let log_destination = {
             // additional connection for logging
-            let stream = TcpStream::connect(coordinator_addr)
+            let stream = coordinator::connect(coordinator_addr, &security)
                 .await
                 .wrap_err("failed to connect log to dora-coordinator")?;
-            stream
-                .set_nodelay(true)
-                .wrap_err("failed to set TCP_NODELAY")?;
             LogDestination::Coordinator {
                 coordinator_connection: stream,
+                security: security.clone(),
             }
         };
 
         Self::run_general(
             (ReceiverStream::new(ctrlc_events), incoming_events).merge(),
             Some(coordinator_addr),
+            machine_id.clone(),
+            labels.clone(),
+            security.clone(),
             daemon_id,
             None,
             clock.clone(),
             Some(remote_daemon_events_tx),
             Default::default(),
             log_destination,
+            metrics,
         )
         .await
         .map(|_| ())
@@ -184,6 +249,33 @@ impl Daemon {
         session_id: SessionId,
         uv: bool,
         log_destination: LogDestination,
+    ) -> eyre::Result<DataflowResult> {
+        let descriptor = read_as_descriptor(dataflow_path).await?;
+        Self::run_dataflow_descriptor(
+            dataflow_path,
+            descriptor,
+            build_id,
+            local_build,
+            session_id,
+            uv,
+            log_destination,
+        )
+        .await
+    }
+
+    /// Runs the given dataflow descriptor locally.
+    ///
+    /// Like [`run_dataflow`][Self::run_dataflow], but allows modifying the
+    /// descriptor before running it. The `dataflow_path` is used to determine
+    /// the working directory.
+    pub async fn run_dataflow_descriptor(
+        dataflow_path: &Path,
+        descriptor: Descriptor,
+        build_id: Option<BuildId>,
+        local_build: Option<BuildInfo>,
+        session_id: SessionId,
+        uv: bool,
+        log_destination: LogDestination,
     ) -> eyre::Result<DataflowResult> {
         let working_dir = dataflow_path
             .canonicalize()
@@ -192,7 +284,6 @@ impl Daemon {
             .ok_or_else(|| eyre::eyre!("canonicalized dataflow path has no parent"))?
             .to_owned();
 
-        let descriptor = read_as_descriptor(dataflow_path).await?;
         if let Some(node) = descriptor.nodes.iter().find(|n| n.deploy.is_some()) {
             eyre::bail!(
                 "node {} has a `deploy` section, which is not supported in `dora run`\n\n
@@ -241,6 +332,9 @@ impl Daemon {
         let run_result = Self::run_general(
             Box::pin(events),
             None,
+            None,
+            BTreeMap::new(),
+            ClientSecurity::default(),
             DaemonId::new(None),
             Some(exit_when_done),
             clock.clone(),
@@ -256,6 +350,7 @@ impl Daemon {
                 Default::default()
             },
             log_destination,
+            None,
         );
 
         let spawn_result = reply_rx
@@ -269,14 +364,16 @@ impl Daemon {
                 }
             });
 
-        let (mut dataflow_results, ()) = future::try_join(run_result, spawn_result).await?;
+        let (mut run_result, ()) = future::try_join(run_result, spawn_result).await?;
 
         Ok(DataflowResult {
             uuid: dataflow_id,
             timestamp: clock.new_timestamp(),
-            node_results: dataflow_results
+            node_results: run_result
+                .node_results
                 .remove(&dataflow_id)
                 .context("no node results for dataflow_id")?,
+            stopped_by: run_result.stop_requests.remove(&dataflow_id),
         })
     }
 
@@ -284,23 +381,19 @@ impl Daemon {
     async fn run_general(
         external_events: impl Stream<Item = Timestamped<Event>> + Unpin,
         coordinator_addr: Option<SocketAddr>,
+        machine_id: Option<String>,
+        labels: BTreeMap<String, String>,
+        coordinator_security: ClientSecurity,
         daemon_id: DaemonId,
         exit_when_done: Option<BTreeSet<(Uuid, NodeId)>>,
         clock: Arc<HLC>,
         remote_daemon_events_tx: Option<flume::Sender<eyre::Result<Timestamped<InterDaemonEvent>>>>,
         builds: BTreeMap<BuildId, BuildInfo>,
         log_destination: LogDestination,
+        metrics: Option<DaemonMetrics>,
     ) -> eyre::Result<DaemonRunResult> {
         let coordinator_connection = match coordinator_addr {
-            Some(addr) => {
-                let stream = TcpStream::connect(addr)
-                    .await
-                    .wrap_err("failed to connect to dora-coordinator")?;
-                stream
-                    .set_nodelay(true)
-                    .wrap_err("failed to set TCP_NODELAY")?;
-                Some(stream)
-            }
+            Some(addr) => Some(coordinator::connect(addr, &coordinator_security).await?),
             None => None,
         };
 
@@ -412,18 +505,27 @@ impl Daemon {
             running: HashMap::new(),
             working_dir: HashMap::new(),
             events_tx: dora_events_tx,
+            coordinator_addr,
+            coordinator_security,
             coordinator_connection,
             last_coordinator_heartbeat: Instant::now(),
+            coordinator_reconnecting: false,
+            pending_coordinator_events: Vec::new(),
             daemon_id,
+            machine_id,
+            labels,
             exit_when_done,
             exit_when_all_finished: false,
+            finishing_recordings: BTreeSet::new(),
             dataflow_node_results: BTreeMap::new(),
+            dataflow_stop_requests: BTreeMap::new(),
             clock,
             zenoh_session,
             remote_daemon_events_tx,
             git_manager: Default::default(),
             builds,
             sessions: Default::default(),
+            metrics,
         };
 
         let dora_events = ReceiverStream::new(dora_events_rx);
@@ -484,15 +586,38 @@ impl Daemon {
                             },
                             timestamp: self.clock.new_timestamp(),
                         })?;
-                        socket_stream_send(connection, &msg)
+                        let result = socket_stream_send(connection, &msg)
                             .await
-                            .wrap_err("failed to send watchdog message to dora-coordinator")?;
-
-                        if self.last_coordinator_heartbeat.elapsed() > Duration::from_secs(20) {
-                            bail!("lost connection to coordinator")
+                            .wrap_err("failed to send watchdog message to dora-coordinator");
+
+                        if let Err(err) = result {
+                            self.handle_coordinator_connection_lost(err);
+                        } else if self.last_coordinator_heartbeat.elapsed()
+                            > Duration::from_secs(20)
+                        {
+                            self.handle_coordinator_connection_lost(eyre!(
+                                "no heartbeat from dora-coordinator since {:?}",
+                                self.last_coordinator_heartbeat.elapsed()
+                            ));
                         }
                     }
                 }
+                Event::CoordinatorReconnected {
+                    connection,
+                    log_connection,
+                } => {
+                    tracing::info!("reconnected to dora-coordinator");
+                    self.coordinator_connection = Some(connection);
+                    self.coordinator_reconnecting = false;
+                    self.last_coordinator_heartbeat = Instant::now();
+                    self.logger.set_destination(LogDestination::Coordinator {
+                        coordinator_connection: log_connection,
+                        security: self.coordinator_security.clone(),
+                    });
+                    for event in std::mem::take(&mut self.pending_coordinator_events) {
+                        self.send_to_coordinator(event).await?;
+                    }
+                }
                 Event::CtrlC => {
                     tracing::info!("received ctrlc signal -> stopping all dataflows");
                     for dataflow in self.running.values_mut() {
@@ -557,41 +682,21 @@ impl Daemon {
                             self.builds.remove(&old_build_id);
                         }
                     }
-                    if let Some(connection) = &mut self.coordinator_connection {
-                        let msg = serde_json::to_vec(&Timestamped {
-                            inner: CoordinatorRequest::Event {
-                                daemon_id: self.daemon_id.clone(),
-                                event: DaemonEvent::BuildResult {
-                                    build_id,
-                                    result: result.map_err(|err| format!("{err:?}")),
-                                },
-                            },
-                            timestamp: self.clock.new_timestamp(),
-                        })?;
-                        socket_stream_send(connection, &msg).await.wrap_err(
-                            "failed to send BuildDataflowResult message to dora-coordinator",
-                        )?;
-                    }
+                    self.send_to_coordinator(DaemonEvent::BuildResult {
+                        build_id,
+                        result: result.map_err(|err| format!("{err:?}")),
+                    })
+                    .await?;
                 }
                 Event::SpawnDataflowResult {
                     dataflow_id,
                     result,
                 } => {
-                    if let Some(connection) = &mut self.coordinator_connection {
-                        let msg = serde_json::to_vec(&Timestamped {
-                            inner: CoordinatorRequest::Event {
-                                daemon_id: self.daemon_id.clone(),
-                                event: DaemonEvent::SpawnResult {
-                                    dataflow_id,
-                                    result: result.map_err(|err| format!("{err:?}")),
-                                },
-                            },
-                            timestamp: self.clock.new_timestamp(),
-                        })?;
-                        socket_stream_send(connection, &msg).await.wrap_err(
-                            "failed to send SpawnDataflowResult message to dora-coordinator",
-                        )?;
-                    }
+                    self.send_to_coordinator(DaemonEvent::SpawnResult {
+                        dataflow_id,
+                        result: result.map_err(|err| format!("{err:?}")),
+                    })
+                    .await?;
                 }
                 Event::NodeStopped {
                     dataflow_id,
@@ -599,14 +704,15 @@ impl Daemon {
                 } => {
                     if let Some(exit_when_done) = &mut self.exit_when_done {
                         exit_when_done.remove(&(dataflow_id, node_id));
-                        if exit_when_done.is_empty() {
-                            tracing::info!(
-                                "exiting daemon because all required dataflows are finished"
-                            );
-                            break;
-                        }
                     }
-                    if self.exit_when_all_finished && self.running.is_empty() {
+                    if self.all_required_dataflows_finished() {
+                        break;
+                    }
+                }
+                Event::RecordingFinished { dataflow_id } => {
+                    self.finishing_recordings.remove(&dataflow_id);
+                    self.finish_dataflow(dataflow_id).await?;
+                    if self.all_required_dataflows_finished() {
                         break;
                     }
                 }
@@ -635,7 +741,86 @@ impl Daemon {
                 .wrap_err("failed to send Exit message to dora-coordinator")?;
         }
 
-        Ok(self.dataflow_node_results)
+        Ok(DaemonRunResult {
+            node_results: self.dataflow_node_results,
+            stop_requests: self.dataflow_stop_requests,
+        })
+    }
+
+    /// Checks whether the daemon should exit because the dataflows it waits for are finished.
+    ///
+    /// Dataflows whose record files are still being written are not considered finished.
+    fn all_required_dataflows_finished(&self) -> bool {
+        if !self.finishing_recordings.is_empty() {
+            return false;
+        }
+        if self.exit_when_done.as_ref().is_some_and(|d| d.is_empty()) {
+            tracing::info!("exiting daemon because all required dataflows are finished");
+            return true;
+        }
+        self.exit_when_all_finished && self.running.is_empty()
+    }
+
+    /// Sends the given event to the coordinator.
+    ///
+    /// If the connection to the coordinator was lost, the event is sent after reconnecting.
+    async fn send_to_coordinator(&mut self, event: DaemonEvent) -> eyre::Result<()> {
+        if self.coordinator_addr.is_none() {
+            return Ok(());
+        }
+        let Some(connection) = &mut self.coordinator_connection else {
+            self.pending_coordinator_events.push(event);
+            return Ok(());
+        };
+        let msg = serde_json::to_vec(&Timestamped {
+            inner: CoordinatorRequest::Event {
+                daemon_id: self.daemon_id.clone(),
+                event: event.clone(),
+            },
+            timestamp: self.clock.new_timestamp(),
+        })?;
+        if let Err(err) = socket_stream_send(connection, &msg).await {
+            self.pending_coordinator_events.push(event);
+            self.handle_coordinator_connection_lost(
+                eyre!(err).wrap_err("failed to send message to dora-coordinator"),
+            );
+        }
+        Ok(())
+    }
+
+    /// Keeps the dataflows running and tries to register at the coordinator again.
+    fn handle_coordinator_connection_lost(&mut self, reason: eyre::Report) {
+        self.coordinator_connection = None;
+        let Some(addr) = self.coordinator_addr else {
+            return;
+        };
+        if self.coordinator_reconnecting {
+            return;
+        }
+        tracing::warn!("lost connection to dora-coordinator, trying to reconnect: {reason:?}");
+        self.coordinator_reconnecting = true;
+
+        // dataflows that finished while disconnected are reported after the reconnect
+        let finished = self
+            .pending_coordinator_events
+            .iter()
+            .filter_map(|event| match event {
+                DaemonEvent::AllNodesFinished { dataflow_id, .. } => Some(*dataflow_id),
+                _ => None,
+            });
+        let reconnect = DaemonReconnect {
+            daemon_id: self.daemon_id.clone(),
+            running_dataflows: self.running.keys().copied().chain(finished).collect(),
+        };
+        tokio::spawn(coordinator::reconnect(
+            addr,
+            self.machine_id.clone(),
+            self.labels.clone(),
+            reconnect,
+            self.coordinator_security.clone(),
+            self.clock.clone(),
+            self.events_tx.clone(),
+        ));
     }
 
     async fn handle_coordinator_event(
@@ -847,6 +1032,84 @@ impl Daemon {
                 }
                 RunStatus::Continue
             }
+            DaemonCoordinatorEvent::AddNodes(AddDataflowNodes {
+                dataflow_id,
+                nodes,
+                dataflow_descriptor,
+                spawn_nodes,
+            }) => {
+                let result = self
+                    .add_nodes(dataflow_id, nodes, dataflow_descriptor, spawn_nodes)
+                    .await;
+                let (reply, result_task) = match result {
+                    Ok(result_task) => (Ok(()), Some(result_task)),
+                    Err(err) => (Err(format!("{err:?}")), None),
+                };
+                let _ = reply_tx
+                    .send(Some(DaemonCoordinatorReply::AddNodesResult(reply)))
+                    .map_err(|_| {
+                        error!("could not send add nodes reply from daemon to coordinator")
+                    });
+
+                if let Some(result_task) = result_task {
+                    tokio::spawn(async move {
+                        // failures are reported through the node results
+                        if let Err(err) = result_task.await {
+                            tracing::warn!("failed to spawn added nodes: {err:?}");
+                        }
+                    });
+                }
+
+                RunStatus::Continue
+            }
+            DaemonCoordinatorEvent::RemoveNodes {
+                dataflow_id,
+                node_ids,
+                grace_duration,
+            } => {
+                let mut logger = self.logger.for_dataflow(dataflow_id);
+                let reply = match self.running.get_mut(&dataflow_id) {
+                    Some(dataflow) => {
+                        for node_id in &node_ids {
+                            if dataflow.running_nodes.contains_key(node_id) {
+                                logger
+                                    .log(
+                                        LogLevel::Info,
+                                        Some(node_id.clone()),
+                                        Some("daemon".into()),
+                                        "stopping node because it was removed from the dataflow",
+                                    )
+                                    .await;
+                            }
+                            dataflow.stop_node(node_id, &self.clock, grace_duration);
+                        }
+                        Ok(())
+                    }
+                    None => Err(format!("no running dataflow with ID `{dataflow_id}`")),
+                };
+                let _ = reply_tx
+                    .send(Some(DaemonCoordinatorReply::RemoveNodesResult(reply)))
+                    .map_err(|_| {
+                        error!("could not send remove nodes reply from daemon to coordinator")
+                    });
+                RunStatus::Continue
+            }
+            DaemonCoordinatorEvent::RestartNodes {
+                dataflow_id,
+                node_ids,
+                grace_duration,
+            } => {
+                let result = self
+                    .request_restart(dataflow_id, &node_ids, grace_duration)
+                    .await;
+                let reply = DaemonCoordinatorReply::RestartNodesResult(
+                    result.map_err(|err| format!("{err:?}")),
+                );
+                let _ = reply_tx
+                    .send(Some(reply))
+                    .map_err(|_| error!("could not send restart reply from daemon to coordinator"));
+                RunStatus::Continue
+            }
             DaemonCoordinatorEvent::ReloadDataflow {
                 dataflow_id,
                 node_id,
@@ -1108,8 +1371,23 @@ impl Daemon {
             .try_clone()
             .await
             .context("failed to clone logger")?;
-        let dataflow =
+        let mut dataflow =
             RunningDataflow::new(dataflow_id, self.daemon_id.clone(), &dataflow_descriptor);
+        if let Some(record_dir) = &dataflow_descriptor.debug.record_dir {
+            let record_dir = base_working_dir
+                .join(record_dir)
+                .join(dataflow_id.to_string());
+            logger
+                .log(
+                    LogLevel::Info,
+                    None,
+                    Some("daemon".into()),
+                    format!("recording dataflow outputs to `{}`", record_dir.display()),
+                )
+                .await;
+            dataflow.recorder =
+                Some(Recorder::new(record_dir).wrap_err("failed to set up dataflow recorder")?);
+        }
         let dataflow = match self.running.entry(dataflow_id) {
             std::collections::hash_map::Entry::Vacant(entry) => {
                 self.working_dir
@@ -1140,39 +1418,10 @@ impl Daemon {
             .unwrap_or_default();
 
         // calculate info about mappings
+        let mut blocking_outputs = BTreeSet::new();
         for node in nodes.values() {
             let local = spawn_nodes.contains(&node.id);
-
-            let inputs = node_inputs(node);
-            for (input_id, input) in inputs {
-                if local {
-                    dataflow
-                        .open_inputs
-                        .entry(node.id.clone())
-                        .or_default()
-                        .insert(input_id.clone());
-                    match input.mapping {
-                        InputMapping::User(mapping) => {
-                            dataflow
-                                .mappings
-                                .entry(OutputId(mapping.source, mapping.output))
-                                .or_default()
-                                .insert((node.id.clone(), input_id));
-                        }
-                        InputMapping::Timer { interval } => {
-                            dataflow
-                                .timers
-                                .entry(interval)
-                                .or_default()
-                                .insert((node.id.clone(), input_id));
-                        }
-                    }
-                } else if let InputMapping::User(mapping) = input.mapping {
-                    dataflow
-                        .open_external_mappings
-                        .insert(OutputId(mapping.source, mapping.output));
-                }
-            }
+            dataflow.add_mappings(node, local, &mut blocking_outputs);
         }
 
         let spawner = Spawner {
@@ -1181,7 +1430,10 @@ impl Daemon {
             dataflow_descriptor,
             clock: self.clock.clone(),
             uv,
+            blocking_outputs,
+            report_input_stats: self.metrics.is_some(),
         };
+        dataflow.spawner = Some(spawner.clone());
 
         let mut tasks = Vec::new();
 
@@ -1196,6 +1448,17 @@ impl Daemon {
                 } else {
                     dataflow.pending_nodes.insert(node.id.clone());
                 }
+                dataflow
+                    .declare_output_publishers(&self.zenoh_session, &node)
+                    .await
+                    .wrap_err_with(|| format!("failed to declare publishers of `{}`", node.id))?;
+                dataflow
+                    .subscribe_to_injected_inputs(
+                        &self.zenoh_session,
+                        self.remote_daemon_events_tx.clone(),
+                        &node.id,
+                    )
+                    .await?;
 
                 let node_id = node.id.clone();
                 let node_stderr_most_recent = dataflow
@@ -1220,6 +1483,12 @@ impl Daemon {
                     })
                     .unwrap_or(base_working_dir.clone())
                     .clone();
+                if !dynamic_node {
+                    dataflow.restartable_nodes.insert(
+                        node_id.clone(),
+                        RestartableNode::new(node.clone(), node_working_dir.clone()),
+                    );
+                }
                 match spawner
                     .clone()
                     .spawn_node(node, node_working_dir, node_stderr_most_recent, &mut logger)
@@ -1256,55 +1525,23 @@ impl Daemon {
                 dataflow.pending_nodes.set_external_nodes(true);
 
                 // subscribe to all node outputs that are mapped to some local inputs
-                for output_id in dataflow.mappings.keys().filter(|o| o.0 == node.id) {
-                    let tx = self
-                        .remote_daemon_events_tx
-                        .clone()
-                        .wrap_err("no remote_daemon_events_tx channel")?;
-                    let mut finished_rx = dataflow.finished_tx.subscribe();
-                    let subscribe_topic = dataflow.output_publish_topic(output_id);
-                    tracing::debug!("declaring subscriber on {subscribe_topic}");
-                    let subscriber = self
-                        .zenoh_session
-                        .declare_subscriber(subscribe_topic)
-                        .await
-                        .map_err(|e| eyre!(e))
-                        .wrap_err_with(|| format!("failed to subscribe to {output_id:?}"))?;
-                    tokio::spawn(async move {
-                        let mut finished = pin!(finished_rx.recv());
-                        loop {
-                            let finished_or_next =
-                                futures::future::select(finished, subscriber.recv_async());
-                            match finished_or_next.await {
-                                future::Either::Left((finished, _)) => match finished {
-                                    Err(broadcast::error::RecvError::Closed) => {
-                                        tracing::debug!(
-                                            "dataflow finished, breaking from zenoh subscribe task"
-                                        );
-                                        break;
-                                    }
-                                    other => {
-                                        tracing::warn!(
-                                            "unexpected return value of dataflow finished_rx channel: {other:?}"
-                                        );
-                                        break;
-                                    }
-                                },
-                                future::Either::Right((sample, f)) => {
-                                    finished = f;
-                                    let event = sample.map_err(|e| eyre!(e)).and_then(|s| {
-                                        Timestamped::deserialize_inter_daemon_event(
-                                            &s.payload().to_bytes(),
-                                        )
-                                    });
-                                    if tx.send_async(event).await.is_err() {
-                                        // daemon finished
-                                        break;
-                                    }
-                                }
-                            }
-                        }
-                    });
+                //
+                // replicas send their outputs under a shared ID, so we subscribe only once
+                let first_replica = node.replica.as_ref().is_none_or(|r| r.index == 0);
+                let remote_outputs: Vec<_> = dataflow
+                    .mappings
+                    .keys()
+                    .filter(|o| first_replica && &o.0 == node.output_node_id())
+                    .cloned()
+                    .collect();
+                for output_id in remote_outputs {
+                    dataflow
+                        .subscribe_to_remote_output(
+                            &self.zenoh_session,
+                            self.remote_daemon_events_tx.clone(),
+                            &output_id,
+                        )
+                        .await?;
                 }
             }
         }
@@ -1324,51 +1561,221 @@ impl Daemon {
         Ok(spawn_result)
     }
 
-    async fn spawn_prepared_nodes(
-        dataflow_id: Uuid,
-        mut logger: DataflowLogger<'_>,
-        tasks: Vec<NodeBuildTask<impl Future<Output = eyre::Result<spawn::PreparedNode>>>>,
-        events_tx: mpsc::Sender<Timestamped<Event>>,
-        clock: Arc<HLC>,
-    ) -> eyre::Result<()> {
-        let node_result = |node_id, dynamic_node, result| Timestamped {
-            inner: Event::SpawnNodeResult {
-                dataflow_id,
-                node_id,
-                dynamic_node,
-                result,
-            },
-            timestamp: clock.new_timestamp(),
-        };
-        let mut failed_to_prepare = None;
-        let mut prepared_nodes = Vec::new();
-        for task in tasks {
-            let NodeBuildTask {
-                node_id,
-                dynamic_node,
-                task,
-            } = task;
-            match task.await {
-                Ok(node) => prepared_nodes.push(node),
-                Err(err) => {
-                    if failed_to_prepare.is_none() {
-                        failed_to_prepare = Some(node_id.clone());
-                    }
-                    let node_err: NodeError = NodeError {
-                        timestamp: clock.new_timestamp(),
-                        cause: NodeErrorCause::FailedToSpawn(format!(
-                            "preparing for spawn failed: {err:?}"
-                        )),
-                        exit_status: NodeExitStatus::Unknown,
-                    };
-                    let send_result = events_tx
-                        .send(node_result(node_id, dynamic_node, Err(node_err)))
-                        .await;
-                    if send_result.is_err() {
-                        tracing::error!("failed to send SpawnNodeResult to main daemon task")
-                    }
-                }
-            }
+    /// Adds the given nodes to a running dataflow.
+    ///
+    /// The input mappings of all given nodes are registered, but only the nodes in
+    /// `spawn_nodes` are spawned on this daemon. Returns a future that spawns the nodes
+    /// once they are prepared.
+    async fn add_nodes(
+        &mut self,
+        dataflow_id: DataflowId,
+        nodes: BTreeMap<NodeId, ResolvedNode>,
+        dataflow_descriptor: Descriptor,
+        spawn_nodes: BTreeSet<NodeId>,
+    ) -> eyre::Result<impl Future<Output = eyre::Result<()>> + use<>> {
+        let mut logger = self
+            .logger
+            .for_dataflow(dataflow_id)
+            .try_clone()
+            .await
+            .context("failed to clone logger")?;
+        let base_working_dir = self
+            .working_dir
+            .get(&dataflow_id)
+            .cloned()
+            .wrap_err_with(|| format!("no working dir for dataflow `{dataflow_id}`"))?;
+        let dataflow = self
+            .running
+            .get_mut(&dataflow_id)
+            .wrap_err_with(|| format!("no running dataflow with ID `{dataflow_id}`"))?;
+        if dataflow.stop_sent {
+            bail!("dataflow `{dataflow_id}` is stopping");
+        }
+        if dataflow.pending_nodes.local_nodes_pending() {
+            bail!("dataflow `{dataflow_id}` is still starting");
+        }
+        if let Some(git_node) = nodes
+            .values()
+            .find(|n| spawn_nodes.contains(&n.id) && n.has_git_source())
+        {
+            bail!(
+                "node `{}` has a git source, which is not supported for nodes that are \
+                added to a running dataflow",
+                git_node.id
+            );
+        }
+        let mut spawner = dataflow
+            .spawner
+            .clone()
+            .wrap_err("dataflow has no spawner")?;
+
+        // remote outputs that are already mapped to local inputs are subscribed already
+        let mapped_outputs: BTreeSet<_> = dataflow.mappings.keys().cloned().collect();
+        for node in nodes.values() {
+            let local = spawn_nodes.contains(&node.id);
+            if local {
+                dataflow
+                    .declare_output_publishers(&self.zenoh_session, node)
+                    .await
+                    .wrap_err_with(|| format!("failed to declare publishers of `{}`", node.id))?;
+            }
+            dataflow.add_mappings(node, local, &mut spawner.blocking_outputs);
+        }
+        spawner.dataflow_descriptor = dataflow_descriptor;
+        dataflow.spawner = Some(spawner.clone());
+
+        // local outputs have a publisher, all other outputs are sent by remote nodes
+        let remote_outputs: Vec<_> = dataflow
+            .mappings
+            .keys()
+            .filter(|o| !mapped_outputs.contains(*o) && !dataflow.publishers.contains_key(*o))
+            .cloned()
+            .collect();
+        for output_id in remote_outputs {
+            dataflow
+                .subscribe_to_remote_output(
+                    &self.zenoh_session,
+                    self.remote_daemon_events_tx.clone(),
+                    &output_id,
+                )
+                .await?;
+        }
+
+        let mut stopped = Vec::new();
+        let mut tasks = Vec::new();
+        for node in nodes.into_values() {
+            if !spawn_nodes.contains(&node.id) {
+                continue;
+            }
+            let mut logger = logger.reborrow().for_node(node.id.clone());
+            let node_id = node.id.clone();
+            let dynamic_node = node.kind.dynamic();
+            if dynamic_node {
+                dataflow.dynamic_nodes.insert(node_id.clone());
+            } else {
+                dataflow.added_nodes.insert(node_id.clone());
+            }
+            dataflow
+                .subscribe_to_injected_inputs(
+                    &self.zenoh_session,
+                    self.remote_daemon_events_tx.clone(),
+                    &node_id,
+                )
+                .await?;
+
+            let node_stderr_most_recent = dataflow
+                .node_stderr_most_recent
+                .entry(node_id.clone())
+                .or_insert_with(|| Arc::new(ArrayQueue::new(STDERR_LOG_LINES)))
+                .clone();
+            let node_working_dir = node
+                .deploy
+                .as_ref()
+                .and_then(|d| d.working_dir.as_ref().map(|d| base_working_dir.join(d)))
+                .unwrap_or(base_working_dir.clone());
+            if !dynamic_node {
+                dataflow.restartable_nodes.insert(
+                    node_id.clone(),
+                    RestartableNode::new(node.clone(), node_working_dir.clone()),
+                );
+            }
+            match spawner
+                .clone()
+                .spawn_node(node, node_working_dir, node_stderr_most_recent, &mut logger)
+                .await
+                .wrap_err_with(|| format!("failed to spawn node `{node_id}`"))
+            {
+                Ok(result) => {
+                    tasks.push(NodeBuildTask {
+                        node_id,
+                        task: result,
+                        dynamic_node,
+                    });
+                }
+                Err(err) => {
+                    logger
+                        .log(LogLevel::Error, Some("daemon".into()), format!("{err:?}"))
+                        .await;
+                    self.dataflow_node_results
+                        .entry(dataflow_id)
+                        .or_default()
+                        .insert(
+                            node_id.clone(),
+                            Err(NodeError {
+                                timestamp: self.clock.new_timestamp(),
+                                cause: NodeErrorCause::FailedToSpawn(format!("{err:?}")),
+                                exit_status: NodeExitStatus::Unknown,
+                            }),
+                        );
+                    stopped.push((node_id, dynamic_node));
+                }
+            }
+        }
+
+        // start the timers of new timer inputs
+        dataflow.start(&self.events_tx, &self.clock).await?;
+
+        for (node_id, dynamic) in stopped {
+            self.handle_node_stop(dataflow_id, &node_id, dynamic)
+                .await?;
+        }
+
+        let spawn_result = Self::spawn_prepared_nodes(
+            dataflow_id,
+            logger,
+            tasks,
+            self.events_tx.clone(),
+            self.clock.clone(),
+        );
+
+        Ok(spawn_result)
+    }
+
+    async fn spawn_prepared_nodes(
+        dataflow_id: Uuid,
+        mut logger: DataflowLogger<'_>,
+        tasks: Vec<NodeBuildTask<impl Future<Output = eyre::Result<spawn::PreparedNode>>>>,
+        events_tx: mpsc::Sender<Timestamped<Event>>,
+        clock: Arc<HLC>,
+    ) -> eyre::Result<()> {
+        let node_result = |node_id, dynamic_node, result| Timestamped {
+            inner: Event::SpawnNodeResult {
+                dataflow_id,
+                node_id,
+                dynamic_node,
+                result,
+            },
+            timestamp: clock.new_timestamp(),
+        };
+        let mut failed_to_prepare = None;
+        let mut prepared_nodes = Vec::new();
+        for task in tasks {
+            let NodeBuildTask {
+                node_id,
+                dynamic_node,
+                task,
+            } = task;
+            match task.await {
+                Ok(node) => prepared_nodes.push(node),
+                Err(err) => {
+                    if failed_to_prepare.is_none() {
+                        failed_to_prepare = Some(node_id.clone());
+                    }
+                    let node_err: NodeError = NodeError {
+                        timestamp: clock.new_timestamp(),
+                        cause: NodeErrorCause::FailedToSpawn(format!(
+                            "preparing for spawn failed: {err:?}"
+                        )),
+                        exit_status: NodeExitStatus::Unknown,
+                    };
+                    let send_result = events_tx
+                        .send(node_result(node_id, dynamic_node, Err(node_err)))
+                        .await;
+                    if send_result.is_err() {
+                        tracing::error!("failed to send SpawnNodeResult to main daemon task")
+                    }
+                }
+            }
         }
 
         // once all nodes are prepared, do the actual spawning
@@ -1527,6 +1934,17 @@ impl Daemon {
                     Err(err) => {
                         let _ = reply_sender.send(DaemonReply::Result(Err(err)));
                     }
+                    Ok(dataflow)
+                        if dataflow.added_nodes.contains(&node_id)
+                            || dataflow
+                                .restartable_nodes
+                                .get(&node_id)
+                                .is_some_and(|n| n.respawned) =>
+                    {
+                        // added and restarted nodes don't need to wait for other nodes
+                        Self::subscribe(dataflow, node_id.clone(), event_sender, &self.clock).await;
+                        let _ = reply_sender.send(DaemonReply::Result(Ok(())));
+                    }
                     Ok(dataflow) => {
                         Self::subscribe(dataflow, node_id.clone(), event_sender, &self.clock).await;
 
@@ -1580,6 +1998,18 @@ impl Daemon {
             } => {
                 // notify downstream nodes
                 let inner = async {
+                    if self.restart_pending(dataflow_id, &node_id) {
+                        // outputs are closed once the node stops for good
+                        return Ok(());
+                    }
+                    if self
+                        .running
+                        .get(&dataflow_id)
+                        .is_some_and(|d| d.replicas.is_replica(&node_id))
+                    {
+                        // outputs of replicas are closed once all replicas are done
+                        return Ok(());
+                    }
                     self.send_output_closed_events(dataflow_id, node_id, outputs)
                         .await
                 };
@@ -1588,7 +2018,12 @@ impl Daemon {
                 let _ = reply_sender.send(DaemonReply::Result(reply));
             }
             DaemonNodeEvent::OutputsDone { reply_sender } => {
-                let result = self.handle_outputs_done(dataflow_id, &node_id).await;
+                let result = if self.restart_pending(dataflow_id, &node_id) {
+                    // outputs are closed once the node stops for good
+                    Ok(())
+                } else {
+                    self.handle_outputs_done(dataflow_id, &node_id).await
+                };
 
                 let _ = reply_sender.send(DaemonReply::Result(
                     result.map_err(|err| format!("{err:?}")),
@@ -1598,10 +2033,49 @@ impl Daemon {
                 output_id,
                 metadata,
                 data,
-            } => self
-                .send_out(dataflow_id, node_id, output_id, metadata, data)
+                reply_sender,
+            } => {
+                self.send_out(
+                    dataflow_id,
+                    node_id.clone(),
+                    output_id.clone(),
+                    metadata,
+                    data,
+                )
                 .await
-                .context("failed to send out")?,
+                .context("failed to send out")?;
+                if let Some(reply_sender) = reply_sender {
+                    match self.running.get_mut(&dataflow_id) {
+                        Some(dataflow) => {
+                            let sender_id = dataflow.replicas.output_node_id(&node_id).clone();
+                            dataflow
+                                .apply_backpressure(&OutputId(sender_id, output_id), reply_sender)
+                        }
+                        None => {
+                            let _ = reply_sender.send(DaemonReply::Result(Ok(())));
+                        }
+                    }
+                }
+            }
+            DaemonNodeEvent::InputsConsumed { inputs } => {
+                if let Some(dataflow) = self.running.get_mut(&dataflow_id) {
+                    dataflow.replicas.inputs_consumed(&node_id, inputs.len());
+                    for input_id in inputs {
+                        if let Some(queue) = dataflow
+                            .blocking_inputs
+                            .get_mut(&(node_id.clone(), input_id))
+                        {
+                            queue.in_flight = queue.in_flight.saturating_sub(1);
+                        }
+                    }
+                    dataflow.unblock_senders();
+                }
+            }
+            DaemonNodeEvent::InputStats { inputs } => {
+                if let Some(metrics) = &self.metrics {
+                    metrics.record_input_stats(dataflow_id, &node_id, inputs);
+                }
+            }
             DaemonNodeEvent::ReportDrop { tokens } => {
                 let dataflow = self.running.get_mut(&dataflow_id).wrap_err_with(|| {
                     format!(
@@ -1637,16 +2111,80 @@ impl Daemon {
                         .get_mut(&dataflow_id)
                         .wrap_err_with(|| format!("no running dataflow with ID `{dataflow_id}`"))?;
                     dataflow.subscribe_channels.remove(&node_id);
+                    dataflow.release_blocking_inputs(&node_id);
                     Result::<_, eyre::Error>::Ok(())
                 };
 
                 let reply = inner.await.map_err(|err| format!("{err:?}"));
                 let _ = reply_sender.send(DaemonReply::Result(reply));
             }
+            DaemonNodeEvent::StopDataflow {
+                reason,
+                reply_sender,
+            } => {
+                let reply = self
+                    .handle_stop_dataflow_request(dataflow_id, node_id, reason)
+                    .await
+                    .map_err(|err| format!("{err:?}"));
+                let _ = reply_sender.send(DaemonReply::Result(reply));
+            }
         }
         Ok(())
     }
 
+    /// Stops the whole dataflow on behalf of one of its nodes.
+    ///
+    /// If the daemon is connected to a coordinator, the request is forwarded to it so
+    /// that the nodes on all machines are stopped.
+    async fn handle_stop_dataflow_request(
+        &mut self,
+        dataflow_id: DataflowId,
+        node_id: NodeId,
+        reason: Option<String>,
+    ) -> eyre::Result<()> {
+        let mut logger = self.logger.for_dataflow(dataflow_id);
+        let dataflow = self
+            .running
+            .get_mut(&dataflow_id)
+            .wrap_err_with(|| format!("no running dataflow with ID `{dataflow_id}`"))?;
+        let request = DataflowStopRequest { node_id, reason };
+        logger
+            .log(
+                LogLevel::Info,
+                Some(request.node_id.clone()),
+                Some("daemon".into()),
+                match &request.reason {
+                    Some(reason) => format!("node requested to stop the dataflow: {reason}"),
+                    None => "node requested to stop the dataflow".to_owned(),
+                },
+            )
+            .await;
+        if dataflow.stop_sent || self.dataflow_stop_requests.contains_key(&dataflow_id) {
+            // the dataflow is already stopping
+            return Ok(());
+        }
+        self.dataflow_stop_requests
+            .insert(dataflow_id, request.clone());
+
+        if self.coordinator_addr.is_some() {
+            drop(logger);
+            self.send_to_coordinator(DaemonEvent::StopDataflowRequested {
+                dataflow_id,
+                request,
+            })
+            .await
+        } else {
+            dataflow
+                .stop_all(
+                    &mut self.coordinator_connection,
+                    &self.clock,
+                    None,
+                    &mut logger,
+                )
+                .await
+        }
+    }
+
     async fn send_reload(
         &mut self,
         dataflow_id: Uuid,
@@ -1678,6 +2216,28 @@ impl Daemon {
         let dataflow = self.running.get_mut(&dataflow_id).wrap_err_with(|| {
             format!("send out failed: no running dataflow with ID `{dataflow_id}`")
         })?;
+        if let Err(err) = dataflow.check_output_type(&node_id, &output_id, &metadata) {
+            // the message is not delivered, so the sender can reuse its memory right away
+            if let Some(token) = data.as_ref().and_then(|d| d.drop_token()) {
+                dataflow
+                    .pending_drop_tokens
+                    .entry(token)
+                    .or_insert_with(|| DropTokenInformation {
+                        owner: node_id.clone(),
+                        pending_nodes: Default::default(),
+                    });
+                dataflow.check_drop_token(token, &self.clock).await?;
+            }
+            let mut logger = self.logger.for_dataflow(dataflow_id).for_node(node_id);
+            logger
+                .log(
+                    LogLevel::Warn,
+                    Some("daemon".into()),
+                    format!("{:?}", err.wrap_err("dropped output message")),
+                )
+                .await;
+            return Ok(());
+        }
         let data_bytes = send_output_to_local_receivers(
             node_id.clone(),
             output_id.clone(),
@@ -1688,20 +2248,41 @@ impl Daemon {
         )
         .await?;
 
+        // outputs of replicas are sent under the ID of the replicated node
+        let node_id = dataflow.replicas.output_node_id(&node_id).clone();
         let output_id = OutputId(node_id, output_id);
+        if let Some(metrics) = &self.metrics {
+            let bytes = data_bytes.as_ref().map(|d| d.len()).unwrap_or_default();
+            metrics.record_output(dataflow_id, &output_id, bytes);
+        }
+        let recording = dataflow.recorder.is_some();
         let remote_receivers = dataflow.open_external_mappings.contains(&output_id)
-            || dataflow.publish_all_messages_to_zenoh;
+            || dataflow.publish_all_messages_to_zenoh
+            || dataflow.output_has_subscribers(&output_id);
+        if !remote_receivers && !recording {
+            return Ok(());
+        }
+
+        let event = InterDaemonEvent::Output {
+            dataflow_id,
+            node_id: output_id.0.clone(),
+            output_id: output_id.1.clone(),
+            metadata,
+            data: data_bytes,
+        };
         if remote_receivers {
-            let event = InterDaemonEvent::Output {
-                dataflow_id,
-                node_id: output_id.0.clone(),
-                output_id: output_id.1.clone(),
-                metadata,
-                data: data_bytes,
-            };
-            self.send_to_remote_receivers(dataflow_id, &output_id, event)
+            self.send_to_remote_receivers(dataflow_id, &output_id, &event)
                 .await?;
         }
+        // the recorder takes the message after it was sent to avoid copying the data
+        let recorder = self
+            .running
+            .get(&dataflow_id)
+            .and_then(|dataflow| dataflow.recorder.as_ref());
+        if let (Some(recorder), InterDaemonEvent::Output { metadata, data, .. }) = (recorder, event)
+        {
+            recorder.record(output_id, metadata, data);
+        }
 
         Ok(())
     }
@@ -1710,7 +2291,7 @@ impl Daemon {
         &mut self,
         dataflow_id: Uuid,
         output_id: &OutputId,
-        event: InterDaemonEvent,
+        event: &InterDaemonEvent,
     ) -> Result<(), eyre::Error> {
         let dataflow = self.running.get_mut(&dataflow_id).wrap_err_with(|| {
             format!("send out failed: no running dataflow with ID `{dataflow_id}`")
@@ -1780,7 +2361,7 @@ impl Daemon {
                 node_id: output_id.0.clone(),
                 output_id: output_id.1.clone(),
             };
-            self.send_to_remote_receivers(dataflow_id, &output_id, event)
+            self.send_to_remote_receivers(dataflow_id, &output_id, &event)
                 .await?;
         }
 
@@ -1826,6 +2407,7 @@ impl Daemon {
             let _ = send_with_timestamp(&event_sender, NodeEvent::Stop, clock);
         }
 
+        dataflow.replicas.reset_pending_inputs(&node_id);
         dataflow.subscribe_channels.insert(node_id, event_sender);
     }
 
@@ -1840,15 +2422,23 @@ impl Daemon {
             .get_mut(&dataflow_id)
             .ok_or_else(|| eyre!("no running dataflow with ID `{dataflow_id}`"))?;
 
-        let outputs = dataflow
-            .mappings
-            .keys()
-            .filter(|m| &m.0 == node_id)
-            .map(|m| &m.1)
-            .cloned()
-            .collect();
-        self.send_output_closed_events(dataflow_id, node_id.clone(), outputs)
-            .await?;
+        // the outputs of replicas are closed once all replicas of the group are done
+        let output_node_id = if dataflow.replicas.is_replica(node_id) {
+            dataflow.replicas.close_outputs(node_id)
+        } else {
+            Some(node_id.clone())
+        };
+        if let Some(output_node_id) = output_node_id {
+            let outputs = dataflow
+                .mappings
+                .keys()
+                .filter(|m| m.0 == output_node_id)
+                .map(|m| &m.1)
+                .cloned()
+                .collect();
+            self.send_output_closed_events(dataflow_id, output_node_id, outputs)
+                .await?;
+        }
 
         let dataflow = self
             .running
@@ -1915,59 +2505,80 @@ impl Daemon {
 
         self.handle_outputs_done(dataflow_id, node_id).await?;
 
-        let mut logger = self.logger.for_dataflow(dataflow_id);
         let dataflow = self.running.get_mut(&dataflow_id).wrap_err_with(|| {
             format!("failed to get downstream nodes: no running dataflow with ID `{dataflow_id}`")
         })?;
         if let Some(mut pid) = dataflow.running_nodes.remove(node_id).and_then(|n| n.pid) {
             pid.mark_as_stopped()
         }
+        dataflow.release_blocking_inputs(node_id);
         if !dataflow.pending_nodes.local_nodes_pending()
             && dataflow
                 .running_nodes
                 .iter()
                 .all(|(_id, n)| n.node_config.dynamic)
+            && !self.finishing_recordings.contains(&dataflow_id)
         {
-            let result = DataflowDaemonResult {
-                timestamp: self.clock.new_timestamp(),
-                node_results: self
-                    .dataflow_node_results
-                    .get(&dataflow.id)
-                    .context("failed to get dataflow node results")?
-                    .clone(),
-            };
+            match dataflow.recorder.take() {
+                Some(recorder) => {
+                    // finish the record files in the background and report the dataflow as
+                    // finished afterwards
+                    self.finishing_recordings.insert(dataflow_id);
+                    let events_tx = self.events_tx.clone();
+                    let clock = self.clock.clone();
+                    tokio::spawn(async move {
+                        recorder.finish().await;
+                        let _ = events_tx
+                            .send(Timestamped {
+                                inner: Event::RecordingFinished { dataflow_id },
+                                timestamp: clock.new_timestamp(),
+                            })
+                            .await;
+                    });
+                }
+                None => self.finish_dataflow(dataflow_id).await?,
+            }
+        }
 
-            self.git_manager
-                .clones_in_use
-                .values_mut()
-                .for_each(|dataflows| {
-                    dataflows.remove(&dataflow_id);
-                });
+        Ok(())
+    }
 
-            logger
-                .log(
-                    LogLevel::Info,
-                    None,
-                    Some("daemon".into()),
-                    format!("dataflow finished on machine `{}`", self.daemon_id),
-                )
-                .await;
-            if let Some(connection) = &mut self.coordinator_connection {
-                let msg = serde_json::to_vec(&Timestamped {
-                    inner: CoordinatorRequest::Event {
-                        daemon_id: self.daemon_id.clone(),
-                        event: DaemonEvent::AllNodesFinished {
-                            dataflow_id,
-                            result,
-                        },
-                    },
-                    timestamp: self.clock.new_timestamp(),
-                })?;
-                socket_stream_send(connection, &msg)
-                    .await
-                    .wrap_err("failed to report dataflow finish to dora-coordinator")?;
-            }
-            self.running.remove(&dataflow_id);
+    /// Reports the given dataflow as finished to the coordinator and removes it.
+    async fn finish_dataflow(&mut self, dataflow_id: Uuid) -> eyre::Result<()> {
+        let mut logger = self.logger.for_dataflow(dataflow_id);
+        let result = DataflowDaemonResult {
+            timestamp: self.clock.new_timestamp(),
+            node_results: self
+                .dataflow_node_results
+                .get(&dataflow_id)
+                .context("failed to get dataflow node results")?
+                .clone(),
+            stop_request: self.dataflow_stop_requests.get(&dataflow_id).cloned(),
+        };
+
+        self.git_manager
+            .clones_in_use
+            .values_mut()
+            .for_each(|dataflows| {
+                dataflows.remove(&dataflow_id);
+            });
+
+        logger
+            .log(
+                LogLevel::Info,
+                None,
+                Some("daemon".into()),
+                format!("dataflow finished on machine `{}`", self.daemon_id),
+            )
+            .await;
+        self.send_to_coordinator(DaemonEvent::AllNodesFinished {
+            dataflow_id,
+            result,
+        })
+        .await?;
+        self.running.remove(&dataflow_id);
+        if let Some(metrics) = &self.metrics {
+            metrics.remove_dataflow(dataflow_id);
         }
 
         Ok(())
@@ -1988,6 +2599,11 @@ impl Daemon {
                 let Some(subscribers) = dataflow.timers.get(&interval) else {
                     return Ok(());
                 };
+                let subscribers = dataflow.replicas.select_receivers(
+                    subscribers,
+                    |r| dataflow.subscribe_channels.contains_key(&r.0),
+                    |r| dataflow.blocking_inputs.get(r).is_some_and(|q| q.is_full()),
+                );
 
                 let mut closed = Vec::new();
                 for (receiver_id, input_id) in subscribers {
@@ -2034,6 +2650,11 @@ impl Daemon {
                     );
                     return Ok(());
                 };
+                let subscribers = dataflow.replicas.select_receivers(
+                    subscribers,
+                    |r| dataflow.subscribe_channels.contains_key(&r.0),
+                    |r| dataflow.blocking_inputs.get(r).is_some_and(|q| q.is_full()),
+                );
 
                 let mut closed = Vec::new();
                 for (receiver_id, input_id) in subscribers {
@@ -2067,6 +2688,7 @@ impl Daemon {
                 node_id,
                 dynamic_node,
                 exit_status,
+                exceeded_limit,
             } => {
                 let mut logger = self
                     .logger
@@ -2107,7 +2729,7 @@ impl Daemon {
                             }
                             None if grace_duration_kill => NodeErrorCause::GraceDuration,
                             None => {
-                                let cause = dataflow
+                                let stderr = dataflow
                                     .and_then(|d| d.node_stderr_most_recent.get(&node_id))
                                     .map(|queue| {
                                         let mut s = if queue.is_full() {
@@ -2122,7 +2744,10 @@ impl Daemon {
                                     })
                                     .unwrap_or_default();
 
-                                NodeErrorCause::Other { stderr: cause }
+                                match exceeded_limit {
+                                    Some(limit) => NodeErrorCause::ResourceLimit { limit, stderr },
+                                    None => NodeErrorCause::Other { stderr },
+                                }
                             }
                         };
                         Err(NodeError {
@@ -2148,28 +2773,213 @@ impl Daemon {
                     )
                     .await;
 
-                self.dataflow_node_results
-                    .entry(dataflow_id)
-                    .or_default()
-                    .insert(node_id.clone(), node_result);
+                let restarted = !dynamic_node
+                    && self
+                        .restart_node(dataflow_id, &node_id, node_result.is_ok())
+                        .await?;
+                if !restarted {
+                    self.dataflow_node_results
+                        .entry(dataflow_id)
+                        .or_default()
+                        .insert(node_id.clone(), node_result);
 
-                self.handle_node_stop(dataflow_id, &node_id, dynamic_node)
-                    .await?;
+                    self.handle_node_stop(dataflow_id, &node_id, dynamic_node)
+                        .await?;
+                }
             }
         }
         Ok(())
     }
 
-    fn base_working_dir(
-        &self,
-        local_working_dir: Option<PathBuf>,
-        session_id: SessionId,
-    ) -> eyre::Result<PathBuf> {
-        match local_working_dir {
-            Some(working_dir) => {
-                // check that working directory exists
-                if working_dir.exists() {
-                    Ok(working_dir)
+    /// Stops the given local nodes and respawns them once they exited.
+    ///
+    /// The outputs of the nodes are kept open, so downstream nodes don't notice the restart.
+    /// Nodes that don't run on this daemon are ignored.
+    async fn request_restart(
+        &mut self,
+        dataflow_id: DataflowId,
+        node_ids: &BTreeSet<NodeId>,
+        grace_duration: Option<Duration>,
+    ) -> eyre::Result<()> {
+        let mut logger = self.logger.for_dataflow(dataflow_id);
+        let dataflow = self
+            .running
+            .get_mut(&dataflow_id)
+            .wrap_err_with(|| format!("no running dataflow with ID `{dataflow_id}`"))?;
+        if dataflow.stop_sent {
+            bail!("dataflow `{dataflow_id}` is stopping");
+        }
+        for node_id in node_ids {
+            if dataflow.dynamic_nodes.contains(node_id) {
+                bail!("node `{node_id}` is a dynamic node, which cannot be restarted");
+            }
+            let Some(restartable) = dataflow.restartable_nodes.get_mut(node_id) else {
+                continue;
+            };
+            if !dataflow.running_nodes.contains_key(node_id) {
+                bail!("node `{node_id}` is not running");
+            }
+            restartable.restart_requested = true;
+            logger
+                .log(
+                    LogLevel::Info,
+                    Some(node_id.clone()),
+                    Some("daemon".into()),
+                    "stopping node for restart",
+                )
+                .await;
+            dataflow.send_stop(node_id, &self.clock, grace_duration);
+        }
+        Ok(())
+    }
+
+    /// Returns whether the given node might be restarted after it exits.
+    ///
+    /// We don't close the outputs of such nodes when they exit to keep the
+    /// inputs of downstream nodes open.
+    fn restart_pending(&self, dataflow_id: DataflowId, node_id: &NodeId) -> bool {
+        self.running.get(&dataflow_id).is_some_and(|dataflow| {
+            !dataflow.stop_sent
+                && dataflow
+                    .restartable_nodes
+                    .get(node_id)
+                    .is_some_and(|n| n.restart_pending())
+        })
+    }
+
+    /// Respawns the given node if its restart policy allows it or if a restart was requested.
+    ///
+    /// Returns `false` if the node should not be restarted. In this case, the node
+    /// stop needs to be handled as usual.
+    async fn restart_node(
+        &mut self,
+        dataflow_id: DataflowId,
+        node_id: &NodeId,
+        success: bool,
+    ) -> eyre::Result<bool> {
+        let Some(dataflow) = self.running.get_mut(&dataflow_id) else {
+            return Ok(false);
+        };
+        let Some(restartable) = dataflow.restartable_nodes.get_mut(node_id) else {
+            return Ok(false);
+        };
+        let Some(spawner) = dataflow.spawner.clone() else {
+            return Ok(false);
+        };
+        if dataflow.stop_sent || dataflow.pending_nodes.is_pending(node_id) {
+            return Ok(false);
+        }
+        // a node whose inputs were all closed would exit immediately again
+        if dataflow
+            .open_inputs
+            .get(node_id)
+            .is_some_and(|inputs| inputs.is_empty())
+        {
+            return Ok(false);
+        }
+        let requested = restartable.restart_requested;
+        let killed = dataflow.grace_duration_kills.contains(node_id);
+        let Some(backoff) = restartable.next_restart(success, killed) else {
+            return Ok(false);
+        };
+
+        let (level, message) = if requested {
+            // the node was stopped on request, so it's not a failure if it was killed
+            dataflow.grace_duration_kills.remove(node_id);
+            (LogLevel::Info, "restarting node on request".to_owned())
+        } else {
+            let message = format!(
+                "restarting node in {backoff:?} (restart {}{})",
+                restartable.restarts,
+                match restartable.node.restart.max_retries {
+                    Some(max) => format!(" of {max}"),
+                    None => String::new(),
+                }
+            );
+            (LogLevel::Warn, message)
+        };
+        let node = restartable.node.clone();
+        let working_dir = restartable.working_dir.clone();
+
+        // the old node instance is gone -> remove its channels and release its drop tokens
+        dataflow.subscribe_channels.remove(node_id);
+        dataflow.drop_channels.remove(node_id);
+        dataflow.release_blocking_inputs(node_id);
+        let tokens: Vec<_> = dataflow
+            .pending_drop_tokens
+            .iter_mut()
+            .filter_map(|(token, info)| info.pending_nodes.remove(node_id).then_some(*token))
+            .collect();
+        for token in tokens {
+            dataflow.check_drop_token(token, &self.clock).await?;
+        }
+        if let Some(pid) = dataflow
+            .running_nodes
+            .get_mut(node_id)
+            .and_then(|n| n.pid.as_mut())
+        {
+            pid.mark_as_stopped();
+        }
+        let node_stderr_most_recent = dataflow
+            .node_stderr_most_recent
+            .entry(node_id.clone())
+            .or_insert_with(|| Arc::new(ArrayQueue::new(STDERR_LOG_LINES)))
+            .clone();
+
+        let mut logger = self
+            .logger
+            .for_dataflow(dataflow_id)
+            .for_node(node_id.clone())
+            .try_clone()
+            .await
+            .context("failed to clone logger")?;
+        logger.log(level, Some("daemon".into()), message).await;
+
+        let node_id = node_id.clone();
+        let events_tx = self.events_tx.clone();
+        let clock = self.clock.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(backoff).await;
+            let result = async {
+                let prepared = spawner
+                    .spawn_node(node, working_dir, node_stderr_most_recent, &mut logger)
+                    .await?
+                    .await?;
+                prepared.spawn(&mut logger).await
+            }
+            .await
+            .map_err(|err| NodeError {
+                timestamp: clock.new_timestamp(),
+                cause: NodeErrorCause::FailedToSpawn(format!("restart failed: {err:?}")),
+                exit_status: NodeExitStatus::Unknown,
+            });
+            let event = Timestamped {
+                inner: Event::SpawnNodeResult {
+                    dataflow_id,
+                    node_id,
+                    dynamic_node: false,
+                    result,
+                },
+                timestamp: clock.new_timestamp(),
+            };
+            if events_tx.send(event).await.is_err() {
+                tracing::error!("failed to send SpawnNodeResult to main daemon task")
+            }
+        });
+
+        Ok(true)
+    }
+
+    fn base_working_dir(
+        &self,
+        local_working_dir: Option<PathBuf>,
+        session_id: SessionId,
+    ) -> eyre::Result<PathBuf> {
+        match local_working_dir {
+            Some(working_dir) => {
+                // check that working directory exists
+                if working_dir.exists() {
+                    Ok(working_dir)
                 } else {
                     bail!(
                         "working directory does not exist: {}",
@@ -2189,9 +2999,28 @@ impl Daemon {
     }
 }
 
+/// Labels describing the system that the daemon runs on.
+fn system_labels() -> BTreeMap<String, String> {
+    let mut labels = BTreeMap::new();
+    labels.insert("arch".to_owned(), std::env::consts::ARCH.to_owned());
+    labels.insert("os".to_owned(), std::env::consts::OS.to_owned());
+    if let Ok(cpus) = std::thread::available_parallelism() {
+        labels.insert("cpus".to_owned(), cpus.to_string());
+    }
+    let mut system = sysinfo::System::new();
+    system.refresh_memory();
+    labels.insert(
+        "memory_mb".to_owned(),
+        (system.total_memory() / 1_000_000).to_string(),
+    );
+    labels
+}
+
 async fn set_up_event_stream(
     coordinator_addr: SocketAddr,
     machine_id: &Option<String>,
+    labels: &BTreeMap<String, String>,
+    security: &ClientSecurity,
     clock: &Arc<HLC>,
     remote_daemon_events_rx: flume::Receiver<eyre::Result<Timestamped<InterDaemonEvent>>>,
     // used for dynamic nodes
@@ -2208,10 +3037,16 @@ async fn set_up_event_stream(
             timestamp: clock_cloned.new_timestamp(),
         },
     });
-    let (daemon_id, coordinator_events) =
-        coordinator::register(coordinator_addr, machine_id.clone(), clock)
-            .await
-            .wrap_err("failed to connect to dora-coordinator")?;
+    let (daemon_id, coordinator_events) = coordinator::register(
+        coordinator_addr,
+        machine_id.clone(),
+        labels.clone(),
+        None,
+        security,
+        clock,
+    )
+    .await
+    .wrap_err("failed to connect to dora-coordinator")?;
     let coordinator_events = coordinator_events.map(
         |Timestamped {
              inner: event,
@@ -2248,9 +3083,23 @@ async fn send_output_to_local_receivers(
 ) -> Result<Option<AVec<u8, ConstAlign<128>>>, eyre::ErrReport> {
     let timestamp = metadata.timestamp();
     let empty_set = BTreeSet::new();
-    let output_id = OutputId(node_id, output_id);
+    // outputs of replicas are sent under the ID of the replicated node
+    let output_id = OutputId(
+        dataflow.replicas.output_node_id(&node_id).clone(),
+        output_id,
+    );
     let local_receivers = dataflow.mappings.get(&output_id).unwrap_or(&empty_set);
-    let OutputId(node_id, _) = output_id;
+    let local_receivers = dataflow.replicas.select_receivers(
+        local_receivers,
+        |(id, input)| {
+            dataflow.subscribe_channels.contains_key(id)
+                && dataflow
+                    .open_inputs
+                    .get(id)
+                    .is_some_and(|i| i.contains(input))
+        },
+        |r| dataflow.blocking_inputs.get(r).is_some_and(|q| q.is_full()),
+    );
     let mut closed = Vec::new();
     for (receiver_id, input_id) in local_receivers {
         if let Some(channel) = dataflow.subscribe_channels.get(receiver_id) {
@@ -2264,6 +3113,12 @@ async fn send_output_to_local_receivers(
                 timestamp,
             }) {
                 Ok(()) => {
+                    if let Some(queue) = dataflow
+                        .blocking_inputs
+                        .get_mut(&(receiver_id.clone(), input_id.clone()))
+                    {
+                        queue.in_flight += 1;
+                    }
                     if let Some(token) = data.as_ref().and_then(|d| d.drop_token()) {
                         dataflow
                             .pending_drop_tokens
@@ -2316,6 +3171,47 @@ async fn send_output_to_local_receivers(
     Ok(data_bytes)
 }
 
+/// Forwards the inter-daemon events received by the given subscriber to the daemon until the
+/// dataflow is finished.
+fn spawn_zenoh_subscriber_task(
+    subscriber: zenoh::pubsub::Subscriber<
+        zenoh::handlers::FifoChannelHandler<zenoh::sample::Sample>,
+    >,
+    mut finished_rx: broadcast::Receiver<()>,
+    tx: flume::Sender<eyre::Result<Timestamped<InterDaemonEvent>>>,
+) {
+    tokio::spawn(async move {
+        let mut finished = pin!(finished_rx.recv());
+        loop {
+            let finished_or_next = futures::future::select(finished, subscriber.recv_async());
+            match finished_or_next.await {
+                future::Either::Left((finished, _)) => match finished {
+                    Err(broadcast::error::RecvError::Closed) => {
+                        tracing::debug!("dataflow finished, breaking from zenoh subscribe task");
+                        break;
+                    }
+                    other => {
+                        tracing::warn!(
+                            "unexpected return value of dataflow finished_rx channel: {other:?}"
+                        );
+                        break;
+                    }
+                },
+                future::Either::Right((sample, f)) => {
+                    finished = f;
+                    let event = sample.map_err(|e| eyre!(e)).and_then(|s| {
+                        Timestamped::deserialize_inter_daemon_event(&s.payload().to_bytes())
+                    });
+                    if tx.send_async(event).await.is_err() {
+                        // daemon finished
+                        break;
+                    }
+                }
+            }
+        }
+    });
+}
+
 fn node_inputs(node: &ResolvedNode) -> BTreeMap<DataId, Input> {
     match &node.kind {
         CoreNodeKind::Custom(n) => n.run_config.inputs.clone(),
@@ -2403,6 +3299,8 @@ pub struct RunningDataflow {
     subscribe_channels: HashMap<NodeId, UnboundedSender<Timestamped<NodeEvent>>>,
     drop_channels: HashMap<NodeId, UnboundedSender<Timestamped<NodeDropEvent>>>,
     mappings: HashMap<OutputId, BTreeSet<InputId>>,
+    /// Local replicas of replicated nodes, which share their inputs and outputs.
+    replicas: ReplicaGroups,
     timers: BTreeMap<Duration, BTreeSet<InputId>>,
     open_inputs: BTreeMap<NodeId, BTreeSet<DataId>>,
     running_nodes: BTreeMap<NodeId, RunningNode>,
@@ -2433,10 +3331,109 @@ pub struct RunningDataflow {
     node_stderr_most_recent: BTreeMap<NodeId, Arc<ArrayQueue<String>>>,
 
     publishers: BTreeMap<OutputId, zenoh::pubsub::Publisher<'static>>,
+    /// Tracks whether there are zenoh subscribers for the outputs of local nodes.
+    ///
+    /// Outputs with subscribers are published even if no remote node is connected to
+    /// them, e.g. for `dora topic echo`.
+    output_subscribers: BTreeMap<OutputId, Arc<AtomicBool>>,
 
     finished_tx: broadcast::Sender<()>,
 
     publish_all_messages_to_zenoh: bool,
+    /// Whether messages can be injected into the inputs of local nodes, e.g. by
+    /// `dora topic pub`.
+    allow_input_injection: bool,
+
+    /// Writes all outputs to disk if recording is enabled for this dataflow.
+    recorder: Option<Recorder>,
+
+    /// Local nodes that are not dynamic, together with the information needed to respawn
+    /// them.
+    restartable_nodes: BTreeMap<NodeId, RestartableNode>,
+    /// Used to respawn nodes, e.g. according to their restart policy.
+    spawner: Option<Spawner>,
+
+    /// Local nodes that were added while the dataflow was running.
+    added_nodes: BTreeSet<NodeId>,
+
+    /// Local inputs with a [`QueuePolicy::Block`] queue policy.
+    blocking_inputs: BTreeMap<InputId, BlockingInput>,
+    /// Senders that are waiting for room in the queues of blocking inputs.
+    blocked_senders: Vec<BlockedSender>,
+
+    /// Declared data types of the outputs of local nodes, by sending node.
+    output_types: BTreeMap<OutputId, DataTypeSpec>,
+}
+
+struct BlockingInput {
+    queue_size: usize,
+    /// Number of inputs that were sent to the receiver, but not consumed yet.
+    in_flight: usize,
+}
+
+impl BlockingInput {
+    fn is_full(&self) -> bool {
+        self.in_flight >= self.queue_size
+    }
+}
+
+struct BlockedSender {
+    /// The blocking inputs that were full when the message was sent.
+    full_inputs: BTreeSet<InputId>,
+    reply_sender: oneshot::Sender<DaemonReply>,
+}
+
+struct RestartableNode {
+    node: ResolvedNode,
+    working_dir: PathBuf,
+    /// Number of times the node was restarted according to its restart policy.
+    restarts: u32,
+    /// Whether the node was spawned again after it exited.
+    respawned: bool,
+    /// Whether a restart of the node was requested, e.g. through `dora restart`.
+    ///
+    /// Such nodes are restarted when they exit, independent of their restart policy.
+    restart_requested: bool,
+}
+
+impl RestartableNode {
+    fn new(node: ResolvedNode, working_dir: PathBuf) -> Self {
+        Self {
+            node,
+            working_dir,
+            restarts: 0,
+            respawned: false,
+            restart_requested: false,
+        }
+    }
+
+    /// Whether the node might be restarted after it exits.
+    fn restart_pending(&self) -> bool {
+        self.restart_requested || self.node.restart.mode != RestartMode::Never
+    }
+
+    /// Decides whether the node should be respawned after it exited with the given status.
+    ///
+    /// Returns the backoff duration before the restart, or `None` if the node should not be
+    /// restarted. Requested restarts happen immediately and don't count towards the restart
+    /// policy. Otherwise, nodes that were `killed` after their grace duration are not
+    /// restarted.
+    fn next_restart(&mut self, success: bool, killed: bool) -> Option<Duration> {
+        let backoff = if self.restart_requested {
+            self.restart_requested = false;
+            Duration::ZERO
+        } else {
+            let policy = &self.node.restart;
+            if killed || !policy.should_restart(success, self.restarts) {
+                return None;
+            }
+            let backoff = policy.backoff(self.restarts);
+            self.restarts += 1;
+            backoff
+        };
+        self.respawned = true;
+        Some(backoff)
+    }
 }
 
 impl RunningDataflow {
@@ -2452,6 +3449,7 @@ impl RunningDataflow {
             subscribe_channels: HashMap::new(),
             drop_channels: HashMap::new(),
             mappings: HashMap::new(),
+            replicas: Default::default(),
             timers: BTreeMap::new(),
             open_inputs: BTreeMap::new(),
             running_nodes: BTreeMap::new(),
@@ -2465,8 +3463,17 @@ impl RunningDataflow {
             grace_duration_kills: Default::default(),
             node_stderr_most_recent: BTreeMap::new(),
             publishers: Default::default(),
+            output_subscribers: Default::default(),
             finished_tx,
             publish_all_messages_to_zenoh: dataflow_descriptor.debug.publish_all_messages_to_zenoh,
+            allow_input_injection: dataflow_descriptor.debug.allow_input_injection,
+            recorder: None,
+            restartable_nodes: BTreeMap::new(),
+            added_nodes: BTreeSet::new(),
+            spawner: None,
+            blocking_inputs: BTreeMap::new(),
+            blocked_senders: Vec::new(),
+            output_types: BTreeMap::new(),
         }
     }
 
@@ -2502,7 +3509,7 @@ impl RunningDataflow {
 
                     let metadata = metadata::Metadata::from_parameters(
                         hlc.new_timestamp(),
-                        empty_type_info(),
+                        ArrowTypeInfo::empty(),
                         parameters,
                     );
 
@@ -2530,7 +3537,7 @@ impl RunningDataflow {
 
     async fn stop_all(
         &mut self,
-        coordinator_connection: &mut Option<TcpStream>,
+        coordinator_connection: &mut Option<MaybeTlsStream>,
         clock: &HLC,
         grace_duration: Option<Duration>,
         logger: &mut DataflowLogger<'_>,
@@ -2572,6 +3579,136 @@ impl RunningDataflow {
             }
         });
         self.stop_sent = true;
+        self.unblock_senders();
+        Ok(())
+    }
+
+    /// Acknowledges the sent message right away if all blocking receivers of the output have
+    /// room in their queues. Otherwise, the sender is blocked until the receivers catch up.
+    fn apply_backpressure(
+        &mut self,
+        output_id: &OutputId,
+        reply_sender: oneshot::Sender<DaemonReply>,
+    ) {
+        let full_inputs: BTreeSet<_> = self
+            .mappings
+            .get(output_id)
+            .into_iter()
+            .flatten()
+            .filter(|(receiver_id, input_id)| {
+                // replicated receivers only block if the queues of all replicas are full
+                std::iter::once(receiver_id)
+                    .chain(self.replicas.siblings(receiver_id))
+                    .all(|id| {
+                        self.blocking_inputs
+                            .get(&(id.clone(), input_id.clone()))
+                            .is_some_and(|q| q.is_full())
+                    })
+            })
+            .cloned()
+            .collect();
+        if full_inputs.is_empty() || self.stop_sent {
+            let _ = reply_sender.send(DaemonReply::Result(Ok(())));
+        } else {
+            self.blocked_senders.push(BlockedSender {
+                full_inputs,
+                reply_sender,
+            });
+        }
+    }
+
+    /// Unblocks all senders whose receivers have room in their queues again.
+    fn unblock_senders(&mut self) {
+        let (unblocked, blocked) = mem::take(&mut self.blocked_senders)
+            .into_iter()
+            .partition::<Vec<_>, _>(|sender| {
+                self.stop_sent
+                    || sender
+                        .full_inputs
+                        .iter()
+                        .all(|input| !self.blocking_inputs.get(input).is_some_and(|q| q.is_full()))
+            });
+        self.blocked_senders = blocked;
+        for sender in unblocked {
+            let _ = sender.reply_sender.send(DaemonReply::Result(Ok(())));
+        }
+    }
+
+    /// Stops the given local node because it was removed from the dataflow.
+    ///
+    /// The node receives no further inputs and is killed if it doesn't stop within the
+    /// grace duration. Nodes that don't run on this daemon are ignored.
+    fn stop_node(&mut self, node_id: &NodeId, clock: &HLC, grace_duration: Option<Duration>) {
+        for receivers in self.mappings.values_mut().chain(self.timers.values_mut()) {
+            receivers.retain(|(receiver_id, _)| receiver_id != node_id);
+        }
+        self.blocking_inputs
+            .retain(|(receiver_id, _), _| receiver_id != node_id);
+        self.output_types
+            .retain(|OutputId(sender_id, _), _| sender_id != node_id);
+        self.restartable_nodes.remove(node_id);
+        self.send_stop(node_id, clock, grace_duration);
+        self.unblock_senders();
+    }
+
+    /// Asks the given local node to stop and kills it if it doesn't exit within the grace
+    /// duration.
+    fn send_stop(&mut self, node_id: &NodeId, clock: &HLC, grace_duration: Option<Duration>) {
+        if let Some(channel) = self.subscribe_channels.remove(node_id) {
+            let _ = send_with_timestamp(&channel, NodeEvent::Stop, clock);
+        }
+        if let Some(mut pid) = self
+            .running_nodes
+            .get_mut(node_id)
+            .and_then(|n| n.pid.take())
+        {
+            let grace_duration_kills = self.grace_duration_kills.clone();
+            let node_id = node_id.clone();
+            tokio::spawn(async move {
+                let duration = grace_duration.unwrap_or(Duration::from_millis(15000));
+                tokio::time::sleep(duration).await;
+
+                if pid.kill() {
+                    grace_duration_kills.insert(node_id.clone());
+                    warn!(
+                        "{node_id} was killed due to not stopping within the {:#?} grace period",
+                        duration
+                    )
+                }
+            });
+        }
+    }
+
+    /// Resets the blocking input queues of the given node, e.g. because the node stopped.
+    fn release_blocking_inputs(&mut self, node_id: &NodeId) {
+        for ((receiver_id, _), queue) in &mut self.blocking_inputs {
+            if receiver_id == node_id {
+                queue.in_flight = 0;
+            }
+        }
+        self.unblock_senders();
+    }
+
+    /// Checks the data type of an output message against the declared type of the output.
+    fn check_output_type(
+        &self,
+        node_id: &NodeId,
+        output_id: &DataId,
+        metadata: &metadata::Metadata,
+    ) -> eyre::Result<()> {
+        let Some(expected) = self
+            .output_types
+            .get(&OutputId(node_id.clone(), output_id.clone()))
+        else {
+            return Ok(());
+        };
+        let actual = &metadata.type_info.data_type;
+        if !expected.matches(actual) {
+            bail!(
+                "output `{node_id}/{output_id}` has type `{actual}`, but the dataflow \
+                declares type `{expected}`"
+            );
+        }
         Ok(())
     }
 
@@ -2611,23 +3748,174 @@ impl RunningDataflow {
         Ok(())
     }
 
+    /// Registers the input mappings of the given node.
+    ///
+    /// For local nodes, the mapped outputs with a [`QueuePolicy::Block`] queue policy are
+    /// added to `blocking_outputs` and the declared output types are recorded. For remote
+    /// nodes, only the local outputs that they are mapped to are recorded.
+    fn add_mappings(
+        &mut self,
+        node: &ResolvedNode,
+        local: bool,
+        blocking_outputs: &mut BTreeSet<OutputId>,
+    ) {
+        if local {
+            self.replicas.insert(node);
+            for output in node.kind.run_config().outputs {
+                if let Some(data_type) = output.data_type {
+                    self.output_types
+                        .insert(OutputId(node.id.clone(), output.id), data_type);
+                }
+            }
+        }
+
+        let inputs = node_inputs(node);
+        for (input_id, input) in inputs {
+            if local {
+                self.open_inputs
+                    .entry(node.id.clone())
+                    .or_default()
+                    .insert(input_id.clone());
+                if self.allow_input_injection {
+                    let output_id = OutputId(
+                        NodeId::from(INJECTED_INPUTS_SOURCE.to_owned()),
+                        injected_input_output_id(&node.id, &input_id),
+                    );
+                    self.mappings
+                        .entry(output_id)
+                        .or_default()
+                        .insert((node.id.clone(), input_id.clone()));
+                }
+                match input.mapping {
+                    InputMapping::User(mapping) => {
+                        let output_id = OutputId(mapping.source, mapping.output);
+                        if input.queue_policy == Some(QueuePolicy::Block) {
+                            self.blocking_inputs.insert(
+                                (node.id.clone(), input_id.clone()),
+                                BlockingInput {
+                                    queue_size: input.queue_size.unwrap_or(10),
+                                    in_flight: 0,
+                                },
+                            );
+                            blocking_outputs.insert(output_id.clone());
+                        }
+                        self.mappings
+                            .entry(output_id)
+                            .or_default()
+                            .insert((node.id.clone(), input_id));
+                    }
+                    InputMapping::Timer { interval } => {
+                        self.timers
+                            .entry(interval)
+                            .or_default()
+                            .insert((node.id.clone(), input_id));
+                    }
+                }
+            } else if let InputMapping::User(mapping) = input.mapping {
+                self.open_external_mappings
+                    .insert(OutputId(mapping.source, mapping.output));
+            }
+        }
+    }
+
+    /// Subscribes to inputs that are injected into the given local node, e.g. by
+    /// `dora topic pub`.
+    ///
+    /// Does nothing if the dataflow does not allow input injection. Injected messages are
+    /// delivered through the mappings of the [`INJECTED_INPUTS_SOURCE`] pseudo node.
+    async fn subscribe_to_injected_inputs(
+        &self,
+        zenoh_session: &zenoh::Session,
+        remote_daemon_events_tx: Option<flume::Sender<eyre::Result<Timestamped<InterDaemonEvent>>>>,
+        node_id: &NodeId,
+    ) -> eyre::Result<()> {
+        let Some(tx) = remote_daemon_events_tx.filter(|_| self.allow_input_injection) else {
+            return Ok(());
+        };
+        let finished_rx = self.finished_tx.subscribe();
+        let subscribe_topic = self.input_subscribe_topic(node_id);
+        tracing::debug!("declaring subscriber on {subscribe_topic}");
+        let subscriber = zenoh_session
+            .declare_subscriber(subscribe_topic)
+            .await
+            .map_err(|e| eyre!(e))
+            .wrap_err_with(|| format!("failed to subscribe to inputs of `{node_id}`"))?;
+        spawn_zenoh_subscriber_task(subscriber, finished_rx, tx);
+        Ok(())
+    }
+
+    /// Subscribes to the given output of a remote node.
+    async fn subscribe_to_remote_output(
+        &self,
+        zenoh_session: &zenoh::Session,
+        remote_daemon_events_tx: Option<flume::Sender<eyre::Result<Timestamped<InterDaemonEvent>>>>,
+        output_id: &OutputId,
+    ) -> eyre::Result<()> {
+        let tx = remote_daemon_events_tx.wrap_err("no remote_daemon_events_tx channel")?;
+        let finished_rx = self.finished_tx.subscribe();
+        let subscribe_topic = self.output_publish_topic(output_id);
+        tracing::debug!("declaring subscriber on {subscribe_topic}");
+        let subscriber = zenoh_session
+            .declare_subscriber(subscribe_topic)
+            .await
+            .map_err(|e| eyre!(e))
+            .wrap_err_with(|| format!("failed to subscribe to {output_id:?}"))?;
+        spawn_zenoh_subscriber_task(subscriber, finished_rx, tx);
+        Ok(())
+    }
+
+    /// Declares zenoh publishers for all outputs of the given local node.
+    ///
+    /// This allows outputs to be subscribed on demand. The outputs are only published when
+    /// there are matching subscribers.
+    async fn declare_output_publishers(
+        &mut self,
+        zenoh_session: &zenoh::Session,
+        node: &ResolvedNode,
+    ) -> eyre::Result<()> {
+        for output in node.kind.run_config().outputs {
+            let output_id = OutputId(node.output_node_id().clone(), output.id);
+            if self.publishers.contains_key(&output_id) {
+                continue;
+            }
+            let publish_topic = self.output_publish_topic(&output_id);
+            tracing::debug!("declaring publisher on {publish_topic}");
+            let publisher = zenoh_session
+                .declare_publisher(publish_topic)
+                .await
+                .map_err(|e| eyre!(e))
+                .context("failed to create zenoh publisher")?;
+            let subscribed = Arc::new(AtomicBool::new(false));
+            publisher
+                .matching_listener()
+                .callback({
+                    let subscribed = subscribed.clone();
+                    move |status| subscribed.store(status.matching(), atomic::Ordering::Relaxed)
+                })
+                .background()
+                .await
+                .map_err(|e| eyre!(e))
+                .context("failed to declare zenoh matching listener")?;
+            self.publishers.insert(output_id.clone(), publisher);
+            self.output_subscribers.insert(output_id, subscribed);
+        }
+        Ok(())
+    }
+
+    fn output_has_subscribers(&self, output_id: &OutputId) -> bool {
+        self.output_subscribers
+            .get(output_id)
+            .is_some_and(|s| s.load(atomic::Ordering::Relaxed))
+    }
+
     fn output_publish_topic(&self, output_id: &OutputId) -> String {
-        let network_id = "default";
-        let dataflow_id = self.id;
         let OutputId(node_id, output_id) = output_id;
-        format!("dora/{network_id}/{dataflow_id}/output/{node_id}/{output_id}")
+        zenoh_output_publish_topic(self.id, node_id, output_id)
     }
-}
 
-fn empty_type_info() -> ArrowTypeInfo {
-    ArrowTypeInfo {
-        data_type: DataType::Null,
-        len: 0,
-        null_count: 0,
-        validity: None,
-        offset: 0,
-        buffer_offsets: Vec::new(),
-        child_data: Vec::new(),
+    /// Key expression that matches all inputs that are injected into the given node.
+    fn input_subscribe_topic(&self, node_id: &NodeId) -> String {
+        zenoh_input_publish_topic(self.id, node_id, &DataId::from("**".to_owned()))
     }
 }
 
@@ -2651,6 +3939,11 @@ pub enum Event {
         event: DaemonNodeEvent,
     },
     Coordinator(CoordinatorEvent),
+    /// New connections to the coordinator after the previous ones were lost.
+    CoordinatorReconnected {
+        connection: MaybeTlsStream,
+        log_connection: MaybeTlsStream,
+    },
     Daemon(InterDaemonEvent),
     Dora(DoraEvent),
     DynamicNode(DynamicNodeEventWrapper),
@@ -2677,6 +3970,10 @@ pub enum Event {
         dataflow_id: Uuid,
         node_id: NodeId,
     },
+    /// The record files of a finished dataflow were written completely.
+    RecordingFinished {
+        dataflow_id: Uuid,
+    },
 }
 
 impl From<DoraEvent> for Event {
@@ -2690,6 +3987,7 @@ impl Event {
         match self {
             Event::Node { .. } => "Node",
             Event::Coordinator(_) => "Coordinator",
+            Event::CoordinatorReconnected { .. } => "CoordinatorReconnected",
             Event::Daemon(_) => "Daemon",
             Event::Dora(_) => "Dora",
             Event::DynamicNode(_) => "DynamicNode",
@@ -2701,6 +3999,7 @@ impl Event {
             Event::BuildDataflowResult { .. } => "BuildDataflowResult",
             Event::SpawnDataflowResult { .. } => "SpawnDataflowResult",
             Event::NodeStopped { .. } => "NodeStopped",
+            Event::RecordingFinished { .. } => "RecordingFinished",
         }
     }
 }
@@ -2727,13 +4026,27 @@ pub enum DaemonNodeEvent {
         output_id: DataId,
         metadata: metadata::Metadata,
         data: Option<DataMessage>,
+        /// Set if the output is mapped to inputs with a blocking queue policy.
+        ///
+        /// The reply is delayed until all blocking receivers have room in their queues.
+        reply_sender: Option<oneshot::Sender<DaemonReply>>,
     },
     ReportDrop {
         tokens: Vec<DropToken>,
     },
+    InputsConsumed {
+        inputs: Vec<DataId>,
+    },
+    InputStats {
+        inputs: BTreeMap<DataId, InputStats>,
+    },
     EventStreamDropped {
         reply_sender: oneshot::Sender<DaemonReply>,
     },
+    StopDataflow {
+        reason: Option<String>,
+        reply_sender: oneshot::Sender<DaemonReply>,
+    },
 }
 
 #[derive(Debug)]
@@ -2754,6 +4067,8 @@ pub enum DoraEvent {
         node_id: NodeId,
         dynamic_node: bool,
         exit_status: NodeExitStatus,
+        /// Set if the node exceeded one of its resource limits.
+        exceeded_limit: Option<ResourceLimit>,
     },
 }
 
@@ -2840,15 +4155,14 @@ fn runtime_node_inputs(n: &RuntimeNode) -> BTreeMap<DataId, Input> {
         .collect()
 }
 
-fn runtime_node_outputs(n: &RuntimeNode) -> BTreeSet<DataId> {
+fn runtime_node_outputs(n: &RuntimeNode) -> BTreeSet<Output> {
     n.operators
         .iter()
         .flat_map(|operator| {
-            operator
-                .config
-                .outputs
-                .iter()
-                .map(|output_id| DataId::from(format!("{}/{output_id}", operator.id)))
+            operator.config.outputs.iter().map(|output| Output {
+                id: DataId::from(format!("{}/{}", operator.id, output.id)),
+                data_type: output.data_type.clone(),
+            })
         })
         .collect()
 }
@@ -2878,3 +4192,310 @@ impl CoreNodeKindExt for CoreNodeKind {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use dora_core::descriptor::DescriptorExt;
+    use dora_message::descriptor::RestartPolicy;
+    use dora_node_api::arrow::datatypes::DataType;
+
+    use super::*;
+
+    const DATAFLOW: &str = r#"
+nodes:
+  - id: camera
+    path: camera
+    outputs: [image]
+  - id: detector
+    path: detector
+    inputs:
+      image:
+        source: camera/image
+        queue_size: 2
+        queue_policy: block
+      tick: dora/timer/millis/100
+    outputs:
+      - id: boxes
+        type: {list: float32}
+  - id: plot
+    path: plot
+    inputs:
+      boxes: detector/boxes
+"#;
+
+    fn running_dataflow() -> (RunningDataflow, BTreeMap<NodeId, ResolvedNode>) {
+        let descriptor = Descriptor::parse(DATAFLOW.as_bytes().to_vec()).unwrap();
+        let nodes = descriptor.resolve_aliases_and_set_defaults().unwrap();
+        let dataflow = RunningDataflow::new(Uuid::new_v4(), DaemonId::new(None), &descriptor);
+        (dataflow, nodes)
+    }
+
+    fn output(node: &str, output: &str) -> OutputId {
+        OutputId(node.to_owned().into(), output.to_owned().into())
+    }
+
+    fn receiver(node: &str, input: &str) -> (NodeId, DataId) {
+        (node.to_owned().into(), input.to_owned().into())
+    }
+
+    #[test]
+    fn add_mappings_of_local_node() {
+        let (mut dataflow, nodes) = running_dataflow();
+        let mut blocking_outputs = BTreeSet::new();
+        dataflow.add_mappings(
+            &nodes[&NodeId::from("detector".to_owned())],
+            true,
+            &mut blocking_outputs,
+        );
+
+        assert!(
+            dataflow.mappings[&output("camera", "image")].contains(&receiver("detector", "image"))
+        );
+        assert!(
+            dataflow.timers[&Duration::from_millis(100)].contains(&receiver("detector", "tick"))
+        );
+        assert_eq!(dataflow.open_inputs(&"detector".to_owned().into()).len(), 2);
+        assert_eq!(
+            blocking_outputs,
+            BTreeSet::from([output("camera", "image")])
+        );
+        let blocking = &dataflow.blocking_inputs[&receiver("detector", "image")];
+        assert_eq!(blocking.queue_size, 2);
+        assert!(dataflow.open_external_mappings.is_empty());
+    }
+
+    #[test]
+    fn add_mappings_of_remote_node() {
+        let (mut dataflow, nodes) = running_dataflow();
+        let mut blocking_outputs = BTreeSet::new();
+        dataflow.add_mappings(
+            &nodes[&NodeId::from("plot".to_owned())],
+            false,
+            &mut blocking_outputs,
+        );
+
+        assert!(dataflow.mappings.is_empty());
+        assert!(dataflow.open_inputs.is_empty());
+        assert!(
+            dataflow
+                .open_external_mappings
+                .contains(&output("detector", "boxes"))
+        );
+    }
+
+    #[test]
+    fn stop_node_removes_mappings() {
+        let (mut dataflow, nodes) = running_dataflow();
+        let mut blocking_outputs = BTreeSet::new();
+        for node in nodes.values() {
+            dataflow.add_mappings(node, true, &mut blocking_outputs);
+        }
+
+        dataflow.stop_node(&"detector".to_owned().into(), &HLC::default(), None);
+
+        let receivers: Vec<_> = dataflow
+            .mappings
+            .values()
+            .chain(dataflow.timers.values())
+            .flatten()
+            .collect();
+        assert_eq!(receivers, [&receiver("plot", "boxes")]);
+        assert!(dataflow.blocking_inputs.is_empty());
+        assert!(dataflow.output_types.is_empty());
+    }
+
+    #[test]
+    fn reject_outputs_with_undeclared_type() {
+        let (mut dataflow, nodes) = running_dataflow();
+        let mut blocking_outputs = BTreeSet::new();
+        for node in nodes.values() {
+            dataflow.add_mappings(node, true, &mut blocking_outputs);
+        }
+        let metadata = |data_type| {
+            let type_info = ArrowTypeInfo {
+                data_type,
+                ..ArrowTypeInfo::empty()
+            };
+            metadata::Metadata::new(HLC::default().new_timestamp(), type_info)
+        };
+        let list = |item| DataType::new_list(item, true);
+        let detector = NodeId::from("detector".to_owned());
+        let boxes = DataId::from("boxes".to_owned());
+
+        dataflow
+            .check_output_type(&detector, &boxes, &metadata(list(DataType::Float32)))
+            .unwrap();
+        let err = dataflow
+            .check_output_type(&detector, &boxes, &metadata(list(DataType::UInt8)))
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("declares type `list<float32>`"),
+            "{err}"
+        );
+        assert!(
+            dataflow
+                .check_output_type(&detector, &boxes, &metadata(DataType::Float32))
+                .is_err()
+        );
+
+        // outputs without declared type accept all data types
+        let camera = NodeId::from("camera".to_owned());
+        dataflow
+            .check_output_type(
+                &camera,
+                &"image".to_owned().into(),
+                &metadata(DataType::Utf8),
+            )
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn deliver_injected_inputs_through_mappings() {
+        let injected = |node: &str, input: &str| {
+            OutputId(
+                INJECTED_INPUTS_SOURCE.to_owned().into(),
+                injected_input_output_id(&node.to_owned().into(), &input.to_owned().into()),
+            )
+        };
+        let (mut dataflow, nodes) = running_dataflow();
+        let detector = &nodes[&NodeId::from("detector".to_owned())];
+        dataflow.add_mappings(detector, true, &mut BTreeSet::new());
+        // input injection is disabled by default
+        assert!(
+            !dataflow
+                .mappings
+                .contains_key(&injected("detector", "tick"))
+        );
+
+        let (mut dataflow, _) = running_dataflow();
+        dataflow.allow_input_injection = true;
+        dataflow.add_mappings(detector, true, &mut BTreeSet::new());
+        assert_eq!(
+            dataflow.mappings[&injected("detector", "tick")],
+            [receiver("detector", "tick")].into()
+        );
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        dataflow.subscribe_channels.insert(detector.id.clone(), tx);
+        let clock = HLC::default();
+        let metadata = metadata::Metadata::new(clock.new_timestamp(), ArrowTypeInfo::empty());
+        let OutputId(source, output_id) = injected("detector", "tick");
+        send_output_to_local_receivers(source, output_id, &mut dataflow, &metadata, None, &clock)
+            .await
+            .unwrap();
+
+        let event = rx.try_recv().unwrap().inner;
+        assert!(matches!(event, NodeEvent::Input { id, .. } if id.as_str() == "tick"));
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn block_senders_until_inputs_are_consumed() {
+        let (mut dataflow, nodes) = running_dataflow();
+        for node in nodes.values() {
+            dataflow.add_mappings(node, true, &mut BTreeSet::new());
+        }
+        let (tx, _rx) = mpsc::unbounded_channel();
+        dataflow
+            .subscribe_channels
+            .insert("detector".to_owned().into(), tx);
+        let clock = HLC::default();
+        let metadata = metadata::Metadata::new(clock.new_timestamp(), ArrowTypeInfo::empty());
+        for _ in 0..2 {
+            send_output_to_local_receivers(
+                "camera".to_owned().into(),
+                "image".to_owned().into(),
+                &mut dataflow,
+                &metadata,
+                None,
+                &clock,
+            )
+            .await
+            .unwrap();
+        }
+        assert!(dataflow.blocking_inputs[&receiver("detector", "image")].is_full());
+
+        // outputs without full blocking receivers are acknowledged right away
+        let (reply_tx, mut reply_rx) = oneshot::channel();
+        dataflow.apply_backpressure(&output("detector", "boxes"), reply_tx);
+        assert!(matches!(
+            reply_rx.try_recv(),
+            Ok(DaemonReply::Result(Ok(())))
+        ));
+
+        let (reply_tx, mut reply_rx) = oneshot::channel();
+        dataflow.apply_backpressure(&output("camera", "image"), reply_tx);
+        assert!(reply_rx.try_recv().is_err());
+
+        // the receiver consumed one of the queued inputs
+        dataflow
+            .blocking_inputs
+            .get_mut(&receiver("detector", "image"))
+            .unwrap()
+            .in_flight -= 1;
+        dataflow.unblock_senders();
+        assert!(matches!(
+            reply_rx.try_recv(),
+            Ok(DaemonReply::Result(Ok(())))
+        ));
+    }
+
+    fn restartable(restart: RestartPolicy) -> RestartableNode {
+        let (_, nodes) = running_dataflow();
+        let mut node = nodes[&NodeId::from("detector".to_owned())].clone();
+        node.restart = restart;
+        RestartableNode::new(node, PathBuf::new())
+    }
+
+    #[test]
+    fn restart_pending() {
+        assert!(!restartable(RestartPolicy::new(RestartMode::Never)).restart_pending());
+        assert!(restartable(RestartPolicy::new(RestartMode::OnFailure)).restart_pending());
+        assert!(restartable(RestartPolicy::new(RestartMode::Always)).restart_pending());
+
+        let mut node = restartable(RestartPolicy::new(RestartMode::Never));
+        node.restart_requested = true;
+        assert!(node.restart_pending());
+    }
+
+    #[test]
+    fn restart_according_to_policy() {
+        let mut node = restartable(RestartPolicy {
+            max_retries: Some(2),
+            ..RestartPolicy::new(RestartMode::OnFailure)
+        });
+        assert_eq!(node.next_restart(true, false), None);
+        assert!(!node.respawned);
+
+        assert_eq!(
+            node.next_restart(false, false),
+            Some(Duration::from_millis(500))
+        );
+        assert_eq!(node.restarts, 1);
+        assert!(node.respawned);
+        assert_eq!(node.next_restart(false, true), None);
+        assert_eq!(
+            node.next_restart(false, false),
+            Some(Duration::from_secs(1))
+        );
+        assert_eq!(node.restarts, 2);
+        assert_eq!(node.next_restart(false, false), None);
+        assert_eq!(node.restarts, 2);
+    }
+
+    #[test]
+    fn requested_restart_ignores_policy() {
+        let mut node = restartable(RestartPolicy {
+            max_retries: Some(0),
+            ..RestartPolicy::new(RestartMode::Never)
+        });
+        node.restart_requested = true;
+
+        assert_eq!(node.next_restart(false, true), Some(Duration::ZERO));
+        assert_eq!(node.restarts, 0);
+        assert!(node.respawned);
+        assert!(!node.restart_requested);
+        assert!(!node.restart_pending());
+        assert_eq!(node.next_restart(false, false), None);
+    }
+}
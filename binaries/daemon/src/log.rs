@@ -4,6 +4,7 @@ use std::{
     sync::Arc,
 };
 
+use dora_core::security::{ClientSecurity, MaybeTlsStream};
 use dora_core::{
     build::{BuildLogger, LogLevelOrStdout},
     config::NodeId,
@@ -16,7 +17,6 @@ use dora_message::{
 };
 use eyre::Context;
 use flume::Sender;
-use tokio::net::TcpStream;
 use uuid::Uuid;
 
 use crate::socket_stream_utils::socket_stream_send;
@@ -172,6 +172,11 @@ impl DaemonLogger {
         &self.logger
     }
 
+    /// Replaces the destination of log messages, e.g. after reconnecting to the coordinator.
+    pub fn set_destination(&mut self, destination: LogDestination) {
+        self.logger.destination = destination;
+    }
+
     pub async fn log(
         &mut self,
         level: LogLevel,
@@ -248,6 +253,7 @@ impl Logger {
         match &mut self.destination {
             LogDestination::Coordinator {
                 coordinator_connection,
+                ..
             } => {
                 let message = Timestamped {
                     inner: CoordinatorRequest::Event {
@@ -341,15 +347,17 @@ impl Logger {
         let destination = match &self.destination {
             LogDestination::Coordinator {
                 coordinator_connection,
+                security,
             } => {
                 let addr = coordinator_connection
                     .peer_addr()
                     .context("failed to get coordinator peer addr")?;
-                let new_connection = TcpStream::connect(addr)
+                let new_connection = crate::coordinator::connect(addr, security)
                     .await
                     .context("failed to connect to coordinator during logger clone")?;
                 LogDestination::Coordinator {
                     coordinator_connection: new_connection,
+                    security: security.clone(),
                 }
             }
             LogDestination::Channel { sender } => LogDestination::Channel {
@@ -367,7 +375,7 @@ impl Logger {
 
     async fn log_to_coordinator(
         message: Timestamped<CoordinatorRequest>,
-        connection: &mut TcpStream,
+        connection: &mut MaybeTlsStream,
     ) {
         let msg = serde_json::to_vec(&message).expect("failed to serialize log message");
         match socket_stream_send(connection, &msg)
@@ -381,8 +389,13 @@ impl Logger {
 }
 
 pub enum LogDestination {
-    Coordinator { coordinator_connection: TcpStream },
-    Channel { sender: Sender<LogMessage> },
+    Coordinator {
+        coordinator_connection: MaybeTlsStream,
+        security: ClientSecurity,
+    },
+    Channel {
+        sender: Sender<LogMessage>,
+    },
     Tracing,
 }
 
@@ -0,0 +1,332 @@
+//! Prometheus metrics about the messages that are sent through the daemon.
+//!
+//! The metrics are served in the Prometheus text format on the `/metrics` endpoint of an HTTP
+//! server, which is started through [`DaemonMetrics::serve`]. Message rates and throughput can
+//! be derived from the exported counters using the `rate` function of Prometheus.
+
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+};
+
+use dora_core::config::{DataId, NodeId};
+use dora_message::{
+    DataflowId,
+    node_to_daemon::{InputStats, LATENCY_BUCKETS, LatencyHistogram},
+};
+use eyre::Context;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+use crate::OutputId;
+
+/// Collects message metrics of all running dataflows.
+#[derive(Clone, Default)]
+pub struct DaemonMetrics(Arc<Mutex<Registry>>);
+
+#[derive(Default)]
+struct Registry {
+    outputs: BTreeMap<(DataflowId, NodeId, DataId), OutputMetrics>,
+    inputs: BTreeMap<(DataflowId, NodeId, DataId), InputMetrics>,
+}
+
+#[derive(Default)]
+struct OutputMetrics {
+    messages: u64,
+    bytes: u64,
+}
+
+#[derive(Default)]
+struct InputMetrics {
+    queue_depth: usize,
+    dropped: u64,
+    latency: LatencyHistogram,
+}
+
+impl DaemonMetrics {
+    pub fn record_output(&self, dataflow_id: DataflowId, output_id: &OutputId, bytes: usize) {
+        let mut registry = self.0.lock().unwrap();
+        let metrics = registry
+            .outputs
+            .entry((dataflow_id, output_id.0.clone(), output_id.1.clone()))
+            .or_default();
+        metrics.messages += 1;
+        metrics.bytes += bytes as u64;
+    }
+
+    pub fn record_input_stats(
+        &self,
+        dataflow_id: DataflowId,
+        node_id: &NodeId,
+        inputs: BTreeMap<DataId, InputStats>,
+    ) {
+        let mut registry = self.0.lock().unwrap();
+        for (input_id, stats) in inputs {
+            let metrics = registry
+                .inputs
+                .entry((dataflow_id, node_id.clone(), input_id))
+                .or_default();
+            metrics.queue_depth = stats.queue_depth;
+            metrics.dropped += stats.dropped;
+            metrics.latency.merge(&stats.latency);
+        }
+    }
+
+    /// Removes the metrics of a finished dataflow.
+    pub fn remove_dataflow(&self, dataflow_id: DataflowId) {
+        let mut registry = self.0.lock().unwrap();
+        registry.outputs.retain(|(id, _, _), _| *id != dataflow_id);
+        registry.inputs.retain(|(id, _, _), _| *id != dataflow_id);
+    }
+
+    /// Renders all metrics in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let registry = self.0.lock().unwrap();
+        let mut out = String::new();
+
+        let outputs = &registry.outputs;
+        write_header(
+            &mut out,
+            "dora_output_messages_total",
+            "counter",
+            "Number of messages sent on an output.",
+        );
+        for ((dataflow_id, node_id, output_id), metrics) in outputs {
+            let labels = labels(dataflow_id, node_id, "output", output_id);
+            let _ = writeln!(
+                out,
+                "dora_output_messages_total{{{labels}}} {}",
+                metrics.messages
+            );
+        }
+        write_header(
+            &mut out,
+            "dora_output_bytes_total",
+            "counter",
+            "Number of data bytes sent on an output.",
+        );
+        for ((dataflow_id, node_id, output_id), metrics) in outputs {
+            let labels = labels(dataflow_id, node_id, "output", output_id);
+            let _ = writeln!(out, "dora_output_bytes_total{{{labels}}} {}", metrics.bytes);
+        }
+
+        let inputs = &registry.inputs;
+        write_header(
+            &mut out,
+            "dora_input_queue_depth",
+            "gauge",
+            "Number of inputs waiting in the input queue of a node.",
+        );
+        for ((dataflow_id, node_id, input_id), metrics) in inputs {
+            let labels = labels(dataflow_id, node_id, "input", input_id);
+            let _ = writeln!(
+                out,
+                "dora_input_queue_depth{{{labels}}} {}",
+                metrics.queue_depth
+            );
+        }
+        write_header(
+            &mut out,
+            "dora_input_dropped_total",
+            "counter",
+            "Number of inputs dropped because the input queue was full.",
+        );
+        for ((dataflow_id, node_id, input_id), metrics) in inputs {
+            let labels = labels(dataflow_id, node_id, "input", input_id);
+            let _ = writeln!(
+                out,
+                "dora_input_dropped_total{{{labels}}} {}",
+                metrics.dropped
+            );
+        }
+        write_header(
+            &mut out,
+            "dora_input_latency_seconds",
+            "histogram",
+            "Time between sending an output and receiving it as input.",
+        );
+        for ((dataflow_id, node_id, input_id), metrics) in inputs {
+            let labels = labels(dataflow_id, node_id, "input", input_id);
+            let mut cumulative = 0;
+            for (i, bound) in LATENCY_BUCKETS.iter().enumerate() {
+                cumulative += metrics.latency.buckets.get(i).copied().unwrap_or(0);
+                let _ = writeln!(
+                    out,
+                    r#"dora_input_latency_seconds_bucket{{{labels},le="{}"}} {cumulative}"#,
+                    bound.as_secs_f64()
+                );
+            }
+            let count = metrics.latency.count();
+            let _ = writeln!(
+                out,
+                r#"dora_input_latency_seconds_bucket{{{labels},le="+Inf"}} {count}"#
+            );
+            let _ = writeln!(
+                out,
+                "dora_input_latency_seconds_sum{{{labels}}} {}",
+                metrics.latency.sum.as_secs_f64()
+            );
+            let _ = writeln!(out, "dora_input_latency_seconds_count{{{labels}}} {count}");
+        }
+
+        out
+    }
+
+    /// Serves the metrics on the `/metrics` endpoint of an HTTP server bound to `addr`.
+    pub async fn serve(self, addr: SocketAddr) -> eyre::Result<()> {
+        let listener = TcpListener::bind(addr)
+            .await
+            .wrap_err_with(|| format!("failed to bind metrics server to {addr}"))?;
+        tracing::info!("serving metrics on http://{addr}/metrics");
+        loop {
+            let (connection, _) = match listener.accept().await {
+                Ok(c) => c,
+                Err(err) => {
+                    tracing::warn!("failed to accept metrics connection: {err}");
+                    continue;
+                }
+            };
+            let metrics = self.clone();
+            tokio::spawn(async move {
+                if let Err(err) = metrics.handle_connection(connection).await {
+                    tracing::debug!("failed to handle metrics request: {err:?}");
+                }
+            });
+        }
+    }
+
+    async fn handle_connection(&self, mut connection: TcpStream) -> eyre::Result<()> {
+        let mut request = Vec::new();
+        let mut buf = [0; 1024];
+        while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+            let read = connection.read(&mut buf).await?;
+            if read == 0 || request.len() > 8 * 1024 {
+                eyre::bail!("incomplete HTTP request");
+            }
+            request.extend_from_slice(&buf[..read]);
+        }
+        let request = String::from_utf8_lossy(&request);
+        let mut request_line = request.lines().next().unwrap_or_default().split(' ');
+        let method = request_line.next().unwrap_or_default();
+        let path = request_line.next().unwrap_or_default();
+
+        let (status, body) = match (method, path) {
+            ("GET", "/metrics") => ("200 OK", self.render()),
+            ("GET", _) => ("404 Not Found", "not found\n".to_owned()),
+            _ => ("405 Method Not Allowed", "method not allowed\n".to_owned()),
+        };
+        let response = format!(
+            "HTTP/1.1 {status}\r\n\
+            Content-Type: text/plain; version=0.0.4\r\n\
+            Content-Length: {}\r\n\
+            Connection: close\r\n\r\n{body}",
+            body.len()
+        );
+        connection.write_all(response.as_bytes()).await?;
+        connection.shutdown().await?;
+        Ok(())
+    }
+}
+
+/// Formats the labels of an output or input metric.
+fn labels(dataflow_id: &DataflowId, node_id: &NodeId, kind: &str, data_id: &DataId) -> String {
+    format!(
+        r#"dataflow="{dataflow_id}",node="{}",{kind}="{}""#,
+        escape_label_value(node_id.as_ref()),
+        escape_label_value(data_id)
+    )
+}
+
+/// Escapes backslashes, double quotes, and line feeds as required for label values of the
+/// Prometheus text format.
+fn escape_label_value(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str(r"\\"),
+            '"' => escaped.push_str(r#"\""#),
+            '\n' => escaped.push_str(r"\n"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn write_header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use uuid::Uuid;
+
+    use super::*;
+
+    fn input_stats(queue_depth: usize, dropped: u64, latency: Duration) -> InputStats {
+        let mut stats = InputStats {
+            queue_depth,
+            dropped,
+            ..Default::default()
+        };
+        stats.latency.record(latency);
+        stats
+    }
+
+    #[test]
+    fn render_recorded_metrics() {
+        let metrics = DaemonMetrics::default();
+        let dataflow_id = Uuid::nil();
+        let node_id = NodeId::from("camera".to_owned());
+        let output_id = OutputId(node_id.clone(), DataId::from("image".to_owned()));
+        metrics.record_output(dataflow_id, &output_id, 100);
+        metrics.record_output(dataflow_id, &output_id, 50);
+
+        let input_id = DataId::from("tick".to_owned());
+        let stats = |queue_depth, dropped, latency| {
+            [(input_id.clone(), input_stats(queue_depth, dropped, latency))].into()
+        };
+        metrics.record_input_stats(dataflow_id, &node_id, stats(3, 2, Duration::ZERO));
+        metrics.record_input_stats(dataflow_id, &node_id, stats(0, 1, Duration::from_secs(10)));
+
+        let output_labels = format!(r#"dataflow="{dataflow_id}",node="camera",output="image""#);
+        let input_labels = format!(r#"dataflow="{dataflow_id}",node="camera",input="tick""#);
+        let rendered = metrics.render();
+        for line in [
+            format!("dora_output_messages_total{{{output_labels}}} 2"),
+            format!("dora_output_bytes_total{{{output_labels}}} 150"),
+            // the queue depth is replaced by the latest report, the other values add up
+            format!("dora_input_queue_depth{{{input_labels}}} 0"),
+            format!("dora_input_dropped_total{{{input_labels}}} 3"),
+            format!(r#"dora_input_latency_seconds_bucket{{{input_labels},le="5"}} 1"#),
+            format!(r#"dora_input_latency_seconds_bucket{{{input_labels},le="+Inf"}} 2"#),
+            format!("dora_input_latency_seconds_sum{{{input_labels}}} 10"),
+        ] {
+            assert!(rendered.lines().any(|l| l == line), "missing `{line}`");
+        }
+
+        metrics.remove_dataflow(dataflow_id);
+        assert!(!metrics.render().contains("camera"));
+    }
+
+    #[test]
+    fn escape_label_values() {
+        let metrics = DaemonMetrics::default();
+        let dataflow_id = Uuid::nil();
+        let node_id = NodeId::from(r#"cam"era\"#.to_owned());
+        let output_id = OutputId(node_id, DataId::from("a\nb".to_owned()));
+        metrics.record_output(dataflow_id, &output_id, 1);
+
+        let line = format!(
+            r#"dora_output_messages_total{{dataflow="{dataflow_id}",node="cam\"era\\",output="a\nb"}} 1"#
+        );
+        let rendered = metrics.render();
+        assert!(rendered.lines().any(|l| l == line), "{rendered}");
+    }
+}
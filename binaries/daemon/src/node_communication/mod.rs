@@ -358,13 +358,22 @@ impl Listener {
                 output_id,
                 metadata,
                 data,
+                blocking,
             } => {
+                // wait for the daemon if the receivers might not have room for the message
+                let (reply_sender, reply) = if blocking {
+                    let (reply_sender, reply) = oneshot::channel();
+                    (Some(reply_sender), Some(reply))
+                } else {
+                    (None, None)
+                };
                 let event = crate::DaemonNodeEvent::SendOut {
                     output_id,
                     metadata,
                     data,
+                    reply_sender,
                 };
-                self.process_daemon_event(event, None, connection).await?;
+                self.process_daemon_event(event, reply, connection).await?;
             }
             DaemonRequest::Subscribe => {
                 let (tx, rx) = mpsc::unbounded_channel();
@@ -430,6 +439,30 @@ impl Listener {
                     .await
                     .wrap_err("failed to send ReportDropTokens reply")?;
             }
+            DaemonRequest::ReportInputsConsumed { inputs } => {
+                self.process_daemon_event(
+                    DaemonNodeEvent::InputsConsumed { inputs },
+                    None,
+                    connection,
+                )
+                .await?;
+            }
+            DaemonRequest::ReportInputStats { inputs } => {
+                self.process_daemon_event(DaemonNodeEvent::InputStats { inputs }, None, connection)
+                    .await?;
+            }
+            DaemonRequest::StopDataflow { reason } => {
+                let (reply_sender, reply) = oneshot::channel();
+                self.process_daemon_event(
+                    DaemonNodeEvent::StopDataflow {
+                        reason,
+                        reply_sender,
+                    },
+                    Some(reply),
+                    connection,
+                )
+                .await?;
+            }
             DaemonRequest::NextFinishedDropTokens => {
                 let reply = match self.subscribed_drop_events.as_mut() {
                     // wait for next event
@@ -1,5 +1,6 @@
 use std::collections::{BTreeSet, HashMap, HashSet};
 
+use dora_core::security::MaybeTlsStream;
 use dora_core::{
     config::NodeId,
     uhlc::{HLC, Timestamp},
@@ -11,7 +12,7 @@ use dora_message::{
     daemon_to_node::DaemonReply,
 };
 use eyre::{Context, bail};
-use tokio::{net::TcpStream, sync::oneshot};
+use tokio::sync::oneshot;
 
 use crate::{CascadingErrorCauses, log::DataflowLogger, socket_stream_utils::socket_stream_send};
 
@@ -63,11 +64,15 @@ impl PendingNodes {
         !self.local_nodes.is_empty()
     }
 
+    pub fn is_pending(&self, node_id: &NodeId) -> bool {
+        self.local_nodes.contains(node_id)
+    }
+
     pub async fn handle_node_subscription(
         &mut self,
         node_id: NodeId,
         reply_sender: oneshot::Sender<DaemonReply>,
-        coordinator_connection: &mut Option<TcpStream>,
+        coordinator_connection: &mut Option<MaybeTlsStream>,
         clock: &HLC,
         cascading_errors: &mut CascadingErrorCauses,
         logger: &mut DataflowLogger<'_>,
@@ -83,7 +88,7 @@ impl PendingNodes {
     pub async fn handle_node_stop(
         &mut self,
         node_id: &NodeId,
-        coordinator_connection: &mut Option<TcpStream>,
+        coordinator_connection: &mut Option<MaybeTlsStream>,
         clock: &HLC,
         cascading_errors: &mut CascadingErrorCauses,
         logger: &mut DataflowLogger<'_>,
@@ -106,7 +111,7 @@ impl PendingNodes {
 
     pub async fn handle_dataflow_stop(
         &mut self,
-        coordinator_connection: &mut Option<TcpStream>,
+        coordinator_connection: &mut Option<MaybeTlsStream>,
         clock: &HLC,
         cascading_errors: &mut CascadingErrorCauses,
         dynamic_nodes: &BTreeSet<NodeId>,
@@ -145,7 +150,7 @@ impl PendingNodes {
 
     async fn update_dataflow_status(
         &mut self,
-        coordinator_connection: &mut Option<TcpStream>,
+        coordinator_connection: &mut Option<MaybeTlsStream>,
         clock: &HLC,
         cascading_errors: &mut CascadingErrorCauses,
         logger: &mut DataflowLogger<'_>,
@@ -202,7 +207,7 @@ impl PendingNodes {
 
     async fn report_nodes_ready(
         &self,
-        coordinator_connection: &mut Option<TcpStream>,
+        coordinator_connection: &mut Option<MaybeTlsStream>,
         timestamp: Timestamp,
         logger: &mut DataflowLogger<'_>,
     ) -> eyre::Result<()> {
@@ -0,0 +1,428 @@
+//! Recording of dataflow outputs to disk.
+//!
+//! Each recorded output is stored as an Arrow IPC stream file at
+//! `<record_dir>/<dataflow_id>/<node_id>/<output_id>.arrow`. Every message is
+//! stored as a single row with the following columns:
+//!
+//! - `timestamp`: the `uhlc` timestamp of the message as NTP64 value
+//! - `metadata`: the JSON-serialized message [`Metadata`]
+//! - `data`: the raw message payload (null for messages without data)
+//!
+//! The raw payload can be turned back into an Arrow array using the `type_info`
+//! field of the metadata.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::File,
+    io::BufWriter,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use aligned_vec::{AVec, ConstAlign};
+use dora_core::config::{DataId, NodeId};
+use dora_message::metadata::Metadata;
+use dora_node_api::arrow::{
+    array::{Array, AsArray, BinaryArray, RecordBatch, UInt64Array},
+    datatypes::{DataType, Field, Schema, SchemaRef, UInt64Type},
+    ipc::{reader::StreamReader, writer::StreamWriter},
+};
+use eyre::{Context, ContextCompat, bail};
+
+use crate::OutputId;
+
+/// File extension of recorded output files.
+pub const RECORD_FILE_EXTENSION: &str = "arrow";
+
+/// Returns the Arrow schema of recorded output files.
+pub fn record_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("timestamp", DataType::UInt64, false),
+        Field::new("metadata", DataType::Binary, false),
+        Field::new("data", DataType::Binary, true),
+    ]))
+}
+
+/// Writes the outputs of a running dataflow to disk.
+///
+/// The file I/O happens on a separate thread to avoid blocking the daemon
+/// event loop. Use [`finish`][Self::finish] to wait until all record files are
+/// complete.
+pub(crate) struct Recorder {
+    sender: Option<std::sync::mpsc::Sender<RecordItem>>,
+    thread: Option<std::thread::JoinHandle<()>>,
+}
+
+struct RecordItem {
+    output_id: OutputId,
+    metadata: Metadata,
+    data: Option<AVec<u8, ConstAlign<128>>>,
+}
+
+impl Recorder {
+    pub fn new(dir: PathBuf) -> eyre::Result<Self> {
+        std::fs::create_dir_all(&dir)
+            .wrap_err_with(|| format!("failed to create record directory `{}`", dir.display()))?;
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let thread = std::thread::spawn(move || record_loop(dir, receiver));
+        Ok(Self {
+            sender: Some(sender),
+            thread: Some(thread),
+        })
+    }
+
+    pub fn record(
+        &self,
+        output_id: OutputId,
+        metadata: Metadata,
+        data: Option<AVec<u8, ConstAlign<128>>>,
+    ) {
+        let item = RecordItem {
+            output_id,
+            metadata,
+            data,
+        };
+        if let Some(sender) = &self.sender {
+            if sender.send(item).is_err() {
+                tracing::warn!("record thread exited unexpectedly");
+            }
+        }
+    }
+
+    /// Waits until all recorded messages are written and the record files are finished.
+    pub async fn finish(mut self) {
+        // close the channel to make the record thread finish its files
+        self.sender.take();
+        if let Some(thread) = self.thread.take() {
+            match tokio::task::spawn_blocking(move || thread.join()).await {
+                Ok(Ok(())) => {}
+                Ok(Err(_)) => tracing::error!("record thread panicked"),
+                Err(err) => tracing::error!("failed to wait for record thread: {err}"),
+            }
+        }
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        // the record thread finishes its files in the background once the channel is closed
+        self.sender.take();
+    }
+}
+
+fn record_loop(dir: PathBuf, receiver: std::sync::mpsc::Receiver<RecordItem>) {
+    let schema = record_schema();
+    let mut writers: HashMap<OutputId, StreamWriter<BufWriter<File>>> = HashMap::new();
+    let mut failed = Vec::new();
+
+    for item in receiver {
+        if failed.contains(&item.output_id) {
+            continue;
+        }
+        let result = (|| {
+            let writer = match writers.entry(item.output_id.clone()) {
+                std::collections::hash_map::Entry::Occupied(entry) => entry.into_mut(),
+                std::collections::hash_map::Entry::Vacant(entry) => {
+                    let OutputId(node_id, output_id) = &item.output_id;
+                    let path = output_file_path(&dir, node_id, output_id);
+                    if let Some(parent) = path.parent() {
+                        std::fs::create_dir_all(parent).wrap_err_with(|| {
+                            format!("failed to create directory `{}`", parent.display())
+                        })?;
+                    }
+                    let file = File::create(&path).wrap_err_with(|| {
+                        format!("failed to create record file `{}`", path.display())
+                    })?;
+                    entry.insert(
+                        StreamWriter::try_new(BufWriter::new(file), &schema)
+                            .context("failed to create record writer")?,
+                    )
+                }
+            };
+            let batch = message_batch(&schema, &item.metadata, item.data.as_deref())?;
+            writer.write(&batch).context("failed to write record batch")
+        })();
+        if let Err(err) = result {
+            let OutputId(node_id, output_id) = &item.output_id;
+            tracing::warn!("failed to record output `{node_id}/{output_id}`: {err:?}");
+            failed.push(item.output_id);
+        }
+    }
+
+    for (OutputId(node_id, output_id), mut writer) in writers {
+        if let Err(err) = writer.finish() {
+            tracing::warn!("failed to finish record file for `{node_id}/{output_id}`: {err}");
+        }
+    }
+}
+
+fn message_batch(
+    schema: &SchemaRef,
+    metadata: &Metadata,
+    data: Option<&[u8]>,
+) -> eyre::Result<RecordBatch> {
+    let timestamp = UInt64Array::from(vec![metadata.timestamp().get_time().as_u64()]);
+    let metadata = serde_json::to_vec(metadata).context("failed to serialize message metadata")?;
+    let metadata = BinaryArray::from_vec(vec![metadata.as_slice()]);
+    let data = BinaryArray::from_opt_vec(vec![data]);
+    RecordBatch::try_new(
+        schema.clone(),
+        vec![Arc::new(timestamp), Arc::new(metadata), Arc::new(data)],
+    )
+    .context("failed to create record batch")
+}
+
+fn output_file_path(dir: &Path, node_id: &NodeId, output_id: &DataId) -> PathBuf {
+    // output IDs of operators contain a `/`, which results in a sub-directory
+    dir.join(node_id.to_string())
+        .join(format!("{output_id}.{RECORD_FILE_EXTENSION}"))
+}
+
+/// A single output message read from a recording.
+#[derive(Debug)]
+pub struct RecordedMessage {
+    pub output_id: DataId,
+    /// The original `uhlc` timestamp of the message as NTP64 value.
+    pub timestamp: u64,
+    pub metadata: Metadata,
+    pub data: Option<Vec<u8>>,
+}
+
+/// Returns the recorded output IDs of a single node, together with their file paths.
+///
+/// The given directory is the `<record_dir>/<dataflow_id>/<node_id>` directory
+/// created by the recorder.
+pub fn recorded_outputs(node_dir: &Path) -> eyre::Result<BTreeMap<DataId, PathBuf>> {
+    let mut outputs = BTreeMap::new();
+    collect_recorded_outputs(node_dir, node_dir, &mut outputs)?;
+    Ok(outputs)
+}
+
+/// Reads the recorded outputs of a single node, sorted by timestamp.
+///
+/// The given directory is the `<record_dir>/<dataflow_id>/<node_id>` directory
+/// created by the recorder. The record files are read incrementally while iterating.
+pub fn read_recorded_node(node_dir: &Path) -> eyre::Result<RecordedNodeMessages> {
+    let outputs = recorded_outputs(node_dir)?
+        .into_iter()
+        .map(|(output_id, path)| {
+            RecordedOutputMessages::open(&path, output_id).map(Iterator::peekable)
+        })
+        .collect::<eyre::Result<_>>()?;
+    Ok(RecordedNodeMessages { outputs })
+}
+
+/// Reads all messages of a single recorded output file.
+pub fn read_recorded_file(path: &Path, output_id: DataId) -> eyre::Result<Vec<RecordedMessage>> {
+    RecordedOutputMessages::open(path, output_id)?.collect()
+}
+
+/// Iterator over the recorded messages of a single node, sorted by timestamp.
+///
+/// Merges the messages of the record files of all outputs of the node.
+pub struct RecordedNodeMessages {
+    outputs: Vec<std::iter::Peekable<RecordedOutputMessages>>,
+}
+
+impl Iterator for RecordedNodeMessages {
+    type Item = eyre::Result<RecordedMessage>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // errors are returned right away, before any message
+        let mut next: Option<(usize, (u8, u64))> = None;
+        for (i, output) in self.outputs.iter_mut().enumerate() {
+            let key = match output.peek() {
+                Some(Ok(message)) => (1, message.timestamp),
+                Some(Err(_)) => (0, 0),
+                None => continue,
+            };
+            if next.is_none_or(|(_, next_key)| key < next_key) {
+                next = Some((i, key));
+            }
+        }
+        self.outputs[next?.0].next()
+    }
+}
+
+/// Iterator over the messages of a single record file, reading one record batch at a time.
+struct RecordedOutputMessages {
+    path: PathBuf,
+    output_id: DataId,
+    reader: StreamReader<std::io::BufReader<File>>,
+    batch: Option<RecordBatch>,
+    row: usize,
+}
+
+impl RecordedOutputMessages {
+    fn open(path: &Path, output_id: DataId) -> eyre::Result<Self> {
+        let file = File::open(path)
+            .wrap_err_with(|| format!("failed to open record file `{}`", path.display()))?;
+        let reader = StreamReader::try_new(std::io::BufReader::new(file), None)
+            .wrap_err_with(|| format!("failed to create reader for `{}`", path.display()))?;
+        Ok(Self {
+            path: path.to_owned(),
+            output_id,
+            reader,
+            batch: None,
+            row: 0,
+        })
+    }
+
+    fn next_message(&mut self) -> eyre::Result<Option<RecordedMessage>> {
+        loop {
+            if let Some(batch) = &self.batch {
+                if self.row < batch.num_rows() {
+                    let message = recorded_message(batch, self.row, &self.output_id)?;
+                    self.row += 1;
+                    return Ok(Some(message));
+                }
+            }
+            match self.reader.next() {
+                Some(batch) => {
+                    let batch = batch.context("failed to read record batch")?;
+                    if batch.schema().fields() != record_schema().fields() {
+                        bail!("unexpected record schema");
+                    }
+                    self.batch = Some(batch);
+                    self.row = 0;
+                }
+                None => return Ok(None),
+            }
+        }
+    }
+}
+
+impl Iterator for RecordedOutputMessages {
+    type Item = eyre::Result<RecordedMessage>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_message()
+            .wrap_err_with(|| format!("failed to read record file `{}`", self.path.display()))
+            .transpose()
+    }
+}
+
+fn collect_recorded_outputs(
+    base: &Path,
+    dir: &Path,
+    outputs: &mut BTreeMap<DataId, PathBuf>,
+) -> eyre::Result<()> {
+    let entries = std::fs::read_dir(dir)
+        .wrap_err_with(|| format!("failed to read directory `{}`", dir.display()))?;
+    for entry in entries {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_recorded_outputs(base, &path, outputs)?;
+        } else if path.extension().is_some_and(|e| e == RECORD_FILE_EXTENSION) {
+            let output_id = path
+                .strip_prefix(base)?
+                .with_extension("")
+                .iter()
+                .map(|c| c.to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            outputs.insert(DataId::from(output_id), path);
+        }
+    }
+    Ok(())
+}
+
+fn recorded_message(
+    batch: &RecordBatch,
+    row: usize,
+    output_id: &DataId,
+) -> eyre::Result<RecordedMessage> {
+    let timestamps = batch
+        .column(0)
+        .as_primitive_opt::<UInt64Type>()
+        .context("invalid timestamp column")?;
+    let metadata = batch
+        .column(1)
+        .as_binary_opt::<i32>()
+        .context("invalid metadata column")?;
+    let data = batch
+        .column(2)
+        .as_binary_opt::<i32>()
+        .context("invalid data column")?;
+    Ok(RecordedMessage {
+        output_id: output_id.clone(),
+        timestamp: timestamps.value(row),
+        metadata: serde_json::from_slice(metadata.value(row))
+            .context("failed to deserialize message metadata")?,
+        data: data.is_valid(row).then(|| data.value(row).to_owned()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use dora_core::{metadata::ArrowTypeInfoExt, uhlc::HLC};
+    use dora_message::metadata::{ArrowTypeInfo, Parameter};
+
+    use super::*;
+
+    fn metadata(clock: &HLC, seq: i64) -> Metadata {
+        let type_info = ArrowTypeInfo {
+            data_type: DataType::UInt8,
+            len: 4,
+            ..ArrowTypeInfo::empty()
+        };
+        let parameters = [("seq".to_owned(), Parameter::Integer(seq))].into();
+        Metadata::from_parameters(clock.new_timestamp(), type_info, parameters)
+    }
+
+    #[tokio::test]
+    async fn replay_recorded_outputs() {
+        let dir = std::env::temp_dir().join(format!("dora-record-test-{}", uuid::Uuid::new_v4()));
+        let recorder = Recorder::new(dir.clone()).unwrap();
+        let clock = HLC::default();
+        let node_id = NodeId::from("camera".to_owned());
+        // output IDs of operators contain a `/`
+        let outputs = ["image", "op/status"].map(|id| DataId::from(id.to_owned()));
+
+        let mut sent = Vec::new();
+        for seq in 0..6 {
+            let output_id = outputs[seq % 2].clone();
+            let metadata = metadata(&clock, seq as i64);
+            let data = (seq % 3 != 0).then(|| vec![seq as u8; 4]);
+            sent.push((
+                output_id.clone(),
+                metadata.timestamp().get_time().as_u64(),
+                data.clone(),
+            ));
+            recorder.record(
+                OutputId(node_id.clone(), output_id),
+                metadata,
+                data.map(|d| AVec::from_slice(128, &d)),
+            );
+        }
+        recorder.finish().await;
+
+        let node_dir = dir.join(node_id.to_string());
+        let replayed = read_recorded_node(&node_dir)
+            .unwrap()
+            .collect::<eyre::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(replayed.len(), sent.len());
+        for (seq, (message, (output_id, timestamp, data))) in replayed.iter().zip(&sent).enumerate()
+        {
+            assert_eq!(&message.output_id, output_id);
+            assert_eq!(message.timestamp, *timestamp);
+            assert_eq!(&message.data, data);
+            assert_eq!(
+                message.metadata.parameters["seq"],
+                Parameter::Integer(seq as i64)
+            );
+        }
+
+        let status = read_recorded_file(
+            &node_dir
+                .join("op")
+                .join(format!("status.{RECORD_FILE_EXTENSION}")),
+            outputs[1].clone(),
+        )
+        .unwrap();
+        assert_eq!(status.len(), 3);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
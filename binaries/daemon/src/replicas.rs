@@ -0,0 +1,323 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use dora_core::{
+    config::{DataId, NodeId},
+    descriptor::ResolvedNode,
+};
+use dora_message::descriptor::ReplicaPolicy;
+
+use crate::InputId;
+
+/// Keeps track of the local replicas of replicated nodes.
+///
+/// Each input message for a replicated node is delivered to only one of its replicas.
+#[derive(Debug, Default)]
+pub struct ReplicaGroups {
+    groups: BTreeMap<NodeId, ReplicaGroup>,
+    /// Maps the ID of each replica to the ID of its group.
+    group_of: BTreeMap<NodeId, NodeId>,
+}
+
+#[derive(Debug)]
+struct ReplicaGroup {
+    policy: ReplicaPolicy,
+    /// The local replicas of this group, ordered by index.
+    members: Vec<NodeId>,
+    /// Replicas whose outputs were not closed yet.
+    open_outputs: BTreeSet<NodeId>,
+    /// Position in `members` at which the search for the next receiver starts, per input.
+    next: BTreeMap<DataId, usize>,
+    /// Number of inputs that were sent to each replica, but not consumed yet.
+    ///
+    /// Only tracked for the [`ReplicaPolicy::LeastLoaded`] policy.
+    pending_inputs: BTreeMap<NodeId, usize>,
+}
+
+impl ReplicaGroups {
+    /// Registers the given node if it is a replica.
+    pub fn insert(&mut self, node: &ResolvedNode) {
+        let Some(replica) = &node.replica else {
+            return;
+        };
+        let group = self
+            .groups
+            .entry(replica.group.clone())
+            .or_insert_with(|| ReplicaGroup {
+                policy: replica.policy,
+                members: Vec::new(),
+                open_outputs: BTreeSet::new(),
+                next: BTreeMap::new(),
+                pending_inputs: BTreeMap::new(),
+            });
+        if !group.members.contains(&node.id) {
+            group.members.push(node.id.clone());
+        }
+        group.open_outputs.insert(node.id.clone());
+        self.group_of.insert(node.id.clone(), replica.group.clone());
+    }
+
+    pub fn is_replica(&self, node_id: &NodeId) -> bool {
+        self.group_of.contains_key(node_id)
+    }
+
+    /// Returns the node ID under which the outputs of the given node are sent.
+    pub fn output_node_id<'a>(&'a self, node_id: &'a NodeId) -> &'a NodeId {
+        self.group_of.get(node_id).unwrap_or(node_id)
+    }
+
+    /// Marks the outputs of the given replica as closed.
+    ///
+    /// Returns the ID of the replicated node if the outputs of all its replicas are closed now.
+    pub fn close_outputs(&mut self, node_id: &NodeId) -> Option<NodeId> {
+        let group_id = self.group_of.get(node_id)?;
+        let group = self.groups.get_mut(group_id)?;
+        if group.open_outputs.remove(node_id) && group.open_outputs.is_empty() {
+            Some(group_id.clone())
+        } else {
+            None
+        }
+    }
+
+    /// Selects the receivers of an input message.
+    ///
+    /// Receivers that are not replicas are always selected. For each group of replicated
+    /// receivers, a single replica is selected among the replicas that are `available`,
+    /// according to the policy of the group. Replicas whose input queue is `full` are only
+    /// selected if all available replicas are full.
+    pub fn select_receivers<'a>(
+        &mut self,
+        receivers: impl IntoIterator<Item = &'a InputId>,
+        available: impl Fn(&InputId) -> bool,
+        full: impl Fn(&InputId) -> bool,
+    ) -> Vec<&'a InputId> {
+        let mut selected = Vec::new();
+        let mut candidates: BTreeMap<(NodeId, &DataId), Vec<&'a InputId>> = BTreeMap::new();
+        for receiver in receivers {
+            match self.group_of.get(&receiver.0) {
+                None => selected.push(receiver),
+                Some(group_id) => {
+                    if available(receiver) {
+                        candidates
+                            .entry((group_id.clone(), &receiver.1))
+                            .or_default()
+                            .push(receiver);
+                    }
+                }
+            }
+        }
+
+        for ((group_id, input_id), candidates) in candidates {
+            let Some(group) = self.groups.get_mut(&group_id) else {
+                continue;
+            };
+            let not_full: Vec<_> = candidates.iter().copied().filter(|r| !full(r)).collect();
+            let candidates = if not_full.is_empty() {
+                candidates
+            } else {
+                not_full
+            };
+            if let Some(receiver) = group.select(input_id, &candidates) {
+                selected.push(receiver);
+            }
+        }
+        selected
+    }
+
+    /// Records that the given replica consumed the given number of inputs.
+    pub fn inputs_consumed(&mut self, node_id: &NodeId, count: usize) {
+        if let Some(pending) = self.pending_inputs_mut(node_id) {
+            *pending = pending.saturating_sub(count);
+        }
+    }
+
+    /// Resets the number of pending inputs of the given replica, e.g. because it was
+    /// (re)started.
+    pub fn reset_pending_inputs(&mut self, node_id: &NodeId) {
+        if let Some(pending) = self.pending_inputs_mut(node_id) {
+            *pending = 0;
+        }
+    }
+
+    /// Returns the other replicas of the group that the given node belongs to.
+    pub fn siblings(&self, node_id: &NodeId) -> impl Iterator<Item = &NodeId> {
+        self.group_of
+            .get(node_id)
+            .and_then(|group_id| self.groups.get(group_id))
+            .into_iter()
+            .flat_map(|group| &group.members)
+            .filter(move |id| *id != node_id)
+    }
+
+    fn pending_inputs_mut(&mut self, node_id: &NodeId) -> Option<&mut usize> {
+        let group = self.groups.get_mut(self.group_of.get(node_id)?)?;
+        group.pending_inputs.get_mut(node_id)
+    }
+}
+
+impl ReplicaGroup {
+    fn select<'a>(&mut self, input_id: &DataId, candidates: &[&'a InputId]) -> Option<&'a InputId> {
+        let start = self.next.get(input_id).copied().unwrap_or_default();
+        let mut ordered = (0..self.members.len())
+            .map(|offset| (start + offset) % self.members.len())
+            .filter_map(|position| {
+                let member = &self.members[position];
+                let receiver = candidates.iter().find(|(id, _)| id == member)?;
+                Some((position, *receiver))
+            });
+        let (position, receiver) = match self.policy {
+            ReplicaPolicy::RoundRobin => ordered.next()?,
+            ReplicaPolicy::LeastLoaded => ordered.min_by_key(|(_, (id, _))| {
+                self.pending_inputs.get(id).copied().unwrap_or_default()
+            })?,
+        };
+        self.next
+            .insert(input_id.clone(), (position + 1) % self.members.len());
+        if self.policy == ReplicaPolicy::LeastLoaded {
+            *self.pending_inputs.entry(receiver.0.clone()).or_default() += 1;
+        }
+        Some(receiver)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dora_core::descriptor::{Descriptor, DescriptorExt};
+
+    fn replica_groups() -> ReplicaGroups {
+        let descriptor = Descriptor::parse(
+            br#"
+nodes:
+  - id: camera
+    path: camera.py
+    outputs: [image]
+  - id: detector
+    path: detector.py
+    replicas: 3
+    inputs:
+      image: camera/image
+    outputs: [bbox]
+  - id: tracker
+    path: tracker.py
+    replicas:
+      count: 2
+      policy: least-loaded
+    inputs:
+      bbox: detector/bbox
+"#
+            .to_vec(),
+        )
+        .unwrap();
+        let mut groups = ReplicaGroups::default();
+        for node in descriptor
+            .resolve_aliases_and_set_defaults()
+            .unwrap()
+            .values()
+        {
+            groups.insert(node);
+        }
+        groups
+    }
+
+    fn input_id(node_id: &str, input_id: &str) -> InputId {
+        (
+            NodeId::from(node_id.to_owned()),
+            DataId::from(input_id.to_owned()),
+        )
+    }
+
+    fn node_ids(selected: Vec<&InputId>) -> Vec<&str> {
+        selected.into_iter().map(|(id, _)| id.as_ref()).collect()
+    }
+
+    #[test]
+    fn select_round_robin() {
+        let mut groups = replica_groups();
+        let receivers = [
+            input_id("detector.0", "image"),
+            input_id("detector.1", "image"),
+            input_id("detector.2", "image"),
+            input_id("plot", "image"),
+        ];
+        let selected: Vec<_> = (0..4)
+            .map(|_| node_ids(groups.select_receivers(&receivers, |_| true, |_| false)))
+            .collect();
+        assert_eq!(
+            selected,
+            [
+                ["plot", "detector.0"],
+                ["plot", "detector.1"],
+                ["plot", "detector.2"],
+                ["plot", "detector.0"],
+            ]
+        );
+    }
+
+    #[test]
+    fn skip_unavailable_and_full_replicas() {
+        let mut groups = replica_groups();
+        let receivers = [
+            input_id("detector.0", "image"),
+            input_id("detector.1", "image"),
+            input_id("detector.2", "image"),
+        ];
+        let available = |(id, _): &InputId| id.as_ref() != "detector.1";
+        let full = |(id, _): &InputId| id.as_ref() == "detector.0";
+        assert_eq!(
+            node_ids(groups.select_receivers(&receivers, available, full)),
+            ["detector.2"]
+        );
+        assert_eq!(
+            node_ids(groups.select_receivers(&receivers, available, |_| false)),
+            ["detector.0"]
+        );
+        // full replicas are still selected if all available replicas are full
+        assert_eq!(
+            node_ids(groups.select_receivers(&receivers, available, |_| true)),
+            ["detector.2"]
+        );
+        assert!(
+            groups
+                .select_receivers(&receivers, |_| false, |_| false)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn select_least_loaded() {
+        let mut groups = replica_groups();
+        let receivers = [input_id("tracker.0", "bbox"), input_id("tracker.1", "bbox")];
+        let select = |groups: &mut ReplicaGroups| {
+            node_ids(groups.select_receivers(&receivers, |_| true, |_| false))
+        };
+        assert_eq!(select(&mut groups), ["tracker.0"]);
+        assert_eq!(select(&mut groups), ["tracker.1"]);
+        assert_eq!(select(&mut groups), ["tracker.0"]);
+
+        // tracker.0 has two pending inputs, tracker.1 has one
+        groups.inputs_consumed(&NodeId::from("tracker.0".to_owned()), 2);
+        assert_eq!(select(&mut groups), ["tracker.0"]);
+        groups.reset_pending_inputs(&NodeId::from("tracker.1".to_owned()));
+        assert_eq!(select(&mut groups), ["tracker.1"]);
+    }
+
+    #[test]
+    fn close_replica_outputs() {
+        let mut groups = replica_groups();
+        let replica = |index| NodeId::from(format!("tracker.{index}"));
+        assert!(groups.is_replica(&replica(0)));
+        assert!(!groups.is_replica(&NodeId::from("camera".to_owned())));
+        assert_eq!(groups.output_node_id(&replica(1)).as_ref(), "tracker");
+        assert_eq!(
+            groups.siblings(&replica(0)).collect::<Vec<_>>(),
+            [&replica(1)]
+        );
+
+        assert_eq!(groups.close_outputs(&replica(0)), None);
+        assert_eq!(groups.close_outputs(&replica(0)), None);
+        assert_eq!(
+            groups.close_outputs(&replica(1)),
+            Some(NodeId::from("tracker".to_owned()))
+        );
+    }
+}
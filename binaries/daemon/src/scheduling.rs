@@ -0,0 +1,80 @@
+//! CPU affinity, niceness, and real-time scheduling for node processes.
+
+use dora_message::descriptor::NodeScheduling;
+
+/// Configures the command to apply the given scheduling settings to the spawned process
+/// before it starts executing the node.
+#[cfg(target_os = "linux")]
+pub fn apply(
+    command: &mut tokio::process::Command,
+    scheduling: &NodeScheduling,
+) -> eyre::Result<()> {
+    use dora_message::descriptor::RealtimePolicy;
+    use std::io;
+
+    // prepare everything in the parent process because allocating is not allowed between
+    // `fork` and `exec`
+    let cpu_set = match &scheduling.cpu_affinity {
+        Some(cpus) => {
+            // SAFETY: `cpu_set_t` is a plain bit mask, for which all zeros is a valid value
+            let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+            for &cpu in cpus {
+                if cpu >= libc::CPU_SETSIZE as usize {
+                    eyre::bail!("CPU {cpu} in `cpu_affinity` is out of range");
+                }
+                // SAFETY: we checked above that `cpu` is within the bounds of the set
+                unsafe { libc::CPU_SET(cpu, &mut set) };
+            }
+            Some(set)
+        }
+        None => None,
+    };
+    let nice = scheduling.nice;
+    let realtime = scheduling
+        .policy
+        .zip(scheduling.priority)
+        .map(|(policy, priority)| {
+            let policy = match policy {
+                RealtimePolicy::Fifo => libc::SCHED_FIFO,
+                RealtimePolicy::RoundRobin => libc::SCHED_RR,
+            };
+            (
+                policy,
+                libc::sched_param {
+                    sched_priority: priority.into(),
+                },
+            )
+        });
+
+    // SAFETY: the closure only performs async-signal-safe syscalls on data that was
+    // prepared before
+    unsafe {
+        command.pre_exec(move || {
+            if let Some(set) = &cpu_set {
+                if libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), set) != 0 {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+            if let Some(nice) = nice {
+                if libc::setpriority(libc::PRIO_PROCESS, 0, nice) != 0 {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+            if let Some((policy, param)) = &realtime {
+                if libc::sched_setscheduler(0, *policy, param) != 0 {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+            Ok(())
+        });
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn apply(
+    _command: &mut tokio::process::Command,
+    _scheduling: &NodeScheduling,
+) -> eyre::Result<()> {
+    eyre::bail!("scheduling settings are only supported on Linux")
+}
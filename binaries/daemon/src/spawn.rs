@@ -1,8 +1,9 @@
 use crate::{
     CoreNodeKindExt, DoraEvent, Event, OutputId, RunningNode,
+    cgroup::NodeCgroup,
     log::{self, NodeLogger},
     node_communication::spawn_listener_loop,
-    node_inputs,
+    node_inputs, scheduling,
 };
 use aligned_vec::{AVec, ConstAlign};
 use crossbeam::queue::ArrayQueue;
@@ -22,6 +23,7 @@ use dora_message::{
     common::{LogLevel, LogMessage},
     daemon_to_coordinator::{DataMessage, NodeExitStatus, Timestamped},
     daemon_to_node::{NodeConfig, RuntimeConfig},
+    descriptor::ReplicaPolicy,
     id::NodeId,
 };
 use dora_node_api::{
@@ -31,6 +33,7 @@ use dora_node_api::{
 };
 use eyre::{ContextCompat, WrapErr, bail};
 use std::{
+    collections::BTreeSet,
     future::Future,
     path::{Path, PathBuf},
     process::Stdio,
@@ -51,6 +54,10 @@ pub struct Spawner {
     /// clock is required for generating timestamps when dropping messages early because queue is full
     pub clock: Arc<HLC>,
     pub uv: bool,
+    /// Outputs that are mapped to local inputs with a blocking queue policy.
+    pub blocking_outputs: BTreeSet<OutputId>,
+    /// Whether nodes should report input statistics, which are only needed for metrics.
+    pub report_input_stats: bool,
 }
 
 impl Spawner {
@@ -75,6 +82,12 @@ impl Spawner {
             .into_iter()
             .map(|(k, v)| (k, v.queue_size.unwrap_or(10)))
             .collect();
+        let blocking_outputs = self
+            .blocking_outputs
+            .iter()
+            .filter(|output| &output.0 == node.output_node_id())
+            .map(|output| output.1.clone())
+            .collect();
         let daemon_communication = spawn_listener_loop(
             &dataflow_id,
             &node_id,
@@ -93,6 +106,12 @@ impl Spawner {
             dataflow_descriptor: serde_yaml::to_value(&self.dataflow_descriptor)
                 .context("failed to serialize dataflow descriptor to YAML")?,
             dynamic: node.kind.dynamic(),
+            report_consumed_inputs: node
+                .replica
+                .as_ref()
+                .is_some_and(|r| r.policy == ReplicaPolicy::LeastLoaded),
+            blocking_outputs,
+            report_input_stats: self.report_input_stats,
         };
 
         let mut logger = logger
@@ -357,8 +376,21 @@ impl PreparedNode {
     }
 
     pub async fn spawn(mut self, logger: &mut NodeLogger<'_>) -> eyre::Result<RunningNode> {
+        let mut cgroup = None;
         let mut child = match &mut self.command {
             Some(command) => {
+                if let Some(resources) = &self.node.resources {
+                    // the cgroup is removed again when it's dropped on an error below
+                    let node_cgroup = cgroup.insert(
+                        NodeCgroup::create(self.dataflow_id, &self.node.id, resources)
+                            .wrap_err("failed to apply resource limits")?,
+                    );
+                    node_cgroup.apply(command)?;
+                }
+                if let Some(scheduling) = &self.node.scheduling {
+                    scheduling::apply(command, scheduling)
+                        .wrap_err("failed to apply scheduling settings")?;
+                }
                 let std_command = command.as_std();
                 logger
                     .log(
@@ -374,7 +406,23 @@ impl PreparedNode {
                         ),
                     )
                     .await;
-                command.spawn().wrap_err(self.spawn_error_msg)?
+                match command.spawn() {
+                    Ok(child) => child,
+                    Err(err) => {
+                        if self.node.scheduling.is_some()
+                            && err.kind() == std::io::ErrorKind::PermissionDenied
+                        {
+                            return Err(err)
+                                .wrap_err(
+                                    "failed to apply scheduling settings (negative `nice` \
+                                    values and real-time policies require the `CAP_SYS_NICE` \
+                                    capability)",
+                                )
+                                .wrap_err(self.spawn_error_msg);
+                        }
+                        return Err(err).wrap_err(self.spawn_error_msg);
+                    }
+                }
             }
             None => {
                 return Ok(RunningNode {
@@ -519,11 +567,13 @@ impl PreparedNode {
         let dataflow_id = self.dataflow_id;
         tokio::spawn(async move {
             let exit_status = NodeExitStatus::from(child.wait().await);
+            let exceeded_limit = cgroup.and_then(NodeCgroup::finish);
             let _ = log_finish_rx.await;
             let event = DoraEvent::SpawnedNodeResult {
                 dataflow_id,
                 node_id,
                 exit_status,
+                exceeded_limit,
                 dynamic_node,
             }
             .into();
@@ -548,6 +598,7 @@ impl PreparedNode {
             .node
             .send_stdout_as()
             .context("Could not resolve `send_stdout_as` configuration")?;
+        let output_node_id = self.node.output_node_id().clone();
 
         // Log to file stream.
         tokio::spawn(async move {
@@ -566,7 +617,7 @@ impl PreparedNode {
 
                     let metadata = Metadata::new(uhlc.new_timestamp(), type_info);
                     let output_id = OutputId(
-                        node_id.clone(),
+                        output_node_id.clone(),
                         DataId::from(stdout_output_name.to_string()),
                     );
                     let event = DoraEvent::Logs {
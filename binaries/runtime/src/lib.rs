@@ -1,7 +1,7 @@
 #![warn(unsafe_op_in_unsafe_fn)]
 
 use dora_core::{
-    config::{DataId, OperatorId},
+    config::{DataId, OperatorId, QueuePolicy},
     descriptor::OperatorConfig,
 };
 use dora_message::daemon_to_node::{NodeConfig, RuntimeConfig};
@@ -69,8 +69,9 @@ pub fn main() -> eyre::Result<()> {
 
     let mut operator_channels = HashMap::new();
     let queue_sizes = queue_sizes(&operator_definition.config);
+    let queue_policies = queue_policies(&operator_definition.config);
     let (operator_channel, incoming_events) =
-        operator::channel::channel(tokio_runtime.handle(), queue_sizes);
+        operator::channel::channel(tokio_runtime.handle(), queue_sizes, queue_policies);
     operator_channels.insert(operator_definition.id.clone(), operator_channel);
 
     tracing::info!("spawning main task");
@@ -119,6 +120,14 @@ fn queue_sizes(config: &OperatorConfig) -> std::collections::BTreeMap<DataId, us
     sizes
 }
 
+fn queue_policies(config: &OperatorConfig) -> BTreeMap<DataId, QueuePolicy> {
+    config
+        .inputs
+        .iter()
+        .filter_map(|(input_id, input)| Some((input_id.clone(), input.queue_policy?)))
+        .collect()
+}
+
 #[tracing::instrument(skip(operator_events, operator_channels), level = "trace")]
 async fn run(
     operators: HashMap<OperatorId, OperatorConfig>,
@@ -169,17 +178,11 @@ async fn run(
                     }
                     OperatorEvent::Finished { reason } => {
                         if let StopReason::ExplicitStopAll = reason {
-                            // let hlc = dora_core::message::uhlc::HLC::default();
-                            // let metadata = dora_core::message::Metadata::new(hlc.new_timestamp());
-                            // let data = metadata
-                            // .serialize()
-                            // .wrap_err("failed to serialize stop message")?;
-                            todo!("instruct dora-daemon/dora-coordinator to stop other nodes");
-                            // manual_stop_publisher
-                            //     .publish(&data)
-                            //     .map_err(|err| eyre::eyre!(err))
-                            //     .wrap_err("failed to send stop message")?;
-                            // break;
+                            // ask the daemon to stop all nodes of the dataflow
+                            node.stop_dataflow(Some(format!(
+                                "operator `{operator_id}` requested to stop the dataflow"
+                            )))
+                            .wrap_err("failed to request dataflow stop")?;
                         }
 
                         let Some(config) = operators.get(&operator_id) else {
@@ -191,7 +194,7 @@ async fn run(
                         let outputs = config
                             .outputs
                             .iter()
-                            .map(|output_id| operator_output_id(&operator_id, output_id))
+                            .map(|output| operator_output_id(&operator_id, &output.id))
                             .collect();
                         let result;
                         (node, result) = tokio::task::spawn_blocking(move || {
@@ -1,4 +1,4 @@
-use dora_core::config::DataId;
+use dora_core::config::{DataId, QueuePolicy};
 use dora_node_api::Event;
 use futures::{
     FutureExt,
@@ -9,12 +9,13 @@ use std::collections::{BTreeMap, VecDeque};
 pub fn channel(
     runtime: &tokio::runtime::Handle,
     queue_sizes: BTreeMap<DataId, usize>,
+    queue_policies: BTreeMap<DataId, QueuePolicy>,
 ) -> (flume::Sender<Event>, flume::Receiver<Event>) {
     let (incoming_tx, incoming_rx) = flume::bounded(10);
     let (outgoing_tx, outgoing_rx) = flume::bounded(0);
 
     runtime.spawn(async {
-        let mut buffer = InputBuffer::new(queue_sizes);
+        let mut buffer = InputBuffer::new(queue_sizes, queue_policies);
         buffer.run(incoming_rx, outgoing_tx).await;
     });
 
@@ -24,13 +25,18 @@ pub fn channel(
 struct InputBuffer {
     queue: VecDeque<Option<Event>>,
     queue_sizes: BTreeMap<DataId, usize>,
+    queue_policies: BTreeMap<DataId, QueuePolicy>,
 }
 
 impl InputBuffer {
-    pub fn new(queue_sizes: BTreeMap<DataId, usize>) -> Self {
+    pub fn new(
+        queue_sizes: BTreeMap<DataId, usize>,
+        queue_policies: BTreeMap<DataId, QueuePolicy>,
+    ) -> Self {
         Self {
             queue: VecDeque::new(),
             queue_sizes,
+            queue_policies,
         }
     }
 
@@ -38,7 +44,8 @@ impl InputBuffer {
         let mut send_out_buf = future::Fuse::terminated();
         let mut incoming_closed = false;
         loop {
-            let next_incoming = if incoming_closed {
+            // stop receiving while a blocking input queue is full to apply backpressure
+            let next_incoming = if incoming_closed || self.blocking_queue_full() {
                 future::Fuse::terminated()
             } else {
                 incoming.recv_async().fuse()
@@ -92,6 +99,14 @@ impl InputBuffer {
     }
 
     fn add_event(&mut self, event: Event) {
+        if let Event::Input { id: input_id, .. } = &event {
+            if self.queue_policy(input_id) == QueuePolicy::DropNewest
+                && self.queued_inputs(input_id) >= self.queue_size(input_id)
+            {
+                tracing::debug!("dropped new operator input `{input_id}` because queue was full");
+                return;
+            }
+        }
         self.queue.push_back(Some(event));
 
         // drop oldest input events to maintain max queue length queue
@@ -107,6 +122,9 @@ impl InputBuffer {
             let Some(Event::Input { id: input_id, .. }) = event.as_mut() else {
                 continue;
             };
+            if self.queue_policies.get(input_id) == Some(&QueuePolicy::Block) {
+                continue;
+            }
             match queue_size_remaining.get_mut(input_id) {
                 Some(0) => {
                     dropped += 1;
@@ -125,4 +143,32 @@ impl InputBuffer {
             tracing::debug!("dropped {dropped} operator inputs because event queue was too full");
         }
     }
+
+    fn blocking_queue_full(&self) -> bool {
+        self.queue_policies
+            .iter()
+            .filter(|(_, policy)| **policy == QueuePolicy::Block)
+            .any(|(input_id, _)| self.queued_inputs(input_id) >= self.queue_size(input_id))
+    }
+
+    fn queued_inputs(&self, input_id: &DataId) -> usize {
+        self.queue
+            .iter()
+            .filter(|event| matches!(event, Some(Event::Input { id, .. }) if id == input_id))
+            .count()
+    }
+
+    fn queue_size(&self, input_id: &DataId) -> usize {
+        self.queue_sizes
+            .get(input_id)
+            .copied()
+            .unwrap_or(usize::MAX)
+    }
+
+    fn queue_policy(&self, input_id: &DataId) -> QueuePolicy {
+        self.queue_policies
+            .get(input_id)
+            .copied()
+            .unwrap_or_default()
+    }
 }
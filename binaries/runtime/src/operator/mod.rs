@@ -12,6 +12,8 @@ pub mod channel;
 #[cfg(feature = "python")]
 mod python;
 mod shared_lib;
+#[cfg(feature = "wasm")]
+mod wasm;
 
 #[allow(unused_variables)]
 pub fn run_operator(
@@ -62,8 +64,28 @@ pub fn run_operator(
                 "Dora runtime tried spawning Python Operator outside of python environment."
             );
         }
-        OperatorSource::Wasm(_) => {
-            tracing::error!("WASM operators are not supported yet");
+        #[allow(unused_variables)]
+        OperatorSource::Wasm(source) => {
+            #[cfg(feature = "wasm")]
+            wasm::run(
+                node_id,
+                &operator_definition.id,
+                source,
+                events_tx,
+                incoming_events,
+                init_done,
+            )
+            .wrap_err_with(|| {
+                format!(
+                    "failed to spawn WASM operator for {}",
+                    operator_definition.id
+                )
+            })?;
+            #[cfg(not(feature = "wasm"))]
+            tracing::error!(
+                "Dora runtime was built without WASM operator support. \
+                Enable the `wasm` feature of `dora-cli` to run WASM operators."
+            );
         }
     }
     Ok(())
@@ -0,0 +1,489 @@
+use super::{OperatorEvent, StopReason};
+use aligned_vec::{AVec, ConstAlign};
+use arrow::{
+    array::{Array, ArrayData, RecordBatch},
+    datatypes::{Field, Schema},
+    ipc::{reader::StreamReader, writer::StreamWriter},
+};
+use dora_core::{
+    config::{DataId, NodeId, OperatorId},
+    descriptor::source_is_url,
+};
+use dora_download::download_file;
+use dora_node_api::{
+    Event, MetadataParameters,
+    arrow_utils::{copy_array_into_sample, required_data_size},
+};
+use eyre::{Context, Result, bail, eyre};
+use std::{
+    io::Cursor,
+    panic::{AssertUnwindSafe, catch_unwind},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+use tokio::sync::{mpsc::Sender, oneshot};
+use wasmtime::{
+    Config, Engine, Store,
+    component::{Component, Linker, ResourceTable},
+};
+use wasmtime_wasi::{IoView, WasiCtx, WasiCtxBuilder, WasiView};
+
+use bindings::{
+    Operator,
+    dora::operator::{host, types},
+};
+
+mod bindings {
+    wasmtime::component::bindgen!({
+        path: "wit",
+        world: "operator",
+    });
+}
+
+/// Interval in which the epoch of the WASM engine is incremented.
+const EPOCH_TICK: Duration = Duration::from_millis(10);
+
+/// Limits the execution of WASM operators per event.
+///
+/// An operator that exceeds a limit traps, which stops it with an error. This prevents
+/// operators that are stuck, e.g. in an endless loop, from blocking the runtime forever.
+#[derive(Debug, Clone, Copy)]
+struct ExecutionLimits {
+    /// Maximum amount of fuel, which corresponds roughly to the number of executed
+    /// WASM instructions.
+    fuel: u64,
+    /// Maximum wall-clock time, enforced through epoch interruption.
+    timeout: Duration,
+}
+
+impl Default for ExecutionLimits {
+    fn default() -> Self {
+        Self {
+            fuel: 10_000_000_000,
+            timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+pub fn run(
+    node_id: &NodeId,
+    operator_id: &OperatorId,
+    source: &str,
+    events_tx: Sender<OperatorEvent>,
+    incoming_events: flume::Receiver<Event>,
+    init_done: oneshot::Sender<Result<()>>,
+) -> eyre::Result<()> {
+    let path = if source_is_url(source) {
+        let target_path = &Path::new("build");
+        // try to download the WASM component
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+        rt.block_on(download_file(source, target_path))
+            .wrap_err("failed to download WASM operator")?
+    } else {
+        PathBuf::from(source)
+    };
+
+    let closure = AssertUnwindSafe(|| {
+        let host = WasmHost::new(path, events_tx.clone(), ExecutionLimits::default())?;
+        let operator = match host.instantiate() {
+            Ok(operator) => operator,
+            Err(err) => {
+                let _ = init_done.send(Err(eyre!("{err:?}")));
+                return Err(err.wrap_err(format!(
+                    "failed to instantiate WASM operator `{node_id}/{operator_id}`"
+                )));
+            }
+        };
+        let _ = init_done.send(Ok(()));
+
+        WasmOperator {
+            incoming_events,
+            host,
+            operator,
+        }
+        .run()
+    });
+    match catch_unwind(closure) {
+        Ok(Ok(reason)) => {
+            let _ = events_tx.blocking_send(OperatorEvent::Finished { reason });
+        }
+        Ok(Err(err)) => {
+            let _ = events_tx.blocking_send(OperatorEvent::Error(err));
+        }
+        Err(panic) => {
+            let _ = events_tx.blocking_send(OperatorEvent::Panic(panic));
+        }
+    }
+
+    Ok(())
+}
+
+/// Loads and instantiates the WASM component of an operator.
+struct WasmHost {
+    path: PathBuf,
+    engine: Engine,
+    linker: Linker<OperatorState>,
+    events_tx: Sender<OperatorEvent>,
+    limits: ExecutionLimits,
+    /// Stops incrementing the epoch of the engine when dropped.
+    _epoch_ticker: flume::Sender<()>,
+}
+
+impl WasmHost {
+    fn new(
+        path: PathBuf,
+        events_tx: Sender<OperatorEvent>,
+        limits: ExecutionLimits,
+    ) -> eyre::Result<Self> {
+        let mut config = Config::new();
+        config.consume_fuel(true).epoch_interruption(true);
+        let engine = Engine::new(&config)
+            .map_err(|err| eyre!("{err:?}"))
+            .wrap_err("failed to create WASM engine")?;
+        let mut linker = Linker::new(&engine);
+        wasmtime_wasi::add_to_linker_sync(&mut linker)
+            .map_err(|err| eyre!("{err:?}"))
+            .wrap_err("failed to add WASI to linker")?;
+        Operator::add_to_linker(&mut linker, |state: &mut OperatorState| state)
+            .map_err(|err| eyre!("{err:?}"))
+            .wrap_err("failed to add dora host functions to linker")?;
+        let epoch_ticker = spawn_epoch_ticker(engine.clone());
+        Ok(Self {
+            path,
+            engine,
+            linker,
+            events_tx,
+            limits,
+            _epoch_ticker: epoch_ticker,
+        })
+    }
+
+    /// Reads the component from disk and creates a new instance of it.
+    ///
+    /// The operator only gets access to stdout and stderr, it has no access to the
+    /// file system or the network.
+    fn instantiate(&self) -> eyre::Result<OperatorInstance> {
+        let component = Component::from_file(&self.engine, &self.path)
+            .map_err(|err| eyre!("{err:?}"))
+            .wrap_err_with(|| {
+                format!("failed to load WASM component at `{}`", self.path.display())
+            })?;
+        let state = OperatorState {
+            wasi: WasiCtxBuilder::new()
+                .inherit_stdout()
+                .inherit_stderr()
+                .build(),
+            table: ResourceTable::new(),
+            events_tx: self.events_tx.clone(),
+        };
+        let mut store = Store::new(&self.engine, state);
+        reset_limits(&mut store, self.limits)?;
+        let bindings = Operator::instantiate(&mut store, &component, &self.linker)
+            .map_err(|err| eyre!("{err:?}"))
+            .wrap_err("failed to instantiate WASM component")?;
+        Ok(OperatorInstance {
+            store,
+            bindings,
+            limits: self.limits,
+        })
+    }
+}
+
+struct OperatorInstance {
+    store: Store<OperatorState>,
+    bindings: Operator,
+    limits: ExecutionLimits,
+}
+
+struct WasmOperator {
+    incoming_events: flume::Receiver<Event>,
+    host: WasmHost,
+    operator: OperatorInstance,
+}
+
+impl WasmOperator {
+    fn run(mut self) -> eyre::Result<StopReason> {
+        let reason = loop {
+            let Ok(event) = self.incoming_events.recv() else {
+                break StopReason::InputsClosed;
+            };
+
+            let operator_event = match event {
+                Event::Stop(_) => types::Event::Stop,
+                Event::Input { id, metadata, data } => types::Event::Input(types::Input {
+                    id: id.to_string(),
+                    data: encode_arrow(data.0)?,
+                    metadata: serde_json::to_string(&metadata.parameters)
+                        .context("failed to serialize metadata parameters")?,
+                }),
+                Event::InputClosed { id } => types::Event::InputClosed(id.to_string()),
+                Event::Reload { .. } => {
+                    // WASM operators keep all their state in the component instance, so we
+                    // can safely replace it with a new instance
+                    match self.host.instantiate() {
+                        Ok(operator) => {
+                            self.operator = operator;
+                            tracing::info!("reloaded WASM operator");
+                        }
+                        Err(err) => tracing::error!("failed to reload WASM operator: {err:?}"),
+                    }
+                    continue;
+                }
+                Event::Error(err) => types::Event::Error(err),
+                other => {
+                    tracing::warn!("unexpected event: {other:?}");
+                    continue;
+                }
+            };
+
+            let OperatorInstance {
+                store,
+                bindings,
+                limits,
+            } = &mut self.operator;
+            reset_limits(store, *limits)?;
+            let status = bindings
+                .call_on_event(store, &operator_event)
+                .map_err(|err| eyre!("{err:?}"))
+                .wrap_err("WASM operator trapped")?;
+            match status {
+                Err(error) => bail!("on_event failed: {error}"),
+                Ok(types::Status::Continue) => {}
+                Ok(types::Status::Stop) => break StopReason::ExplicitStop,
+                Ok(types::Status::StopAll) => break StopReason::ExplicitStopAll,
+            }
+        };
+        Ok(reason)
+    }
+}
+
+struct OperatorState {
+    wasi: WasiCtx,
+    table: ResourceTable,
+    events_tx: Sender<OperatorEvent>,
+}
+
+impl IoView for OperatorState {
+    fn table(&mut self) -> &mut ResourceTable {
+        &mut self.table
+    }
+}
+
+impl WasiView for OperatorState {
+    fn ctx(&mut self) -> &mut WasiCtx {
+        &mut self.wasi
+    }
+}
+
+impl types::Host for OperatorState {}
+
+impl host::Host for OperatorState {
+    fn send_output(&mut self, id: String, data: Vec<u8>, metadata: String) -> Result<(), String> {
+        let arrow_array = decode_arrow(&data).map_err(|err| format!("{err:?}"))?;
+        let parameters: MetadataParameters = serde_json::from_str(&metadata)
+            .map_err(|err| format!("invalid metadata parameters: {err}"))?;
+
+        let total_len = required_data_size(&arrow_array);
+        let mut sample: AVec<u8, ConstAlign<128>> = AVec::__from_elem(128, 0, total_len);
+
+        let type_info = copy_array_into_sample(&mut sample, &arrow_array);
+
+        let event = OperatorEvent::Output {
+            output_id: DataId::from(id),
+            type_info,
+            parameters,
+            data: Some(sample.into()),
+        };
+
+        self.events_tx
+            .blocking_send(event)
+            .map_err(|_| "runtime process closed unexpectedly".to_owned())
+    }
+}
+
+/// Refills the fuel of the store and sets a new epoch deadline, before handling the next event.
+fn reset_limits(store: &mut Store<OperatorState>, limits: ExecutionLimits) -> eyre::Result<()> {
+    store
+        .set_fuel(limits.fuel)
+        .map_err(|err| eyre!("{err:?}"))
+        .wrap_err("failed to set fuel of WASM store")?;
+    let ticks = limits.timeout.as_nanos().div_ceil(EPOCH_TICK.as_nanos());
+    store.set_epoch_deadline(ticks.try_into().unwrap_or(u64::MAX));
+    Ok(())
+}
+
+/// Increments the epoch of the given engine every [`EPOCH_TICK`], until the returned sender
+/// is dropped.
+fn spawn_epoch_ticker(engine: Engine) -> flume::Sender<()> {
+    let (stop_tx, stop_rx) = flume::bounded(0);
+    std::thread::spawn(move || {
+        while let Err(flume::RecvTimeoutError::Timeout) = stop_rx.recv_timeout(EPOCH_TICK) {
+            engine.increment_epoch();
+        }
+    });
+    stop_tx
+}
+
+/// Encodes the given array as a single-column record batch in the Arrow IPC streaming format.
+fn encode_arrow(array: Arc<dyn Array>) -> eyre::Result<Vec<u8>> {
+    let schema = Arc::new(Schema::new(vec![Field::new(
+        "data",
+        array.data_type().clone(),
+        true,
+    )]));
+    let batch = RecordBatch::try_new(schema.clone(), vec![array])
+        .context("failed to create record batch")?;
+    let mut writer =
+        StreamWriter::try_new(Vec::new(), &schema).context("failed to create Arrow IPC writer")?;
+    writer
+        .write(&batch)
+        .context("failed to write Arrow IPC stream")?;
+    writer
+        .into_inner()
+        .context("failed to finish Arrow IPC stream")
+}
+
+/// Decodes an array that was encoded using [`encode_arrow`].
+fn decode_arrow(data: &[u8]) -> eyre::Result<ArrayData> {
+    let mut reader =
+        StreamReader::try_new(Cursor::new(data), None).context("invalid Arrow IPC stream")?;
+    let batch = reader
+        .next()
+        .ok_or_else(|| eyre!("Arrow IPC stream contains no record batch"))?
+        .context("failed to read record batch")?;
+    if batch.num_columns() != 1 {
+        bail!(
+            "expected a record batch with a single column, got {} columns",
+            batch.num_columns()
+        );
+    }
+    Ok(batch.column(0).to_data())
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::array::UInt8Array;
+    use dora_node_api::{Metadata, Parameter, uhlc::HLC};
+
+    use super::*;
+
+    /// An operator that never returns from `on-event`.
+    const ENDLESS_LOOP: &str = r#"
+(component
+  (type $input' (record (field "id" string) (field "data" (list u8)) (field "metadata" string)))
+  (export $input "input" (type $input'))
+  (type $event' (variant (case "input" $input) (case "input-closed" string) (case "stop") (case "error" string)))
+  (export $event "event" (type $event'))
+  (type $status' (enum "continue" "stop" "stop-all"))
+  (export $status "status" (type $status'))
+  (core module $main
+    (memory (export "memory") 1)
+    (func (export "realloc") (param i32 i32 i32 i32) (result i32)
+      (i32.const 1024))
+    (func (export "on-event") (param i32 i32 i32 i32 i32 i32 i32) (result i32)
+      (loop $forever (br $forever))
+      (i32.const 0))
+  )
+  (core instance $main (instantiate $main))
+  (func $on-event (param "event" $event) (result (result $status (error string)))
+    (canon lift (core func $main "on-event") (memory $main "memory") (realloc (func $main "realloc"))))
+  (export "on-event" (func $on-event))
+)
+"#;
+
+    fn echo_component() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("../../examples/wasm-dataflow/echo.wat")
+    }
+
+    fn run_operator(
+        path: PathBuf,
+        limits: ExecutionLimits,
+        events: Vec<Event>,
+    ) -> (eyre::Result<StopReason>, Vec<OperatorEvent>) {
+        let (events_tx, mut events_rx) = tokio::sync::mpsc::channel(10);
+        let (incoming_tx, incoming_events) = flume::unbounded();
+        for event in events {
+            incoming_tx.send(event).unwrap();
+        }
+        drop(incoming_tx);
+
+        let host = WasmHost::new(path, events_tx, limits).unwrap();
+        let operator = host.instantiate().unwrap();
+        let result = WasmOperator {
+            incoming_events,
+            host,
+            operator,
+        }
+        .run();
+
+        let mut sent = Vec::new();
+        while let Ok(event) = events_rx.try_recv() {
+            sent.push(event);
+        }
+        (result, sent)
+    }
+
+    #[test]
+    fn pass_data_and_metadata_through_operator() {
+        let array = UInt8Array::from(vec![1, 2, 3]);
+        let mut sample: AVec<u8, ConstAlign<128>> =
+            AVec::__from_elem(128, 0, required_data_size(&array.to_data()));
+        let type_info = copy_array_into_sample(&mut sample, &array.to_data());
+        let parameters = MetadataParameters::from([
+            ("seq".to_owned(), Parameter::Integer(7)),
+            ("frame".to_owned(), Parameter::String("camera".to_owned())),
+        ]);
+        let input = Event::Input {
+            id: DataId::from("message".to_owned()),
+            metadata: Metadata::from_parameters(
+                HLC::default().new_timestamp(),
+                type_info,
+                parameters.clone(),
+            ),
+            data: (Arc::new(array) as Arc<dyn Array>).into(),
+        };
+
+        let (result, sent) =
+            run_operator(echo_component(), ExecutionLimits::default(), vec![input]);
+        assert!(matches!(result, Ok(StopReason::InputsClosed)));
+        let [
+            OperatorEvent::Output {
+                output_id,
+                parameters: sent_parameters,
+                ..
+            },
+        ] = sent.as_slice()
+        else {
+            panic!("expected a single output, got {sent:?}");
+        };
+        assert_eq!(output_id.as_str(), "status");
+        assert_eq!(sent_parameters, &parameters);
+    }
+
+    #[test]
+    fn stop_operators_that_exceed_limits() {
+        let path =
+            std::env::temp_dir().join(format!("dora-wasm-endless-loop-{}.wat", std::process::id()));
+        std::fs::write(&path, ENDLESS_LOOP).unwrap();
+        let stop = || vec![Event::Stop(dora_node_api::StopCause::Manual)];
+
+        let fuel_limit = ExecutionLimits {
+            fuel: 1_000_000,
+            timeout: Duration::from_secs(600),
+        };
+        let (result, _) = run_operator(path.clone(), fuel_limit, stop());
+        assert!(format!("{:?}", result.unwrap_err()).contains("trapped"));
+
+        let time_limit = ExecutionLimits {
+            fuel: u64::MAX,
+            timeout: Duration::from_millis(50),
+        };
+        let (result, _) = run_operator(path.clone(), time_limit, stop());
+        assert!(format!("{:?}", result.unwrap_err()).contains("trapped"));
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -54,6 +54,8 @@ async fn main() -> eyre::Result<()> {
         coordinator_bind,
         coordinator_control_bind,
         ReceiverStream::new(coordinator_events_rx),
+        None,
+        Default::default(),
     )
     .await?;
 
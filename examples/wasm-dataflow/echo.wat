@@ -0,0 +1,62 @@
+;; A WebAssembly component implementing the `operator` world of
+;; `binaries/runtime/wit/operator.wit`, written by hand in the text format.
+;;
+;; It forwards the data and metadata of all inputs to the `status` output.
+(component
+  (type $input' (record (field "id" string) (field "data" (list u8)) (field "metadata" string)))
+  (export $input "input" (type $input'))
+  (type $event' (variant (case "input" $input) (case "input-closed" string) (case "stop") (case "error" string)))
+  (export $event "event" (type $event'))
+  (type $status' (enum "continue" "stop" "stop-all"))
+  (export $status "status" (type $status'))
+
+  (import "dora:operator/host@0.1.0" (instance $host
+    (export "send-output" (func (param "id" string) (param "data" (list u8)) (param "metadata" string) (result (result (error string)))))
+  ))
+
+  ;; memory and a simple bump allocator, used by the host to pass strings and lists
+  (core module $libc
+    (memory (export "memory") 1)
+    (global $heap (export "heap") (mut i32) (i32.const 1024))
+    (func (export "realloc") (param i32 i32 i32 i32) (result i32)
+      (local $ret i32)
+      (local.set $ret
+        (i32.and
+          (i32.add (global.get $heap) (i32.sub (local.get 2) (i32.const 1)))
+          (i32.xor (i32.sub (local.get 2) (i32.const 1)) (i32.const -1))))
+      (global.set $heap (i32.add (local.get $ret) (local.get 3)))
+      (if (i32.gt_u (global.get $heap) (i32.mul (memory.size) (i32.const 65536)))
+        (then (drop (memory.grow (i32.const 1)))))
+      (local.get $ret))
+  )
+  (core instance $libc (instantiate $libc))
+
+  (core func $send-output (canon lower (func $host "send-output")
+    (memory $libc "memory") (realloc (func $libc "realloc"))))
+
+  (core module $main
+    (import "libc" "memory" (memory 1))
+    (import "libc" "heap" (global $heap (mut i32)))
+    (import "host" "send-output" (func $send (param i32 i32 i32 i32 i32 i32 i32)))
+    (data (i32.const 16) "status")
+    (func (export "on-event") (param $disc i32) (param i32 i32 i32 i32 i32 i32) (result i32)
+      ;; echo all inputs on the `status` output
+      (if (i32.eqz (local.get $disc))
+        (then
+          (call $send (i32.const 16) (i32.const 6) (local.get 3) (local.get 4) (local.get 5) (local.get 6)
+            (i32.const 64))))
+      ;; free all memory that was allocated for this event
+      (global.set $heap (i32.const 1024))
+      ;; ok(continue)
+      (i32.store8 (i32.const 128) (i32.const 0))
+      (i32.store8 (i32.const 132) (i32.const 0))
+      (i32.const 128))
+  )
+  (core instance $main (instantiate $main
+    (with "libc" (instance $libc))
+    (with "host" (instance (export "send-output" (func $send-output))))))
+
+  (func $on-event (param "event" $event) (result (result $status (error string)))
+    (canon lift (core func $main "on-event") (memory $libc "memory") (realloc (func $libc "realloc"))))
+  (export "on-event" (func $on-event))
+)
@@ -0,0 +1,423 @@
+//! Derive macros for converting Rust types to and from Apache Arrow arrays.
+//!
+//! The macros are re-exported by the `dora-arrow-convert` crate, see its documentation for
+//! details on the generated Arrow data types.
+
+use proc_macro::TokenStream;
+use proc_macro_crate::{FoundCrate, crate_name};
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use syn::{Data, DeriveInput, Fields, Generics, Ident, Index, Member, Type, parse_macro_input};
+
+/// Derives `IntoArrow` and `IntoArrowArray` for a struct or enum.
+///
+/// Structs are converted to Arrow `Struct` arrays and enums to dense `Union` arrays. All
+/// fields must implement `IntoArrowArray`.
+#[proc_macro_derive(IntoArrow)]
+pub fn derive_into_arrow(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    into_arrow_impl(&input)
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
+
+/// Derives `FromArrowArray` and `TryFrom<&ArrowData>` for a struct or enum.
+///
+/// Reads the Arrow data types produced by the `IntoArrow` derive. All fields must implement
+/// `FromArrowArray`.
+#[proc_macro_derive(FromArrow)]
+pub fn derive_from_arrow(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    from_arrow_impl(&input)
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
+
+/// Returns the path of the crate that provides the conversion traits.
+///
+/// Nodes and operators usually depend on `dora-node-api` or `dora-operator-api` only, which
+/// re-export the `dora-arrow-convert` crate.
+fn crate_path() -> TokenStream2 {
+    for name in ["dora-arrow-convert", "dora-node-api", "dora-operator-api"] {
+        match crate_name(name) {
+            Ok(FoundCrate::Itself) if name == "dora-arrow-convert" => {
+                return quote!(::dora_arrow_convert);
+            }
+            Ok(FoundCrate::Itself) => return quote!(crate),
+            Ok(FoundCrate::Name(name)) => {
+                let ident = Ident::new(&name, Span::call_site());
+                return quote!(::#ident);
+            }
+            Err(_) => {}
+        }
+    }
+    quote!(::dora_arrow_convert)
+}
+
+/// Fields of a struct or enum variant.
+struct FieldList {
+    /// Column names in the Arrow struct.
+    names: Vec<String>,
+    members: Vec<Member>,
+    types: Vec<Type>,
+    kind: FieldsKind,
+}
+
+#[derive(PartialEq)]
+enum FieldsKind {
+    Named,
+    Unnamed,
+    Unit,
+}
+
+impl FieldList {
+    fn new(fields: &Fields) -> Self {
+        let kind = match fields {
+            Fields::Named(_) => FieldsKind::Named,
+            Fields::Unnamed(_) => FieldsKind::Unnamed,
+            Fields::Unit => FieldsKind::Unit,
+        };
+        let members: Vec<_> = fields
+            .iter()
+            .enumerate()
+            .map(|(i, field)| match &field.ident {
+                Some(ident) => Member::Named(ident.clone()),
+                None => Member::Unnamed(Index::from(i)),
+            })
+            .collect();
+        let names = members
+            .iter()
+            .map(|member| match member {
+                Member::Named(ident) => ident.to_string(),
+                Member::Unnamed(index) => index.index.to_string(),
+            })
+            .collect();
+        Self {
+            names,
+            members,
+            types: fields.iter().map(|f| f.ty.clone()).collect(),
+            kind,
+        }
+    }
+
+    /// Single-field tuple structs are converted like their inner value.
+    fn is_newtype(&self) -> bool {
+        self.kind == FieldsKind::Unnamed && self.members.len() == 1
+    }
+
+    /// Returns a pattern that binds the fields to the given identifiers.
+    fn pattern(&self, path: TokenStream2, bindings: &[Ident]) -> TokenStream2 {
+        let members = &self.members;
+        match self.kind {
+            FieldsKind::Named => quote!(#path { #(#members: #bindings),* }),
+            FieldsKind::Unnamed => quote!(#path ( #(#bindings),* )),
+            FieldsKind::Unit => path,
+        }
+    }
+
+    /// Returns an expression that constructs the value from the given field expressions.
+    fn construct(&self, path: TokenStream2, values: &[TokenStream2]) -> TokenStream2 {
+        let members = &self.members;
+        match self.kind {
+            FieldsKind::Named => quote!(#path { #(#members: #values),* }),
+            FieldsKind::Unnamed => quote!(#path ( #(#values),* )),
+            FieldsKind::Unit => path,
+        }
+    }
+}
+
+/// Adds a bound on the given trait to all type parameters.
+fn add_trait_bounds(generics: &Generics, bound: TokenStream2) -> syn::Result<Generics> {
+    let mut generics = generics.clone();
+    for param in generics.type_params_mut() {
+        param.bounds.push(syn::parse2(bound.clone())?);
+    }
+    Ok(generics)
+}
+
+fn into_arrow_impl(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let krate = crate_path();
+    let support = quote!(#krate::derive_support);
+    let name = &input.ident;
+    let generics = add_trait_bounds(&input.generics, quote!(#krate::IntoArrowArray))?;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let body = match &input.data {
+        Data::Struct(data) => {
+            let fields = FieldList::new(&data.fields);
+            if fields.is_newtype() {
+                let ty = &fields.types[0];
+                quote! {
+                    <#ty as #krate::IntoArrowArray>::into_array(
+                        values.into_iter().map(|v| v.map(|Self(inner)| inner)).collect(),
+                    )
+                }
+            } else {
+                let columns = columns(&fields, "field");
+                let bindings = bindings(&fields);
+                let pattern = fields.pattern(quote!(Self), &bindings);
+                let declare = declare_columns(&columns);
+                let push_some = push_some(&columns, &bindings);
+                let push_none = push_none(&columns);
+                let array = struct_array(&krate, &fields, &columns, quote!(validity));
+                quote! {
+                    let validity: ::std::vec::Vec<bool> =
+                        values.iter().map(::std::option::Option::is_some).collect();
+                    #declare
+                    for value in values {
+                        match value {
+                            ::std::option::Option::Some(#pattern) => { #push_some }
+                            ::std::option::Option::None => { #push_none }
+                        }
+                    }
+                    #array
+                }
+            }
+        }
+        Data::Enum(data) => {
+            if data.variants.is_empty() {
+                return Err(syn::Error::new_spanned(
+                    input,
+                    "cannot derive `IntoArrow` for enums without variants",
+                ));
+            }
+            if data.variants.len() > i8::MAX as usize {
+                return Err(syn::Error::new_spanned(
+                    input,
+                    "cannot derive `IntoArrow` for enums with more than 127 variants",
+                ));
+            }
+
+            let mut declare = Vec::new();
+            let mut arms = Vec::new();
+            let mut children = Vec::new();
+            let mut push_null = TokenStream2::new();
+            for (i, variant) in data.variants.iter().enumerate() {
+                let type_id = i as i8;
+                let fields = FieldList::new(&variant.fields);
+                let columns = columns(&fields, &format!("variant{i}"));
+                let validity = format_ident!("__variant{}_validity", i);
+                let ident = &variant.ident;
+                let bindings = bindings(&fields);
+                let pattern = fields.pattern(quote!(Self::#ident), &bindings);
+                let push_some = push_some(&columns, &bindings);
+
+                let declare_columns = declare_columns(&columns);
+                declare.push(quote! {
+                    let mut #validity: ::std::vec::Vec<bool> = ::std::vec::Vec::new();
+                    #declare_columns
+                });
+                arms.push(quote! {
+                    ::std::option::Option::Some(#pattern) => {
+                        type_ids.push(#type_id);
+                        offsets.push(#validity.len() as i32);
+                        #validity.push(true);
+                        #push_some
+                    }
+                });
+                if i == 0 {
+                    // nulls are stored in the first variant
+                    let push_none = push_none(&columns);
+                    push_null = quote! {
+                        type_ids.push(0);
+                        offsets.push(#validity.len() as i32);
+                        #validity.push(false);
+                        #push_none
+                    };
+                }
+                let variant_name = ident.to_string();
+                let array = struct_array(&krate, &fields, &columns, quote!(#validity));
+                children.push(quote!((#variant_name, #array)));
+            }
+            quote! {
+                let mut type_ids: ::std::vec::Vec<i8> = ::std::vec::Vec::with_capacity(values.len());
+                let mut offsets: ::std::vec::Vec<i32> = ::std::vec::Vec::with_capacity(values.len());
+                #(#declare)*
+                for value in values {
+                    match value {
+                        #(#arms)*
+                        ::std::option::Option::None => { #push_null }
+                    }
+                }
+                #support::union_array(::std::vec![#(#children),*], type_ids, offsets)
+            }
+        }
+        Data::Union(_) => {
+            return Err(syn::Error::new_spanned(
+                input,
+                "cannot derive `IntoArrow` for unions",
+            ));
+        }
+    };
+
+    Ok(quote! {
+        #[automatically_derived]
+        impl #impl_generics #krate::IntoArrowArray for #name #ty_generics #where_clause {
+            fn into_array(
+                values: ::std::vec::Vec<::std::option::Option<Self>>,
+            ) -> #support::ArrayRef {
+                #body
+            }
+        }
+
+        #[automatically_derived]
+        impl #impl_generics #krate::IntoArrow for #name #ty_generics #where_clause {
+            type A = #support::ArrayRef;
+
+            fn into_arrow(self) -> #support::ArrayRef {
+                <Self as #krate::IntoArrowArray>::into_array(::std::vec![::std::option::Option::Some(self)])
+            }
+        }
+    })
+}
+
+fn from_arrow_impl(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let krate = crate_path();
+    let support = quote!(#krate::derive_support);
+    let name = &input.ident;
+    let generics = add_trait_bounds(&input.generics, quote!(#krate::FromArrowArray))?;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let (body, is_null) = match &input.data {
+        Data::Struct(data) => {
+            let fields = FieldList::new(&data.fields);
+            if fields.is_newtype() {
+                let ty = &fields.types[0];
+                let body = quote! {
+                    <#ty as #krate::FromArrowArray>::from_array(array, index).map(Self)
+                };
+                let is_null = quote! {
+                    <#ty as #krate::FromArrowArray>::is_null(array, index)
+                };
+                (body, Some(is_null))
+            } else {
+                let values = read_fields(&support, &fields);
+                let construct = fields.construct(quote!(Self), &values);
+                let body = quote! {
+                    let array = #support::as_struct::<Self>(array, index)?;
+                    ::std::result::Result::Ok(#construct)
+                };
+                (body, None)
+            }
+        }
+        Data::Enum(data) => {
+            let arms = data.variants.iter().map(|variant| {
+                let ident = &variant.ident;
+                let variant_name = ident.to_string();
+                let fields = FieldList::new(&variant.fields);
+                let values = read_fields(&support, &fields);
+                let construct = fields.construct(quote!(Self::#ident), &values);
+                quote!(#variant_name => ::std::result::Result::Ok(#construct),)
+            });
+            let body = quote! {
+                let (variant, array, index) = #support::as_union::<Self>(array, index)?;
+                match variant {
+                    #(#arms)*
+                    other => ::std::result::Result::Err(#support::unknown_variant::<Self>(other)),
+                }
+            };
+            let is_null = quote!(#support::union_is_null(array, index));
+            (body, Some(is_null))
+        }
+        Data::Union(_) => {
+            return Err(syn::Error::new_spanned(
+                input,
+                "cannot derive `FromArrow` for unions",
+            ));
+        }
+    };
+    let is_null = is_null.map(|is_null| {
+        quote! {
+            fn is_null(array: &dyn #support::Array, index: usize) -> bool {
+                #is_null
+            }
+        }
+    });
+
+    Ok(quote! {
+        #[automatically_derived]
+        impl #impl_generics #krate::FromArrowArray for #name #ty_generics #where_clause {
+            fn from_array(
+                array: &dyn #support::Array,
+                index: usize,
+            ) -> #support::eyre::Result<Self> {
+                #body
+            }
+
+            #is_null
+        }
+
+        #[automatically_derived]
+        impl #impl_generics ::std::convert::TryFrom<&#krate::ArrowData> for #name #ty_generics #where_clause {
+            type Error = #support::eyre::Report;
+
+            fn try_from(value: &#krate::ArrowData) -> ::std::result::Result<Self, Self::Error> {
+                #support::from_single(value)
+            }
+        }
+    })
+}
+
+/// Column of a struct field during conversion.
+struct Column {
+    ident: Ident,
+    ty: Type,
+}
+
+fn columns(fields: &FieldList, prefix: &str) -> Vec<Column> {
+    fields
+        .types
+        .iter()
+        .enumerate()
+        .map(|(i, ty)| Column {
+            ident: format_ident!("__{}_{}", prefix, i),
+            ty: ty.clone(),
+        })
+        .collect()
+}
+
+fn bindings(fields: &FieldList) -> Vec<Ident> {
+    (0..fields.types.len())
+        .map(|i| format_ident!("__value{}", i))
+        .collect()
+}
+
+fn declare_columns(columns: &[Column]) -> TokenStream2 {
+    let declare = columns.iter().map(|Column { ident, ty }| {
+        quote! {
+            let mut #ident: ::std::vec::Vec<::std::option::Option<#ty>> = ::std::vec::Vec::new();
+        }
+    });
+    quote!(#(#declare)*)
+}
+
+fn push_some(columns: &[Column], bindings: &[Ident]) -> TokenStream2 {
+    let idents = columns.iter().map(|c| &c.ident);
+    quote!(#(#idents.push(::std::option::Option::Some(#bindings));)*)
+}
+
+fn push_none(columns: &[Column]) -> TokenStream2 {
+    let idents = columns.iter().map(|c| &c.ident);
+    quote!(#(#idents.push(::std::option::Option::None);)*)
+}
+
+fn struct_array(
+    krate: &TokenStream2,
+    fields: &FieldList,
+    columns: &[Column],
+    validity: TokenStream2,
+) -> TokenStream2 {
+    let arrays = columns.iter().zip(&fields.names).map(|(column, name)| {
+        let Column { ident, ty } = column;
+        quote!((#name, <#ty as #krate::IntoArrowArray>::into_array(#ident)))
+    });
+    quote!(#krate::derive_support::struct_array(::std::vec![#(#arrays),*], #validity))
+}
+
+fn read_fields(support: &TokenStream2, fields: &FieldList) -> Vec<TokenStream2> {
+    fields
+        .names
+        .iter()
+        .map(|name| quote!(#support::field(array, #name, index)?))
+        .collect()
+}
@@ -0,0 +1,122 @@
+use std::sync::Arc;
+
+use arrow::{
+    array::{Array, ArrayRef, AsArray, BooleanArray, ListArray, PrimitiveArray, StringArray},
+    buffer::OffsetBuffer,
+    datatypes::{
+        Field, Float16Type, Float32Type, Float64Type, Int8Type, Int16Type, Int32Type, Int64Type,
+        UInt8Type, UInt16Type, UInt32Type, UInt64Type,
+    },
+};
+use eyre::{ContextCompat, Result, bail};
+use half::f16;
+
+use crate::{FromArrowArray, IntoArrowArray, derive_support::null_buffer};
+
+impl IntoArrowArray for bool {
+    fn into_array(values: Vec<Option<Self>>) -> ArrayRef {
+        Arc::new(BooleanArray::from(values))
+    }
+}
+
+impl FromArrowArray for bool {
+    fn from_array(array: &dyn Array, index: usize) -> Result<Self> {
+        let array = array.as_boolean_opt().context("not a bool array")?;
+        if array.is_null(index) {
+            bail!("unexpected null value");
+        }
+        Ok(array.value(index))
+    }
+}
+
+macro_rules! impl_arrow_array {
+    ($($t:ty => $arrow_type:ty),*) => {
+        $(
+            impl IntoArrowArray for $t {
+                fn into_array(values: Vec<Option<Self>>) -> ArrayRef {
+                    Arc::new(PrimitiveArray::<$arrow_type>::from(values))
+                }
+            }
+
+            impl FromArrowArray for $t {
+                fn from_array(array: &dyn Array, index: usize) -> Result<Self> {
+                    let array = array
+                        .as_primitive_opt::<$arrow_type>()
+                        .context(concat!("not a primitive ", stringify!($arrow_type), " array"))?;
+                    if array.is_null(index) {
+                        bail!("unexpected null value");
+                    }
+                    Ok(array.value(index))
+                }
+            }
+        )*
+    };
+}
+
+impl_arrow_array!(
+    u8 => UInt8Type,
+    u16 => UInt16Type,
+    u32 => UInt32Type,
+    u64 => UInt64Type,
+    i8 => Int8Type,
+    i16 => Int16Type,
+    i32 => Int32Type,
+    i64 => Int64Type,
+    f16 => Float16Type,
+    f32 => Float32Type,
+    f64 => Float64Type
+);
+
+impl IntoArrowArray for String {
+    fn into_array(values: Vec<Option<Self>>) -> ArrayRef {
+        Arc::new(StringArray::from(values))
+    }
+}
+
+impl FromArrowArray for String {
+    fn from_array(array: &dyn Array, index: usize) -> Result<Self> {
+        let array = array.as_string_opt::<i32>().context("not a string array")?;
+        if array.is_null(index) {
+            bail!("unexpected null value");
+        }
+        Ok(array.value(index).to_owned())
+    }
+}
+
+impl<T: IntoArrowArray> IntoArrowArray for Option<T> {
+    fn into_array(values: Vec<Option<Self>>) -> ArrayRef {
+        T::into_array(values.into_iter().map(Option::flatten).collect())
+    }
+}
+
+impl<T: FromArrowArray> FromArrowArray for Option<T> {
+    fn from_array(array: &dyn Array, index: usize) -> Result<Self> {
+        if T::is_null(array, index) {
+            Ok(None)
+        } else {
+            T::from_array(array, index).map(Some)
+        }
+    }
+}
+
+impl<T: IntoArrowArray> IntoArrowArray for Vec<T> {
+    fn into_array(values: Vec<Option<Self>>) -> ArrayRef {
+        let offsets =
+            OffsetBuffer::from_lengths(values.iter().map(|v| v.as_ref().map_or(0, Vec::len)));
+        let nulls = null_buffer(values.iter().map(Option::is_some).collect());
+        let items = T::into_array(values.into_iter().flatten().flatten().map(Some).collect());
+        let field = Arc::new(Field::new("item", items.data_type().clone(), true));
+        Arc::new(ListArray::new(field, offsets, items, nulls))
+    }
+}
+
+impl<T: FromArrowArray> FromArrowArray for Vec<T> {
+    fn from_array(array: &dyn Array, index: usize) -> Result<Self> {
+        let array = array.as_list_opt::<i32>().context("not a list array")?;
+        if array.is_null(index) {
+            bail!("unexpected null value");
+        }
+        let items = array.value(index);
+        (0..items.len()).map(|i| T::from_array(&items, i)).collect()
+    }
+}
@@ -0,0 +1,129 @@
+//! Helper functions for the code generated by the `IntoArrow` and `FromArrow` derive macros.
+
+use std::{any::type_name, sync::Arc};
+
+use ::eyre::{ContextCompat, WrapErr, bail};
+use arrow::{
+    array::{AsArray, StructArray, UnionArray},
+    buffer::NullBuffer,
+    datatypes::{DataType, Field, UnionFields},
+};
+
+use crate::{ArrowData, FromArrowArray};
+
+pub use ::eyre;
+pub use arrow::array::{Array, ArrayRef};
+
+/// Creates a null buffer from the given validity, or `None` if all values are valid.
+pub fn null_buffer(validity: Vec<bool>) -> Option<NullBuffer> {
+    if validity.iter().all(|valid| *valid) {
+        None
+    } else {
+        Some(NullBuffer::from(validity))
+    }
+}
+
+pub fn struct_array(fields: Vec<(&str, ArrayRef)>, validity: Vec<bool>) -> ArrayRef {
+    let len = validity.len();
+    let nulls = null_buffer(validity);
+    if fields.is_empty() {
+        return Arc::new(StructArray::new_empty_fields(len, nulls));
+    }
+    let (fields, arrays): (Vec<_>, Vec<_>) = fields
+        .into_iter()
+        .map(|(name, array)| (Field::new(name, array.data_type().clone(), true), array))
+        .unzip();
+    Arc::new(StructArray::new(fields.into(), arrays, nulls))
+}
+
+pub fn union_array(
+    variants: Vec<(&str, ArrayRef)>,
+    type_ids: Vec<i8>,
+    offsets: Vec<i32>,
+) -> ArrayRef {
+    let fields = UnionFields::new(
+        0..variants.len() as i8,
+        variants
+            .iter()
+            .map(|(name, array)| Field::new(*name, array.data_type().clone(), true)),
+    );
+    let children = variants.into_iter().map(|(_, array)| array).collect();
+    let array = UnionArray::try_new(fields, type_ids.into(), Some(offsets.into()), children)
+        .expect("derived union array is invalid");
+    Arc::new(array)
+}
+
+/// Returns the given array as struct array, checking that the element at `index` is not null.
+pub fn as_struct<T>(array: &dyn Array, index: usize) -> eyre::Result<&StructArray> {
+    let array = array.as_struct_opt().wrap_err_with(|| {
+        format!(
+            "expected struct array for `{}`, got {}",
+            type_name::<T>(),
+            array.data_type()
+        )
+    })?;
+    if array.is_null(index) {
+        bail!("unexpected null value for `{}`", type_name::<T>());
+    }
+    Ok(array)
+}
+
+/// Reads the given field of the struct element at `index`.
+pub fn field<T: FromArrowArray>(array: &StructArray, name: &str, index: usize) -> eyre::Result<T> {
+    let column = array
+        .column_by_name(name)
+        .wrap_err_with(|| format!("missing field `{name}`"))?;
+    T::from_array(column, index).wrap_err_with(|| format!("failed to read field `{name}`"))
+}
+
+/// Returns the variant name, the variant struct array, and the index in the variant array
+/// of the union element at `index`.
+pub fn as_union<T>(array: &dyn Array, index: usize) -> eyre::Result<(&str, &StructArray, usize)> {
+    let union: &UnionArray = array.as_any().downcast_ref().wrap_err_with(|| {
+        format!(
+            "expected union array for `{}`, got {}",
+            type_name::<T>(),
+            array.data_type()
+        )
+    })?;
+    let DataType::Union(fields, _) = union.data_type() else {
+        bail!("union array has no union data type");
+    };
+    let type_id = union.type_id(index);
+    let name = fields
+        .iter()
+        .find(|(id, _)| *id == type_id)
+        .map(|(_, field)| field.name().as_str())
+        .wrap_err_with(|| format!("invalid type ID {type_id}"))?;
+    let variant = union
+        .child(type_id)
+        .as_struct_opt()
+        .wrap_err_with(|| format!("variant `{name}` is not a struct array"))?;
+    let offset = union.value_offset(index);
+    if variant.is_null(offset) {
+        bail!("unexpected null value for `{}`", type_name::<T>());
+    }
+    Ok((name, variant, offset))
+}
+
+/// Null values of unions are stored in the variant arrays.
+pub fn union_is_null(array: &dyn Array, index: usize) -> bool {
+    match array.as_any().downcast_ref::<UnionArray>() {
+        Some(union) => union
+            .child(union.type_id(index))
+            .is_null(union.value_offset(index)),
+        None => array.is_null(index),
+    }
+}
+
+pub fn unknown_variant<T>(name: &str) -> eyre::Report {
+    ::eyre::eyre!("unknown variant `{name}` for `{}`", type_name::<T>())
+}
+
+/// Reads a single value from the given data, which must have length 1.
+pub fn from_single<T: FromArrowArray>(data: &ArrowData) -> eyre::Result<T> {
+    if data.len() != 1 {
+        bail!("expected length 1, got {}", data.len());
+    }
+    T::from_array(data.0.as_ref(), 0)
+}
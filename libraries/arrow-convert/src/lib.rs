@@ -1,18 +1,78 @@
 //! Provides functions for converting between Apache Arrow arrays and Rust data types.
+//!
+//! ## Deriving conversions
+//!
+//! Custom structs and enums can be converted through the [`IntoArrow`](macro@IntoArrow) and
+//! [`FromArrow`](macro@FromArrow) derive macros:
+//!
+//! ```
+//! use dora_arrow_convert::{ArrowData, FromArrow, IntoArrow};
+//! use std::sync::Arc;
+//!
+//! #[derive(Debug, PartialEq, IntoArrow, FromArrow)]
+//! struct Detection {
+//!     label: String,
+//!     confidence: f32,
+//!     bbox: Vec<f32>,
+//!     track_id: Option<u64>,
+//! }
+//!
+//! let detection = Detection {
+//!     label: "cat".into(),
+//!     confidence: 0.9,
+//!     bbox: vec![10., 20., 30., 40.],
+//!     track_id: None,
+//! };
+//! let data = ArrowData(Arc::new(detection.into_arrow()));
+//! let received: Detection = (&data).try_into().unwrap();
+//! assert_eq!(received.label, "cat");
+//! ```
+//!
+//! Structs are converted to single-element Arrow `Struct` arrays with one nullable field per
+//! struct field. Tuple struct fields are named `0`, `1`, and so on. Single-field tuple structs
+//! (newtypes) are converted like their inner value instead.
+//!
+//! Enums are converted to dense `Union` arrays with one child per variant. Each child is a
+//! `Struct` array containing the variant fields, so unit variants are represented by structs
+//! without fields.
+//!
+//! Fields can be of any type implementing [`IntoArrowArray`] and [`FromArrowArray`], which
+//! includes primitives, strings, other derived types, and `Option`s and `Vec`s of them. `Vec`s
+//! are stored as Arrow `List` arrays and `None` values as nulls.
+//!
+//! ## Tensors
+//!
+//! With the `ndarray` feature enabled, `ndarray::Array`s can be converted into flat Arrow
+//! primitive arrays through [`IntoArrow`]. Received tensors can be read back through
+//! `ndarray_view` and `into_ndarray`, using the `shape` and `strides` metadata parameters
+//! defined in `dora_message::metadata::tensor`.
 
 #![warn(missing_docs)]
 
+// allows the derive macros to refer to this crate as `::dora_arrow_convert` in tests
+extern crate self as dora_arrow_convert;
+
 use arrow::array::{
-    Array, Float32Array, Float64Array, Int8Array, Int16Array, Int32Array, Int64Array, UInt8Array,
-    UInt16Array, UInt32Array,
+    Array, ArrayRef, Float32Array, Float64Array, Int8Array, Int16Array, Int32Array, Int64Array,
+    UInt8Array, UInt16Array, UInt32Array,
 };
 use arrow::datatypes::DataType;
 use eyre::{ContextCompat, Result, eyre};
 use num::NumCast;
 use std::ops::{Deref, DerefMut};
 
+pub use dora_arrow_convert_macros::{FromArrow, IntoArrow};
+
+mod array_impls;
+#[doc(hidden)]
+pub mod derive_support;
 mod from_impls;
 mod into_impls;
+#[cfg(feature = "ndarray")]
+mod ndarray_impls;
+
+#[cfg(feature = "ndarray")]
+pub use ndarray_impls::{TensorElement, into_ndarray, ndarray_view};
 
 /// Data that can be converted to an Arrow array.
 pub trait IntoArrow {
@@ -23,6 +83,31 @@ pub trait IntoArrow {
     fn into_arrow(self) -> Self::A;
 }
 
+/// Data that can be stored as elements of an Arrow array.
+///
+/// This is used to convert nested data, e.g. the fields of types that derive
+/// [`IntoArrow`](macro@IntoArrow). It is implemented for primitives, strings, derived types, and
+/// for `Option`s and `Vec`s of them.
+pub trait IntoArrowArray: Sized {
+    /// Converts the given values into an Arrow array with one element per value.
+    ///
+    /// `None` values are stored as nulls.
+    fn into_array(values: Vec<Option<Self>>) -> ArrayRef;
+}
+
+/// Data that can be read from the elements of an Arrow array.
+///
+/// Counterpart of [`IntoArrowArray`], used by the [`FromArrow`](macro@FromArrow) derive macro.
+pub trait FromArrowArray: Sized {
+    /// Reads the element at the given index.
+    fn from_array(array: &dyn Array, index: usize) -> Result<Self>;
+
+    /// Checks whether the element at the given index is null.
+    fn is_null(array: &dyn Array, index: usize) -> bool {
+        array.is_null(index)
+    }
+}
+
 /// Wrapper type for an Arrow [`ArrayRef`](arrow::array::ArrayRef).
 #[derive(Debug)]
 pub struct ArrowData(pub arrow::array::ArrayRef);
@@ -0,0 +1,133 @@
+use std::mem::size_of;
+
+use arrow::{
+    array::{Array, AsArray, PrimitiveArray},
+    buffer::ScalarBuffer,
+    datatypes::{
+        ArrowNativeType, ArrowPrimitiveType, Float16Type, Float32Type, Float64Type, Int8Type,
+        Int16Type, Int32Type, Int64Type, UInt8Type, UInt16Type, UInt32Type, UInt64Type,
+    },
+};
+use eyre::{Context, ContextCompat, Result, bail};
+use half::f16;
+use ndarray::{ArrayD, ArrayViewD, Dimension, IxDyn, ShapeBuilder};
+
+use crate::IntoArrow;
+
+/// Element types of tensors that can be converted from and to Arrow primitive arrays.
+pub trait TensorElement: ArrowNativeType {
+    /// The corresponding Arrow primitive type.
+    type ArrowType: ArrowPrimitiveType<Native = Self>;
+}
+
+macro_rules! impl_tensor_element {
+    ($($t:ty => $arrow_type:ty),*) => {
+        $(
+            impl TensorElement for $t {
+                type ArrowType = $arrow_type;
+            }
+        )*
+    };
+}
+
+impl_tensor_element!(
+    u8 => UInt8Type,
+    u16 => UInt16Type,
+    u32 => UInt32Type,
+    u64 => UInt64Type,
+    i8 => Int8Type,
+    i16 => Int16Type,
+    i32 => Int32Type,
+    i64 => Int64Type,
+    f16 => Float16Type,
+    f32 => Float32Type,
+    f64 => Float64Type
+);
+
+/// Flattens the array in row-major order.
+///
+/// The shape is not part of the resulting Arrow array, it should be sent as `shape` metadata
+/// parameter instead. Arrays in standard layout are converted without copying.
+impl<T: TensorElement, D: Dimension> IntoArrow for ndarray::Array<T, D> {
+    type A = PrimitiveArray<T::ArrowType>;
+
+    fn into_arrow(self) -> Self::A {
+        let len = self.len();
+        let values = if self.is_standard_layout() {
+            let (mut values, offset) = self.into_raw_vec_and_offset();
+            values.drain(..offset.unwrap_or(0));
+            values.truncate(len);
+            values
+        } else {
+            self.iter().copied().collect()
+        };
+        PrimitiveArray::new(ScalarBuffer::from(values), None)
+    }
+}
+
+/// Borrows the given Arrow primitive array as n-dimensional array view.
+///
+/// The `strides` are given in bytes, following the `strides` metadata convention. Without
+/// strides, the data is interpreted as contiguous array in row-major order.
+pub fn ndarray_view<'a, T: TensorElement>(
+    array: &'a dyn Array,
+    shape: &[usize],
+    strides: Option<&[usize]>,
+) -> Result<ArrayViewD<'a, T>> {
+    let array = array.as_primitive_opt::<T::ArrowType>().with_context(|| {
+        format!(
+            "expected {} array, got {}",
+            T::ArrowType::DATA_TYPE,
+            array.data_type()
+        )
+    })?;
+    if array.null_count() != 0 {
+        bail!("tensor data must not contain null values");
+    }
+    let values: &[T] = array.values().as_ref();
+    match strides {
+        Some(strides) => {
+            if strides.len() != shape.len() {
+                bail!(
+                    "strides {strides:?} don't match the number of dimensions of shape {shape:?}"
+                );
+            }
+            let strides = strides
+                .iter()
+                .map(|stride| {
+                    if stride % size_of::<T>() == 0 {
+                        Ok(stride / size_of::<T>())
+                    } else {
+                        Err(eyre::eyre!(
+                            "stride {stride} is not a multiple of the element size {}",
+                            size_of::<T>()
+                        ))
+                    }
+                })
+                .collect::<Result<Vec<_>>>()?;
+            ArrayViewD::from_shape(IxDyn(shape).strides(IxDyn(&strides)), values)
+        }
+        None => {
+            let expected: usize = shape.iter().product();
+            if values.len() != expected {
+                bail!(
+                    "shape {shape:?} requires {expected} elements, but the array has {}",
+                    values.len()
+                );
+            }
+            ArrayViewD::from_shape(IxDyn(shape), values)
+        }
+    }
+    .context("tensor layout does not match the array")
+}
+
+/// Copies the given Arrow primitive array into an owned n-dimensional array.
+///
+/// See [`ndarray_view`] for details.
+pub fn into_ndarray<T: TensorElement>(
+    array: &dyn Array,
+    shape: &[usize],
+    strides: Option<&[usize]>,
+) -> Result<ArrayD<T>> {
+    ndarray_view(array, shape, strides).map(|view| view.to_owned())
+}
@@ -0,0 +1,201 @@
+use arrow::datatypes::DataType;
+use dora_arrow_convert::{ArrowData, FromArrow, IntoArrow};
+use std::sync::Arc;
+
+#[derive(Debug, Clone, PartialEq, IntoArrow, FromArrow)]
+struct Point {
+    x: f64,
+    y: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, IntoArrow, FromArrow)]
+struct Detection {
+    label: String,
+    confidence: f32,
+    center: Point,
+    track_id: Option<u64>,
+    keypoints: Vec<Point>,
+    tags: Vec<Option<String>>,
+}
+
+#[derive(Debug, Clone, PartialEq, IntoArrow, FromArrow)]
+struct Meters(f64);
+
+#[derive(Debug, Clone, PartialEq, IntoArrow, FromArrow)]
+struct Pair(i32, String);
+
+#[derive(Debug, Clone, PartialEq, IntoArrow, FromArrow)]
+struct Marker;
+
+#[derive(Debug, Clone, PartialEq, IntoArrow, FromArrow)]
+enum Command {
+    Stop,
+    Move { target: Point, speed: Option<f32> },
+    Say(String),
+    Rotate(f64, bool),
+}
+
+/// Variant names must not clash with the associated type of `IntoArrow`.
+#[derive(Debug, Clone, PartialEq, IntoArrow, FromArrow)]
+enum Choice {
+    A(u8),
+    B,
+}
+
+#[derive(Debug, Clone, PartialEq, IntoArrow, FromArrow)]
+struct Plan {
+    name: String,
+    steps: Vec<Command>,
+    fallback: Option<Command>,
+}
+
+#[derive(Debug, Clone, PartialEq, IntoArrow, FromArrow)]
+struct Wrapper<T> {
+    value: T,
+    history: Vec<T>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use eyre::Report;
+
+    fn round_trip<T>(value: T) -> Result<(), Report>
+    where
+        T: IntoArrow + PartialEq + std::fmt::Debug + Clone,
+        T: for<'a> TryFrom<&'a ArrowData, Error = Report>,
+        T::A: 'static,
+    {
+        let arrow_array = value.clone().into_arrow();
+        let data = ArrowData(Arc::new(arrow_array));
+        let result: T = TryFrom::try_from(&data)?;
+        assert_eq!(value, result);
+        Ok(())
+    }
+
+    fn detection() -> Detection {
+        Detection {
+            label: "cat".into(),
+            confidence: 0.9,
+            center: Point { x: 1.0, y: 2.0 },
+            track_id: Some(7),
+            keypoints: vec![Point { x: 0.5, y: 0.5 }, Point { x: 1.5, y: 2.5 }],
+            tags: vec![Some("small".into()), None],
+        }
+    }
+
+    #[test]
+    fn test_struct_round_trip() -> Result<(), Report> {
+        round_trip(detection())?;
+        round_trip(Detection {
+            track_id: None,
+            keypoints: Vec::new(),
+            tags: Vec::new(),
+            ..detection()
+        })
+    }
+
+    #[test]
+    fn test_struct_data_type() {
+        let array = detection().into_arrow();
+        assert_eq!(array.len(), 1);
+        let DataType::Struct(fields) = array.data_type() else {
+            panic!("expected struct data type, got {}", array.data_type());
+        };
+        let names: Vec<_> = fields.iter().map(|f| f.name().as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "label",
+                "confidence",
+                "center",
+                "track_id",
+                "keypoints",
+                "tags"
+            ]
+        );
+        assert_eq!(fields[0].data_type(), &DataType::Utf8);
+        assert_eq!(fields[1].data_type(), &DataType::Float32);
+        assert!(matches!(fields[2].data_type(), DataType::Struct(_)));
+        assert_eq!(fields[3].data_type(), &DataType::UInt64);
+        assert!(matches!(fields[4].data_type(), DataType::List(_)));
+    }
+
+    #[test]
+    fn test_tuple_and_unit_structs() -> Result<(), Report> {
+        round_trip(Meters(3.5))?;
+        round_trip(Pair(-1, "one".into()))?;
+        round_trip(Marker)?;
+
+        assert_eq!(Meters(3.5).into_arrow().data_type(), &DataType::Float64);
+        let pair = Pair(-1, "one".into()).into_arrow();
+        let DataType::Struct(fields) = pair.data_type() else {
+            panic!("expected struct data type, got {}", pair.data_type());
+        };
+        assert_eq!(fields[0].name(), "0");
+        assert_eq!(fields[1].name(), "1");
+        Ok(())
+    }
+
+    #[test]
+    fn test_enum_round_trip() -> Result<(), Report> {
+        round_trip(Command::Stop)?;
+        round_trip(Command::Move {
+            target: Point { x: 3.0, y: 4.0 },
+            speed: None,
+        })?;
+        round_trip(Command::Say("hello".into()))?;
+        round_trip(Command::Rotate(1.5, true))?;
+        round_trip(Choice::A(1))?;
+        round_trip(Choice::B)?;
+
+        let array = Command::Stop.into_arrow();
+        assert!(matches!(array.data_type(), DataType::Union(_, _)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_nested_enums() -> Result<(), Report> {
+        let plan = Plan {
+            name: "patrol".into(),
+            steps: vec![
+                Command::Say("start".into()),
+                Command::Move {
+                    target: Point { x: 1.0, y: 0.0 },
+                    speed: Some(0.5),
+                },
+                Command::Stop,
+                Command::Rotate(-0.5, false),
+            ],
+            fallback: None,
+        };
+        round_trip(plan.clone())?;
+        round_trip(Plan {
+            fallback: Some(Command::Stop),
+            ..plan
+        })
+    }
+
+    #[test]
+    fn test_generic_struct() -> Result<(), Report> {
+        round_trip(Wrapper {
+            value: 3u8,
+            history: vec![1, 2],
+        })?;
+        round_trip(Wrapper {
+            value: Point { x: 0.0, y: 1.0 },
+            history: Vec::new(),
+        })
+    }
+
+    #[test]
+    fn test_type_mismatch() {
+        let data = ArrowData(Arc::new(Point { x: 1.0, y: 2.0 }.into_arrow()));
+        let result: Result<Detection, _> = TryFrom::try_from(&data);
+        assert!(result.is_err());
+
+        let data = ArrowData(Arc::new(42u32.into_arrow()));
+        let result: Result<Point, _> = TryFrom::try_from(&data);
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,57 @@
+#![cfg(feature = "ndarray")]
+
+use dora_arrow_convert::{IntoArrow, into_ndarray, ndarray_view};
+use ndarray::{Array2, Array3, ShapeBuilder, s};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use eyre::Report;
+
+    #[test]
+    fn test_ndarray_round_trip() -> Result<(), Report> {
+        let image = Array3::from_shape_fn((2, 3, 4), |(y, x, c)| (y * 100 + x * 10 + c) as u8);
+        let array = image.clone().into_arrow();
+        assert_eq!(array.len(), 24);
+
+        let result = into_ndarray::<u8>(&array, &[2, 3, 4], None)?;
+        assert_eq!(result, image.into_dyn());
+        Ok(())
+    }
+
+    #[test]
+    fn test_non_standard_layout() -> Result<(), Report> {
+        let matrix = Array2::from_shape_vec((2, 3).f(), vec![1.0f32, 2., 3., 4., 5., 6.])?;
+        let array = matrix.clone().into_arrow();
+        let view = ndarray_view::<f32>(&array, &[2, 3], None)?;
+        assert_eq!(view, matrix.view().into_dyn());
+
+        let sliced =
+            Array2::from_shape_fn((4, 4), |(i, j)| (i * 4 + j) as i64).slice_move(s![1.., 1..3]);
+        let array = sliced.clone().into_arrow();
+        assert_eq!(
+            into_ndarray::<i64>(&array, &[3, 2], None)?,
+            sliced.into_dyn()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_byte_strides() -> Result<(), Report> {
+        let array = ndarray::Array1::from(vec![1u16, 2, 3, 4, 5, 6]).into_arrow();
+        // column-major 2x3 matrix
+        let view = ndarray_view::<u16>(&array, &[2, 3], Some(&[2, 4]))?;
+        assert_eq!(view[[0, 1]], 3);
+        assert_eq!(view[[1, 2]], 6);
+
+        assert!(ndarray_view::<u16>(&array, &[2, 3], Some(&[1, 4])).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_layout() {
+        let array = ndarray::Array1::from(vec![1u8, 2, 3]).into_arrow();
+        assert!(ndarray_view::<u8>(&array, &[2, 2], None).is_err());
+        assert!(ndarray_view::<u16>(&array, &[3], None).is_err());
+    }
+}
@@ -94,11 +94,17 @@ impl RequestReplyLayer for TcpLayer {
     }
 }
 
-pub struct TcpConnection {
-    pub stream: TcpStream,
+/// Connection that sends length-prefixed messages over a byte stream.
+///
+/// The stream is usually a [`TcpStream`], but can be any other stream, e.g. a TLS stream.
+pub struct TcpConnection<S = TcpStream> {
+    pub stream: S,
 }
 
-impl ListenConnection for TcpConnection {
+impl<S> ListenConnection for TcpConnection<S>
+where
+    S: Read + Write + Send + Sync,
+{
     type RequestData = Vec<u8>;
     type ReplyData = Vec<u8>;
     type Error = std::io::Error;
@@ -114,7 +120,10 @@ impl ListenConnection for TcpConnection {
     }
 }
 
-impl RequestReplyConnection for TcpConnection {
+impl<S> RequestReplyConnection for TcpConnection<S>
+where
+    S: Read + Write + Send + Sync,
+{
     type RequestData = Vec<u8>;
     type ReplyData = Vec<u8>;
     type Error = std::io::Error;
@@ -127,7 +136,10 @@ impl RequestReplyConnection for TcpConnection {
     }
 }
 
-impl TcpConnection {
+impl<S> TcpConnection<S>
+where
+    S: Read + Write,
+{
     pub fn send(&mut self, request: &[u8]) -> std::io::Result<()> {
         let len_raw = (request.len() as u64).to_le_bytes();
         self.stream.write_all(&len_raw)?;
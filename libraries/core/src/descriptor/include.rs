@@ -0,0 +1,448 @@
+use dora_message::{
+    config::{Input, InputMapping, UserInputMapping},
+    descriptor::{Deploy, INCLUDE_INPUTS_SOURCE, NAMESPACE_SEPARATOR},
+    id::{DataId, NodeId},
+};
+use eyre::{Context, bail, eyre};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+};
+
+use super::{Descriptor, DescriptorExt, Node};
+
+/// Reads the dataflow files referenced by [`Node::include`] fields and stores them in the
+/// corresponding [`Node::included`] fields.
+///
+/// Relative include paths are resolved against the given `base_dir`. The given descriptor is
+/// the root dataflow, so it must not export any inputs or outputs.
+pub(super) fn load_includes(descriptor: &mut Descriptor, base_dir: &Path) -> eyre::Result<()> {
+    if !descriptor.inputs.is_empty() || !descriptor.outputs.is_empty() {
+        bail!("only included dataflows can export `inputs` and `outputs`");
+    }
+    load_includes_inner(descriptor, base_dir, &mut Vec::new())
+}
+
+fn load_includes_inner(
+    descriptor: &mut Descriptor,
+    base_dir: &Path,
+    stack: &mut Vec<PathBuf>,
+) -> eyre::Result<()> {
+    for node in &mut descriptor.nodes {
+        let Some(include) = &node.include else {
+            continue;
+        };
+        let path = base_dir.join(include);
+        let path = dunce::canonicalize(&path).wrap_err_with(|| {
+            format!(
+                "could not find dataflow `{}` included by node `{}`",
+                path.display(),
+                node.id
+            )
+        })?;
+        if stack.contains(&path) {
+            bail!(
+                "node `{}` includes dataflow `{}` recursively",
+                node.id,
+                path.display()
+            );
+        }
+        let buf = std::fs::read(&path)
+            .wrap_err_with(|| format!("failed to read included dataflow `{}`", path.display()))?;
+        let mut included = Descriptor::parse(buf)
+            .wrap_err_with(|| format!("failed to parse included dataflow `{}`", path.display()))?;
+
+        let include_dir = path.parent().unwrap_or(base_dir).to_owned();
+        stack.push(path);
+        load_includes_inner(&mut included, &include_dir, stack)?;
+        stack.pop();
+
+        node.included = Some(Box::new(included));
+    }
+    Ok(())
+}
+
+/// Replaces all nodes that [include](Node::include) another dataflow by the nodes of the
+/// included dataflow.
+///
+/// The included nodes are prefixed with the ID of the including node. Input mappings that
+/// refer to exported inputs or outputs are replaced by the mappings they are connected to.
+pub(super) fn flatten_includes(nodes: &[Node]) -> eyre::Result<Vec<Node>> {
+    if nodes.iter().all(|n| n.include.is_none()) {
+        return Ok(nodes.to_vec());
+    }
+
+    let mut flattened = Vec::new();
+    let mut exports: BTreeMap<NodeId, BTreeMap<DataId, InputMapping>> = BTreeMap::new();
+    for node in nodes {
+        let Some(include) = &node.include else {
+            flattened.push(node.clone());
+            continue;
+        };
+        if node.path.is_some()
+            || node.operators.is_some()
+            || node.operator.is_some()
+            || node.custom.is_some()
+            || node.replicas.is_some()
+        {
+            bail!(
+                "node `{}` has an `include` field, which cannot be combined with \
+                `path`, `custom`, `operators`, `operator`, or `replicas`",
+                node.id
+            );
+        }
+        let included = node.included.as_deref().ok_or_else(|| {
+            eyre!(
+                "dataflow `{}` included by node `{}` was not loaded",
+                include.display(),
+                node.id
+            )
+        })?;
+        if let Some(reserved) = included
+            .nodes
+            .iter()
+            .find(|n| n.id.as_ref() == INCLUDE_INPUTS_SOURCE)
+        {
+            bail!(
+                "node ID `{}` is reserved in included dataflows (included by node `{}`)",
+                reserved.id,
+                node.id
+            );
+        }
+        if let Some(input_id) = node.inputs.keys().find(|i| !included.inputs.contains(*i)) {
+            bail!(
+                "node `{}` connects input `{input_id}`, which is not exported by dataflow `{}`",
+                node.id,
+                include.display()
+            );
+        }
+
+        let resolve = |mapping: &InputMapping| namespaced_mapping(mapping, node, &included.inputs);
+
+        let working_dir = include.parent().filter(|p| !p.as_os_str().is_empty());
+        for mut included_node in flatten_includes(&included.nodes)? {
+            let included_id = included_node.id.clone();
+            for input in node_inputs_mut(&mut included_node) {
+                input.mapping = resolve(&input.mapping).wrap_err_with(|| {
+                    format!("failed to resolve inputs of node `{included_id}`")
+                })?;
+            }
+            included_node.id = namespaced_id(&node.id, &included_node.id);
+            if let Some(working_dir) = working_dir {
+                let deploy = included_node.deploy.get_or_insert_with(Deploy::default);
+                deploy.working_dir = Some(match &deploy.working_dir {
+                    Some(dir) => working_dir.join(dir),
+                    None => working_dir.to_owned(),
+                });
+            }
+            flattened.push(included_node);
+        }
+
+        let outputs = included
+            .outputs
+            .iter()
+            .map(|(output_id, mapping)| Ok((output_id.clone(), resolve(mapping)?)))
+            .collect::<eyre::Result<_>>()
+            .wrap_err_with(|| format!("failed to resolve outputs exported to `{}`", node.id))?;
+        exports.insert(node.id.clone(), outputs);
+    }
+
+    // replace references to exported outputs
+    for node in &mut flattened {
+        let node_id = node.id.clone();
+        for input in node_inputs_mut(node) {
+            input.mapping = resolve_exported_output(&input.mapping, &exports)
+                .wrap_err_with(|| format!("failed to resolve inputs of node `{node_id}`"))?;
+        }
+    }
+
+    Ok(flattened)
+}
+
+fn namespaced_id(namespace: &NodeId, id: &NodeId) -> NodeId {
+    NodeId::from(format!("{namespace}{NAMESPACE_SEPARATOR}{id}"))
+}
+
+/// Translates an input mapping of an included node to the namespace of the including node.
+fn namespaced_mapping(
+    mapping: &InputMapping,
+    including_node: &Node,
+    exported_inputs: &BTreeSet<DataId>,
+) -> eyre::Result<InputMapping> {
+    match mapping {
+        InputMapping::User(UserInputMapping { source, output })
+            if source.as_ref() == INCLUDE_INPUTS_SOURCE =>
+        {
+            if !exported_inputs.contains(output) {
+                bail!("`{mapping}` refers to input `{output}`, which is not exported");
+            }
+            let input = including_node.inputs.get(output).ok_or_else(|| {
+                eyre!(
+                    "exported input `{output}` is not connected by node `{}`",
+                    including_node.id
+                )
+            })?;
+            Ok(input.mapping.clone())
+        }
+        InputMapping::User(UserInputMapping { source, output }) => {
+            Ok(InputMapping::User(UserInputMapping {
+                source: namespaced_id(&including_node.id, source),
+                output: output.clone(),
+            }))
+        }
+        InputMapping::Timer { .. } => Ok(mapping.clone()),
+    }
+}
+
+fn resolve_exported_output(
+    mapping: &InputMapping,
+    exports: &BTreeMap<NodeId, BTreeMap<DataId, InputMapping>>,
+) -> eyre::Result<InputMapping> {
+    let mut mapping = mapping.clone();
+    // exported outputs might be connected to exported outputs of other includes
+    for _ in 0..=exports.len() {
+        let InputMapping::User(UserInputMapping { source, output }) = &mapping else {
+            return Ok(mapping);
+        };
+        let Some(outputs) = exports.get(source) else {
+            return Ok(mapping);
+        };
+        mapping = outputs
+            .get(output)
+            .ok_or_else(|| eyre!("`{source}` does not export an output named `{output}`"))?
+            .clone();
+    }
+    bail!("exported outputs form a cycle")
+}
+
+fn node_inputs_mut(node: &mut Node) -> Vec<&mut Input> {
+    let mut inputs: Vec<_> = node.inputs.values_mut().collect();
+    if let Some(runtime) = &mut node.operators {
+        inputs.extend(
+            runtime
+                .operators
+                .iter_mut()
+                .flat_map(|op| op.config.inputs.values_mut()),
+        );
+    }
+    if let Some(operator) = &mut node.operator {
+        inputs.extend(operator.config.inputs.values_mut());
+    }
+    if let Some(custom) = &mut node.custom {
+        inputs.extend(custom.run_config.inputs.values_mut());
+    }
+    inputs
+}
+
+/// Returns the IDs of all nodes that include another dataflow, including nested includes.
+///
+/// The returned IDs are prefixed with the IDs of their parent nodes, like the IDs of the
+/// flattened nodes.
+pub(super) fn collect_include_ids(nodes: &[Node]) -> BTreeSet<NodeId> {
+    let mut ids = BTreeSet::new();
+    for node in nodes {
+        if let Some(included) = &node.included {
+            ids.insert(node.id.clone());
+            ids.extend(
+                collect_include_ids(&included.nodes)
+                    .iter()
+                    .map(|id| namespaced_id(&node.id, id)),
+            );
+        }
+    }
+    ids
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dora_message::descriptor::CoreNodeKind;
+    use uuid::Uuid;
+
+    /// Writes the given files to a new temporary directory and reads `dataflow.yml` from it.
+    fn read_dataflow(files: &[(&str, &str)]) -> eyre::Result<Descriptor> {
+        let dir = std::env::temp_dir().join(format!("dora-include-{}", Uuid::new_v4()));
+        for (path, content) in files {
+            let path = dir.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        }
+        let result = Descriptor::blocking_read(&dir.join("dataflow.yml"));
+        std::fs::remove_dir_all(&dir).unwrap();
+        result
+    }
+
+    fn input_sources(node: &dora_message::descriptor::ResolvedNode) -> BTreeMap<String, String> {
+        let CoreNodeKind::Custom(custom) = &node.kind else {
+            panic!("expected custom node");
+        };
+        custom
+            .run_config
+            .inputs
+            .iter()
+            .map(|(id, input)| (id.to_string(), input.mapping.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn namespace_included_nodes() {
+        let descriptor = read_dataflow(&[
+            (
+                "dataflow.yml",
+                r#"
+nodes:
+  - id: camera
+    path: camera.py
+    outputs: [image]
+  - id: vision
+    include: vision/dataflow.yml
+    inputs:
+      image: camera/image
+  - id: plot
+    path: plot.py
+    inputs:
+      bbox: vision/bbox
+"#,
+            ),
+            (
+                "vision/dataflow.yml",
+                r#"
+inputs: [image]
+outputs:
+  bbox: detector/bbox
+nodes:
+  - id: detector
+    path: detector.py
+    inputs:
+      image: inputs/image
+      tick: dora/timer/millis/100
+    outputs: [bbox]
+  - id: tracker
+    include: tracker.yml
+    inputs:
+      bbox: detector/bbox
+"#,
+            ),
+            (
+                "vision/tracker.yml",
+                r#"
+inputs: [bbox]
+nodes:
+  - id: filter
+    path: filter.py
+    inputs:
+      bbox: inputs/bbox
+"#,
+            ),
+        ])
+        .unwrap();
+        let nodes = descriptor.resolve_aliases_and_set_defaults().unwrap();
+
+        let ids: Vec<_> = nodes.keys().map(|id| id.to_string()).collect();
+        assert_eq!(
+            ids,
+            ["camera", "plot", "vision.detector", "vision.tracker.filter"]
+        );
+        let detector = &nodes[&NodeId::from("vision.detector".to_owned())];
+        assert_eq!(
+            input_sources(detector),
+            BTreeMap::from([
+                ("image".into(), "camera/image".into()),
+                ("tick".into(), "dora/timer/millis/100".into()),
+            ])
+        );
+        assert_eq!(
+            detector
+                .deploy
+                .as_ref()
+                .and_then(|d| d.working_dir.as_deref()),
+            Some(Path::new("vision"))
+        );
+        let filter = &nodes[&NodeId::from("vision.tracker.filter".to_owned())];
+        assert_eq!(
+            input_sources(filter),
+            BTreeMap::from([("bbox".into(), "vision.detector/bbox".into())])
+        );
+        let plot = &nodes[&NodeId::from("plot".to_owned())];
+        assert_eq!(
+            input_sources(plot),
+            BTreeMap::from([("bbox".into(), "vision.detector/bbox".into())])
+        );
+
+        assert_eq!(
+            collect_include_ids(&descriptor.nodes),
+            BTreeSet::from([
+                "vision".to_owned().into(),
+                "vision.tracker".to_owned().into()
+            ])
+        );
+    }
+
+    #[test]
+    fn reject_recursive_includes() {
+        let err = read_dataflow(&[
+            ("dataflow.yml", "nodes: [{id: outer, include: sub/a.yml}]"),
+            ("sub/a.yml", "nodes: [{id: a, include: b.yml}]"),
+            ("sub/b.yml", "nodes: [{id: b, include: a.yml}]"),
+        ])
+        .unwrap_err();
+        assert!(err.to_string().contains("includes dataflow"), "{err}");
+        assert!(err.to_string().contains("recursively"), "{err}");
+    }
+
+    #[test]
+    fn reject_exported_output_cycles() {
+        let descriptor = read_dataflow(&[
+            (
+                "dataflow.yml",
+                r#"
+nodes:
+  - id: a
+    include: forward.yml
+    inputs:
+      value: b/value
+  - id: b
+    include: forward.yml
+    inputs:
+      value: a/value
+  - id: sink
+    path: sink.py
+    inputs:
+      value: a/value
+"#,
+            ),
+            (
+                "forward.yml",
+                r#"
+inputs: [value]
+outputs:
+  value: inputs/value
+nodes: []
+"#,
+            ),
+        ])
+        .unwrap();
+        let err = descriptor.resolve_aliases_and_set_defaults().unwrap_err();
+        assert!(
+            format!("{err:?}").contains("exported outputs form a cycle"),
+            "{err:?}"
+        );
+    }
+
+    #[test]
+    fn reject_root_exports() {
+        let err = read_dataflow(&[(
+            "dataflow.yml",
+            "{inputs: [image], nodes: [{id: camera, path: camera.py}]}",
+        )])
+        .unwrap_err();
+        assert!(err.to_string().contains("only included dataflows"), "{err}");
+
+        let err = read_dataflow(&[(
+            "dataflow.yml",
+            "{outputs: {image: camera/image}, nodes: [{id: camera, path: camera.py}]}",
+        )])
+        .unwrap_err();
+        assert!(err.to_string().contains("only included dataflows"), "{err}");
+    }
+}
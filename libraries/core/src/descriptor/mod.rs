@@ -1,6 +1,6 @@
 use dora_message::{
     config::{Input, InputMapping, NodeRunConfig},
-    descriptor::{GitRepoRev, NodeSource},
+    descriptor::{GitRepoRev, NAMESPACE_SEPARATOR, NodeSource, Replica, Replicas},
     id::{DataId, NodeId, OperatorId},
 };
 use eyre::{Context, OptionExt, Result, bail};
@@ -18,9 +18,10 @@ pub use dora_message::descriptor::{
     OperatorSource, PythonSource, ResolvedNode, RuntimeNode, SHELL_SOURCE,
     SingleOperatorDefinition,
 };
-pub use validate::ResolvedNodeExt;
+pub use validate::{ResolvedNodeExt, check_blocking_inputs, check_node_inputs};
 pub use visualize::collect_dora_timers;
 
+mod include;
 mod validate;
 mod visualize;
 
@@ -39,8 +40,9 @@ impl DescriptorExt for Descriptor {
     fn resolve_aliases_and_set_defaults(&self) -> eyre::Result<BTreeMap<NodeId, ResolvedNode>> {
         let default_op_id = OperatorId::from(SINGLE_OPERATOR_DEFAULT_ID.to_string());
 
-        let single_operator_nodes: HashMap<_, _> = self
-            .nodes
+        let nodes = include::flatten_includes(&self.nodes)?;
+
+        let single_operator_nodes: HashMap<_, _> = nodes
             .iter()
             .filter_map(|n| {
                 n.operator
@@ -50,7 +52,7 @@ impl DescriptorExt for Descriptor {
             .collect();
 
         let mut resolved = BTreeMap::new();
-        for mut node in self.nodes.clone() {
+        for mut node in nodes.clone() {
             // adjust input mappings
             let mut node_kind = node_kind_mut(&mut node)?;
             let input_mappings: Vec<_> = match &mut node_kind {
@@ -103,17 +105,34 @@ impl DescriptorExt for Descriptor {
                 }),
             };
 
-            resolved.insert(
-                node.id.clone(),
-                ResolvedNode {
-                    id: node.id,
-                    name: node.name,
-                    description: node.description,
-                    env: node.env,
-                    deploy: node.deploy,
-                    kind,
-                },
-            );
+            let resolved_node = ResolvedNode {
+                id: node.id,
+                name: node.name,
+                description: node.description,
+                env: node.env,
+                deploy: node.deploy,
+                restart: node.restart.unwrap_or_default(),
+                replica: None,
+                resources: node.resources.filter(|r| !r.is_empty()),
+                scheduling: node.scheduling,
+                kind,
+            };
+            match node.replicas {
+                None => {
+                    resolved.insert(resolved_node.id.clone(), resolved_node);
+                }
+                Some(replicas) => {
+                    for replica in expand_replicas(resolved_node, replicas)? {
+                        if nodes.iter().any(|n| n.id == replica.id) {
+                            bail!(
+                                "replica ID `{}` is already used by another node",
+                                replica.id
+                            );
+                        }
+                        resolved.insert(replica.id.clone(), replica);
+                    }
+                }
+            }
         }
 
         Ok(resolved)
@@ -121,14 +140,17 @@ impl DescriptorExt for Descriptor {
 
     fn visualize_as_mermaid(&self) -> eyre::Result<String> {
         let resolved = self.resolve_aliases_and_set_defaults()?;
-        let flowchart = visualize::visualize_nodes(&resolved);
+        let includes = include::collect_include_ids(&self.nodes);
+        let flowchart = visualize::visualize_nodes(&resolved, &includes);
 
         Ok(flowchart)
     }
 
     fn blocking_read(path: &Path) -> eyre::Result<Descriptor> {
         let buf = std::fs::read(path).context("failed to open given file")?;
-        Descriptor::parse(buf)
+        let mut descriptor = Descriptor::parse(buf)?;
+        include::load_includes(&mut descriptor, path.parent().unwrap_or(Path::new("")))?;
+        Ok(descriptor)
     }
 
     fn parse(buf: Vec<u8>) -> eyre::Result<Descriptor> {
@@ -150,7 +172,31 @@ pub async fn read_as_descriptor(path: &Path) -> eyre::Result<Descriptor> {
     let buf = tokio::fs::read(path)
         .await
         .context("failed to open given file")?;
-    Descriptor::parse(buf)
+    let mut descriptor = Descriptor::parse(buf)?;
+    include::load_includes(&mut descriptor, path.parent().unwrap_or(Path::new("")))?;
+    Ok(descriptor)
+}
+
+/// Creates the instances of a replicated node.
+fn expand_replicas(
+    node: ResolvedNode,
+    replicas: Replicas,
+) -> eyre::Result<impl Iterator<Item = ResolvedNode>> {
+    if replicas.count == 0 {
+        bail!("node `{}` must have at least one replica", node.id);
+    }
+    if matches!(&node.kind, CoreNodeKind::Custom(n) if n.path == DYNAMIC_SOURCE) {
+        bail!("dynamic node `{}` cannot be replicated", node.id);
+    }
+    Ok((0..replicas.count).map(move |index| ResolvedNode {
+        id: NodeId::from(format!("{}{NAMESPACE_SEPARATOR}{index}", node.id)),
+        replica: Some(Replica {
+            group: node.id.clone(),
+            index,
+            policy: replicas.policy,
+        }),
+        ..node.clone()
+    }))
 }
 
 fn node_kind_mut(node: &mut Node) -> eyre::Result<NodeKindMut> {
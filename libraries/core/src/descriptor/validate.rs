@@ -5,8 +5,11 @@ use crate::{
 };
 
 use dora_message::{
-    config::{Input, InputMapping, UserInputMapping},
-    descriptor::{CoreNodeKind, DYNAMIC_SOURCE, OperatorSource, ResolvedNode, SHELL_SOURCE},
+    config::{Input, InputMapping, QueuePolicy, UserInputMapping},
+    descriptor::{
+        CoreNodeKind, DYNAMIC_SOURCE, NodeResources, NodeScheduling, OperatorSource, ResolvedNode,
+        SHELL_SOURCE,
+    },
     id::{DataId, NodeId, OperatorId},
 };
 use eyre::{Context, bail, eyre};
@@ -27,6 +30,10 @@ pub fn check_dataflow(
 
     // check that nodes and operators exist
     for node in nodes.values() {
+        let working_dir = &match node.deploy.as_ref().and_then(|d| d.working_dir.as_ref()) {
+            Some(node_working_dir) => working_dir.join(node_working_dir),
+            None => working_dir.to_owned(),
+        };
         match &node.kind {
             descriptor::CoreNodeKind::Custom(custom) => match &custom.source {
                 dora_message::descriptor::NodeSource::Local => match custom.path.as_str() {
@@ -97,32 +104,40 @@ pub fn check_dataflow(
 
     // check that all inputs mappings point to an existing output
     for node in nodes.values() {
-        match &node.kind {
-            descriptor::CoreNodeKind::Custom(custom_node) => {
-                for (input_id, input) in &custom_node.run_config.inputs {
-                    check_input(input, &nodes, &format!("{}/{input_id}", node.id))?;
-                }
-            }
-            descriptor::CoreNodeKind::Runtime(runtime_node) => {
-                for operator_definition in &runtime_node.operators {
-                    for (input_id, input) in &operator_definition.config.inputs {
-                        check_input(
-                            input,
-                            &nodes,
-                            &format!("{}/{}/{input_id}", operator_definition.id, node.id),
-                        )?;
-                    }
-                }
-            }
-        };
+        check_node_inputs(node, &nodes)?;
     }
 
+    // check that blocking inputs are not mapped to outputs of other machines
+    let machines = nodes
+        .values()
+        .filter_map(|node| match &node.deploy {
+            // nodes with deploy labels are placed by the coordinator at runtime
+            Some(deploy) if !deploy.labels.is_empty() => None,
+            deploy => Some((
+                node.id.clone(),
+                deploy.as_ref().and_then(|d| d.machine.clone()),
+            )),
+        })
+        .collect();
+    check_blocking_inputs(&nodes, &machines)?;
+
     // Check that nodes can resolve `send_stdout_as`
     for node in nodes.values() {
         node.send_stdout_as()
             .context("Could not resolve `send_stdout_as` configuration")?;
     }
 
+    for node in nodes.values() {
+        if let Some(resources) = &node.resources {
+            check_resources(resources)
+                .wrap_err_with(|| format!("invalid `resources` of node `{}`", node.id))?;
+        }
+        if let Some(scheduling) = &node.scheduling {
+            check_scheduling(scheduling)
+                .wrap_err_with(|| format!("invalid `scheduling` of node `{}`", node.id))?;
+        }
+    }
+
     if has_python_operator {
         check_python_runtime()?;
     }
@@ -130,6 +145,126 @@ pub fn check_dataflow(
     Ok(())
 }
 
+/// Checks that all input mappings of the given node point to an existing output of the
+/// given `nodes`.
+pub fn check_node_inputs(
+    node: &ResolvedNode,
+    nodes: &BTreeMap<NodeId, ResolvedNode>,
+) -> eyre::Result<()> {
+    match &node.kind {
+        descriptor::CoreNodeKind::Custom(custom_node) => {
+            for (input_id, input) in &custom_node.run_config.inputs {
+                check_input(input, nodes, &format!("{}/{input_id}", node.id))?;
+            }
+        }
+        descriptor::CoreNodeKind::Runtime(runtime_node) => {
+            for operator_definition in &runtime_node.operators {
+                for (input_id, input) in &operator_definition.config.inputs {
+                    check_input(
+                        input,
+                        nodes,
+                        &format!("{}/{}/{input_id}", operator_definition.id, node.id),
+                    )?;
+                }
+            }
+        }
+    };
+    Ok(())
+}
+
+/// Checks that inputs with a [`QueuePolicy::Block`] queue policy are only mapped to outputs of
+/// nodes that run on the same machine.
+///
+/// Backpressure is not propagated between daemons, so blocking inputs can't be used across
+/// machines. The `placement` maps node IDs to the machine or daemon that they run on. Nodes
+/// that are not part of the map are not checked.
+pub fn check_blocking_inputs<M: PartialEq>(
+    nodes: &BTreeMap<NodeId, ResolvedNode>,
+    placement: &BTreeMap<NodeId, M>,
+) -> eyre::Result<()> {
+    for node in nodes.values() {
+        let Some(receiver_placement) = placement.get(&node.id) else {
+            continue;
+        };
+        let inputs: Vec<_> = match &node.kind {
+            CoreNodeKind::Custom(custom) => custom
+                .run_config
+                .inputs
+                .iter()
+                .map(|(input_id, input)| (format!("{}/{input_id}", node.id), input))
+                .collect(),
+            CoreNodeKind::Runtime(runtime) => runtime
+                .operators
+                .iter()
+                .flat_map(|operator| {
+                    operator.config.inputs.iter().map(move |(input_id, input)| {
+                        (format!("{}/{}/{input_id}", node.id, operator.id), input)
+                    })
+                })
+                .collect(),
+        };
+        for (input_id, input) in inputs {
+            let InputMapping::User(UserInputMapping { source, .. }) = &input.mapping else {
+                continue;
+            };
+            if input.queue_policy != Some(QueuePolicy::Block) {
+                continue;
+            }
+            if placement
+                .get(source)
+                .is_some_and(|source_placement| source_placement != receiver_placement)
+            {
+                bail!(
+                    "input `{input_id}` uses the `block` queue policy, but its source node                     `{source}` runs on a different machine (blocking inputs are only                     supported between nodes on the same machine)"
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+fn check_resources(resources: &NodeResources) -> eyre::Result<()> {
+    if resources.memory_max.is_some_and(|m| m.0 == 0) {
+        bail!("`memory_max` must be greater than zero");
+    }
+    if let Some(quota) = resources.cpu_quota {
+        if !(quota.is_finite() && quota > 0.0) {
+            bail!("`cpu_quota` must be a positive number, got {quota}");
+        }
+    }
+    if resources.pids_max == Some(0) {
+        bail!("`pids_max` must be greater than zero");
+    }
+    Ok(())
+}
+
+fn check_scheduling(scheduling: &NodeScheduling) -> eyre::Result<()> {
+    if scheduling
+        .cpu_affinity
+        .as_ref()
+        .is_some_and(|cpus| cpus.is_empty())
+    {
+        bail!("`cpu_affinity` must contain at least one CPU");
+    }
+    if let Some(nice) = scheduling.nice {
+        if !(-20..=19).contains(&nice) {
+            bail!("`nice` must be between -20 and 19, got {nice}");
+        }
+    }
+    match (scheduling.policy, scheduling.priority) {
+        (Some(_), Some(priority)) if !(1..=99).contains(&priority) => {
+            bail!("`priority` must be between 1 and 99, got {priority}")
+        }
+        (Some(_), Some(_)) if scheduling.nice.is_some() => {
+            bail!("`nice` has no effect for real-time scheduling policies")
+        }
+        (Some(_), Some(_)) | (None, None) => {}
+        (Some(_), None) => bail!("real-time scheduling `policy` requires a `priority`"),
+        (None, Some(_)) => bail!("`priority` requires a real-time scheduling `policy`"),
+    }
+    Ok(())
+}
+
 pub trait ResolvedNodeExt {
     fn send_stdout_as(&self) -> eyre::Result<Option<String>>;
 }
@@ -173,17 +308,20 @@ fn check_input(
     match &input.mapping {
         InputMapping::Timer { interval: _ } => {}
         InputMapping::User(UserInputMapping { source, output }) => {
-            let source_node = nodes.values().find(|n| &n.id == source).ok_or_else(|| {
-                eyre!("source node `{source}` mapped to input `{input_id_str}` does not exist",)
-            })?;
-            match &source_node.kind {
+            let source_node = nodes
+                .values()
+                .find(|n| n.output_node_id() == source)
+                .ok_or_else(|| {
+                    eyre!("source node `{source}` mapped to input `{input_id_str}` does not exist",)
+                })?;
+            let declared_output = match &source_node.kind {
                 CoreNodeKind::Custom(custom_node) => {
-                    if !custom_node.run_config.outputs.contains(output) {
-                        bail!(
+                    custom_node.run_config.outputs.get(output).ok_or_else(|| {
+                        eyre!(
                             "output `{source}/{output}` mapped to \
                             input `{input_id_str}` does not exist",
-                        );
-                    }
+                        )
+                    })?
                 }
                 CoreNodeKind::Runtime(runtime) => {
                     let (operator_id, output) = output.split_once('/').unwrap_or_default();
@@ -201,12 +339,23 @@ fn check_input(
                             )
                         })?;
 
-                    if !operator.config.outputs.contains(&output) {
-                        bail!(
+                    operator.config.outputs.get(&output).ok_or_else(|| {
+                        eyre!(
                             "output `{source}/{operator_id}/{output}` mapped to \
                             input `{input_id_str}` does not exist",
-                        );
-                    }
+                        )
+                    })?
+                }
+            };
+
+            // check that declared types are compatible
+            if let (Some(expected), Some(declared)) = (&input.data_type, &declared_output.data_type)
+            {
+                if !expected.matches(&declared.to_arrow()) {
+                    bail!(
+                        "input `{input_id_str}` expects type `{expected}`, but output \
+                        `{source}/{output}` is declared with type `{declared}`",
+                    );
                 }
             }
         }
@@ -241,3 +390,152 @@ assert dora.__version__=='{VERSION}',  'Python dora-rs should be {VERSION}, but
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check_inputs(yaml: &str) -> eyre::Result<()> {
+        let descriptor = Descriptor::parse(yaml.as_bytes().to_vec()).unwrap();
+        let nodes = descriptor.resolve_aliases_and_set_defaults().unwrap();
+        for node in nodes.values() {
+            check_node_inputs(node, &nodes)?;
+        }
+        Ok(())
+    }
+
+    fn dataflow(output_type: &str, input_type: &str) -> String {
+        format!(
+            r#"
+nodes:
+  - id: camera
+    path: camera.py
+    outputs:
+      - id: image
+        type: {output_type}
+  - id: detector
+    path: detector.py
+    inputs:
+      image:
+        source: camera/image
+        type: {input_type}
+"#
+        )
+    }
+
+    #[test]
+    fn compatible_input_types() {
+        check_inputs(&dataflow("{list: uint8}", "{list: uint8}")).unwrap();
+        check_inputs(&dataflow("boolean", "bool")).unwrap();
+        check_inputs(&dataflow(
+            "{struct: [{name: x, type: float32}]}",
+            "{struct: [{name: x, type: float32}]}",
+        ))
+        .unwrap();
+    }
+
+    #[test]
+    fn incompatible_input_types() {
+        let err = check_inputs(&dataflow("{list: uint8}", "{list: float32}")).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("expects type `list<float32>`, but output `camera/image`"),
+            "{err}"
+        );
+        assert!(check_inputs(&dataflow("uint8", "{list: uint8}")).is_err());
+        assert!(
+            check_inputs(&dataflow(
+                "{fixed_size_list: float32, size: 3}",
+                "{fixed_size_list: float32, size: 4}",
+            ))
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn blocking_inputs_across_machines() {
+        let check = |camera_deploy: &str, detector_deploy: &str, policy: &str| {
+            let yaml = format!(
+                r#"
+nodes:
+  - id: camera
+    path: camera.py
+    _unstable_deploy: {camera_deploy}
+    outputs:
+      - image
+  - id: detector
+    path: detector.py
+    _unstable_deploy: {detector_deploy}
+    inputs:
+      image:
+        source: camera/image
+        queue_policy: {policy}
+"#
+            );
+            let descriptor = Descriptor::parse(yaml.into_bytes()).unwrap();
+            let nodes = descriptor.resolve_aliases_and_set_defaults().unwrap();
+            let machines = nodes
+                .values()
+                .map(|n| {
+                    (
+                        n.id.clone(),
+                        n.deploy.as_ref().and_then(|d| d.machine.clone()),
+                    )
+                })
+                .collect();
+            check_blocking_inputs(&nodes, &machines)
+        };
+
+        check("{machine: a}", "{machine: a}", "block").unwrap();
+        check("{}", "{}", "block").unwrap();
+        check("{machine: a}", "{machine: b}", "drop_oldest").unwrap();
+
+        let err = check("{machine: a}", "{machine: b}", "block").unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("input `detector/image` uses the `block` queue policy"),
+            "{err}"
+        );
+        assert!(check("{}", "{machine: b}", "block").is_err());
+    }
+
+    #[test]
+    fn validate_scheduling() {
+        let check = |yaml: &str| {
+            let scheduling: NodeScheduling = serde_yaml::from_str(yaml).unwrap();
+            check_scheduling(&scheduling)
+        };
+        check("{}").unwrap();
+        check("{cpu_affinity: [0, 2], nice: 10}").unwrap();
+        check("{nice: -20}").unwrap();
+        check("{policy: fifo, priority: 80}").unwrap();
+        check("{cpu_affinity: [1], policy: rr, priority: 1}").unwrap();
+
+        let err = |yaml| check(yaml).unwrap_err().to_string();
+        assert!(err("{cpu_affinity: []}").contains("at least one CPU"));
+        assert!(err("{nice: 20}").contains("between -20 and 19"));
+        assert!(err("{policy: fifo, priority: 0}").contains("between 1 and 99"));
+        assert!(err("{policy: fifo, priority: 100}").contains("between 1 and 99"));
+        assert!(err("{policy: fifo, priority: 50, nice: 5}").contains("no effect"));
+        assert!(err("{policy: fifo}").contains("requires a `priority`"));
+        assert!(err("{priority: 50}").contains("requires a real-time"));
+    }
+
+    #[test]
+    fn undeclared_types_are_not_checked() {
+        let yaml = r#"
+nodes:
+  - id: camera
+    path: camera.py
+    outputs:
+      - image
+  - id: detector
+    path: detector.py
+    inputs:
+      image:
+        source: camera/image
+        type: {list: uint8}
+"#;
+        check_inputs(yaml).unwrap();
+    }
+}
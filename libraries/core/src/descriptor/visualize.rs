@@ -1,6 +1,6 @@
 use dora_message::{
     config::{Input, InputMapping, UserInputMapping, format_duration},
-    descriptor::{CoreNodeKind, OperatorDefinition},
+    descriptor::{CoreNodeKind, NAMESPACE_SEPARATOR, OperatorDefinition},
     id::{DataId, NodeId},
 };
 
@@ -11,14 +11,32 @@ use std::{
     time::Duration,
 };
 
-pub fn visualize_nodes(nodes: &BTreeMap<NodeId, ResolvedNode>) -> String {
+/// Visualizes the given nodes as a mermaid flowchart.
+///
+/// The `includes` set contains the (namespaced) IDs of all nodes that include another
+/// dataflow. The nodes of included dataflows are grouped into a subgraph.
+pub fn visualize_nodes(
+    nodes: &BTreeMap<NodeId, ResolvedNode>,
+    includes: &BTreeSet<NodeId>,
+) -> String {
+    // show replicated nodes as a single node
+    let nodes: BTreeMap<NodeId, ResolvedNode> = nodes
+        .values()
+        .filter(|n| n.replica.as_ref().is_none_or(|r| r.index == 0))
+        .map(|n| {
+            let id = n.output_node_id().clone();
+            (id.clone(), ResolvedNode { id, ..n.clone() })
+        })
+        .collect();
+    let nodes = &nodes;
+
     let mut flowchart = "flowchart TB\n".to_owned();
     let mut all_nodes = HashMap::new();
 
     for node in nodes.values() {
-        visualize_node(node, &mut flowchart);
         all_nodes.insert(&node.id, node);
     }
+    visualize_cluster(None, nodes, includes, &mut flowchart);
 
     let dora_timers = collect_dora_timers(nodes);
     if !dora_timers.is_empty() {
@@ -39,6 +57,49 @@ pub fn visualize_nodes(nodes: &BTreeMap<NodeId, ResolvedNode>) -> String {
     flowchart
 }
 
+/// Visualizes all nodes and sub-clusters that belong to the given include cluster.
+///
+/// Nodes that don't belong to any included dataflow are part of the `None` cluster.
+fn visualize_cluster(
+    cluster: Option<&NodeId>,
+    nodes: &BTreeMap<NodeId, ResolvedNode>,
+    includes: &BTreeSet<NodeId>,
+    flowchart: &mut String,
+) {
+    for node in nodes.values() {
+        if parent_cluster(&node.id, includes) == cluster {
+            visualize_node(node, flowchart);
+        }
+    }
+    for include in includes {
+        if parent_cluster(include, includes) == cluster {
+            let name = match cluster {
+                Some(parent) => include
+                    .as_ref()
+                    .strip_prefix(parent.as_ref())
+                    .and_then(|s| s.strip_prefix(NAMESPACE_SEPARATOR))
+                    .unwrap_or(include.as_ref()),
+                None => include.as_ref(),
+            };
+            writeln!(flowchart, "subgraph {include} [{name}]").unwrap();
+            visualize_cluster(Some(include), nodes, includes, flowchart);
+            flowchart.push_str("end\n");
+        }
+    }
+}
+
+/// Returns the innermost include cluster that the given ID belongs to.
+fn parent_cluster<'a>(id: &NodeId, includes: &'a BTreeSet<NodeId>) -> Option<&'a NodeId> {
+    includes
+        .iter()
+        .filter(|include| {
+            id.as_ref()
+                .strip_prefix(include.as_ref())
+                .is_some_and(|rest| rest.starts_with(NAMESPACE_SEPARATOR))
+        })
+        .max_by_key(|include| include.as_ref().len())
+}
+
 pub fn collect_dora_timers(nodes: &BTreeMap<NodeId, ResolvedNode>) -> BTreeSet<Duration> {
     let mut dora_timers = BTreeSet::new();
     for node in nodes.values() {
@@ -11,6 +11,8 @@ pub use dora_message::{config, uhlc};
 pub mod build;
 pub mod descriptor;
 pub mod metadata;
+#[cfg(feature = "security")]
+pub mod security;
 pub mod topics;
 
 pub fn adjust_shared_library_path(path: &Path) -> Result<std::path::PathBuf, eyre::ErrReport> {
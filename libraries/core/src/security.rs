@@ -0,0 +1,619 @@
+//! Optional TLS encryption and token authentication for the connections between the CLI, the
+//! coordinator, and the daemons.
+//!
+//! If the coordinator is configured with authentication tokens, clients send their token as
+//! first message after connecting (and after the TLS handshake, if enabled). The coordinator
+//! replies with the authentication result and closes the connection if the token is invalid.
+//!
+//! These settings don't apply to the zenoh connections that daemons use to exchange outputs
+//! with each other, which need to be secured through the zenoh configuration instead.
+
+use eyre::{Context, ContextCompat, bail, eyre};
+use rustls::{
+    ClientConfig, ClientConnection, RootCertStore, ServerConfig, StreamOwned,
+    crypto::CryptoProvider,
+    pki_types::{CertificateDer, PrivateKeyDer, ServerName, pem::PemObject},
+};
+use std::{
+    io::{Read, Write},
+    net::SocketAddr,
+    path::Path,
+    pin::Pin,
+    sync::Arc,
+    task::{Context as TaskContext, Poll},
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadBuf},
+    net::TcpStream,
+};
+use tokio_rustls::{TlsAcceptor, TlsConnector};
+
+/// Environment variable for the token that is used to authenticate at the coordinator.
+pub const DORA_TOKEN_ENV: &str = "DORA_TOKEN";
+
+/// Tokens longer than this are rejected without reading them.
+const MAX_TOKEN_LEN: usize = 4096;
+/// Authentication replies longer than this are rejected without reading them.
+const MAX_REPLY_LEN: usize = 4096;
+/// Maximum time for the TLS handshake and authentication of a new connection.
+///
+/// Prevents clients from holding connections open without ever authenticating.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Security settings of the coordinator for incoming connections.
+#[derive(Clone, Default)]
+pub struct ServerSecurity {
+    tls: Option<TlsAcceptor>,
+    tokens: Arc<Vec<String>>,
+}
+
+impl ServerSecurity {
+    /// Enables TLS using the given PEM-encoded certificate chain and private key.
+    pub fn with_tls(mut self, cert: &Path, key: &Path) -> eyre::Result<Self> {
+        let certs = CertificateDer::pem_file_iter(cert)
+            .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+            .map_err(|err| eyre!("{err}"))
+            .wrap_err_with(|| format!("failed to read TLS certificate `{}`", cert.display()))?;
+        let key = PrivateKeyDer::from_pem_file(key)
+            .map_err(|err| eyre!("{err}"))
+            .wrap_err_with(|| format!("failed to read TLS private key `{}`", key.display()))?;
+        let config = ServerConfig::builder_with_provider(crypto_provider())
+            .with_safe_default_protocol_versions()
+            .context("failed to set up TLS protocol versions")?
+            .with_no_client_auth()
+            .with_single_cert(certs, key)
+            .context("invalid TLS certificate or key")?;
+        self.tls = Some(TlsAcceptor::from(Arc::new(config)));
+        Ok(self)
+    }
+
+    /// Only accepts clients that authenticate with one of the given tokens.
+    ///
+    /// All clients are accepted if no tokens are given.
+    pub fn with_tokens(mut self, tokens: impl IntoIterator<Item = String>) -> Self {
+        self.tokens = Arc::new(tokens.into_iter().collect());
+        self
+    }
+
+    /// Performs the TLS handshake and checks the authentication token of a new connection.
+    ///
+    /// Fails if the client doesn't complete the handshake within 10 seconds.
+    pub async fn accept(&self, stream: TcpStream) -> eyre::Result<MaybeTlsStream> {
+        tokio::time::timeout(HANDSHAKE_TIMEOUT, self.accept_inner(stream))
+            .await
+            .map_err(|_| eyre!("client did not authenticate within {HANDSHAKE_TIMEOUT:?}"))?
+    }
+
+    async fn accept_inner(&self, stream: TcpStream) -> eyre::Result<MaybeTlsStream> {
+        let mut stream = match &self.tls {
+            Some(acceptor) => MaybeTlsStream::ServerTls(Box::new(
+                acceptor
+                    .accept(stream)
+                    .await
+                    .context("TLS handshake failed")?,
+            )),
+            None => MaybeTlsStream::Plain(stream),
+        };
+
+        if !self.tokens.is_empty() {
+            let token = receive_token(&mut stream)
+                .await
+                .context("failed to receive authentication token")?;
+            let result = if self
+                .tokens
+                .iter()
+                .any(|t| constant_time_eq(t.as_bytes(), &token))
+            {
+                Ok(())
+            } else {
+                Err("invalid authentication token".to_owned())
+            };
+            let reply = serde_json::to_vec(&result)?;
+            send_frame(&mut stream, &reply)
+                .await
+                .context("failed to send authentication reply")?;
+            result.map_err(|err| eyre!(err))?;
+        }
+
+        Ok(stream)
+    }
+}
+
+/// Security settings for connections to the coordinator.
+#[derive(Clone, Default)]
+pub struct ClientSecurity {
+    tls: Option<ClientTls>,
+    token: Option<String>,
+}
+
+#[derive(Clone)]
+struct ClientTls {
+    config: Arc<ClientConfig>,
+    server_name: Option<ServerName<'static>>,
+}
+
+impl ClientSecurity {
+    /// Enables TLS, trusting the given PEM-encoded CA certificate.
+    ///
+    /// The certificate of the coordinator is checked against the given `server_name`. If no
+    /// name is given, the IP address of the coordinator is used.
+    pub fn with_tls(mut self, ca: &Path, server_name: Option<String>) -> eyre::Result<Self> {
+        let mut roots = RootCertStore::empty();
+        let certs = CertificateDer::pem_file_iter(ca)
+            .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+            .map_err(|err| eyre!("{err}"))
+            .wrap_err_with(|| format!("failed to read TLS CA certificate `{}`", ca.display()))?;
+        for cert in certs {
+            roots
+                .add(cert)
+                .context("failed to add TLS CA certificate")?;
+        }
+        let config = ClientConfig::builder_with_provider(crypto_provider())
+            .with_safe_default_protocol_versions()
+            .context("failed to set up TLS protocol versions")?
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        let server_name = server_name
+            .map(ServerName::try_from)
+            .transpose()
+            .context("invalid TLS server name")?;
+        self.tls = Some(ClientTls {
+            config: Arc::new(config),
+            server_name,
+        });
+        Ok(self)
+    }
+
+    /// Authenticates at the coordinator using the given token.
+    pub fn with_token(mut self, token: Option<String>) -> Self {
+        self.token = token;
+        self
+    }
+
+    /// Performs the TLS handshake and authentication on a new connection to the coordinator.
+    pub async fn connect(&self, stream: TcpStream) -> eyre::Result<MaybeTlsStream> {
+        let mut stream = match &self.tls {
+            Some(tls) => {
+                let server_name = tls.server_name(stream.peer_addr()?)?;
+                let stream = TlsConnector::from(tls.config.clone())
+                    .connect(server_name, stream)
+                    .await
+                    .context("TLS handshake failed")?;
+                MaybeTlsStream::ClientTls(Box::new(stream))
+            }
+            None => MaybeTlsStream::Plain(stream),
+        };
+
+        if let Some(token) = &self.token {
+            send_frame(&mut stream, token.as_bytes())
+                .await
+                .context("failed to send authentication token")?;
+            let reply = receive_reply(&mut stream)
+                .await
+                .context("failed to receive authentication reply")?;
+            check_auth_reply(&reply)?;
+        }
+
+        Ok(stream)
+    }
+
+    /// Blocking version of [`Self::connect`].
+    pub fn connect_blocking(
+        &self,
+        stream: std::net::TcpStream,
+    ) -> eyre::Result<BlockingMaybeTlsStream> {
+        let mut stream = match &self.tls {
+            Some(tls) => {
+                let server_name = tls.server_name(stream.peer_addr()?)?;
+                let connection = ClientConnection::new(tls.config.clone(), server_name)
+                    .context("failed to set up TLS connection")?;
+                BlockingMaybeTlsStream::Tls(Box::new(StreamOwned::new(connection, stream)))
+            }
+            None => BlockingMaybeTlsStream::Plain(stream),
+        };
+
+        if let Some(token) = &self.token {
+            let len = (token.len() as u64).to_le_bytes();
+            stream.write_all(&len)?;
+            stream.write_all(token.as_bytes())?;
+            stream.flush()?;
+
+            let mut len = [0; 8];
+            stream
+                .read_exact(&mut len)
+                .context("failed to receive authentication reply")?;
+            let mut reply = vec![0; check_reply_len(len)?];
+            stream
+                .read_exact(&mut reply)
+                .context("failed to receive authentication reply")?;
+            check_auth_reply(&reply)?;
+        }
+
+        Ok(stream)
+    }
+}
+
+impl ClientTls {
+    fn server_name(&self, peer_addr: SocketAddr) -> eyre::Result<ServerName<'static>> {
+        Ok(match &self.server_name {
+            Some(name) => name.clone(),
+            None => ServerName::IpAddress(peer_addr.ip().into()),
+        })
+    }
+}
+
+/// A connection that is optionally encrypted using TLS.
+#[derive(Debug)]
+pub enum MaybeTlsStream {
+    Plain(TcpStream),
+    ClientTls(Box<tokio_rustls::client::TlsStream<TcpStream>>),
+    ServerTls(Box<tokio_rustls::server::TlsStream<TcpStream>>),
+}
+
+impl MaybeTlsStream {
+    fn tcp_stream(&self) -> &TcpStream {
+        match self {
+            MaybeTlsStream::Plain(stream) => stream,
+            MaybeTlsStream::ClientTls(stream) => stream.get_ref().0,
+            MaybeTlsStream::ServerTls(stream) => stream.get_ref().0,
+        }
+    }
+
+    pub fn peer_addr(&self) -> std::io::Result<SocketAddr> {
+        self.tcp_stream().peer_addr()
+    }
+}
+
+impl AsyncRead for MaybeTlsStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut TaskContext<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        match self.get_mut() {
+            MaybeTlsStream::Plain(stream) => Pin::new(stream).poll_read(cx, buf),
+            MaybeTlsStream::ClientTls(stream) => Pin::new(stream).poll_read(cx, buf),
+            MaybeTlsStream::ServerTls(stream) => Pin::new(stream).poll_read(cx, buf),
+        }
+    }
+}
+
+impl AsyncWrite for MaybeTlsStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut TaskContext<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        match self.get_mut() {
+            MaybeTlsStream::Plain(stream) => Pin::new(stream).poll_write(cx, buf),
+            MaybeTlsStream::ClientTls(stream) => Pin::new(stream).poll_write(cx, buf),
+            MaybeTlsStream::ServerTls(stream) => Pin::new(stream).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<std::io::Result<()>> {
+        match self.get_mut() {
+            MaybeTlsStream::Plain(stream) => Pin::new(stream).poll_flush(cx),
+            MaybeTlsStream::ClientTls(stream) => Pin::new(stream).poll_flush(cx),
+            MaybeTlsStream::ServerTls(stream) => Pin::new(stream).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<std::io::Result<()>> {
+        match self.get_mut() {
+            MaybeTlsStream::Plain(stream) => Pin::new(stream).poll_shutdown(cx),
+            MaybeTlsStream::ClientTls(stream) => Pin::new(stream).poll_shutdown(cx),
+            MaybeTlsStream::ServerTls(stream) => Pin::new(stream).poll_shutdown(cx),
+        }
+    }
+}
+
+/// Blocking connection to the coordinator that is optionally encrypted using TLS.
+#[derive(Debug)]
+pub enum BlockingMaybeTlsStream {
+    Plain(std::net::TcpStream),
+    Tls(Box<StreamOwned<ClientConnection, std::net::TcpStream>>),
+}
+
+impl Read for BlockingMaybeTlsStream {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            BlockingMaybeTlsStream::Plain(stream) => stream.read(buf),
+            BlockingMaybeTlsStream::Tls(stream) => stream.read(buf),
+        }
+    }
+}
+
+impl Write for BlockingMaybeTlsStream {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            BlockingMaybeTlsStream::Plain(stream) => stream.write(buf),
+            BlockingMaybeTlsStream::Tls(stream) => stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            BlockingMaybeTlsStream::Plain(stream) => stream.flush(),
+            BlockingMaybeTlsStream::Tls(stream) => stream.flush(),
+        }
+    }
+}
+
+fn crypto_provider() -> Arc<CryptoProvider> {
+    Arc::new(rustls::crypto::ring::default_provider())
+}
+
+fn check_auth_reply(reply: &[u8]) -> eyre::Result<()> {
+    let result: Result<(), String> = serde_json::from_slice(reply).ok().context(
+        "unexpected authentication reply, is token authentication enabled on the coordinator?",
+    )?;
+    match result {
+        Ok(()) => Ok(()),
+        Err(err) => bail!("coordinator rejected connection: {err}"),
+    }
+}
+
+async fn receive_token(stream: &mut MaybeTlsStream) -> eyre::Result<Vec<u8>> {
+    let mut len = [0; 8];
+    stream.read_exact(&mut len).await?;
+    let len = u64::from_le_bytes(len) as usize;
+    if len > MAX_TOKEN_LEN {
+        bail!("authentication token is too long ({len} bytes)");
+    }
+    let mut token = vec![0; len];
+    stream.read_exact(&mut token).await?;
+    Ok(token)
+}
+
+async fn send_frame(stream: &mut MaybeTlsStream, message: &[u8]) -> std::io::Result<()> {
+    let len = (message.len() as u64).to_le_bytes();
+    stream.write_all(&len).await?;
+    stream.write_all(message).await?;
+    stream.flush().await
+}
+
+async fn receive_reply(stream: &mut MaybeTlsStream) -> eyre::Result<Vec<u8>> {
+    let mut len = [0; 8];
+    stream.read_exact(&mut len).await?;
+    let mut reply = vec![0; check_reply_len(len)?];
+    stream.read_exact(&mut reply).await?;
+    Ok(reply)
+}
+
+fn check_reply_len(len: [u8; 8]) -> eyre::Result<usize> {
+    let len = u64::from_le_bytes(len) as usize;
+    if len > MAX_REPLY_LEN {
+        bail!(
+            "authentication reply is too long ({len} bytes), is token authentication enabled \
+            on the coordinator?"
+        );
+    }
+    Ok(len)
+}
+
+/// Compares the given byte slices in constant time to prevent timing attacks.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use tokio::net::TcpListener;
+
+    fn testdata(file: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("testdata")
+            .join(file)
+    }
+
+    fn server_tls() -> ServerSecurity {
+        ServerSecurity::default()
+            .with_tls(&testdata("server.pem"), &testdata("server-key.pem"))
+            .unwrap()
+    }
+
+    fn client_tls(server_name: Option<&str>) -> ClientSecurity {
+        ClientSecurity::default()
+            .with_tls(&testdata("ca.pem"), server_name.map(ToOwned::to_owned))
+            .unwrap()
+    }
+
+    /// Connects the given client to the given server through a loopback connection.
+    async fn connect(
+        server: ServerSecurity,
+        client: ClientSecurity,
+    ) -> (eyre::Result<MaybeTlsStream>, eyre::Result<MaybeTlsStream>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::join!(
+            async {
+                let (stream, _) = listener.accept().await.unwrap();
+                server.accept(stream).await
+            },
+            async {
+                client
+                    .connect(TcpStream::connect(addr).await.unwrap())
+                    .await
+            }
+        )
+    }
+
+    async fn assert_connected(server: &mut MaybeTlsStream, client: &mut MaybeTlsStream) {
+        send_frame(client, b"ping").await.unwrap();
+        assert_eq!(receive_reply(server).await.unwrap(), b"ping");
+        send_frame(server, b"pong").await.unwrap();
+        assert_eq!(receive_reply(client).await.unwrap(), b"pong");
+    }
+
+    fn tokens() -> ServerSecurity {
+        ServerSecurity::default().with_tokens(["old".to_owned(), "secret".to_owned()])
+    }
+
+    #[tokio::test]
+    async fn accept_without_security() {
+        let (server, client) = connect(ServerSecurity::default(), ClientSecurity::default()).await;
+        let (mut server, mut client) = (server.unwrap(), client.unwrap());
+        assert!(matches!(server, MaybeTlsStream::Plain(_)));
+        assert_connected(&mut server, &mut client).await;
+    }
+
+    #[tokio::test]
+    async fn accept_valid_token() {
+        for token in ["old", "secret"] {
+            let client = ClientSecurity::default().with_token(Some(token.to_owned()));
+            let (server, client) = connect(tokens(), client).await;
+            assert_connected(&mut server.unwrap(), &mut client.unwrap()).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn reject_wrong_token() {
+        for token in ["wrong", "secre", "secret2", ""] {
+            let client = ClientSecurity::default().with_token(Some(token.to_owned()));
+            let (server, client) = connect(tokens(), client).await;
+            let server_err = server.unwrap_err().to_string();
+            assert!(
+                server_err.contains("invalid authentication token"),
+                "{server_err}"
+            );
+            let client_err = client.unwrap_err().to_string();
+            assert!(
+                client_err.contains("coordinator rejected connection"),
+                "{client_err}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn reject_missing_token() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (server, client) = tokio::join!(
+            async {
+                let (stream, _) = listener.accept().await.unwrap();
+                tokens().accept(stream).await
+            },
+            async {
+                // the client sends a regular request instead of a token
+                let mut stream = ClientSecurity::default()
+                    .connect(TcpStream::connect(addr).await.unwrap())
+                    .await?;
+                send_frame(&mut stream, br#"{"request": "List"}"#).await?;
+                receive_reply(&mut stream).await
+            }
+        );
+        let err = server.unwrap_err();
+        assert!(
+            err.to_string().contains("invalid authentication token"),
+            "{err}"
+        );
+        assert!(check_auth_reply(&client.unwrap()).is_err());
+
+        // the client closes the connection without sending a token
+        let (server, ()) = tokio::join!(
+            async {
+                let (stream, _) = listener.accept().await.unwrap();
+                tokens().accept(stream).await
+            },
+            async {
+                drop(TcpStream::connect(addr).await.unwrap());
+            }
+        );
+        let err = server.unwrap_err();
+        assert!(
+            format!("{err:?}").contains("failed to receive authentication token"),
+            "{err:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn reject_oversized_token() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let mut client = TcpStream::connect(addr).await.unwrap();
+        client.write_all(&u64::MAX.to_le_bytes()).await.unwrap();
+
+        let (stream, _) = listener.accept().await.unwrap();
+        let err = tokens().accept(stream).await.unwrap_err();
+        assert!(format!("{err:?}").contains("too long"), "{err:?}");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn time_out_handshake() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        // the client connects, but never sends its token
+        let _client = TcpStream::connect(addr).await.unwrap();
+
+        let (stream, _) = listener.accept().await.unwrap();
+        let start = tokio::time::Instant::now();
+        let err = tokens().accept(stream).await.unwrap_err();
+        assert!(err.to_string().contains("did not authenticate"), "{err}");
+        assert_eq!(start.elapsed(), HANDSHAKE_TIMEOUT);
+    }
+
+    #[tokio::test]
+    async fn accept_tls_with_token() {
+        let server = server_tls().with_tokens(["secret".to_owned()]);
+        let client = client_tls(None).with_token(Some("secret".to_owned()));
+        let (server, client) = connect(server, client).await;
+        let (mut server, mut client) = (server.unwrap(), client.unwrap());
+        assert!(matches!(server, MaybeTlsStream::ServerTls(_)));
+        assert!(matches!(client, MaybeTlsStream::ClientTls(_)));
+        assert_connected(&mut server, &mut client).await;
+
+        let client = client_tls(Some("localhost")).with_token(Some("wrong".to_owned()));
+        let server = server_tls().with_tokens(["secret".to_owned()]);
+        let (server, client) = connect(server, client).await;
+        assert!(server.is_err());
+        assert!(client.is_err());
+    }
+
+    #[tokio::test]
+    async fn reject_tls_with_wrong_server_name() {
+        let (server, client) = connect(server_tls(), client_tls(Some("example.com"))).await;
+        assert!(format!("{:?}", client.unwrap_err()).contains("TLS handshake failed"));
+        assert!(server.is_err());
+    }
+
+    #[tokio::test]
+    async fn reject_plain_client_on_tls_server() {
+        let client = ClientSecurity::default().with_token(Some("secret".to_owned()));
+        let (server, client) = connect(server_tls(), client).await;
+        assert!(server.is_err());
+        assert!(client.is_err());
+    }
+
+    #[tokio::test]
+    async fn connect_blocking_with_tls_and_token() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let client = tokio::task::spawn_blocking(move || {
+            let client = client_tls(None).with_token(Some("secret".to_owned()));
+            let mut stream = client.connect_blocking(std::net::TcpStream::connect(addr)?)?;
+            stream.write_all(&4u64.to_le_bytes())?;
+            stream.write_all(b"ping")?;
+            stream.flush()?;
+            eyre::Ok(())
+        });
+
+        let (stream, _) = listener.accept().await.unwrap();
+        let server = server_tls().with_tokens(["secret".to_owned()]);
+        let mut stream = server.accept(stream).await.unwrap();
+        assert_eq!(receive_reply(&mut stream).await.unwrap(), b"ping");
+        client.await.unwrap().unwrap();
+    }
+
+    #[test]
+    fn compare_in_constant_time() {
+        assert!(constant_time_eq(b"secret", b"secret"));
+        assert!(constant_time_eq(b"", b""));
+        assert!(!constant_time_eq(b"secret", b"secreT"));
+        assert!(!constant_time_eq(b"secret", b"secret2"));
+        assert!(!constant_time_eq(b"secret", b""));
+    }
+}
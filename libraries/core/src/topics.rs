@@ -1,8 +1,51 @@
 use std::net::{IpAddr, Ipv4Addr};
 
+use dora_message::{
+    DataflowId,
+    config::{DataId, NodeId},
+};
+
 pub const LOCALHOST: IpAddr = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
 pub const DORA_COORDINATOR_PORT_DEFAULT: u16 = 53290;
 pub const DORA_DAEMON_LOCAL_LISTEN_PORT_DEFAULT: u16 = 53291;
 pub const DORA_COORDINATOR_PORT_CONTROL_DEFAULT: u16 = 6012;
 
 pub const MANUAL_STOP: &str = "dora/stop";
+
+/// Zenoh key expression on which the outputs of the given node are published.
+///
+/// Daemons publish outputs on this topic when they have subscribers, e.g. nodes on remote
+/// machines or `dora topic` commands.
+pub fn zenoh_output_publish_topic(
+    dataflow_id: DataflowId,
+    node_id: &NodeId,
+    output_id: &DataId,
+) -> String {
+    let network_id = "default";
+    format!("dora/{network_id}/{dataflow_id}/output/{node_id}/{output_id}")
+}
+
+/// Zenoh key expression for injecting messages into the given input of a node.
+///
+/// Daemons subscribe to this topic for all of their local nodes if the dataflow allows input
+/// injection. It is used by `dora topic pub`.
+pub fn zenoh_input_publish_topic(
+    dataflow_id: DataflowId,
+    node_id: &NodeId,
+    input_id: &DataId,
+) -> String {
+    let network_id = "default";
+    format!("dora/{network_id}/{dataflow_id}/input/{node_id}/{input_id}")
+}
+
+/// Pseudo node that sends the messages that are injected into node inputs.
+///
+/// Injected messages are delivered like regular outputs of this node. The output ID is the
+/// `node_id/input_id` of the receiving input (see [`injected_input_output_id`]). Node IDs
+/// cannot contain `/`, so this ID never clashes with a node of the dataflow.
+pub const INJECTED_INPUTS_SOURCE: &str = "dora/injected";
+
+/// Output ID of [`INJECTED_INPUTS_SOURCE`] for messages that are injected into the given input.
+pub fn injected_input_output_id(node_id: &NodeId, input_id: &DataId) -> DataId {
+    DataId::from(format!("{node_id}/{input_id}"))
+}
@@ -5,7 +5,7 @@ use uuid::Uuid;
 use crate::{
     BuildId, SessionId,
     common::GitSource,
-    descriptor::Descriptor,
+    descriptor::{Descriptor, Node},
     id::{NodeId, OperatorId},
 };
 
@@ -68,6 +68,39 @@ pub enum ControlRequest {
         name: Option<String>,
         node: String,
     },
+    /// Lists the outputs and inputs of a running dataflow.
+    Topics {
+        uuid: Option<Uuid>,
+        name: Option<String>,
+    },
+    /// Adds a node to a running dataflow.
+    ///
+    /// The node is spawned together with its input mappings, without restarting the other
+    /// nodes of the dataflow.
+    AddNode {
+        uuid: Option<Uuid>,
+        name: Option<String>,
+        node: Box<Node>,
+    },
+    /// Stops a node of a running dataflow and removes it from the dataflow.
+    ///
+    /// The inputs that the node's outputs are mapped to are closed.
+    RemoveNode {
+        uuid: Option<Uuid>,
+        name: Option<String>,
+        node_id: NodeId,
+        grace_duration: Option<Duration>,
+    },
+    /// Stops a node of a running dataflow and spawns it again.
+    ///
+    /// The inputs and outputs of the node stay connected, so other nodes don't notice the
+    /// restart.
+    RestartNode {
+        uuid: Option<Uuid>,
+        name: Option<String>,
+        node_id: NodeId,
+        grace_duration: Option<Duration>,
+    },
     Destroy,
     List,
     DaemonConnected,
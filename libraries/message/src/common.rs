@@ -5,7 +5,9 @@ use aligned_vec::{AVec, ConstAlign};
 use eyre::Context as _;
 use uuid::Uuid;
 
-use crate::{BuildId, DataflowId, daemon_to_daemon::InterDaemonEvent, id::NodeId};
+use crate::{
+    BuildId, DataflowId, daemon_to_daemon::InterDaemonEvent, descriptor::MemorySize, id::NodeId,
+};
 
 pub use log::Level as LogLevel;
 
@@ -45,8 +47,17 @@ pub struct NodeError {
 
 impl std::fmt::Display for NodeError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        if let NodeErrorCause::FailedToSpawn(err) = &self.cause {
-            return write!(f, "failed to spawn node: {err}");
+        match &self.cause {
+            NodeErrorCause::FailedToSpawn(err) => {
+                return write!(f, "failed to spawn node: {err}");
+            }
+            NodeErrorCause::DaemonLost => {
+                return write!(
+                    f,
+                    "node state is unknown because its daemon did not reconnect to the coordinator"
+                );
+            }
+            _ => {}
         }
         match &self.exit_status {
             NodeExitStatus::Success => write!(f, "<success>"),
@@ -74,6 +85,15 @@ impl std::fmt::Display for NodeError {
                         f,
                         "node was killed by dora because it didn't react to a stop message in time ({signal_str})"
                     )
+                } else if let NodeErrorCause::ResourceLimit {
+                    limit: limit @ ResourceLimit::Memory { .. },
+                    ..
+                } = &self.cause
+                {
+                    write!(
+                        f,
+                        "node was killed because it exceeded its {limit} ({signal_str})"
+                    )
                 } else {
                     write!(f, "exited because of signal {signal_str}")
                 }
@@ -87,19 +107,32 @@ impl std::fmt::Display for NodeError {
                 f,
                 ". This error occurred because node `{caused_by_node}` exited before connecting to dora."
             )?,
-            NodeErrorCause::FailedToSpawn(_) => unreachable!(), // handled above
-            NodeErrorCause::Other { stderr } if stderr.is_empty() => {}
-            NodeErrorCause::Other { stderr } => {
-                let line: &str = "---------------------------------------------------------------------------------\n";
-                let stderr = stderr.trim_end();
-                write!(f, " with stderr output:\n{line}{stderr}\n{line}")?
+            NodeErrorCause::FailedToSpawn(_) | NodeErrorCause::DaemonLost => unreachable!(), // handled above
+            NodeErrorCause::ResourceLimit { limit, stderr } => {
+                let killed = matches!(limit, ResourceLimit::Memory { .. })
+                    && matches!(self.exit_status, NodeExitStatus::Signal(_));
+                if !killed {
+                    write!(f, " after exceeding its {limit}")?;
+                }
+                write_stderr(f, stderr)?
             }
+            NodeErrorCause::Other { stderr } => write_stderr(f, stderr)?,
         }
 
         Ok(())
     }
 }
 
+fn write_stderr(f: &mut std::fmt::Formatter<'_>, stderr: &str) -> std::fmt::Result {
+    if stderr.is_empty() {
+        return Ok(());
+    }
+    let line: &str =
+        "---------------------------------------------------------------------------------\n";
+    let stderr = stderr.trim_end();
+    write!(f, " with stderr output:\n{line}{stderr}\n{line}")
+}
+
 #[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
 pub enum NodeErrorCause {
     /// Node was killed because it didn't react to a stop message in time.
@@ -109,11 +142,59 @@ pub enum NodeErrorCause {
         caused_by_node: NodeId,
     },
     FailedToSpawn(String),
+    /// The daemon running the node did not reconnect after a coordinator restart.
+    DaemonLost,
+    /// Node exceeded one of the resource limits configured in the dataflow.
+    ResourceLimit {
+        limit: ResourceLimit,
+        stderr: String,
+    },
     Other {
         stderr: String,
     },
 }
 
+/// A resource limit of a node that was exceeded.
+///
+/// See [`NodeResources`](crate::descriptor::NodeResources).
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub enum ResourceLimit {
+    /// The node used more memory than allowed and was killed.
+    Memory { max: MemorySize },
+    /// The node tried to create more processes or threads than allowed.
+    Pids { max: u64 },
+}
+
+impl fmt::Display for ResourceLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResourceLimit::Memory { max } => write!(f, "memory limit of {max} (`memory_max`)"),
+            ResourceLimit::Pids { max } => {
+                write!(f, "limit of {max} processes and threads (`pids_max`)")
+            }
+        }
+    }
+}
+
+/// A request to stop a whole dataflow, sent by one of its nodes.
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub struct DataflowStopRequest {
+    /// The node that requested the stop.
+    pub node_id: NodeId,
+    /// Optional explanation why the dataflow was stopped.
+    pub reason: Option<String>,
+}
+
+impl fmt::Display for DataflowStopRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "stopped by node `{}`", self.node_id)?;
+        if let Some(reason) = &self.reason {
+            write!(f, ": {reason}")?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
 pub enum NodeExitStatus {
     Success,
@@ -1,9 +1,12 @@
 use core::fmt;
 use std::{
+    borrow::Borrow,
     collections::{BTreeMap, BTreeSet},
+    sync::Arc,
     time::Duration,
 };
 
+use arrow_schema::{DataType, Field};
 use once_cell::sync::OnceCell;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
@@ -32,8 +35,15 @@ pub struct NodeRunConfig {
     ///  - output_1
     ///
     ///  - output_2
+    ///
+    /// Outputs can optionally declare their Arrow data type:
+    ///
+    /// outputs:
+    ///
+    ///  - id: image
+    ///    type: {list: uint8}
     #[serde(default)]
-    pub outputs: BTreeSet<DataId>,
+    pub outputs: BTreeSet<Output>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
@@ -41,6 +51,10 @@ pub struct NodeRunConfig {
 pub struct Input {
     pub mapping: InputMapping,
     pub queue_size: Option<usize>,
+    /// What to do when the input queue is full (defaults to dropping the oldest input).
+    pub queue_policy: Option<QueuePolicy>,
+    /// Expected data type of the input, checked by `dora check`.
+    pub data_type: Option<DataTypeSpec>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
@@ -50,6 +64,10 @@ pub enum InputDef {
     WithOptions {
         source: InputMapping,
         queue_size: Option<usize>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        queue_policy: Option<QueuePolicy>,
+        #[serde(default, rename = "type", skip_serializing_if = "Option::is_none")]
+        data_type: Option<DataTypeSpec>,
     },
 }
 
@@ -59,13 +77,19 @@ impl From<Input> for InputDef {
             Input {
                 mapping,
                 queue_size: None,
+                queue_policy: None,
+                data_type: None,
             } => Self::MappingOnly(mapping),
             Input {
                 mapping,
                 queue_size,
+                queue_policy,
+                data_type,
             } => Self::WithOptions {
                 source: mapping,
                 queue_size,
+                queue_policy,
+                data_type,
             },
         }
     }
@@ -77,11 +101,322 @@ impl From<InputDef> for Input {
             InputDef::MappingOnly(mapping) => Self {
                 mapping,
                 queue_size: None,
+                queue_policy: None,
+                data_type: None,
             },
-            InputDef::WithOptions { source, queue_size } => Self {
+            InputDef::WithOptions {
+                source,
+                queue_size,
+                queue_policy,
+                data_type,
+            } => Self {
                 mapping: source,
                 queue_size,
+                queue_policy,
+                data_type,
+            },
+        }
+    }
+}
+
+/// Determines what happens when a new input arrives while the input queue is full.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueuePolicy {
+    /// Drop the oldest queued input to make room for the new one.
+    #[default]
+    DropOldest,
+    /// Drop the new input and keep the queued ones.
+    DropNewest,
+    /// Never drop inputs. Instead, the sender is blocked until there is room in the queue.
+    ///
+    /// Backpressure is not propagated between machines, so this policy can only be used for
+    /// inputs whose source node runs on the same machine as the receiver. Dataflows that map
+    /// blocking inputs to outputs of other machines are rejected.
+    Block,
+}
+
+/// Output of a node or operator, optionally with a declared Arrow data type.
+///
+/// Outputs are identified by their ID: the [`PartialEq`], [`Eq`], [`PartialOrd`], and [`Ord`]
+/// implementations only compare the `id` field and ignore the declared `data_type`. So two
+/// outputs with the same ID but different types are considered equal. This is required for
+/// looking up outputs by their [`DataId`] through the [`Borrow`] implementation, e.g. in a
+/// [`BTreeSet<Output>`].
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(from = "OutputDef", into = "OutputDef")]
+pub struct Output {
+    pub id: DataId,
+    /// Declared data type of the output.
+    ///
+    /// If set, sending an output with a different type results in an error.
+    pub data_type: Option<DataTypeSpec>,
+}
+
+/// Compares only the `id` of the outputs, see the [`Output`] docs.
+impl PartialEq for Output {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Eq for Output {}
+
+/// Orders outputs by `id` only, see the [`Output`] docs.
+impl PartialOrd for Output {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Orders outputs by `id` only, see the [`Output`] docs.
+impl Ord for Output {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.id.cmp(&other.id)
+    }
+}
+
+impl Borrow<DataId> for Output {
+    fn borrow(&self) -> &DataId {
+        &self.id
+    }
+}
+
+impl Borrow<str> for Output {
+    fn borrow(&self) -> &str {
+        self.id.borrow()
+    }
+}
+
+impl From<DataId> for Output {
+    fn from(id: DataId) -> Self {
+        Self {
+            id,
+            data_type: None,
+        }
+    }
+}
+
+impl fmt::Display for Output {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.id, f)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum OutputDef {
+    IdOnly(DataId),
+    WithType {
+        id: DataId,
+        #[serde(rename = "type")]
+        data_type: DataTypeSpec,
+    },
+}
+
+impl From<Output> for OutputDef {
+    fn from(output: Output) -> Self {
+        match output.data_type {
+            None => Self::IdOnly(output.id),
+            Some(data_type) => Self::WithType {
+                id: output.id,
+                data_type,
+            },
+        }
+    }
+}
+
+impl From<OutputDef> for Output {
+    fn from(value: OutputDef) -> Self {
+        match value {
+            OutputDef::IdOnly(id) => Self {
+                id,
+                data_type: None,
             },
+            OutputDef::WithType { id, data_type } => Self {
+                id,
+                data_type: Some(data_type),
+            },
+        }
+    }
+}
+
+/// Declared Arrow data type of an input or output.
+///
+/// This is a compact representation of the most common Arrow data types, for example
+/// `uint8`, `{list: float32}`, `{fixed_size_list: float64, size: 3}`, or
+/// `{struct: [{name: x, type: float32}, {name: y, type: float32}]}`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum DataTypeSpec {
+    Primitive(PrimitiveType),
+    List {
+        list: Box<DataTypeSpec>,
+    },
+    LargeList {
+        large_list: Box<DataTypeSpec>,
+    },
+    FixedSizeList {
+        fixed_size_list: Box<DataTypeSpec>,
+        size: i32,
+    },
+    Struct {
+        #[serde(rename = "struct")]
+        fields: Vec<StructFieldSpec>,
+    },
+}
+
+impl DataTypeSpec {
+    /// Checks whether the given Arrow data type matches this declaration.
+    ///
+    /// The names (for lists), nullability, and metadata of nested fields are ignored.
+    ///
+    /// This is the single compatibility check for declared types, which is used both by
+    /// `dora check` and when sending outputs.
+    pub fn matches(&self, data_type: &DataType) -> bool {
+        match (self, data_type) {
+            (Self::Primitive(primitive), data_type) => &primitive.to_arrow() == data_type,
+            (Self::List { list }, DataType::List(field)) => list.matches(field.data_type()),
+            (Self::LargeList { large_list }, DataType::LargeList(field)) => {
+                large_list.matches(field.data_type())
+            }
+            (
+                Self::FixedSizeList {
+                    fixed_size_list,
+                    size,
+                },
+                DataType::FixedSizeList(field, actual_size),
+            ) => size == actual_size && fixed_size_list.matches(field.data_type()),
+            (Self::Struct { fields }, DataType::Struct(actual)) => {
+                fields.len() == actual.len()
+                    && fields.iter().zip(actual.iter()).all(|(expected, actual)| {
+                        &expected.name == actual.name()
+                            && expected.data_type.matches(actual.data_type())
+                    })
+            }
+            _ => false,
+        }
+    }
+
+    /// Converts the declaration to the corresponding Arrow data type.
+    pub fn to_arrow(&self) -> DataType {
+        let item = |spec: &DataTypeSpec| Arc::new(Field::new("item", spec.to_arrow(), true));
+        match self {
+            Self::Primitive(primitive) => primitive.to_arrow(),
+            Self::List { list } => DataType::List(item(list)),
+            Self::LargeList { large_list } => DataType::LargeList(item(large_list)),
+            Self::FixedSizeList {
+                fixed_size_list,
+                size,
+            } => DataType::FixedSizeList(item(fixed_size_list), *size),
+            Self::Struct { fields } => DataType::Struct(
+                fields
+                    .iter()
+                    .map(|f| Field::new(&f.name, f.data_type.to_arrow(), true))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl fmt::Display for DataTypeSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Primitive(primitive) => write!(f, "{}", primitive.name()),
+            Self::List { list } => write!(f, "list<{list}>"),
+            Self::LargeList { large_list } => write!(f, "large_list<{large_list}>"),
+            Self::FixedSizeList {
+                fixed_size_list,
+                size,
+            } => write!(f, "fixed_size_list<{fixed_size_list}, {size}>"),
+            Self::Struct { fields } => {
+                write!(f, "struct<")?;
+                for (i, field) in fields.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}: {}", field.name, field.data_type)?;
+                }
+                write!(f, ">")
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct StructFieldSpec {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub data_type: DataTypeSpec,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PrimitiveType {
+    Null,
+    #[serde(alias = "boolean")]
+    Bool,
+    Int8,
+    Int16,
+    Int32,
+    Int64,
+    Uint8,
+    Uint16,
+    Uint32,
+    Uint64,
+    Float16,
+    Float32,
+    Float64,
+    #[serde(alias = "string")]
+    Utf8,
+    LargeUtf8,
+    Binary,
+    LargeBinary,
+}
+
+impl PrimitiveType {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Null => "null",
+            Self::Bool => "bool",
+            Self::Int8 => "int8",
+            Self::Int16 => "int16",
+            Self::Int32 => "int32",
+            Self::Int64 => "int64",
+            Self::Uint8 => "uint8",
+            Self::Uint16 => "uint16",
+            Self::Uint32 => "uint32",
+            Self::Uint64 => "uint64",
+            Self::Float16 => "float16",
+            Self::Float32 => "float32",
+            Self::Float64 => "float64",
+            Self::Utf8 => "utf8",
+            Self::LargeUtf8 => "large_utf8",
+            Self::Binary => "binary",
+            Self::LargeBinary => "large_binary",
+        }
+    }
+
+    pub fn to_arrow(self) -> DataType {
+        match self {
+            Self::Null => DataType::Null,
+            Self::Bool => DataType::Boolean,
+            Self::Int8 => DataType::Int8,
+            Self::Int16 => DataType::Int16,
+            Self::Int32 => DataType::Int32,
+            Self::Int64 => DataType::Int64,
+            Self::Uint8 => DataType::UInt8,
+            Self::Uint16 => DataType::UInt16,
+            Self::Uint32 => DataType::UInt32,
+            Self::Uint64 => DataType::UInt64,
+            Self::Float16 => DataType::Float16,
+            Self::Float32 => DataType::Float32,
+            Self::Float64 => DataType::Float64,
+            Self::Utf8 => DataType::Utf8,
+            Self::LargeUtf8 => DataType::LargeUtf8,
+            Self::Binary => DataType::Binary,
+            Self::LargeBinary => DataType::LargeBinary,
         }
     }
 }
@@ -257,3 +592,88 @@ impl Default for RemoteCommunicationConfig {
         Self::Tcp
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(yaml: &str) -> DataTypeSpec {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn match_primitive_types() {
+        assert!(spec("uint8").matches(&DataType::UInt8));
+        assert!(spec("string").matches(&DataType::Utf8));
+        assert!(spec("boolean").matches(&DataType::Boolean));
+        assert!(!spec("uint8").matches(&DataType::Int8));
+        assert!(!spec("float32").matches(&DataType::Float64));
+        assert!(!spec("uint8").matches(&DataType::List(Arc::new(Field::new(
+            "item",
+            DataType::UInt8,
+            true
+        )))));
+    }
+
+    #[test]
+    fn match_nested_types() {
+        let list = |name: &str, nullable| Arc::new(Field::new(name, DataType::Float32, nullable));
+        // field names and nullability of list items are ignored
+        assert!(spec("{list: float32}").matches(&DataType::List(list("item", true))));
+        assert!(spec("{list: float32}").matches(&DataType::List(list("values", false))));
+        assert!(!spec("{list: float32}").matches(&DataType::LargeList(list("item", true))));
+        assert!(!spec("{list: uint8}").matches(&DataType::List(list("item", true))));
+
+        let fixed = spec("{fixed_size_list: float32, size: 3}");
+        assert!(fixed.matches(&DataType::FixedSizeList(list("item", true), 3)));
+        assert!(!fixed.matches(&DataType::FixedSizeList(list("item", true), 4)));
+
+        let point = spec("{struct: [{name: x, type: float32}, {name: y, type: float32}]}");
+        let fields = |y: &str| {
+            DataType::Struct(
+                vec![
+                    Field::new("x", DataType::Float32, false),
+                    Field::new(y, DataType::Float32, true),
+                ]
+                .into(),
+            )
+        };
+        assert!(point.matches(&fields("y")));
+        assert!(!point.matches(&fields("z")));
+        assert!(!point.matches(&DataType::Struct(
+            vec![Field::new("x", DataType::Float32, false)].into()
+        )));
+    }
+
+    #[test]
+    fn declaration_matches_own_arrow_type() {
+        for yaml in [
+            "int64",
+            "large_utf8",
+            "{large_list: {list: uint16}}",
+            "{fixed_size_list: float64, size: 2}",
+            "{struct: [{name: id, type: uint32}, {name: data, type: {list: uint8}}]}",
+        ] {
+            let spec = spec(yaml);
+            assert!(spec.matches(&spec.to_arrow()), "{yaml}");
+        }
+    }
+
+    #[test]
+    fn parse_outputs() {
+        let outputs: BTreeSet<Output> =
+            serde_yaml::from_str("[plain, {id: image, type: {list: uint8}}]").unwrap();
+        assert_eq!(outputs.get("plain").unwrap().data_type, None);
+        assert_eq!(
+            outputs.get("image").unwrap().data_type,
+            Some(spec("{list: uint8}"))
+        );
+
+        // outputs are compared by ID only
+        let typed = Output {
+            id: DataId::from("image".to_owned()),
+            data_type: Some(spec("uint8")),
+        };
+        assert_eq!(typed, Output::from(DataId::from("image".to_owned())));
+    }
+}
@@ -5,8 +5,15 @@ use std::{
 
 use uuid::Uuid;
 
-pub use crate::common::{LogLevel, LogMessage, NodeError, NodeErrorCause, NodeExitStatus};
-use crate::{BuildId, common::DaemonId, id::NodeId};
+pub use crate::common::{
+    DataflowStopRequest, LogLevel, LogMessage, NodeError, NodeErrorCause, NodeExitStatus,
+};
+use crate::{
+    BuildId,
+    common::DaemonId,
+    config::DataTypeSpec,
+    id::{DataId, NodeId},
+};
 
 #[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
 pub enum ControlRequestReply {
@@ -32,22 +39,60 @@ pub enum ControlRequestReply {
         uuid: Uuid,
         result: DataflowResult,
     },
+    NodeAdded {
+        uuid: Uuid,
+        node_id: NodeId,
+    },
+    NodeRemoved {
+        uuid: Uuid,
+        node_id: NodeId,
+    },
+    NodeRestarted {
+        uuid: Uuid,
+        node_id: NodeId,
+    },
     DataflowList(DataflowList),
     DestroyOk,
     DaemonConnected(bool),
     ConnectedDaemons(BTreeSet<DaemonId>),
     Logs(Vec<u8>),
+    Topics {
+        uuid: Uuid,
+        topics: Vec<TopicInfo>,
+        inputs: Vec<InputInfo>,
+    },
     CliAndDefaultDaemonIps {
         default_daemon: Option<IpAddr>,
         cli: Option<IpAddr>,
     },
 }
 
+/// An output of a running dataflow.
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub struct TopicInfo {
+    pub node_id: NodeId,
+    pub output_id: DataId,
+    /// The declared data type of the output, if any.
+    pub data_type: Option<DataTypeSpec>,
+}
+
+/// An input of a running dataflow.
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub struct InputInfo {
+    pub node_id: NodeId,
+    pub input_id: DataId,
+    /// The declared data type of the input, if any.
+    pub data_type: Option<DataTypeSpec>,
+}
+
 #[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
 pub struct DataflowResult {
     pub uuid: Uuid,
     pub timestamp: uhlc::Timestamp,
     pub node_results: BTreeMap<NodeId, Result<(), NodeError>>,
+    /// Set if the dataflow was stopped because one of its nodes requested it.
+    #[serde(default)]
+    pub stopped_by: Option<DataflowStopRequest>,
 }
 
 impl DataflowResult {
@@ -56,6 +101,7 @@ impl DataflowResult {
             uuid,
             timestamp,
             node_results: Default::default(),
+            stopped_by: None,
         }
     }
 
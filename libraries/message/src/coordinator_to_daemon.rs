@@ -43,6 +43,23 @@ pub enum DaemonCoordinatorEvent {
         dataflow_id: DataflowId,
         grace_duration: Option<Duration>,
     },
+    AddNodes(AddDataflowNodes),
+    /// Stops the given nodes, e.g. because they were removed from the running dataflow.
+    ///
+    /// Nodes that are not running on the receiving daemon are ignored.
+    RemoveNodes {
+        dataflow_id: DataflowId,
+        node_ids: BTreeSet<NodeId>,
+        grace_duration: Option<Duration>,
+    },
+    /// Stops the given nodes and spawns them again once they exited.
+    ///
+    /// Nodes that are not running on the receiving daemon are ignored.
+    RestartNodes {
+        dataflow_id: DataflowId,
+        node_ids: BTreeSet<NodeId>,
+        grace_duration: Option<Duration>,
+    },
     ReloadDataflow {
         dataflow_id: DataflowId,
         node_id: NodeId,
@@ -93,3 +110,15 @@ pub struct SpawnDataflowNodes {
     pub spawn_nodes: BTreeSet<NodeId>,
     pub uv: bool,
 }
+
+/// Adds nodes to a running dataflow.
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+pub struct AddDataflowNodes {
+    pub dataflow_id: DataflowId,
+    /// The nodes that are added to the dataflow.
+    pub nodes: BTreeMap<NodeId, ResolvedNode>,
+    /// The updated descriptor of the dataflow, including the added nodes.
+    pub dataflow_descriptor: Descriptor,
+    /// The added nodes that should be spawned on the receiving daemon.
+    pub spawn_nodes: BTreeSet<NodeId>,
+}
@@ -1,7 +1,8 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
 pub use crate::common::{
-    DataMessage, LogLevel, LogMessage, NodeError, NodeErrorCause, NodeExitStatus, Timestamped,
+    DataMessage, DataflowStopRequest, LogLevel, LogMessage, NodeError, NodeErrorCause,
+    NodeExitStatus, Timestamped,
 };
 use crate::{
     BuildId, DataflowId, common::DaemonId, current_crate_version, id::NodeId, versions_compatible,
@@ -20,13 +21,25 @@ pub enum CoordinatorRequest {
 pub struct DaemonRegisterRequest {
     dora_version: semver::Version,
     pub machine_id: Option<String>,
+    /// Labels describing the capabilities of the daemon, used for placing nodes.
+    #[serde(default)]
+    pub labels: BTreeMap<String, String>,
+    /// Set if the daemon was connected to a coordinator before and lost the connection.
+    #[serde(default)]
+    pub reconnect: Option<DaemonReconnect>,
 }
 
 impl DaemonRegisterRequest {
-    pub fn new(machine_id: Option<String>) -> Self {
+    pub fn new(
+        machine_id: Option<String>,
+        labels: BTreeMap<String, String>,
+        reconnect: Option<DaemonReconnect>,
+    ) -> Self {
         Self {
             dora_version: current_crate_version(),
             machine_id,
+            labels,
+            reconnect,
         }
     }
 
@@ -46,7 +59,17 @@ impl DaemonRegisterRequest {
     }
 }
 
-#[derive(Debug, serde::Serialize, serde::Deserialize)]
+/// Information sent by a daemon that registers again after losing its coordinator connection,
+/// e.g. because the coordinator was restarted.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct DaemonReconnect {
+    /// The ID that the daemon was assigned on its previous registration.
+    pub daemon_id: DaemonId,
+    /// The dataflows that are still running on the daemon.
+    pub running_dataflows: BTreeSet<DataflowId>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub enum DaemonEvent {
     BuildResult {
         build_id: BuildId,
@@ -64,6 +87,11 @@ pub enum DaemonEvent {
         dataflow_id: DataflowId,
         result: DataflowDaemonResult,
     },
+    /// A node requested to stop the whole dataflow.
+    StopDataflowRequested {
+        dataflow_id: DataflowId,
+        request: DataflowStopRequest,
+    },
     Heartbeat,
     Log(LogMessage),
     Exit,
@@ -73,6 +101,9 @@ pub enum DaemonEvent {
 pub struct DataflowDaemonResult {
     pub timestamp: uhlc::Timestamp,
     pub node_results: BTreeMap<NodeId, Result<(), NodeError>>,
+    /// Set if a local node requested to stop the dataflow.
+    #[serde(default)]
+    pub stop_request: Option<DataflowStopRequest>,
 }
 
 impl DataflowDaemonResult {
@@ -86,6 +117,9 @@ pub enum DaemonCoordinatorReply {
     TriggerBuildResult(Result<(), String>),
     TriggerSpawnResult(Result<(), String>),
     ReloadResult(Result<(), String>),
+    AddNodesResult(Result<(), String>),
+    RemoveNodesResult(Result<(), String>),
+    RestartNodesResult(Result<(), String>),
     StopResult(Result<(), String>),
     DestroyResult {
         result: Result<(), String>,
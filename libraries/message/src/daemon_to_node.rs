@@ -1,4 +1,4 @@
-use std::{net::SocketAddr, path::PathBuf};
+use std::{collections::BTreeSet, net::SocketAddr, path::PathBuf};
 
 use crate::{
     DataflowId,
@@ -25,6 +25,22 @@ pub struct NodeConfig {
     pub daemon_communication: DaemonCommunication,
     pub dataflow_descriptor: serde_yaml::Value,
     pub dynamic: bool,
+    /// Report all consumed inputs to the daemon, not only inputs with a blocking queue policy.
+    ///
+    /// This is required for replicas that are load-balanced by the number of pending inputs.
+    #[serde(default)]
+    pub report_consumed_inputs: bool,
+    /// Outputs that are mapped to inputs with a blocking queue policy.
+    ///
+    /// Sending a message on these outputs waits until the receivers have room in their input
+    /// queues.
+    #[serde(default)]
+    pub blocking_outputs: BTreeSet<DataId>,
+    /// Periodically report statistics about the inputs to the daemon.
+    ///
+    /// Set if the daemon serves metrics.
+    #[serde(default)]
+    pub report_input_stats: bool,
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
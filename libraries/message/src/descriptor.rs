@@ -1,7 +1,7 @@
 #![warn(missing_docs)]
 
 use crate::{
-    config::{CommunicationConfig, Input, InputMapping, NodeRunConfig},
+    config::{CommunicationConfig, Input, InputMapping, NodeRunConfig, Output},
     id::{DataId, NodeId, OperatorId},
 };
 use schemars::JsonSchema;
@@ -11,9 +11,19 @@ use std::{
     collections::{BTreeMap, BTreeSet},
     fmt,
     path::PathBuf,
+    time::Duration,
 };
 
 pub const SHELL_SOURCE: &str = "shell";
+/// Separates the ID of a node that [includes](Node::include) another dataflow from the
+/// IDs of the included nodes.
+///
+/// For example, a node `detector` of an included dataflow is assigned the ID
+/// `perception.detector` if the including node has the ID `perception`.
+pub const NAMESPACE_SEPARATOR: &str = ".";
+/// Source name for referring to the exported [`inputs`](Descriptor::inputs) of an included
+/// dataflow (e.g. `inputs/image`).
+pub const INCLUDE_INPUTS_SOURCE: &str = "inputs";
 /// Set the [`Node::path`] field to this value to treat the node as a
 /// [_dynamic node_](https://docs.rs/dora-node-api/latest/dora_node_api/).
 pub const DYNAMIC_SOURCE: &str = "dynamic";
@@ -88,15 +98,109 @@ pub struct Descriptor {
     #[schemars(skip)]
     #[serde(default, rename = "_unstable_debug")]
     pub debug: Debug,
+
+    /// Inputs exported by this dataflow when it is [included](Node::include) by another
+    /// dataflow.
+    ///
+    /// The nodes of the included dataflow can subscribe to an exported input through the
+    /// special `inputs` source (e.g. `inputs/image`). The including node then connects
+    /// the exported input to some output of the parent dataflow.
+    ///
+    /// ## Example
+    ///
+    /// ```yaml
+    /// inputs:
+    ///   - image
+    /// nodes:
+    ///   - id: detector
+    ///     path: detector.py
+    ///     inputs:
+    ///       image: inputs/image
+    /// ```
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub inputs: BTreeSet<DataId>,
+
+    /// Outputs exported by this dataflow when it is [included](Node::include) by another
+    /// dataflow.
+    ///
+    /// Maps the exported output IDs to outputs of nodes in this dataflow. The nodes of the
+    /// parent dataflow can subscribe to exported outputs through the ID of the including
+    /// node (e.g. `perception/bbox`).
+    ///
+    /// ## Example
+    ///
+    /// ```yaml
+    /// outputs:
+    ///   bbox: detector/bbox
+    /// nodes:
+    ///   - id: detector
+    ///     path: detector.py
+    ///     outputs:
+    ///       - bbox
+    /// ```
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub outputs: BTreeMap<DataId, InputMapping>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct Deploy {
     /// Target machine for deployment
     pub machine: Option<String>,
     /// Working directory for the deployment
     pub working_dir: Option<PathBuf>,
+    /// Labels that the daemon running the node must have.
+    ///
+    /// The coordinator deploys the node on a daemon with matching labels. Daemons report
+    /// the `arch`, `os`, `cpus`, and `memory_mb` labels automatically, additional labels can
+    /// be set through `dora daemon --label key=value`. If `machine` is set too, only the
+    /// daemon of that machine is considered.
+    ///
+    /// If multiple daemons match, the node is deployed on the first of them, ordered by
+    /// machine ID. Nodes are not balanced between the matching daemons, so all nodes with
+    /// the same labels end up on the same daemon.
+    ///
+    /// Numeric labels can be compared by prefixing the value with `>=`, `<=`, `>`, or `<`:
+    ///
+    /// ```yaml
+    /// _unstable_deploy:
+    ///   labels:
+    ///     gpu: true
+    ///     cpus: ">=4"
+    /// ```
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, EnvValue>,
+}
+
+impl Deploy {
+    /// Checks whether a daemon with the given labels fulfills the label constraints.
+    pub fn matches_labels(&self, daemon_labels: &BTreeMap<String, String>) -> bool {
+        self.labels.iter().all(|(key, required)| {
+            daemon_labels
+                .get(key)
+                .is_some_and(|value| label_matches(&required.to_string(), value))
+        })
+    }
+}
+
+fn label_matches(required: &str, value: &str) -> bool {
+    let comparison = [">=", "<=", ">", "<"]
+        .into_iter()
+        .find_map(|op| required.strip_prefix(op).map(|bound| (op, bound.trim())));
+    match comparison {
+        Some((op, bound)) => {
+            let (Ok(bound), Ok(value)) = (bound.parse::<f64>(), value.parse::<f64>()) else {
+                return false;
+            };
+            match op {
+                ">=" => value >= bound,
+                "<=" => value <= bound,
+                ">" => value > bound,
+                _ => value < bound,
+            }
+        }
+        None => required == value,
+    }
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
@@ -104,6 +208,15 @@ pub struct Debug {
     /// Whether to publish all messages to Zenoh for debugging
     #[serde(default)]
     pub publish_all_messages_to_zenoh: bool,
+    /// Whether messages can be injected into node inputs through `dora topic pub`
+    ///
+    /// Disabled by default because anyone who can reach the Zenoh network of the dataflow
+    /// could otherwise send arbitrary inputs to its nodes.
+    #[serde(default)]
+    pub allow_input_injection: bool,
+    /// Record all node outputs to the given directory (see `dora record`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub record_dir: Option<PathBuf>,
 }
 
 /// # Dora Node Configuration
@@ -271,6 +384,46 @@ pub struct Node {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub custom: Option<CustomNode>,
 
+    /// Include another dataflow file as a sub-graph.
+    ///
+    /// The nodes of the included dataflow are added to this dataflow, with their IDs
+    /// prefixed by the ID of this node and a [`.`](NAMESPACE_SEPARATOR) separator
+    /// (e.g. `perception.detector`). The path is relative to the directory of the current
+    /// dataflow file. Included nodes run in the directory of the included file.
+    ///
+    /// The included dataflow can export [`inputs`](Descriptor::inputs) and
+    /// [`outputs`](Descriptor::outputs). The exported inputs are connected through the
+    /// [`inputs`](Self::inputs) field of this node. Other nodes can subscribe to the
+    /// exported outputs through the ID of this node. The `include` field cannot be combined
+    /// with [`path`](Self::path), [`operators`](Self::operators), or other node kinds.
+    ///
+    /// ## Example
+    ///
+    /// ```yaml
+    /// nodes:
+    ///   - id: camera
+    ///     path: camera.py
+    ///     outputs:
+    ///       - image
+    ///   - id: perception
+    ///     include: perception/dataflow.yml
+    ///     inputs:
+    ///       image: camera/image
+    ///   - id: plot
+    ///     path: plot.py
+    ///     inputs:
+    ///       bbox: perception/bbox
+    /// ```
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub include: Option<PathBuf>,
+
+    /// Parsed contents of the [`include`](Self::include)d dataflow.
+    ///
+    /// This field is filled in automatically when reading the dataflow file.
+    #[schemars(skip)]
+    #[serde(default, rename = "_included", skip_serializing_if = "Option::is_none")]
+    pub included: Option<Box<Descriptor>>,
+
     /// Output data identifiers produced by this node.
     ///
     /// List of output identifiers that the node sends.
@@ -287,8 +440,27 @@ pub struct Node {
     ///       - processed_image
     ///       - metadata
     /// ```
+    ///
+    /// ## Declared Types
+    ///
+    /// Outputs can optionally declare their Arrow data type. Sending an output with a different
+    /// type results in an error. Inputs can declare their expected type too, which allows
+    /// `dora check` to detect mismatched connections.
+    ///
+    /// ```yaml
+    /// nodes:
+    ///   - id: camera
+    ///     outputs:
+    ///       - id: image
+    ///         type: {list: uint8}
+    ///   - id: plot
+    ///     inputs:
+    ///       image:
+    ///         source: camera/image
+    ///         type: {list: uint8}
+    /// ```
     #[serde(default)]
-    pub outputs: BTreeSet<DataId>,
+    pub outputs: BTreeSet<Output>,
 
     /// Input data connections from other nodes.
     ///
@@ -462,12 +634,484 @@ pub struct Node {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub rev: Option<String>,
 
+    /// Restart policy for the node.
+    ///
+    /// By default, nodes are not restarted when they exit. Using the `restart` key, the
+    /// daemon can be instructed to respawn the node instead. The following policies are
+    /// supported:
+    ///
+    /// - `never` (default): Don't restart the node.
+    /// - `on-failure`: Restart the node if it exits with an error.
+    /// - `always`: Restart the node whenever it exits, unless the dataflow is stopped or all
+    ///   inputs of the node were closed.
+    ///
+    /// Downstream nodes don't receive an `InputClosed` event while a restart is pending.
+    /// Instead, their inputs stay open and receive data again once the node is restarted.
+    ///
+    /// The number of restarts can be limited through `max_retries`. Between restarts, the
+    /// daemon waits for an exponentially increasing backoff duration, starting at
+    /// `initial_backoff_ms` and capped at `max_backoff_ms`.
+    ///
+    /// Restart policies are ignored for dynamic nodes.
+    ///
+    /// ## Example
+    ///
+    /// ```yaml
+    /// nodes:
+    ///   - id: camera
+    ///     path: camera-driver
+    ///     restart: on-failure
+    ///   - id: lidar
+    ///     path: lidar-driver
+    ///     restart:
+    ///       policy: always
+    ///       max_retries: 5
+    ///       initial_backoff_ms: 500
+    ///       max_backoff_ms: 10000
+    /// ```
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub restart: Option<RestartPolicy>,
+
+    /// Run multiple instances of the node to share its workload.
+    ///
+    /// Spawns the given number of instances (_replicas_) of the node. The replicas are
+    /// assigned the IDs `<id>.0`, `<id>.1`, and so on. Each input message is delivered to
+    /// only one of the replicas, which is selected according to the following policies:
+    ///
+    /// - `round-robin` (default): Cycle through the replicas in order.
+    /// - `least-loaded`: Pick the replica with the fewest unprocessed inputs.
+    ///
+    /// The outputs of all replicas are merged and sent out under the ID of this node, so
+    /// downstream nodes subscribe to them as usual (e.g. `detector/bbox`). The outputs are
+    /// closed once all replicas have finished.
+    ///
+    /// All replicas are deployed to the same machine.
+    ///
+    /// ## Example
+    ///
+    /// ```yaml
+    /// nodes:
+    ///   - id: detector
+    ///     path: detector.py
+    ///     replicas: 4
+    ///     inputs:
+    ///       image: camera/image
+    ///     outputs:
+    ///       - bbox
+    ///   - id: tracker
+    ///     path: tracker.py
+    ///     replicas:
+    ///       count: 2
+    ///       policy: least-loaded
+    ///     inputs:
+    ///       bbox: detector/bbox
+    /// ```
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replicas: Option<Replicas>,
+
+    /// Resource limits for the node process.
+    ///
+    /// The daemon places the node in its own [cgroup](https://docs.kernel.org/admin-guide/cgroup-v2.html)
+    /// and configures the given limits for it. This way, a misbehaving node cannot exhaust the
+    /// resources of the whole machine. The following limits are supported:
+    ///
+    /// - `memory_max`: Maximum memory usage, either in bytes or with a `K`, `M`, `G`, or `T`
+    ///   suffix (powers of 1024). The node is killed if it exceeds this limit.
+    /// - `cpu_quota`: Maximum CPU usage, as a number of CPU cores (e.g. `0.5` for half a core).
+    ///   The node is throttled if it exceeds this limit.
+    /// - `pids_max`: Maximum number of processes and threads. Creating new processes or
+    ///   threads fails if the limit is reached.
+    ///
+    /// Resource limits are only supported on Linux with cgroup v2. The daemon needs write
+    /// access to its own cgroup, e.g. by starting it through
+    /// `systemd-run --user --scope -p Delegate=yes dora daemon`. Spawning the node fails if
+    /// the limits cannot be applied. Resource limits are ignored for dynamic nodes.
+    ///
+    /// ## Example
+    ///
+    /// ```yaml
+    /// nodes:
+    ///   - id: detector
+    ///     path: detector.py
+    ///     resources:
+    ///       memory_max: 2G
+    ///       cpu_quota: 1.5
+    ///       pids_max: 64
+    /// ```
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resources: Option<NodeResources>,
+
+    /// CPU scheduling settings for the node process.
+    ///
+    /// The daemon applies these settings to the node process before it starts executing,
+    /// so there is no need to wrap nodes in `taskset` or `chrt` calls. The following settings
+    /// are supported:
+    ///
+    /// - `cpu_affinity`: List of CPU cores that the node is allowed to run on.
+    /// - `nice`: Niceness of the node, from `-20` (highest priority) to `19` (lowest
+    ///   priority). Negative values require the `CAP_SYS_NICE` capability.
+    /// - `policy`: Real-time scheduling policy, either `fifo` (`SCHED_FIFO`) or `rr`
+    ///   (`SCHED_RR`). Requires a `priority`.
+    /// - `priority`: Real-time priority from `1` (lowest) to `99` (highest). Real-time
+    ///   scheduling requires the `CAP_SYS_NICE` capability or a suitable `RLIMIT_RTPRIO`.
+    ///
+    /// The settings are inherited by all threads and child processes of the node. Spawning
+    /// the node fails if the settings cannot be applied. Scheduling settings are only
+    /// supported on Linux and are ignored for dynamic nodes.
+    ///
+    /// ## Example
+    ///
+    /// ```yaml
+    /// nodes:
+    ///   - id: controller
+    ///     path: controller
+    ///     scheduling:
+    ///       cpu_affinity: [2, 3]
+    ///       policy: fifo
+    ///       priority: 80
+    ///   - id: logger
+    ///     path: logger.py
+    ///     scheduling:
+    ///       nice: 10
+    /// ```
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scheduling: Option<NodeScheduling>,
+
     /// Unstable machine deployment configuration
     #[schemars(skip)]
     #[serde(rename = "_unstable_deploy")]
     pub deploy: Option<Deploy>,
 }
 
+/// Specifies whether and how a node is restarted after it exits.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(from = "RestartPolicyDef", into = "RestartPolicyDef")]
+pub struct RestartPolicy {
+    /// When to restart the node.
+    pub mode: RestartMode,
+    /// Maximum number of restarts. Unlimited if `None`.
+    pub max_retries: Option<u32>,
+    /// Backoff duration before the first restart.
+    ///
+    /// The backoff is doubled for every subsequent restart.
+    pub initial_backoff: Duration,
+    /// Upper limit for the backoff duration.
+    pub max_backoff: Duration,
+}
+
+impl RestartPolicy {
+    const DEFAULT_INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+    const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+    /// Creates a new policy with the given mode and default options.
+    pub fn new(mode: RestartMode) -> Self {
+        Self {
+            mode,
+            max_retries: None,
+            initial_backoff: Self::DEFAULT_INITIAL_BACKOFF,
+            max_backoff: Self::DEFAULT_MAX_BACKOFF,
+        }
+    }
+
+    /// Returns the backoff duration before the restart with the given number.
+    ///
+    /// The first restart has number `0`.
+    pub fn backoff(&self, restart: u32) -> Duration {
+        self.initial_backoff
+            .saturating_mul(2u32.saturating_pow(restart))
+            .min(self.max_backoff)
+    }
+
+    /// Returns whether a node that already restarted `restarts` times should be restarted
+    /// after exiting with the given success status.
+    pub fn should_restart(&self, success: bool, restarts: u32) -> bool {
+        let mode_allows = match self.mode {
+            RestartMode::Never => false,
+            RestartMode::OnFailure => !success,
+            RestartMode::Always => true,
+        };
+        mode_allows && self.max_retries.is_none_or(|max| restarts < max)
+    }
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self::new(RestartMode::default())
+    }
+}
+
+/// When a node should be restarted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum RestartMode {
+    /// Never restart the node.
+    #[default]
+    Never,
+    /// Restart the node if it exits with an error.
+    OnFailure,
+    /// Restart the node whenever it exits.
+    Always,
+}
+
+/// Serialization format of [`RestartPolicy`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum RestartPolicyDef {
+    /// Only the restart mode, using default options.
+    ModeOnly(RestartMode),
+    /// Restart mode with additional options.
+    WithOptions {
+        /// When to restart the node.
+        policy: RestartMode,
+        /// Maximum number of restarts.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max_retries: Option<u32>,
+        /// Backoff before the first restart in milliseconds.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        initial_backoff_ms: Option<u64>,
+        /// Upper limit for the backoff in milliseconds.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max_backoff_ms: Option<u64>,
+    },
+}
+
+impl From<RestartPolicyDef> for RestartPolicy {
+    fn from(value: RestartPolicyDef) -> Self {
+        match value {
+            RestartPolicyDef::ModeOnly(mode) => Self::new(mode),
+            RestartPolicyDef::WithOptions {
+                policy,
+                max_retries,
+                initial_backoff_ms,
+                max_backoff_ms,
+            } => Self {
+                mode: policy,
+                max_retries,
+                initial_backoff: initial_backoff_ms
+                    .map(Duration::from_millis)
+                    .unwrap_or(Self::DEFAULT_INITIAL_BACKOFF),
+                max_backoff: max_backoff_ms
+                    .map(Duration::from_millis)
+                    .unwrap_or(Self::DEFAULT_MAX_BACKOFF),
+            },
+        }
+    }
+}
+
+impl From<RestartPolicy> for RestartPolicyDef {
+    fn from(policy: RestartPolicy) -> Self {
+        if policy == RestartPolicy::new(policy.mode) {
+            Self::ModeOnly(policy.mode)
+        } else {
+            Self::WithOptions {
+                policy: policy.mode,
+                max_retries: policy.max_retries,
+                initial_backoff_ms: Some(policy.initial_backoff.as_millis() as u64),
+                max_backoff_ms: Some(policy.max_backoff.as_millis() as u64),
+            }
+        }
+    }
+}
+
+/// Number of instances of a replicated node and how inputs are distributed between them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(from = "ReplicasDef", into = "ReplicasDef")]
+pub struct Replicas {
+    /// Number of instances to spawn.
+    pub count: u32,
+    /// How input messages are distributed between the instances.
+    pub policy: ReplicaPolicy,
+}
+
+/// Selects the replica that receives an input message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum ReplicaPolicy {
+    /// Cycle through the replicas in order.
+    #[default]
+    RoundRobin,
+    /// Pick the replica with the fewest unprocessed inputs.
+    LeastLoaded,
+}
+
+/// Serialization format of [`Replicas`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum ReplicasDef {
+    /// Only the number of instances, using the default policy.
+    CountOnly(u32),
+    /// Number of instances with additional options.
+    WithOptions {
+        /// Number of instances to spawn.
+        count: u32,
+        /// How input messages are distributed between the instances.
+        #[serde(default)]
+        policy: ReplicaPolicy,
+    },
+}
+
+impl From<ReplicasDef> for Replicas {
+    fn from(value: ReplicasDef) -> Self {
+        match value {
+            ReplicasDef::CountOnly(count) => Self {
+                count,
+                policy: ReplicaPolicy::default(),
+            },
+            ReplicasDef::WithOptions { count, policy } => Self { count, policy },
+        }
+    }
+}
+
+impl From<Replicas> for ReplicasDef {
+    fn from(replicas: Replicas) -> Self {
+        if replicas.policy == ReplicaPolicy::default() {
+            Self::CountOnly(replicas.count)
+        } else {
+            Self::WithOptions {
+                count: replicas.count,
+                policy: replicas.policy,
+            }
+        }
+    }
+}
+
+/// Limits for the resources that a node process can use.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct NodeResources {
+    /// Maximum memory usage.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory_max: Option<MemorySize>,
+    /// Maximum CPU usage, as a number of CPU cores.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu_quota: Option<f64>,
+    /// Maximum number of processes and threads.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pids_max: Option<u64>,
+}
+
+impl NodeResources {
+    /// Returns `true` if no limits are set.
+    pub fn is_empty(&self) -> bool {
+        self.memory_max.is_none() && self.cpu_quota.is_none() && self.pids_max.is_none()
+    }
+}
+
+/// CPU scheduling settings of a node process.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct NodeScheduling {
+    /// CPU cores that the node is allowed to run on.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu_affinity: Option<BTreeSet<usize>>,
+    /// Niceness of the node, from `-20` (highest priority) to `19` (lowest priority).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nice: Option<i32>,
+    /// Real-time scheduling policy.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub policy: Option<RealtimePolicy>,
+    /// Real-time priority from `1` (lowest) to `99` (highest).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<u8>,
+}
+
+/// Real-time scheduling policy of a node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum RealtimePolicy {
+    /// First-in, first-out scheduling (`SCHED_FIFO`).
+    #[serde(rename = "fifo")]
+    Fifo,
+    /// Round-robin scheduling (`SCHED_RR`).
+    #[serde(rename = "rr")]
+    RoundRobin,
+}
+
+/// An amount of memory in bytes.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema,
+)]
+#[serde(try_from = "MemorySizeDef", into = "MemorySizeDef")]
+pub struct MemorySize(pub u64);
+
+impl fmt::Display for MemorySize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let units = [
+            ("T", 1 << 40),
+            ("G", 1 << 30),
+            ("M", 1 << 20),
+            ("K", 1 << 10),
+        ];
+        match units
+            .iter()
+            .find(|(_, size)| self.0 >= *size && self.0 % size == 0)
+        {
+            Some((unit, size)) => write!(f, "{}{unit}", self.0 / size),
+            None => write!(f, "{} bytes", self.0),
+        }
+    }
+}
+
+/// Serialization format of [`MemorySize`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum MemorySizeDef {
+    /// Number of bytes.
+    Bytes(u64),
+    /// Number with a `K`, `M`, `G`, or `T` suffix, e.g. `512M`.
+    WithUnit(String),
+}
+
+impl TryFrom<MemorySizeDef> for MemorySize {
+    type Error = String;
+
+    fn try_from(value: MemorySizeDef) -> Result<Self, Self::Error> {
+        let s = match value {
+            MemorySizeDef::Bytes(bytes) => return Ok(Self(bytes)),
+            MemorySizeDef::WithUnit(s) => s,
+        };
+        let trimmed = s.trim();
+        let split = trimmed
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(trimmed.len());
+        let (number, unit) = trimmed.split_at(split);
+        let number: u64 = number
+            .parse()
+            .map_err(|_| format!("invalid memory size `{s}`"))?;
+        let factor: u64 = match unit.trim().to_ascii_uppercase().as_str() {
+            "" | "B" => 1,
+            "K" | "KB" | "KIB" => 1 << 10,
+            "M" | "MB" | "MIB" => 1 << 20,
+            "G" | "GB" | "GIB" => 1 << 30,
+            "T" | "TB" | "TIB" => 1 << 40,
+            other => return Err(format!("unknown unit `{other}` in memory size `{s}`")),
+        };
+        number
+            .checked_mul(factor)
+            .map(Self)
+            .ok_or_else(|| format!("memory size `{s}` is too large"))
+    }
+}
+
+impl From<MemorySize> for MemorySizeDef {
+    fn from(size: MemorySize) -> Self {
+        match size.to_string() {
+            s if s.ends_with(" bytes") => Self::Bytes(size.0),
+            s => Self::WithUnit(s),
+        }
+    }
+}
+
+/// Marks a resolved node as one instance of a replicated node.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Replica {
+    /// ID of the replicated node, under which the outputs of all replicas are sent.
+    pub group: NodeId,
+    /// Index of this instance, starting at `0`.
+    pub index: u32,
+    /// How input messages are distributed between the instances.
+    pub policy: ReplicaPolicy,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ResolvedNode {
     pub id: NodeId,
@@ -478,11 +1122,34 @@ pub struct ResolvedNode {
     #[serde(default)]
     pub deploy: Option<Deploy>,
 
+    /// Restart policy of the node (defaults to never restarting).
+    #[serde(default)]
+    pub restart: RestartPolicy,
+
+    /// Set if this node is one instance of a replicated node.
+    #[serde(default)]
+    pub replica: Option<Replica>,
+
+    /// Resource limits for the node process.
+    #[serde(default)]
+    pub resources: Option<NodeResources>,
+
+    /// CPU scheduling settings for the node process.
+    #[serde(default)]
+    pub scheduling: Option<NodeScheduling>,
+
     #[serde(flatten)]
     pub kind: CoreNodeKind,
 }
 
 impl ResolvedNode {
+    /// The node ID under which the outputs of this node are sent.
+    ///
+    /// This is the ID of the replicated node for replicas and the own ID otherwise.
+    pub fn output_node_id(&self) -> &NodeId {
+        self.replica.as_ref().map(|r| &r.group).unwrap_or(&self.id)
+    }
+
     pub fn has_git_source(&self) -> bool {
         self.kind
             .as_custom()
@@ -545,7 +1212,7 @@ pub struct OperatorConfig {
     pub inputs: BTreeMap<DataId, Input>,
     /// Output data identifiers
     #[serde(default)]
-    pub outputs: BTreeSet<DataId>,
+    pub outputs: BTreeSet<Output>,
 
     /// Operator source configuration (Python, shared library, etc.)
     #[serde(flatten)]
@@ -564,7 +1231,7 @@ pub struct OperatorConfig {
 pub enum OperatorSource {
     SharedLibrary(String),
     Python(PythonSource),
-    #[schemars(skip)]
+    /// Path to a WebAssembly component implementing the `dora:operator` world
     Wasm(String),
 }
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
@@ -700,3 +1367,101 @@ impl fmt::Display for EnvValue {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compare_labels() {
+        assert!(label_matches("true", "true"));
+        assert!(!label_matches("true", "false"));
+        assert!(label_matches(">=4", "4"));
+        assert!(label_matches(">= 4", "8"));
+        assert!(!label_matches(">=4", "2"));
+        assert!(label_matches(">4", "4.5"));
+        assert!(!label_matches(">4", "4"));
+        assert!(label_matches("<=1024", "1024"));
+        assert!(label_matches("<1024", "512"));
+        assert!(!label_matches("<1024", "2048"));
+        // comparisons require numeric values
+        assert!(!label_matches(">=4", "many"));
+        assert!(!label_matches(">=four", "4"));
+    }
+
+    #[test]
+    fn match_daemon_labels() {
+        let deploy: Deploy =
+            serde_yaml::from_str("{labels: {gpu: true, cpus: '>=4', arch: x86_64}}").unwrap();
+        let daemon = |labels: &[(&str, &str)]| -> BTreeMap<String, String> {
+            labels
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect()
+        };
+        assert!(deploy.matches_labels(&daemon(&[
+            ("gpu", "true"),
+            ("cpus", "8"),
+            ("arch", "x86_64"),
+            ("os", "linux"),
+        ])));
+        assert!(!deploy.matches_labels(&daemon(&[
+            ("gpu", "true"),
+            ("cpus", "2"),
+            ("arch", "x86_64"),
+        ])));
+        assert!(!deploy.matches_labels(&daemon(&[("cpus", "8"), ("arch", "x86_64")])));
+        assert!(Deploy::default().matches_labels(&daemon(&[])));
+    }
+
+    #[test]
+    fn restart_backoff() {
+        let policy = RestartPolicy::new(RestartMode::Always);
+        assert_eq!(policy.backoff(0), Duration::from_millis(500));
+        assert_eq!(policy.backoff(1), Duration::from_secs(1));
+        assert_eq!(policy.backoff(3), Duration::from_secs(4));
+        assert_eq!(policy.backoff(6), Duration::from_secs(30));
+        // no overflow for large restart counts
+        assert_eq!(policy.backoff(u32::MAX), Duration::from_secs(30));
+    }
+
+    #[test]
+    fn restart_decision() {
+        let never = RestartPolicy::new(RestartMode::Never);
+        assert!(!never.should_restart(false, 0));
+        assert!(!never.should_restart(true, 0));
+
+        let on_failure = RestartPolicy::new(RestartMode::OnFailure);
+        assert!(on_failure.should_restart(false, 100));
+        assert!(!on_failure.should_restart(true, 0));
+
+        let always = RestartPolicy {
+            max_retries: Some(2),
+            ..RestartPolicy::new(RestartMode::Always)
+        };
+        assert!(always.should_restart(true, 0));
+        assert!(always.should_restart(false, 1));
+        assert!(!always.should_restart(false, 2));
+    }
+
+    #[test]
+    fn parse_restart_policy() {
+        let policy: RestartPolicy = serde_yaml::from_str("on-failure").unwrap();
+        assert_eq!(policy, RestartPolicy::new(RestartMode::OnFailure));
+
+        let policy: RestartPolicy = serde_yaml::from_str(
+            "{policy: always, max_retries: 3, initial_backoff_ms: 100, max_backoff_ms: 1000}",
+        )
+        .unwrap();
+        assert_eq!(
+            policy,
+            RestartPolicy {
+                mode: RestartMode::Always,
+                max_retries: Some(3),
+                initial_backoff: Duration::from_millis(100),
+                max_backoff: Duration::from_secs(1),
+            }
+        );
+        assert_eq!(policy.backoff(4), Duration::from_secs(1));
+    }
+}
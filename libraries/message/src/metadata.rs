@@ -1,8 +1,11 @@
 use std::collections::BTreeMap;
 
 use arrow_schema::DataType;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+pub mod tensor;
+
 /// Additional data that is sent as part of output messages.
 ///
 /// Includes a timestamp, type information, and additional user-provided parameters.
@@ -69,10 +72,115 @@ pub enum Parameter {
     Float(f64),
     ListFloat(Vec<f64>),
     ListString(Vec<String>),
+    // new variants must be added at the end to keep the serialization format compatible
+    /// Arbitrary binary data, e.g. calibration blobs.
+    Binary(Vec<u8>),
+    /// A point in time, e.g. the capture time of a camera frame.
+    Timestamp(DateTime<Utc>),
+    /// Nested parameters.
+    Map(BTreeMap<String, Parameter>),
+    /// List of flags, e.g. which channels of a sensor are active.
+    ListBool(Vec<bool>),
+}
+
+macro_rules! impl_parameter_from {
+    ($($t:ty => $variant:ident),* $(,)?) => {
+        $(
+            impl From<$t> for Parameter {
+                fn from(value: $t) -> Self {
+                    Self::$variant(value.into())
+                }
+            }
+        )*
+    };
 }
 
+impl_parameter_from!(
+    bool => Bool,
+    i64 => Integer,
+    f64 => Float,
+    String => String,
+    &str => String,
+    Vec<i64> => ListInt,
+    Vec<f64> => ListFloat,
+    Vec<String> => ListString,
+    Vec<bool> => ListBool,
+    DateTime<Utc> => Timestamp,
+    BTreeMap<String, Parameter> => Map,
+);
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct BufferOffset {
     pub offset: usize,
     pub len: usize,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parameter_from_values() {
+        let timestamp = DateTime::from_timestamp_micros(1_700_000_000_123_456).unwrap();
+        assert_eq!(Parameter::from(true), Parameter::Bool(true));
+        assert_eq!(Parameter::from(-3i64), Parameter::Integer(-3));
+        assert_eq!(Parameter::from(1.5), Parameter::Float(1.5));
+        assert_eq!(Parameter::from("a"), Parameter::String("a".into()));
+        assert_eq!(
+            Parameter::from("a".to_owned()),
+            Parameter::String("a".into())
+        );
+        assert_eq!(
+            Parameter::from(vec![1i64, 2]),
+            Parameter::ListInt(vec![1, 2])
+        );
+        assert_eq!(Parameter::from(vec![0.5]), Parameter::ListFloat(vec![0.5]));
+        assert_eq!(
+            Parameter::from(vec!["a".to_owned()]),
+            Parameter::ListString(vec!["a".into()])
+        );
+        assert_eq!(
+            Parameter::from(vec![true, false]),
+            Parameter::ListBool(vec![true, false])
+        );
+        assert_eq!(Parameter::from(timestamp), Parameter::Timestamp(timestamp));
+        let map = BTreeMap::from([("x".to_owned(), Parameter::Integer(1))]);
+        assert_eq!(Parameter::from(map.clone()), Parameter::Map(map));
+    }
+
+    #[test]
+    fn serialize_parameters() {
+        let timestamp = DateTime::from_timestamp_micros(1_700_000_000_123_456).unwrap();
+        // the variant index is part of the serialized data, so it must not change
+        let parameters = [
+            Parameter::Bool(true),
+            Parameter::Integer(-3),
+            Parameter::String("a".into()),
+            Parameter::ListInt(vec![1, 2]),
+            Parameter::Float(1.5),
+            Parameter::ListFloat(vec![0.5]),
+            Parameter::ListString(vec!["a".into()]),
+            Parameter::Binary(vec![0, 255]),
+            Parameter::Timestamp(timestamp),
+            Parameter::Map(BTreeMap::from([(
+                "nested".to_owned(),
+                Parameter::ListBool(vec![true]),
+            )])),
+            Parameter::ListBool(vec![true, false]),
+        ];
+        for (index, parameter) in parameters.iter().enumerate() {
+            let serialized = bincode::serialize(parameter).unwrap();
+            assert_eq!(
+                serialized[..4],
+                (index as u32).to_le_bytes(),
+                "{parameter:?}"
+            );
+            let deserialized: Parameter = bincode::deserialize(&serialized).unwrap();
+            assert_eq!(&deserialized, parameter);
+        }
+        assert_eq!(
+            bincode::serialize(&Parameter::Integer(5)).unwrap(),
+            [1, 0, 0, 0, 5, 0, 0, 0, 0, 0, 0, 0]
+        );
+    }
+}
@@ -0,0 +1,528 @@
+//! Standard metadata parameters for n-dimensional tensors and images.
+//!
+//! Tensors and images are sent as flat Arrow primitive arrays (e.g. `UInt8` for `rgb8` images).
+//! Their layout is described through the metadata parameters defined in this module, so that
+//! nodes written by different people and in different languages can exchange them.
+//!
+//! | key       | parameter type | description                                              |
+//! |-----------|----------------|----------------------------------------------------------|
+//! | `shape`   | `ListInt`      | size of each dimension, outermost first                  |
+//! | `strides` | `ListInt`      | optional, byte offset between elements of each dimension |
+//! | `dtype`   | `String`       | element type, e.g. `uint8` or `float32`                  |
+//! | `width`   | `Integer`      | image width in pixels                                    |
+//! | `height`  | `Integer`      | image height in pixels                                   |
+//! | `encoding`| `String`       | image encoding, e.g. `rgb8`, `mono16`, or `jpeg`         |
+//!
+//! Tensors without `strides` are stored contiguously in row-major (C) order. Raw images are
+//! row-major tensors of shape `[height, width, channels]`, or `[height, width]` for single-channel
+//! encodings. Compressed images (`jpeg`, `png`) contain the encoded file as `UInt8` array.
+
+use std::{fmt, str::FromStr};
+
+use arrow_schema::DataType;
+use eyre::{Context, ContextCompat, bail};
+use serde::{Deserialize, Serialize};
+
+use super::{MetadataParameters, Parameter};
+
+/// Parameter key for the size of each tensor dimension.
+pub const SHAPE: &str = "shape";
+/// Parameter key for the byte strides of each tensor dimension.
+pub const STRIDES: &str = "strides";
+/// Parameter key for the tensor element type.
+pub const DTYPE: &str = "dtype";
+/// Parameter key for the image width in pixels.
+pub const WIDTH: &str = "width";
+/// Parameter key for the image height in pixels.
+pub const HEIGHT: &str = "height";
+/// Parameter key for the image encoding.
+pub const ENCODING: &str = "encoding";
+
+/// Describes the layout of an n-dimensional tensor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TensorInfo {
+    /// Size of each dimension, outermost first.
+    pub shape: Vec<usize>,
+    /// Byte offset between consecutive elements of each dimension.
+    ///
+    /// `None` for contiguous tensors in row-major order.
+    pub strides: Option<Vec<usize>>,
+    /// Arrow type of the tensor elements.
+    pub dtype: DataType,
+}
+
+impl TensorInfo {
+    /// Creates the info for a contiguous row-major tensor.
+    pub fn new(shape: Vec<usize>, dtype: DataType) -> Self {
+        Self {
+            shape,
+            strides: None,
+            dtype,
+        }
+    }
+
+    /// Total number of elements of the tensor.
+    pub fn num_elements(&self) -> usize {
+        self.shape.iter().product()
+    }
+
+    /// Returns the byte strides, computing them from the shape for contiguous tensors.
+    pub fn byte_strides(&self) -> eyre::Result<Vec<usize>> {
+        if let Some(strides) = &self.strides {
+            return Ok(strides.clone());
+        }
+        let element_size = self
+            .dtype
+            .primitive_width()
+            .with_context(|| format!("tensor dtype {} has no fixed width", self.dtype))?;
+        let mut strides = vec![element_size; self.shape.len()];
+        for i in (0..self.shape.len().saturating_sub(1)).rev() {
+            strides[i] = strides[i + 1] * self.shape[i + 1];
+        }
+        Ok(strides)
+    }
+
+    /// Adds the `shape`, `dtype`, and (if set) `strides` parameters.
+    pub fn write_parameters(&self, parameters: &mut MetadataParameters) -> eyre::Result<()> {
+        let dtype = dtype_name(&self.dtype)
+            .with_context(|| format!("unsupported tensor dtype {}", self.dtype))?;
+        parameters.insert(SHAPE.into(), Parameter::ListInt(to_ints(&self.shape)?));
+        parameters.insert(DTYPE.into(), Parameter::String(dtype.into()));
+        match &self.strides {
+            Some(strides) => {
+                parameters.insert(STRIDES.into(), Parameter::ListInt(to_ints(strides)?));
+            }
+            None => {
+                parameters.remove(STRIDES);
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads the tensor info from the given parameters.
+    ///
+    /// The `dtype` parameter is optional if the element type is given as `data_type`, which is
+    /// typically the data type of the received Arrow array.
+    pub fn from_parameters(
+        parameters: &MetadataParameters,
+        data_type: Option<&DataType>,
+    ) -> eyre::Result<Self> {
+        let shape = match parameters.get(SHAPE) {
+            Some(Parameter::ListInt(shape)) => from_ints(shape).context("invalid tensor shape")?,
+            Some(other) => bail!("expected `{SHAPE}` parameter of type ListInt, got {other:?}"),
+            None => bail!("missing `{SHAPE}` parameter"),
+        };
+        let strides = match parameters.get(STRIDES) {
+            Some(Parameter::ListInt(strides)) => {
+                let strides = from_ints(strides).context("invalid tensor strides")?;
+                if strides.len() != shape.len() {
+                    bail!(
+                        "tensor strides {strides:?} don't match the number of dimensions of shape {shape:?}"
+                    );
+                }
+                Some(strides)
+            }
+            Some(other) => bail!("expected `{STRIDES}` parameter of type ListInt, got {other:?}"),
+            None => None,
+        };
+        let dtype = match (parameters.get(DTYPE), data_type) {
+            (Some(Parameter::String(name)), data_type) => {
+                let dtype = dtype_from_name(name)
+                    .with_context(|| format!("unsupported tensor dtype `{name}`"))?;
+                if let Some(data_type) = data_type.filter(|d| **d != dtype) {
+                    bail!("tensor dtype `{name}` does not match data type {data_type}");
+                }
+                dtype
+            }
+            (Some(other), _) => bail!("expected `{DTYPE}` parameter of type String, got {other:?}"),
+            (None, Some(data_type)) => data_type.clone(),
+            (None, None) => bail!("missing `{DTYPE}` parameter"),
+        };
+        Ok(Self {
+            shape,
+            strides,
+            dtype,
+        })
+    }
+}
+
+/// Describes the size and encoding of an image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImageInfo {
+    /// Image width in pixels.
+    pub width: usize,
+    /// Image height in pixels.
+    pub height: usize,
+    /// Pixel encoding of the image data.
+    pub encoding: ImageEncoding,
+}
+
+impl ImageInfo {
+    /// Returns the tensor layout of raw images, or `None` for compressed encodings.
+    pub fn tensor_info(&self) -> Option<TensorInfo> {
+        let channels = self.encoding.channels()?;
+        let shape = if channels == 1 {
+            vec![self.height, self.width]
+        } else {
+            vec![self.height, self.width, channels]
+        };
+        Some(TensorInfo::new(shape, self.encoding.dtype()?))
+    }
+
+    /// Adds the `width`, `height`, and `encoding` parameters.
+    ///
+    /// For raw encodings, the `shape` and `dtype` tensor parameters are added too.
+    pub fn write_parameters(&self, parameters: &mut MetadataParameters) -> eyre::Result<()> {
+        parameters.insert(WIDTH.into(), Parameter::Integer(self.width.try_into()?));
+        parameters.insert(HEIGHT.into(), Parameter::Integer(self.height.try_into()?));
+        parameters.insert(
+            ENCODING.into(),
+            Parameter::String(self.encoding.as_str().into()),
+        );
+        if let Some(tensor) = self.tensor_info() {
+            tensor.write_parameters(parameters)?;
+        }
+        Ok(())
+    }
+
+    /// Reads the image info from the given parameters.
+    pub fn from_parameters(parameters: &MetadataParameters) -> eyre::Result<Self> {
+        let dimension = |key| match parameters.get(key) {
+            Some(Parameter::Integer(value)) => {
+                usize::try_from(*value).with_context(|| format!("invalid image {key} {value}"))
+            }
+            Some(other) => bail!("expected `{key}` parameter of type Integer, got {other:?}"),
+            None => bail!("missing `{key}` parameter"),
+        };
+        let encoding = match parameters.get(ENCODING) {
+            Some(Parameter::String(encoding)) => encoding.parse()?,
+            Some(other) => {
+                bail!("expected `{ENCODING}` parameter of type String, got {other:?}")
+            }
+            None => bail!("missing `{ENCODING}` parameter"),
+        };
+        Ok(Self {
+            width: dimension(WIDTH)?,
+            height: dimension(HEIGHT)?,
+            encoding,
+        })
+    }
+}
+
+/// Standard pixel encodings of images.
+///
+/// The names match the encodings of ROS 2 `sensor_msgs/Image` messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageEncoding {
+    /// 8-bit red, green, blue
+    Rgb8,
+    /// 8-bit blue, green, red
+    Bgr8,
+    /// 8-bit red, green, blue, alpha
+    Rgba8,
+    /// 8-bit blue, green, red, alpha
+    Bgra8,
+    /// 8-bit grayscale
+    Mono8,
+    /// 16-bit grayscale, e.g. depth images
+    Mono16,
+    /// JPEG-compressed image
+    Jpeg,
+    /// PNG-compressed image
+    Png,
+}
+
+impl ImageEncoding {
+    /// All supported encodings.
+    pub const ALL: [Self; 8] = [
+        Self::Rgb8,
+        Self::Bgr8,
+        Self::Rgba8,
+        Self::Bgra8,
+        Self::Mono8,
+        Self::Mono16,
+        Self::Jpeg,
+        Self::Png,
+    ];
+
+    /// Returns the name of the encoding, as used in the `encoding` parameter.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Rgb8 => "rgb8",
+            Self::Bgr8 => "bgr8",
+            Self::Rgba8 => "rgba8",
+            Self::Bgra8 => "bgra8",
+            Self::Mono8 => "mono8",
+            Self::Mono16 => "mono16",
+            Self::Jpeg => "jpeg",
+            Self::Png => "png",
+        }
+    }
+
+    /// Number of channels per pixel, or `None` for compressed encodings.
+    pub fn channels(&self) -> Option<usize> {
+        match self {
+            Self::Rgb8 | Self::Bgr8 => Some(3),
+            Self::Rgba8 | Self::Bgra8 => Some(4),
+            Self::Mono8 | Self::Mono16 => Some(1),
+            Self::Jpeg | Self::Png => None,
+        }
+    }
+
+    /// Arrow type of the image data.
+    ///
+    /// Compressed images are sent as `UInt8` arrays, but have no tensor dtype, so this returns
+    /// `None` for them.
+    pub fn dtype(&self) -> Option<DataType> {
+        match self {
+            Self::Mono16 => Some(DataType::UInt16),
+            Self::Jpeg | Self::Png => None,
+            _ => Some(DataType::UInt8),
+        }
+    }
+
+    /// Whether the image data is compressed.
+    pub fn is_compressed(&self) -> bool {
+        self.channels().is_none()
+    }
+}
+
+impl fmt::Display for ImageEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ImageEncoding {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|encoding| encoding.as_str().eq_ignore_ascii_case(s))
+            .with_context(|| format!("unknown image encoding `{s}`"))
+    }
+}
+
+/// Returns the `dtype` name of the given Arrow type, following numpy naming.
+pub fn dtype_name(data_type: &DataType) -> Option<&'static str> {
+    Some(match data_type {
+        DataType::Int8 => "int8",
+        DataType::Int16 => "int16",
+        DataType::Int32 => "int32",
+        DataType::Int64 => "int64",
+        DataType::UInt8 => "uint8",
+        DataType::UInt16 => "uint16",
+        DataType::UInt32 => "uint32",
+        DataType::UInt64 => "uint64",
+        DataType::Float16 => "float16",
+        DataType::Float32 => "float32",
+        DataType::Float64 => "float64",
+        _ => return None,
+    })
+}
+
+/// Returns the Arrow type for the given `dtype` name.
+pub fn dtype_from_name(name: &str) -> Option<DataType> {
+    Some(match name {
+        "int8" => DataType::Int8,
+        "int16" => DataType::Int16,
+        "int32" => DataType::Int32,
+        "int64" => DataType::Int64,
+        "uint8" => DataType::UInt8,
+        "uint16" => DataType::UInt16,
+        "uint32" => DataType::UInt32,
+        "uint64" => DataType::UInt64,
+        "float16" => DataType::Float16,
+        "float32" => DataType::Float32,
+        "float64" => DataType::Float64,
+        _ => return None,
+    })
+}
+
+fn to_ints(values: &[usize]) -> eyre::Result<Vec<i64>> {
+    values
+        .iter()
+        .map(|v| i64::try_from(*v).context("value too large"))
+        .collect()
+}
+
+fn from_ints(values: &[i64]) -> eyre::Result<Vec<usize>> {
+    values
+        .iter()
+        .map(|v| usize::try_from(*v).with_context(|| format!("negative value {v}")))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tensor_info_roundtrip() {
+        let mut info = TensorInfo::new(vec![2, 3, 4], DataType::Float32);
+        assert_eq!(info.num_elements(), 24);
+        assert_eq!(info.byte_strides().unwrap(), [48, 16, 4]);
+
+        let mut parameters = MetadataParameters::default();
+        info.write_parameters(&mut parameters).unwrap();
+        assert_eq!(parameters[SHAPE], Parameter::ListInt(vec![2, 3, 4]));
+        assert_eq!(parameters[DTYPE], Parameter::String("float32".into()));
+        assert!(!parameters.contains_key(STRIDES));
+        assert_eq!(
+            TensorInfo::from_parameters(&parameters, None).unwrap(),
+            info
+        );
+
+        info.strides = Some(vec![4, 8, 24]);
+        assert_eq!(info.byte_strides().unwrap(), [4, 8, 24]);
+        info.write_parameters(&mut parameters).unwrap();
+        assert_eq!(parameters[STRIDES], Parameter::ListInt(vec![4, 8, 24]));
+        assert_eq!(
+            TensorInfo::from_parameters(&parameters, Some(&DataType::Float32)).unwrap(),
+            info
+        );
+    }
+
+    #[test]
+    fn tensor_info_dtype() {
+        let mut parameters = MetadataParameters::default();
+        parameters.insert(SHAPE.into(), Parameter::ListInt(vec![2]));
+        // the dtype defaults to the type of the received data
+        assert_eq!(
+            TensorInfo::from_parameters(&parameters, Some(&DataType::Int16)).unwrap(),
+            TensorInfo::new(vec![2], DataType::Int16)
+        );
+        assert!(TensorInfo::from_parameters(&parameters, None).is_err());
+
+        parameters.insert(DTYPE.into(), Parameter::String("uint8".into()));
+        assert!(TensorInfo::from_parameters(&parameters, Some(&DataType::Int16)).is_err());
+        parameters.insert(DTYPE.into(), Parameter::String("complex64".into()));
+        assert!(TensorInfo::from_parameters(&parameters, None).is_err());
+
+        let info = TensorInfo::new(vec![2], DataType::Utf8);
+        assert!(info.byte_strides().is_err());
+        assert!(info.write_parameters(&mut parameters).is_err());
+    }
+
+    #[test]
+    fn invalid_tensor_parameters() {
+        let parameters = |shape, strides: Option<Parameter>| {
+            let mut parameters = MetadataParameters::default();
+            parameters.insert(SHAPE.into(), shape);
+            parameters.insert(DTYPE.into(), Parameter::String("uint8".into()));
+            if let Some(strides) = strides {
+                parameters.insert(STRIDES.into(), strides);
+            }
+            TensorInfo::from_parameters(&parameters, None)
+        };
+        assert!(parameters(Parameter::ListInt(vec![2, 3]), None).is_ok());
+        assert!(parameters(Parameter::ListInt(vec![2, -3]), None).is_err());
+        assert!(parameters(Parameter::Integer(2), None).is_err());
+        assert!(
+            parameters(
+                Parameter::ListInt(vec![2, 3]),
+                Some(Parameter::ListInt(vec![1]))
+            )
+            .is_err()
+        );
+        assert!(
+            parameters(
+                Parameter::ListInt(vec![2, 3]),
+                Some(Parameter::String("1,3".into()))
+            )
+            .is_err()
+        );
+        assert!(TensorInfo::from_parameters(&MetadataParameters::default(), None).is_err());
+    }
+
+    #[test]
+    fn image_info_roundtrip() {
+        let image = ImageInfo {
+            width: 4,
+            height: 2,
+            encoding: ImageEncoding::Rgb8,
+        };
+        let mut parameters = MetadataParameters::default();
+        image.write_parameters(&mut parameters).unwrap();
+        assert_eq!(parameters[WIDTH], Parameter::Integer(4));
+        assert_eq!(parameters[HEIGHT], Parameter::Integer(2));
+        assert_eq!(parameters[ENCODING], Parameter::String("rgb8".into()));
+        assert_eq!(
+            TensorInfo::from_parameters(&parameters, None).unwrap(),
+            TensorInfo::new(vec![2, 4, 3], DataType::UInt8)
+        );
+        assert_eq!(ImageInfo::from_parameters(&parameters).unwrap(), image);
+
+        let depth = ImageInfo {
+            encoding: ImageEncoding::Mono16,
+            ..image
+        };
+        assert_eq!(
+            depth.tensor_info(),
+            Some(TensorInfo::new(vec![2, 4], DataType::UInt16))
+        );
+
+        let jpeg = ImageInfo {
+            encoding: ImageEncoding::Jpeg,
+            ..image
+        };
+        let mut parameters = MetadataParameters::default();
+        jpeg.write_parameters(&mut parameters).unwrap();
+        assert!(!parameters.contains_key(SHAPE));
+        assert!(!parameters.contains_key(DTYPE));
+        assert_eq!(ImageInfo::from_parameters(&parameters).unwrap(), jpeg);
+    }
+
+    #[test]
+    fn invalid_image_parameters() {
+        let mut parameters = MetadataParameters::default();
+        parameters.insert(WIDTH.into(), Parameter::Integer(4));
+        parameters.insert(HEIGHT.into(), Parameter::Integer(2));
+        assert!(ImageInfo::from_parameters(&parameters).is_err());
+
+        parameters.insert(ENCODING.into(), Parameter::String("yuv422".into()));
+        assert!(ImageInfo::from_parameters(&parameters).is_err());
+
+        parameters.insert(ENCODING.into(), Parameter::String("BGR8".into()));
+        assert_eq!(
+            ImageInfo::from_parameters(&parameters).unwrap().encoding,
+            ImageEncoding::Bgr8
+        );
+
+        parameters.insert(HEIGHT.into(), Parameter::Integer(-2));
+        assert!(ImageInfo::from_parameters(&parameters).is_err());
+        parameters.insert(HEIGHT.into(), Parameter::Float(2.0));
+        assert!(ImageInfo::from_parameters(&parameters).is_err());
+    }
+
+    #[test]
+    fn image_encodings() {
+        for encoding in ImageEncoding::ALL {
+            assert_eq!(
+                encoding.as_str().parse::<ImageEncoding>().unwrap(),
+                encoding
+            );
+            assert_eq!(encoding.to_string(), encoding.as_str());
+            assert_eq!(encoding.is_compressed(), encoding.dtype().is_none());
+        }
+        assert_eq!(ImageEncoding::Rgba8.channels(), Some(4));
+        assert_eq!(ImageEncoding::Mono8.channels(), Some(1));
+        assert_eq!(ImageEncoding::Png.channels(), None);
+        assert!("rgb".parse::<ImageEncoding>().is_err());
+    }
+
+    #[test]
+    fn dtype_names() {
+        for name in [
+            "int8", "int16", "int32", "int64", "uint8", "uint16", "uint32", "uint64", "float16",
+            "float32", "float64",
+        ] {
+            let dtype = dtype_from_name(name).unwrap();
+            assert_eq!(dtype_name(&dtype), Some(name));
+        }
+        assert_eq!(dtype_name(&DataType::Boolean), None);
+        assert_eq!(dtype_from_name("bool"), None);
+    }
+}
@@ -1,6 +1,8 @@
 pub use crate::common::{
     DataMessage, DropToken, LogLevel, LogMessage, SharedMemoryId, Timestamped,
 };
+use std::{collections::BTreeMap, time::Duration};
+
 use crate::{
     DataflowId, current_crate_version,
     id::{DataId, NodeId},
@@ -16,6 +18,11 @@ pub enum DaemonRequest {
         output_id: DataId,
         metadata: Metadata,
         data: Option<DataMessage>,
+        /// Wait until all receivers with a blocking queue policy have room for the message.
+        ///
+        /// The daemon only replies once the message can be sent, which applies backpressure
+        /// to the sender. Set for the `blocking_outputs` of the node configuration.
+        blocking: bool,
     },
     CloseOutputs(Vec<DataId>),
     /// Signals that the node is finished sending outputs and that it received all
@@ -27,21 +34,43 @@ pub enum DaemonRequest {
     ReportDropTokens {
         drop_tokens: Vec<DropToken>,
     },
+    /// Signals that the node took the given inputs out of its input queue.
+    ///
+    /// Only sent for inputs with a [`QueuePolicy::Block`](crate::config::QueuePolicy::Block)
+    /// queue policy. The daemon uses this information to unblock the senders of these inputs.
+    ///
+    /// Nodes report consumed inputs in batches. The daemon does not send a reply over TCP and
+    /// Unix domain sockets.
+    ReportInputsConsumed {
+        inputs: Vec<DataId>,
+    },
+    /// Reports statistics about the inputs of the node, collected since the last report.
+    ///
+    /// Only sent if the daemon enabled it through `NodeConfig::report_input_stats`.
+    ReportInputStats {
+        inputs: BTreeMap<DataId, InputStats>,
+    },
     SubscribeDrop,
     NextFinishedDropTokens,
     EventStreamDropped,
     NodeConfig {
         node_id: NodeId,
     },
+    /// Requests to stop the whole dataflow, including the nodes on other machines.
+    StopDataflow {
+        reason: Option<String>,
+    },
 }
 
 impl DaemonRequest {
     pub fn expects_tcp_bincode_reply(&self) -> bool {
         #[allow(clippy::match_like_matches_macro)]
         match self {
-            DaemonRequest::SendMessage { .. }
-            | DaemonRequest::NodeConfig { .. }
-            | DaemonRequest::ReportDropTokens { .. } => false,
+            DaemonRequest::SendMessage { blocking, .. } => *blocking,
+            DaemonRequest::NodeConfig { .. }
+            | DaemonRequest::ReportDropTokens { .. }
+            | DaemonRequest::ReportInputsConsumed { .. }
+            | DaemonRequest::ReportInputStats { .. } => false,
             DaemonRequest::Register(NodeRegisterRequest { .. })
             | DaemonRequest::Subscribe
             | DaemonRequest::CloseOutputs(_)
@@ -49,7 +78,8 @@ impl DaemonRequest {
             | DaemonRequest::NextEvent { .. }
             | DaemonRequest::SubscribeDrop
             | DaemonRequest::NextFinishedDropTokens
-            | DaemonRequest::EventStreamDropped => true,
+            | DaemonRequest::EventStreamDropped
+            | DaemonRequest::StopDataflow { .. } => true,
         }
     }
 
@@ -65,8 +95,11 @@ impl DaemonRequest {
             | DaemonRequest::SubscribeDrop
             | DaemonRequest::NextFinishedDropTokens
             | DaemonRequest::ReportDropTokens { .. }
+            | DaemonRequest::ReportInputsConsumed { .. }
+            | DaemonRequest::ReportInputStats { .. }
             | DaemonRequest::SendMessage { .. }
-            | DaemonRequest::EventStreamDropped => false,
+            | DaemonRequest::EventStreamDropped
+            | DaemonRequest::StopDataflow { .. } => false,
         }
     }
 }
@@ -134,3 +167,64 @@ pub struct SharedMemoryInput {
 pub enum DynamicNodeEvent {
     NodeConfig { node_id: NodeId },
 }
+
+/// Upper bounds of the buckets of the [`LatencyHistogram`].
+pub const LATENCY_BUCKETS: [Duration; 10] = [
+    Duration::from_micros(100),
+    Duration::from_micros(500),
+    Duration::from_millis(1),
+    Duration::from_millis(5),
+    Duration::from_millis(10),
+    Duration::from_millis(50),
+    Duration::from_millis(100),
+    Duration::from_millis(500),
+    Duration::from_secs(1),
+    Duration::from_secs(5),
+];
+
+/// Statistics about an input of a node.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct InputStats {
+    /// Number of inputs that are currently waiting in the input queue of the node.
+    pub queue_depth: usize,
+    /// Number of inputs that were dropped because the input queue was full.
+    pub dropped: u64,
+    /// Time between sending and receiving the inputs.
+    pub latency: LatencyHistogram,
+}
+
+/// Histogram of latencies with the bucket bounds defined in [`LATENCY_BUCKETS`].
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct LatencyHistogram {
+    /// Number of latencies per bucket (not cumulative).
+    ///
+    /// Contains one additional entry for the latencies above the largest bucket bound.
+    pub buckets: Vec<u64>,
+    /// Sum of all recorded latencies.
+    pub sum: Duration,
+}
+
+impl LatencyHistogram {
+    pub fn record(&mut self, latency: Duration) {
+        self.buckets.resize(LATENCY_BUCKETS.len() + 1, 0);
+        let bucket = LATENCY_BUCKETS
+            .iter()
+            .position(|bound| latency <= *bound)
+            .unwrap_or(LATENCY_BUCKETS.len());
+        self.buckets[bucket] += 1;
+        self.sum += latency;
+    }
+
+    pub fn merge(&mut self, other: &LatencyHistogram) {
+        self.buckets.resize(LATENCY_BUCKETS.len() + 1, 0);
+        for (count, other) in self.buckets.iter_mut().zip(&other.buckets) {
+            *count += other;
+        }
+        self.sum += other.sum;
+    }
+
+    /// Total number of recorded latencies.
+    pub fn count(&self) -> u64 {
+        self.buckets.iter().sum()
+    }
+}